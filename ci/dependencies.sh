@@ -0,0 +1,8 @@
+#!/bin/sh
+# Features of the library dependencies, without patch versions.
+# Compared with docs/dependencies.txt by CI. To update:
+#   ci/dependencies.sh > docs/dependencies.txt
+set -e
+cargo tree -p btmgmt -e normal,features --prefix none "$@" |
+    sed -E 's/ v(0\.[0-9]+|[1-9][0-9]*)\.[^ ]*/ v\1/; s/ \(\*\)//; s/ \(proc-macro\)//; s/ \(\/[^)]*\)//' |
+    sort -u
@@ -1,21 +1,93 @@
+use std::convert::TryFrom;
 use std::str::FromStr;
 
+use anyhow::Context as _;
 use btmgmt::client::Client;
-use btmgmt::command;
-use btmgmt::event::Event;
+use btmgmt::command::{self, CommandRequest};
+use btmgmt::event::{self, Event};
 use btmgmt::packet;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use futures::StreamExt;
+use serde::Serialize;
 
+#[macro_use]
+mod out;
+mod repl;
+
+/// Hex argument not of the expected bytes.
+#[derive(Debug, PartialEq, Eq)]
+struct LengthError {
+    expected: usize,
+    actual: usize,
+}
+
+impl std::fmt::Display for LengthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "expected {} bytes ({} hex chars), got {}",
+            self.expected,
+            self.expected * 2,
+            self.actual
+        )
+    }
+}
+
+impl std::error::Error for LengthError {}
+
+/// Validate a [`HexBinary`] argument of `len` bytes.
 fn length(len: usize) -> impl FnMut(&str) -> Result<(), anyhow::Error> {
     move |s| {
-        if s.len() != len {
-            anyhow::bail!("invalid length {} != {}", len, s.len())
+        let actual = s.parse::<HexBinary>()?.0.len();
+        if actual != len {
+            return Err(LengthError {
+                expected: len,
+                actual,
+            }
+            .into());
         }
         Ok(())
     }
 }
 
+/// Device address, typed by `--bredr`, `--le` or `--random`.
+#[derive(Debug, Args)]
+struct DeviceAddress {
+    #[clap(long, short)]
+    address: packet::BdAddr,
+
+    #[clap(long, short, conflicts_with_all=&["le", "random"])]
+    bredr: bool,
+
+    #[clap(long, short, conflicts_with = "bredr")]
+    le: bool,
+
+    #[clap(long, short, conflicts_with = "bredr")]
+    random: bool,
+}
+
+impl DeviceAddress {
+    async fn resolve(&self, client: &Client, index: u16) -> anyhow::Result<packet::Address> {
+        let addr_type = address_type(self, client, index).await?;
+        Ok(join(&self.address, &AddressType(addr_type)))
+    }
+}
+
+/// Address type given by the flags. Inferred from the controller only without flags.
+async fn address_type(
+    opts: &DeviceAddress,
+    client: &Client,
+    index: u16,
+) -> anyhow::Result<packet::AddressType> {
+    Ok(match (opts.bredr, opts.le, opts.random) {
+        (true, false, false) => packet::AddressType::BrEdr,
+        (false, false, false) => client.infer_address_type(index, &opts.address).await?,
+        (false, true, false) => packet::AddressType::LePublic,
+        (false, false, true) | (false, true, true) => packet::AddressType::LeRandom,
+        _ => unreachable!(),
+    })
+}
+
 fn join(addr: &packet::BdAddr, addr_type: &AddressType) -> packet::Address {
     let addr = addr.clone();
     match addr_type.0 {
@@ -33,6 +105,12 @@ struct Opt {
     #[clap(short, long)]
     listen: bool,
 
+    /// Output format of read commands. (text or json)
+    ///
+    /// Text is for humans and may change between releases; json is stable.
+    #[clap(short, long, default_value = "text")]
+    output: Output,
+
     #[clap(subcommand)]
     command: Option<Command>,
 }
@@ -89,17 +167,25 @@ enum Command {
         #[clap(subcommand)]
         command: OobCommand,
     },
+
+    /// Read commands line by line, against one client.
+    Repl {
+        /// Controller index, instead of the global one.
+        #[clap(long)]
+        index: Option<u16>,
+    },
 }
 
-// TODO pin code reply
-// TODO pair device / confirm / passkey
 // TODO oob
 
-#[derive(Debug, Subcommand)]
+#[derive(Debug, Default, Subcommand)]
 enum ControllerCommand {
+    #[default]
     Show,
 
+    /// Table of controllers. With `--output json`, the index list replies.
     Ls {
+        /// Read Extended Controller Index List. (json only)
         #[clap(long, short)]
         extended: bool,
     },
@@ -111,6 +197,7 @@ enum ControllerCommand {
     Discoverable {
         flag: Discoerable,
 
+        /// Timeout in seconds. 0 for none.
         #[clap(short, long, default_value = "0")]
         timeout: u16,
     },
@@ -152,17 +239,33 @@ enum ControllerCommand {
         minor: u8,
     },
 
-    /*
+    /// Set Device Class from six hex digits of the class of device. (e.g. 5a020c or 0x5a020c)
+    ///
+    /// Major and minor device class are set. Service classes are kept by the kernel.
+    CodRaw {
+        class: packet::ClassOfDevice,
+    },
+
+    /// Set Local Name, truncated to 248 bytes. The short name, truncated to 10 bytes,
+    /// defaults to empty.
     Name {
+        #[clap(parse(from_str = packet::Name::new_truncate))]
         name: packet::Name,
+        #[clap(parse(from_str = packet::ShortName::new_truncate))]
         short_name: Option<packet::ShortName>,
     },
-    */
+
     Uuid {
         #[clap(subcommand)]
         command: UuidCommand,
     },
 
+    /// Supported, configurable and selected PHYs.
+    Phy {
+        #[clap(subcommand)]
+        command: Option<PhyCommand>,
+    },
+
     Advertising {
         flag: OnOff,
         #[clap(short, long)]
@@ -172,53 +275,109 @@ enum ControllerCommand {
     SecureConnections {
         flag: OnOff,
     },
-}
 
-impl Default for ControllerCommand {
-    fn default() -> Self {
-        Self::Show
-    }
+    ConfigInfo,
 }
 
 impl ControllerCommand {
-    async fn proc(&self, client: &Client, index: u16) -> anyhow::Result<()> {
+    async fn proc(&self, client: &Client, index: u16, output: Output) -> anyhow::Result<()> {
         match self {
             Self::Show => {
                 let reply = client
                     .call(index, command::ReadControllerInformation)
                     .await?;
-                println!("address: {}", reply.address());
-                println!("bluetooth version: {}", reply.bluetooth_version());
-                println!("manufacture: {}", reply.manufacturer());
-                println!("supported settings: {:?}", reply.supported_settings());
-                println!("current settings: {:?}", reply.current_settings());
-                println!("class of device: {}", reply.class_of_device());
-                println!("name: {}", reply.name().to_string_lossy());
-                println!("short name: {}", reply.short_name().to_string_lossy());
+                output.print::<command::ReadControllerInformation>(
+                    Some(index),
+                    &reply,
+                    |reply| {
+                        outln!("address: {}", reply.address());
+                        outln!("bluetooth version: {}", reply.bluetooth_version());
+                        outln!("manufacture: {}", reply.manufacturer());
+                        outln!("supported settings: {:?}", reply.supported_settings());
+                        outln!("current settings: {:?}", reply.current_settings());
+                        outln!("class of device: {}", reply.class_of_device());
+                        outln!("name: {}", reply.name().to_string_lossy());
+                        outln!("short name: {}", reply.short_name().to_string_lossy());
+                    },
+                )?;
+            }
+
+            Self::Ls { .. } if matches!(output, Output::Text) => {
+                let summaries = client.controller_summaries().await?;
+                outln!(
+                    "{:<6} {:<17} {:<12} {:<7} {:<7} {:<7} {:<40} name",
+                    "index",
+                    "address",
+                    "type",
+                    "bus",
+                    "version",
+                    "powered",
+                    "manufacturer"
+                );
+                for summary in summaries {
+                    outln!("{}", format_summary(&summary));
+                }
             }
 
             Self::Ls { extended } => {
                 if !extended {
                     let reply = client.call(None, command::ReadControllerIndexList).await?;
-                    for c in reply {
-                        println!("{}", u16::from(c));
-                    }
+                    output.print::<command::ReadControllerIndexList>(None, &reply, |reply| {
+                        for c in reply.iter() {
+                            outln!("{}", u16::from(c.clone()));
+                        }
+                    })?;
                 } else {
                     let reply = client
                         .call(None, command::ReadExtendedControllerIndexList)
                         .await?;
-                    for (index, typ, bus) in reply {
-                        println!("{} {:?} {:?}", u16::from(index), typ, bus);
-                    }
+                    output.print::<command::ReadExtendedControllerIndexList>(
+                        None,
+                        &reply,
+                        |reply| {
+                            for (index, typ, bus) in reply.iter() {
+                                outln!("{} {:?} {:?}", u16::from(index.clone()), typ, bus);
+                            }
+                        },
+                    )?;
                 }
             }
 
+            Self::ConfigInfo => {
+                let reply = client
+                    .call(index, command::ReadControllerConfigurationInformation)
+                    .await?;
+                output.print::<command::ReadControllerConfigurationInformation>(
+                    Some(index),
+                    &reply,
+                    |reply| {
+                        let yes_no = |b| if b { "yes" } else { "no" };
+                        outln!("manufacture: {}", reply.manufacture());
+                        outln!("supported options: {}", reply.supported_options());
+                        outln!("missing options: {}", reply.missing_options());
+                        outln!(
+                            "can set public address: {}",
+                            yes_no(reply.can_set_public_address())
+                        );
+                        outln!(
+                            "needs public address: {}",
+                            yes_no(reply.needs_public_address())
+                        );
+                        outln!(
+                            "needs external config: {}",
+                            yes_no(reply.needs_external_config())
+                        );
+                        outln!("fully configured: {}", yes_no(reply.is_fully_configured()));
+                    },
+                )?;
+            }
+
             Self::Power { flag } => {
                 let powered = matches!(flag, OnOff::On);
-                let reply = client
-                    .call(index, command::SetPowered::new(powered))
+                let settings = client
+                    .set_setting(index, packet::SettingFlag::Powered, powered)
                     .await?;
-                println!("{:?}", &*reply);
+                outln!("{:?}", settings);
             }
 
             Self::Discoverable { flag, timeout } => {
@@ -230,102 +389,131 @@ impl ControllerCommand {
                 let reply = client
                     .call(index, command::SetDiscoverable::new(flag, *timeout))
                     .await?;
-                println!("OK {:?}", &*reply);
+                outln!("OK {:?}", &*reply);
             }
 
             Self::Connectable { flag } => {
                 let flag = matches!(flag, OnOff::On);
-                let reply = client
-                    .call(index, command::SetConnectable::new(flag))
+                let settings = client
+                    .set_setting(index, packet::SettingFlag::Connectable, flag)
                     .await?;
-                println!("OK {:?}", &*reply);
+                outln!("OK {:?}", settings);
             }
 
             Self::FastConnectable { flag } => {
                 let flag = matches!(flag, OnOff::On);
-                let reply = client
-                    .call(index, command::SetFastConnectable::new(flag))
+                let settings = client
+                    .set_setting(index, packet::SettingFlag::FastConnectable, flag)
                     .await?;
-                println!("OK {:?}", &*reply);
+                outln!("OK {:?}", settings);
             }
 
             Self::Bondable { flag } => {
                 let flag = matches!(flag, OnOff::On);
-                let reply = client.call(index, command::SetBondable::new(flag)).await?;
-                println!("OK {:?}", &*reply);
+                let settings = client
+                    .set_setting(index, packet::SettingFlag::Bondable, flag)
+                    .await?;
+                outln!("OK {:?}", settings);
             }
 
             Self::LinkSecurity { flag } => {
                 let flag = matches!(flag, OnOff::On);
-                let reply = client
-                    .call(index, command::SetLinkSecurity::new(flag))
+                let settings = client
+                    .set_setting(index, packet::SettingFlag::LinkSecurity, flag)
                     .await?;
-                println!("OK {:?}", &*reply);
+                outln!("OK {:?}", settings);
             }
 
             Self::Ssp { flag } => {
                 let flag = matches!(flag, OnOff::On);
-                let reply = client
-                    .call(index, command::SetSecureSimplePairing::new(flag))
+                let settings = client
+                    .set_setting(index, packet::SettingFlag::Ssp, flag)
                     .await?;
-                println!("OK {:?}", &*reply);
+                outln!("OK {:?}", settings);
             }
 
             Self::Hs { flag } => {
                 let flag = matches!(flag, OnOff::On);
-                let reply = client.call(index, command::SetHighSpeed::new(flag)).await?;
-                println!("OK {:?}", &*reply);
+                let settings = client
+                    .set_setting(index, packet::SettingFlag::HighSpeed, flag)
+                    .await?;
+                outln!("OK {:?}", settings);
             }
 
             Self::Le { flag } => {
                 let flag = matches!(flag, OnOff::On);
-                let reply = client.call(index, command::SetLowEnergy::new(flag)).await?;
-                println!("OK {:?}", &*reply);
+                let settings = client
+                    .set_setting(index, packet::SettingFlag::Le, flag)
+                    .await?;
+                outln!("OK {:?}", settings);
             }
 
             Self::Bredr { flag } => {
                 let flag = matches!(flag, OnOff::On);
-                let reply = client.call(index, command::SetBrEdr::new(flag)).await?;
-                println!("OK {:?}", &*reply);
+                let settings = client
+                    .set_setting(index, packet::SettingFlag::BrEdr, flag)
+                    .await?;
+                outln!("OK {:?}", settings);
             }
 
             Self::Cod { major, minor } => {
                 let reply = client
                     .call(index, command::SetDeviceClass::new(*major, *minor))
                     .await?;
-                println!("{}", &*reply);
+                outln!("{}", &*reply);
+            }
+
+            Self::CodRaw { class } => {
+                let command =
+                    command::SetDeviceClass::new(class.major_class(), class.minor_class());
+                let reply = client.call(index, command).await?;
+                outln!("{}", &*reply);
             }
 
-            /* FIXME
             Self::Name { name, short_name } => {
+                let short_name = match short_name {
+                    Some(short_name) => short_name.clone(),
+                    None => packet::ShortName::new_truncate(""),
+                };
                 let reply = client
-                    .call(
-                        index,
-                        command::SetLocalName::new(
-                            name.clone(),
-                            short_name
-                                .clone()
-                                .unwrap_or_else(|| packet::ShortName::new("").unwrap()),
-                        ),
-                    )
+                    .call(index, command::SetLocalName::new(name.clone(), short_name))
                     .await?;
-                println!("{}", reply.name().to_string_lossy());
-                println!("{}", reply.short_name().to_string_lossy());
+                outln!("{}", reply.name().to_string_lossy());
+                outln!("{}", reply.short_name().to_string_lossy());
             }
-            */
+
+            Self::Phy { command } => match command.as_ref().unwrap_or(&PhyCommand::Get) {
+                PhyCommand::Get => {
+                    let reply = client.call(index, command::GetPhyConfiguration).await?;
+                    outln!("supported: {}", format_phys(reply.supported_phys()));
+                    outln!("configurable: {}", format_phys(reply.configurable_phys()));
+                    outln!("selected: {}", format_phys(reply.selected_phys()));
+                }
+
+                PhyCommand::Set { phys } => {
+                    let phys = phys
+                        .iter()
+                        .fold(packet::Phys::empty(), |acc, phy| acc | phy.0);
+                    client
+                        .call(index, command::SetPhyConfiguration::new(phys))
+                        .await?;
+                    outln!("selected: {}", format_phys(&phys));
+                }
+            },
+
             Self::Uuid { command } => match command {
                 UuidCommand::Add { val, svc_hint } => {
                     let reply = client
                         .call(index, command::AddUuid::new(val.clone(), *svc_hint))
                         .await?;
-                    println!("{}", &*reply);
+                    outln!("{}", &*reply);
                 }
 
                 UuidCommand::Remove { val } => {
                     let reply = client
                         .call(index, command::RemoveUuid::new(val.clone()))
                         .await?;
-                    println!("{}", &*reply);
+                    outln!("{}", &*reply);
                 }
             },
 
@@ -338,7 +526,7 @@ impl ControllerCommand {
                 let reply = client
                     .call(index, command::SetAdvertising::new(flag))
                     .await?;
-                println!("{:?}", &*reply);
+                outln!("{:?}", &*reply);
             }
 
             Self::SecureConnections { flag } => {
@@ -349,13 +537,25 @@ impl ControllerCommand {
                 let reply = client
                     .call(index, command::SetSecureConnections::new(flag))
                     .await?;
-                println!("{:?}", &*reply);
+                outln!("{:?}", &*reply);
             }
         };
         Ok(())
     }
 }
 
+#[derive(Debug, Subcommand)]
+enum PhyCommand {
+    Get,
+
+    /// Select PHYs, replacing the selection.
+    Set {
+        /// PHY to select, repeated. (e.g. le-1m-tx)
+        #[clap(long = "phy", required = true)]
+        phys: Vec<PhyName>,
+    },
+}
+
 #[derive(Debug, Subcommand)]
 enum UuidCommand {
     Add { val: packet::Uuid, svc_hint: u8 },
@@ -365,12 +565,180 @@ enum UuidCommand {
 
 #[derive(Debug, Subcommand)]
 enum KeyCommand {
-    Link, // TODO
-    Ltk,
+    /// Load link keys from a file, replacing the loaded ones.
+    ///
+    /// One key per line as `address keytype hex16 pinlen`. (e.g. `00:11:22:33:44:55 4
+    /// 00112233445566778899aabbccddeeff 0`) Blank lines and lines starting with `#` are
+    /// skipped.
+    Link {
+        #[clap(long)]
+        file: std::path::PathBuf,
+        /// Keep debug keys.
+        #[clap(long)]
+        debug_keys: bool,
+    },
+
+    /// Load long term keys from a file, replacing the loaded ones.
+    ///
+    /// One key per line as `address addrtype keytype master|slave encsize ediv hex8
+    /// hex16`. (e.g. `00:11:22:33:44:55 le_public 1 master 16 0x1234 0011223344556677
+    /// 00112233445566778899aabbccddeeff`) Blank lines and lines starting with `#` are
+    /// skipped.
+    Ltk {
+        #[clap(long)]
+        file: std::path::PathBuf,
+    },
 }
 
-#[derive(Debug, Subcommand)]
+impl KeyCommand {
+    async fn proc(&self, client: &Client, index: u16) -> anyhow::Result<()> {
+        match self {
+            Self::Link { file, debug_keys } => {
+                let text = std::fs::read_to_string(file)
+                    .with_context(|| format!("failed to read {}", file.display()))?;
+                let keys = parse_link_keys(&text)?;
+                let len = keys.len();
+                client
+                    .call(index, command::LoadLinkKeys::new(*debug_keys, keys))
+                    .await?;
+                outln!("OK {}", len);
+            }
+
+            Self::Ltk { file } => {
+                let text = std::fs::read_to_string(file)
+                    .with_context(|| format!("failed to read {}", file.display()))?;
+                let keys = parse_long_term_keys(&text)?;
+                let len = keys.len();
+                client
+                    .call(index, command::LoadLongTermKey::new(keys))
+                    .await?;
+                outln!("OK {}", len);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Link keys of `address keytype hex16 pinlen` lines. The key type is the wire value,
+/// decimal or `0x` prefixed.
+fn parse_link_keys(text: &str) -> anyhow::Result<Vec<packet::LinkKey>> {
+    parse_key_lines(text, parse_link_key)
+}
+
+/// Skip blank and `#` lines, adding the line number to errors.
+fn parse_key_lines<T, F>(text: &str, parse: F) -> anyhow::Result<Vec<T>>
+where
+    F: Fn(&str) -> anyhow::Result<T>,
+{
+    let mut keys = vec![];
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let key = parse(line).with_context(|| format!("line {}", n + 1))?;
+        keys.push(key);
+    }
+    Ok(keys)
+}
+
+fn parse_hex_array<const N: usize>(s: &str) -> anyhow::Result<[u8; N]> {
+    let value = s.parse::<HexBinary>()?.0;
+    Ok(<[u8; N]>::try_from(&value[..]).map_err(|_| LengthError {
+        expected: N,
+        actual: value.len(),
+    })?)
+}
+
+fn parse_link_key(line: &str) -> anyhow::Result<packet::LinkKey> {
+    use btmgmt::packet::Unpack;
+
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+    let (address, key_type, value, pin_length) = match fields[..] {
+        [address, key_type, value, pin_length] => (address, key_type, value, pin_length),
+        _ => anyhow::bail!(
+            "expected `address keytype hex16 pinlen`, got {} fields",
+            fields.len()
+        ),
+    };
+
+    let address = address
+        .parse::<packet::BdAddr>()
+        .map_err(|err| anyhow::anyhow!("invalid address {:?}: {}", address, err))?;
+    let key_type = u8::try_from(parse_u16(key_type)?)?;
+    let key_type = packet::LinkKeyType::unpack(&mut &[key_type][..])?;
+    let value = parse_hex_array::<16>(value)?;
+    let pin_length = pin_length.parse::<u8>()?;
+    Ok(packet::LinkKey::new(
+        address.to_br_edr_addr(),
+        key_type,
+        value,
+        pin_length,
+    ))
+}
+
+/// Long term keys of `address addrtype keytype master|slave encsize ediv hex8 hex16`
+/// lines. The key type is the wire value, decimal or `0x` prefixed.
+fn parse_long_term_keys(text: &str) -> anyhow::Result<Vec<packet::LongTermKey>> {
+    parse_key_lines(text, parse_long_term_key)
+}
+
+/// Columns are set in order, so the builder names the first absent one.
+fn parse_long_term_key(line: &str) -> anyhow::Result<packet::LongTermKey> {
+    use btmgmt::packet::Unpack;
+
+    let mut fields = line.split_whitespace();
+    let mut builder = packet::LongTermKeyBuilder::default();
+    if let Some(address) = fields.next() {
+        let address = address
+            .parse::<packet::BdAddr>()
+            .map_err(|err| anyhow::anyhow!("invalid address {:?}: {}", address, err))?;
+        let address_type = fields
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("missing address_type column"))?;
+        let address_type = address_type
+            .parse::<AddressType>()
+            .map_err(|v| anyhow::anyhow!("invalid address type {:?}", v))?;
+        builder.address(join(&address, &address_type));
+    }
+    if let Some(key_type) = fields.next() {
+        let key_type = u8::try_from(parse_u16(key_type)?)?;
+        builder.key_type(packet::LongTermKeyType::unpack(&mut &[key_type][..])?);
+    }
+    if let Some(master) = fields.next() {
+        builder.master(match master {
+            "master" => true,
+            "slave" => false,
+            v => anyhow::bail!("expected master or slave, got {:?}", v),
+        });
+    }
+    if let Some(encryption_size) = fields.next() {
+        builder.encryption_size(encryption_size.parse()?);
+    }
+    if let Some(encryption_diversifier) = fields.next() {
+        builder.encryption_diversifier(parse_u16(encryption_diversifier)?);
+    }
+    if let Some(random_number) = fields.next() {
+        builder.random_number(parse_hex_array::<8>(random_number)?);
+    }
+    if let Some(value) = fields.next() {
+        builder.value(parse_hex_array::<16>(value)?);
+    }
+    if let Some(extra) = fields.next() {
+        anyhow::bail!("unexpected field {:?}", extra);
+    }
+
+    builder.build().map_err(|err| match err {
+        packet::LongTermKeyBuilderError::Uninitialized(field) => {
+            anyhow::anyhow!("missing {} column", field)
+        }
+        err => err.into(),
+    })
+}
+
+#[derive(Debug, Default, Subcommand)]
 enum ConnectionCommand {
+    #[default]
     Ls,
 
     Disconnect {
@@ -379,20 +747,16 @@ enum ConnectionCommand {
     },
 }
 
-impl Default for ConnectionCommand {
-    fn default() -> Self {
-        Self::Ls
-    }
-}
-
 impl ConnectionCommand {
-    async fn proc(&self, client: &Client, index: u16) -> anyhow::Result<()> {
+    async fn proc(&self, client: &Client, index: u16, output: Output) -> anyhow::Result<()> {
         match self {
             ConnectionCommand::Ls => {
                 let reply = client.call(index, command::GetConnections).await?;
-                for addr in reply {
-                    println!("{}", addr);
-                }
+                output.print::<command::GetConnections>(Some(index), &reply, |reply| {
+                    for addr in reply.iter() {
+                        outln!("{}", addr);
+                    }
+                })?;
             }
 
             ConnectionCommand::Disconnect {
@@ -401,7 +765,7 @@ impl ConnectionCommand {
             } => {
                 let addr = join(address, address_type);
                 let reply = client.call(index, command::Disconnect::new(addr)).await?;
-                println!("{}", reply.address());
+                outln!("{}", reply.address());
             }
         };
         Ok(())
@@ -457,7 +821,7 @@ impl DiscoveryCommand {
                     let reply = client
                         .call(index, command::StartDiscovery::new(addr_type))
                         .await?;
-                    println!("{:?}", &*reply);
+                    outln!("{:?}", &*reply);
                 } else if rssi.is_some() || !uuid.is_empty() {
                     let rssi = rssi.unwrap_or(127);
                     let mut uuid = uuid.clone();
@@ -470,20 +834,27 @@ impl DiscoveryCommand {
                             command::StartServiceDiscovery::new(addr_type, rssi, uuid),
                         )
                         .await?;
-                    println!("{:?}", &*reply);
+                    outln!("{:?}", &*reply);
                 } else {
                     let reply = client
                         .call(index, command::StartLimitedDiscovery::new(addr_type))
                         .await?;
-                    println!("{:?}", &*reply);
+                    outln!("{:?}", &*reply);
                 }
 
                 if *wait {
                     let mut events = client.events().await;
-                    while let Some((_, event)) = events.next().await {
-                        match event {
-                            Event::Discovering(discov) if !*discov.discovering() => break, // FIXME check index addrt
-                            _ => {}
+                    let (mut bredr, mut le) = (*bredr || !le, *le || !bredr);
+                    while bredr || le {
+                        match events.next().await {
+                            Some((i, Event::Discovering(discov)))
+                                if i == index.into() && !*discov.discovering() =>
+                            {
+                                bredr &= !discov.is_bredr();
+                                le &= !discov.is_le();
+                            }
+                            Some(..) => {}
+                            None => break,
                         }
                     }
                 }
@@ -501,7 +872,7 @@ impl DiscoveryCommand {
                 let reply = client
                     .call(index, command::StopDiscovery::new(addr_type))
                     .await?;
-                println!("{:?}", &*reply);
+                outln!("{:?}", &*reply);
             }
         };
         Ok(())
@@ -523,10 +894,10 @@ enum ConfigurationCommand {
 }
 
 impl ConfigurationCommand {
-    async fn proc(&self, client: &Client, index: u16) -> anyhow::Result<()> {
+    async fn proc(&self, client: &Client, index: u16, output: Output) -> anyhow::Result<()> {
         match self {
-            Self::System { command } => command.proc(client, index).await?,
-            Self::Runtime { command } => command.proc(client, index).await?,
+            Self::System { command } => command.proc(client, index, output).await?,
+            Self::Runtime { command } => command.proc(client, index, output).await?,
         }
         Ok(())
     }
@@ -542,18 +913,41 @@ enum SystemConfigurationCommand {
         #[clap(long)]
         adv_max_interval: Option<u16>,
     },
+
+    /// Set a parameter not known to btmgmt, such as vendor specific ones.
+    SetRaw {
+        /// Parameter type. (e.g. 0x0100)
+        #[clap(long = "type", parse(try_from_str = parse_u16))]
+        param_type: u16,
+        /// Value in hex. (e.g. 1234)
+        #[clap(long)]
+        value: HexBinary,
+    },
 }
 
 impl SystemConfigurationCommand {
-    async fn proc(&self, client: &Client, index: u16) -> anyhow::Result<()> {
+    async fn proc(&self, client: &Client, index: u16, output: Output) -> anyhow::Result<()> {
         match self {
             Self::Get => {
                 let reply = client
                     .call(index, command::ReadDefaultSystemConfiguration)
                     .await?;
-                for item in reply {
-                    println!("{:?} {}", item.for_type(), item.value_as_u16().unwrap());
-                }
+                output.print::<command::ReadDefaultSystemConfiguration>(
+                    Some(index),
+                    &reply,
+                    |reply| {
+                        for item in reply.iter() {
+                            match item {
+                                packet::SystemConfigurationParameter::Raw { param_type, value } => {
+                                    outln!("{:#06x} {}", param_type, hex(value))
+                                }
+                                item => {
+                                    outln!("{:?} {}", item.for_type(), item.value_as_u16().unwrap())
+                                }
+                            }
+                        }
+                    },
+                )?;
             }
 
             Self::Set {
@@ -575,7 +969,24 @@ impl SystemConfigurationCommand {
                             .collect::<command::SetDefaultSystemConfiguration>(),
                     )
                     .await?;
-                println!("OK {:?}", reply);
+                outln!("OK {:?}", reply);
+            }
+
+            Self::SetRaw { param_type, value } => {
+                if let Some(known) = packet::SystemConfigurationParameter::known_type(*param_type) {
+                    anyhow::bail!("type {:#06x} is known, use `set` for {}", param_type, known);
+                }
+                let req = packet::SystemConfigurationParameter::Raw {
+                    param_type: *param_type,
+                    value: value.0.clone(),
+                };
+                let reply = client
+                    .call(
+                        index,
+                        std::iter::once(req).collect::<command::SetDefaultSystemConfiguration>(),
+                    )
+                    .await?;
+                outln!("OK {:?}", reply);
             }
         };
         Ok(())
@@ -586,23 +997,57 @@ impl SystemConfigurationCommand {
 enum RuntimeConfigurationCommand {
     Get,
 
-    Set {},
+    Set {
+        #[clap(long)]
+        le_auto_reconnect_timeout: Option<u16>,
+    },
 }
 
 impl RuntimeConfigurationCommand {
-    #[allow(unreachable_code)]
-    async fn proc(&self, client: &Client, index: u16) -> anyhow::Result<()> {
+    async fn proc(&self, client: &Client, index: u16, output: Output) -> anyhow::Result<()> {
         match self {
             Self::Get => {
                 let reply = client
                     .call(index, command::ReadDefaultRuntimeConfiguration)
                     .await?;
-                for item in reply {
-                    println!("{:?} {}", item.for_type(), item.value_as_u16().unwrap());
-                }
+                output.print::<command::ReadDefaultRuntimeConfiguration>(
+                    Some(index),
+                    &reply,
+                    |reply| {
+                        for item in reply.iter() {
+                            match item {
+                                packet::RuntimeConfigurationParameter::Raw {
+                                    param_type,
+                                    value,
+                                } => {
+                                    outln!("{:#06x} {}", param_type, hex(value))
+                                }
+                                item => {
+                                    outln!("{:?} {}", item.for_type(), item.value_as_u16().unwrap())
+                                }
+                            }
+                        }
+                    },
+                )?;
             }
 
-            Self::Set {} => todo!(),
+            Self::Set {
+                le_auto_reconnect_timeout,
+            } => {
+                use packet::RuntimeConfigurationParameter::*;
+                let mut req = vec![];
+                if let Some(val) = le_auto_reconnect_timeout {
+                    req.push(LeAutoReconnectTimeout(*val));
+                }
+                let reply = client
+                    .call(
+                        index,
+                        req.into_iter()
+                            .collect::<command::SetDefaultRuntimeConfiguration>(),
+                    )
+                    .await?;
+                outln!("OK {:?}", reply);
+            }
         };
         Ok(())
     }
@@ -615,8 +1060,10 @@ enum AdvertiseCommand {
     Add {
         #[clap(long, short, default_value = "1")]
         instance: u8,
+        /// Rotation duration in seconds. 0 for the kernel default.
         #[clap(long, short, default_value = "0")]
         duration: u16,
+        /// Timeout in seconds. 0 for none.
         #[clap(long, short, default_value = "0")]
         timeout: u16,
         #[clap(long, short)]
@@ -626,6 +1073,35 @@ enum AdvertiseCommand {
         // TODO flags
     },
 
+    /// Add Extended Advertising Parameters, then Data. Omitted parameters are left to the
+    /// kernel.
+    AddExt {
+        #[clap(long, short, default_value = "1")]
+        instance: u8,
+        /// Rotation duration in seconds.
+        #[clap(long, short)]
+        duration: Option<u16>,
+        /// Timeout in seconds.
+        #[clap(long, short)]
+        timeout: Option<u16>,
+        /// Min interval in 0.625 ms units.
+        #[clap(long, requires = "max-interval")]
+        min_interval: Option<u32>,
+        /// Max interval in 0.625 ms units.
+        #[clap(long, requires = "min-interval")]
+        max_interval: Option<u32>,
+        /// Tx power in dBm.
+        #[clap(long, allow_hyphen_values = true)]
+        tx_power: Option<i8>,
+        /// Secondary channel PHY. (1m, 2m or coded)
+        #[clap(long)]
+        phy: Option<Phy>,
+        #[clap(long, short)]
+        adv_data: Option<HexBinary>,
+        #[clap(long, short)]
+        scan_resp: Option<HexBinary>,
+    },
+
     Remove {
         #[clap(long, short, default_value = "1")]
         instance: u8,
@@ -638,18 +1114,20 @@ enum AdvertiseCommand {
 }
 
 impl AdvertiseCommand {
-    async fn proc(&self, client: &Client, index: u16) -> anyhow::Result<()> {
+    async fn proc(&self, client: &Client, index: u16, output: Output) -> anyhow::Result<()> {
         match self {
             Self::Features => {
                 let reply = client.call(index, command::ReadAdvertisingFeature).await?;
-                println!("supported flags: {:?}", reply.supported_flags());
-                println!("max adv data len: {}", reply.max_adv_data_len());
-                println!("max scan resp len: {}", reply.max_scan_resp_len());
-                println!("max instances: {}", reply.max_instances());
-                println!("instances:");
-                for n in reply.instances() {
-                    println!("{:?}", n);
-                }
+                output.print::<command::ReadAdvertisingFeature>(Some(index), &reply, |reply| {
+                    outln!("supported flags: {:?}", reply.supported_flags());
+                    outln!("max adv data len: {}", reply.max_adv_data_len());
+                    outln!("max scan resp len: {}", reply.max_scan_resp_len());
+                    outln!("max instances: {}", reply.max_instances());
+                    outln!("instances:");
+                    for n in reply.instances() {
+                        outln!("{:?}", n);
+                    }
+                })?;
             }
 
             Self::Add {
@@ -670,18 +1148,65 @@ impl AdvertiseCommand {
                             flags,
                             *duration,
                             *timeout,
-                            packet::AdvDataScanResp::new(adv_data.0, scan_resp.0),
+                            packet::AdvDataScanResp::try_new(adv_data.0, scan_resp.0)?,
                         ),
                     )
                     .await?;
-                println!("OK {:?}", reply);
+                outln!("OK {:?}", reply);
+            }
+
+            Self::AddExt {
+                instance,
+                duration,
+                timeout,
+                min_interval,
+                max_interval,
+                tx_power,
+                phy,
+                adv_data,
+                scan_resp,
+            } => {
+                let flags = match phy {
+                    Some(Phy::Le1M) => packet::AdvertisingFlag::SecondaryChannelWithLe1M,
+                    Some(Phy::Le2M) => packet::AdvertisingFlag::SecondaryChannelWithLe2M,
+                    Some(Phy::LeCoded) => packet::AdvertisingFlag::SecondaryChannelWithLeCoded,
+                    None => packet::AdvertisingFlag::empty(),
+                };
+                let mut params =
+                    command::AddExtendedAdvertisingParameters::new((*instance).into(), flags);
+                if let Some(duration) = duration {
+                    params = params.duration(*duration);
+                }
+                if let Some(timeout) = timeout {
+                    params = params.timeout(*timeout);
+                }
+                if let (Some(min), Some(max)) = (min_interval, max_interval) {
+                    params = params.interval(*min, *max);
+                }
+                if let Some(tx_power) = tx_power {
+                    params = params.tx_power(*tx_power);
+                }
+                let reply = client.call(index, params).await?;
+                outln!("instance: {:?}", reply.instance());
+                outln!("tx power: {}", reply.tx_power());
+                outln!("max adv data len: {}", reply.max_adv_data_len());
+                outln!("max scan resp len: {}", reply.max_scan_resp_len());
+
+                let adv_data = adv_data.clone().unwrap_or_default();
+                let scan_resp = scan_resp.clone().unwrap_or_default();
+                let data = command::AddExtendedAdvertisingData::new(
+                    (*instance).into(),
+                    packet::AdvDataScanResp::try_new(adv_data.0, scan_resp.0)?,
+                );
+                let reply = client.call(index, data).await?;
+                outln!("OK {:?}", reply);
             }
 
             Self::Remove { instance } => {
                 let reply = client
                     .call(index, command::RemoveAdvertising::new((*instance).into()))
                     .await?;
-                println!("OK {:?}", reply);
+                outln!("OK {:?}", reply);
             }
 
             Self::Monitor { command } => command.proc(client, index).await?,
@@ -722,7 +1247,7 @@ impl AdvertiseMonitorCommand {
                     .map(|((t, o), v)| packet::AdvertisementPattern::new(*t, *o, &v.0))
                     .collect::<command::AddAdvertisementPatternsMonitor>();
                 let reply = client.call(index, patterns).await?;
-                println!("{:?}", &*reply);
+                outln!("{:?}", &*reply);
             }
 
             Self::Remove { handle } => {
@@ -732,7 +1257,7 @@ impl AdvertiseMonitorCommand {
                         command::RemoveAdvertisementPatternsMonitor::new((*handle).into()),
                     )
                     .await?;
-                println!("{:?}", &*reply);
+                outln!("{:?}", &*reply);
             }
         };
         Ok(())
@@ -742,17 +1267,8 @@ impl AdvertiseMonitorCommand {
 #[derive(Debug, Subcommand)]
 enum DeviceCommand {
     Add {
-        #[clap(long, short)]
-        address: packet::BdAddr,
-
-        #[clap(long, short, conflicts_with_all=&["le", "random"])]
-        bredr: bool,
-
-        #[clap(long, short, conflicts_with = "bredr")]
-        le: bool,
-
-        #[clap(long, short, conflicts_with = "bredr")]
-        random: bool,
+        #[clap(flatten)]
+        address: DeviceAddress,
 
         #[clap(long, short = 'A', conflicts_with = "background")]
         autoconnect: bool,
@@ -762,59 +1278,23 @@ enum DeviceCommand {
     },
 
     Remove {
-        #[clap(long, short)]
-        address: packet::BdAddr,
-
-        #[clap(long, short, conflicts_with_all=&["le", "random"])]
-        bredr: bool,
-
-        #[clap(long, short, conflicts_with = "bredr")]
-        le: bool,
-
-        #[clap(long, short, conflicts_with = "bredr")]
-        random: bool,
+        #[clap(flatten)]
+        address: DeviceAddress,
     },
 
     Block {
-        #[clap(long, short)]
-        address: packet::BdAddr,
-
-        #[clap(long, short, conflicts_with_all=&["le", "random"])]
-        bredr: bool,
-
-        #[clap(long, short, conflicts_with = "bredr")]
-        le: bool,
-
-        #[clap(long, short, conflicts_with = "bredr")]
-        random: bool,
+        #[clap(flatten)]
+        address: DeviceAddress,
     },
 
     Unblock {
-        #[clap(long, short)]
-        address: packet::BdAddr,
-
-        #[clap(long, short, conflicts_with_all=&["le", "random"])]
-        bredr: bool,
-
-        #[clap(long, short, conflicts_with = "bredr")]
-        le: bool,
-
-        #[clap(long, short, conflicts_with = "bredr")]
-        random: bool,
+        #[clap(flatten)]
+        address: DeviceAddress,
     },
 
     Pair {
-        #[clap(long, short)]
-        address: packet::BdAddr,
-
-        #[clap(long, short, conflicts_with_all=&["le", "random"])]
-        bredr: bool,
-
-        #[clap(long, short, conflicts_with = "bredr")]
-        le: bool,
-
-        #[clap(long, short, conflicts_with = "bredr")]
-        random: bool,
+        #[clap(flatten)]
+        address: DeviceAddress,
 
         #[clap(long, conflicts_with_all=&["display-yesno", "keyboard-only", "no-input-no-output", "keyboard-display"])]
         display_only: bool,
@@ -833,34 +1313,62 @@ enum DeviceCommand {
     },
 
     CancelPair {
+        #[clap(flatten)]
+        address: DeviceAddress,
+    },
+
+    Unpair {
+        #[clap(flatten)]
+        address: DeviceAddress,
+
         #[clap(long, short)]
-        address: packet::BdAddr,
+        disconnect: bool,
+    },
 
-        #[clap(long, short, conflicts_with_all=&["le", "random"])]
-        bredr: bool,
+    /// Answer a PIN Code Request.
+    Pincode {
+        #[clap(flatten)]
+        address: DeviceAddress,
 
-        #[clap(long, short, conflicts_with = "bredr")]
-        le: bool,
+        /// Up to 16 bytes.
+        pin: PinCode,
+    },
 
-        #[clap(long, short, conflicts_with = "bredr")]
-        random: bool,
+    /// Reject a PIN Code Request.
+    PincodeNegative {
+        #[clap(flatten)]
+        address: DeviceAddress,
     },
 
-    Unpair {
-        #[clap(long, short)]
-        address: packet::BdAddr,
+    /// Accept a User Confirmation Request.
+    Confirm {
+        #[clap(flatten)]
+        address: DeviceAddress,
+    },
 
-        #[clap(long, short, conflicts_with_all=&["le", "random"])]
-        bredr: bool,
+    /// Reject a User Confirmation Request.
+    ConfirmNegative {
+        #[clap(flatten)]
+        address: DeviceAddress,
+    },
 
-        #[clap(long, short, conflicts_with = "bredr")]
-        le: bool,
+    /// Answer a User Passkey Request.
+    Passkey {
+        #[clap(flatten)]
+        address: DeviceAddress,
 
-        #[clap(long, short, conflicts_with = "bredr")]
-        random: bool,
+        passkey: u32,
+    },
 
-        #[clap(long, short)]
-        disconnect: bool,
+    /// Reject a User Passkey Request.
+    PasskeyNegative {
+        #[clap(flatten)]
+        address: DeviceAddress,
+    },
+
+    Flags {
+        #[clap(subcommand)]
+        command: DeviceFlagsCommand,
     },
 }
 
@@ -869,101 +1377,51 @@ impl DeviceCommand {
         match self {
             Self::Add {
                 address,
-                bredr,
-                le,
-                random,
                 autoconnect,
                 background,
             } => {
-                let addr_type = match (bredr, le, random) {
-                    (true, false, false) | (false, false, false) => packet::AddressType::BrEdr,
-                    (false, true, false) => packet::AddressType::LePublic,
-                    (false, false, true) | (false, true, true) => packet::AddressType::LeRandom,
-                    _ => unreachable!(),
-                };
+                let addr = address.resolve(client, index).await?;
                 let action = match (autoconnect, background) {
                     (false, false) => packet::Action::Allow,
                     (true, false) => packet::Action::AutoConnect,
                     (false, true) => packet::Action::Background,
                     _ => unreachable!(),
                 };
-                let addr = join(address, &AddressType(addr_type));
                 let reply = client
                     .call(index, command::AddDevice::new(addr, action))
                     .await?;
-                println!("OK {:?}", reply);
+                outln!("OK {:?}", reply);
             }
 
-            Self::Remove {
-                address,
-                bredr,
-                le,
-                random,
-            } => {
-                let addr_type = match (bredr, le, random) {
-                    (true, false, false) | (false, false, false) => packet::AddressType::BrEdr,
-                    (false, true, false) => packet::AddressType::LePublic,
-                    (false, false, true) | (false, true, true) => packet::AddressType::LeRandom,
-                    _ => unreachable!(),
-                };
-                let addr = join(address, &AddressType(addr_type));
+            Self::Remove { address } => {
+                let addr = address.resolve(client, index).await?;
                 let reply = client.call(index, command::RemoveDevice::new(addr)).await?;
-                println!("OK {:?}", reply);
+                outln!("OK {:?}", reply);
             }
 
-            Self::Block {
-                address,
-                bredr,
-                le,
-                random,
-            } => {
-                let addr_type = match (bredr, le, random) {
-                    (true, false, false) | (false, false, false) => packet::AddressType::BrEdr,
-                    (false, true, false) => packet::AddressType::LePublic,
-                    (false, false, true) | (false, true, true) => packet::AddressType::LeRandom,
-                    _ => unreachable!(),
-                };
-                let addr = join(address, &AddressType(addr_type));
+            Self::Block { address } => {
+                let addr = address.resolve(client, index).await?;
                 let reply = client.call(index, command::BlockDevice::new(addr)).await?;
-                println!("OK {:?}", reply);
+                outln!("OK {:?}", reply);
             }
 
-            Self::Unblock {
-                address,
-                bredr,
-                le,
-                random,
-            } => {
-                let addr_type = match (bredr, le, random) {
-                    (true, false, false) | (false, false, false) => packet::AddressType::BrEdr,
-                    (false, true, false) => packet::AddressType::LePublic,
-                    (false, false, true) | (false, true, true) => packet::AddressType::LeRandom,
-                    _ => unreachable!(),
-                };
-                let addr = join(address, &AddressType(addr_type));
+            Self::Unblock { address } => {
+                let addr = address.resolve(client, index).await?;
                 let reply = client
                     .call(index, command::UnblockDevice::new(addr))
                     .await?;
-                println!("OK {:?}", reply);
+                outln!("OK {:?}", reply);
             }
 
             Self::Pair {
                 address,
-                bredr,
-                le,
-                random,
                 display_only,
                 display_yesno,
                 keyboard_only,
                 no_input_no_output,
                 keyboard_display,
             } => {
-                let addr_type = match (bredr, le, random) {
-                    (true, false, false) | (false, false, false) => packet::AddressType::BrEdr,
-                    (false, true, false) => packet::AddressType::LePublic,
-                    (false, false, true) | (false, true, true) => packet::AddressType::LeRandom,
-                    _ => unreachable!(),
-                };
+                let addr = address.resolve(client, index).await?;
                 let capability = match (
                     display_only,
                     display_yesno,
@@ -979,96 +1437,166 @@ impl DeviceCommand {
                     (false, false, false, false, true) => packet::IoCapability::KeyboardDisplay,
                     _ => unreachable!(),
                 };
-                let addr = join(address, &AddressType(addr_type));
                 let reply = client
                     .call(index, command::PairDevice::new(addr, capability))
                     .await?;
-                println!("OK {:?}", reply);
+                outln!("OK {:?}", reply);
             }
 
-            Self::CancelPair {
-                address,
-                bredr,
-                le,
-                random,
-            } => {
-                let addr_type = match (bredr, le, random) {
-                    (true, false, false) | (false, false, false) => packet::AddressType::BrEdr,
-                    (false, true, false) => packet::AddressType::LePublic,
-                    (false, false, true) | (false, true, true) => packet::AddressType::LeRandom,
-                    _ => unreachable!(),
-                };
-                let addr = join(address, &AddressType(addr_type));
+            Self::CancelPair { address } => {
+                let addr = address.resolve(client, index).await?;
                 let reply = client
                     .call(index, command::CancelPairDevice::new(addr))
                     .await?;
-                println!("OK {:?}", reply);
+                outln!("OK {:?}", reply);
             }
 
             Self::Unpair {
                 address,
-                bredr,
-                le,
-                random,
                 disconnect,
             } => {
-                let addr_type = match (bredr, le, random) {
-                    (true, false, false) | (false, false, false) => packet::AddressType::BrEdr,
-                    (false, true, false) => packet::AddressType::LePublic,
-                    (false, false, true) | (false, true, true) => packet::AddressType::LeRandom,
-                    _ => unreachable!(),
-                };
-                let addr = join(address, &AddressType(addr_type));
+                let addr = address.resolve(client, index).await?;
                 let reply = client
                     .call(index, command::UnpairDevice::new(addr, *disconnect))
                     .await?;
-                println!("OK {:?}", reply);
+                outln!("OK {:?}", reply);
+            }
+
+            Self::Pincode { address, pin } => {
+                let addr = address.resolve(client, index).await?;
+                let reply = client
+                    .call(index, command::PinCodeReply::new(addr, pin.len, pin.code))
+                    .await?;
+                outln!("OK {}", reply.address());
+            }
+
+            Self::PincodeNegative { address } => {
+                let addr = address.resolve(client, index).await?;
+                let reply = client
+                    .call(index, command::PinCodeNegativeReply::new(addr))
+                    .await?;
+                outln!("OK {}", reply.address());
+            }
+
+            Self::Confirm { address } => {
+                let addr = address.resolve(client, index).await?;
+                let reply = client
+                    .call(index, command::UserConfirmationReply::new(addr))
+                    .await?;
+                outln!("OK {}", reply.address());
+            }
+
+            Self::ConfirmNegative { address } => {
+                let addr = address.resolve(client, index).await?;
+                let reply = client
+                    .call(index, command::UserConfirmationNegativeReply::new(addr))
+                    .await?;
+                outln!("OK {}", reply.address());
+            }
+
+            Self::Passkey { address, passkey } => {
+                let addr = address.resolve(client, index).await?;
+                let reply = client
+                    .call(index, command::UserPasskeyReply::new(addr, *passkey))
+                    .await?;
+                outln!("OK {}", reply.address());
             }
+
+            Self::PasskeyNegative { address } => {
+                let addr = address.resolve(client, index).await?;
+                let reply = client
+                    .call(index, command::UserPasskeyNegativeReply::new(addr))
+                    .await?;
+                outln!("OK {}", reply.address());
+            }
+
+            Self::Flags { command } => command.proc(client, index).await?,
         };
         Ok(())
     }
 }
 
 #[derive(Debug, Subcommand)]
-enum OobCommand {
-    Add {
-        #[clap(long, short)]
-        address: packet::BdAddr,
+enum DeviceFlagsCommand {
+    /// Print the supported and current device flags.
+    Get {
+        #[clap(flatten)]
+        address: DeviceAddress,
+    },
 
-        #[clap(long, short, conflicts_with_all=&["le", "random"])]
-        bredr: bool,
+    /// Change the device flags, keeping the others.
+    Set {
+        #[clap(flatten)]
+        address: DeviceAddress,
 
-        #[clap(long, short, conflicts_with = "bredr")]
-        le: bool,
+        #[clap(long)]
+        remote_wakeup: OnOff,
+    },
+}
+
+impl DeviceFlagsCommand {
+    async fn proc(&self, client: &Client, index: u16) -> anyhow::Result<()> {
+        match self {
+            Self::Get { address } => {
+                let addr = address.resolve(client, index).await?;
+                let reply = client
+                    .call(index, command::GetDeviceFlag::new(addr))
+                    .await?;
+                outln!("supported: {:?}", reply.supported_flags());
+                outln!("current: {:?}", reply.current_flags());
+            }
+
+            Self::Set {
+                address,
+                remote_wakeup,
+            } => {
+                let addr = address.resolve(client, index).await?;
+                let reply = client
+                    .call(index, command::GetDeviceFlag::new(addr.clone()))
+                    .await?;
+                let wakeup = packet::DeviceFlags::RemoteWakeupEnabled;
+                let enable = matches!(remote_wakeup, OnOff::On);
+                if enable && !reply.supported_flags().contains(wakeup) {
+                    return Err(btmgmt::Error::DeviceFlagNotSupported {
+                        address: addr,
+                        flags: wakeup,
+                    }
+                    .into());
+                }
+                let mut flags = *reply.current_flags();
+                flags.set(wakeup, enable);
+                client
+                    .call(index, command::SetDeviceFlag::new(addr, flags))
+                    .await?;
+                outln!("current: {:?}", flags);
+            }
+        };
+        Ok(())
+    }
+}
 
-        #[clap(long, short, conflicts_with = "bredr")]
-        random: bool,
+#[derive(Debug, Subcommand)]
+enum OobCommand {
+    Add {
+        #[clap(flatten)]
+        address: DeviceAddress,
 
-        #[clap(long, validator=length(32))]
+        #[clap(long, validator=length(16))]
         hash192: HexBinary,
 
-        #[clap(long, validator=length(32))]
+        #[clap(long, validator=length(16))]
         randomizer192: HexBinary,
 
-        #[clap(long, validator=length(32))]
+        #[clap(long, validator=length(16))]
         hash256: Option<HexBinary>,
 
-        #[clap(long, validator=length(32))]
+        #[clap(long, validator=length(16))]
         randomizer256: Option<HexBinary>,
     },
 
     Remove {
-        #[clap(long, short)]
-        address: packet::BdAddr,
-
-        #[clap(long, short, conflicts_with_all=&["le", "random"])]
-        bredr: bool,
-
-        #[clap(long, short, conflicts_with = "bredr")]
-        le: bool,
-
-        #[clap(long, short, conflicts_with = "bredr")]
-        random: bool,
+        #[clap(flatten)]
+        address: DeviceAddress,
     },
 
     Read {
@@ -1084,13 +1612,10 @@ enum OobCommand {
 }
 
 impl OobCommand {
-    async fn proc(&self, client: &Client, index: u16) -> anyhow::Result<()> {
+    async fn proc(&self, client: &Client, index: u16, output: Output) -> anyhow::Result<()> {
         match self {
             Self::Add {
                 address,
-                bredr,
-                le,
-                random,
                 hash192,
                 randomizer192,
                 hash256,
@@ -1101,13 +1626,7 @@ impl OobCommand {
                     v.copy_from_slice(&b);
                     v
                 };
-                let addr_type = match (bredr, le, random) {
-                    (true, false, false) | (false, false, false) => packet::AddressType::BrEdr,
-                    (false, true, false) => packet::AddressType::LePublic,
-                    (false, false, true) | (false, true, true) => packet::AddressType::LeRandom,
-                    _ => unreachable!(),
-                };
-                let addr = join(address, &AddressType(addr_type));
+                let addr = address.resolve(client, index).await?;
                 let hash192 = into_array(hash192.0.clone());
                 let randomizer192 = into_array(randomizer192.0.clone());
                 let hash256 = hash256.as_ref().map(|b| into_array(b.0.clone()));
@@ -1124,26 +1643,15 @@ impl OobCommand {
                         ),
                     )
                     .await?;
-                println!("OK {:?}", reply);
+                outln!("OK {:?}", reply);
             }
 
-            Self::Remove {
-                address,
-                bredr,
-                le,
-                random,
-            } => {
-                let addr_type = match (bredr, le, random) {
-                    (true, false, false) | (false, false, false) => packet::AddressType::BrEdr,
-                    (false, true, false) => packet::AddressType::LePublic,
-                    (false, false, true) | (false, true, true) => packet::AddressType::LeRandom,
-                    _ => unreachable!(),
-                };
-                let addr = join(address, &AddressType(addr_type));
+            Self::Remove { address } => {
+                let addr = address.resolve(client, index).await?;
                 let reply = client
                     .call(index, command::RemoveRemoteOutOfBandData::new(addr))
                     .await?;
-                println!("OK {:?}", reply);
+                outln!("OK {:?}", reply);
             }
 
             Self::Read {
@@ -1169,10 +1677,33 @@ impl OobCommand {
                             command::ReadLocalOutOfBandExtendedData::new(addr_type),
                         )
                         .await?;
-                    println!("OK {:?}", reply);
+                    output.print::<command::ReadLocalOutOfBandExtendedData>(
+                        Some(index),
+                        &reply,
+                        |reply| {
+                            outln!(
+                                "address types: {}",
+                                format_address_types(reply.address_type())
+                            );
+                            outln!("eir data: {}", hex(reply.eir_data()));
+                        },
+                    )?;
                 } else {
                     let reply = client.call(index, command::ReadLocalOutOfBandData).await?;
-                    println!("OK {:?}", reply);
+                    output.print::<command::ReadLocalOutOfBandData>(
+                        Some(index),
+                        &reply,
+                        |reply| {
+                            outln!("hash192: {}", hex(reply.hash192()));
+                            outln!("randomizer192: {}", hex(reply.randomizer192()));
+                            if let Some(hash256) = reply.hash256() {
+                                outln!("hash256: {}", hex(hash256));
+                            }
+                            if let Some(randomizer256) = reply.randomizer256() {
+                                outln!("randomizer256: {}", hex(randomizer256));
+                            }
+                        },
+                    )?;
                 }
             }
         };
@@ -1197,6 +1728,131 @@ impl FromStr for OnOff {
     }
 }
 
+#[derive(Debug)]
+enum Phy {
+    Le1M,
+    Le2M,
+    LeCoded,
+}
+
+impl FromStr for Phy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1m" => Ok(Self::Le1M),
+            "2m" => Ok(Self::Le2M),
+            "coded" => Ok(Self::LeCoded),
+            v => Err(v.into()),
+        }
+    }
+}
+
+/// PHY names, the dashed lowercase form of [`packet::Phys`] constants.
+const PHY_NAMES: &[(&str, packet::Phys)] = &[
+    ("br-1m-1slot", packet::Phys::Br1M1Slot),
+    ("br-1m-3slot", packet::Phys::Br1M3Slot),
+    ("br-1m-5slot", packet::Phys::Br1M5Slot),
+    ("edr-2m-1slot", packet::Phys::Edr2M1Slot),
+    ("edr-2m-3slot", packet::Phys::Edr2M3Slot),
+    ("edr-2m-5slot", packet::Phys::Edr2M5Slot),
+    ("edr-3m-1slot", packet::Phys::Edr3M1Slot),
+    ("edr-3m-3slot", packet::Phys::Edr3M3Slot),
+    ("edr-3m-5slot", packet::Phys::Edr3M5Slot),
+    ("le-1m-tx", packet::Phys::Le1MTx),
+    ("le-1m-rx", packet::Phys::Le1MRx),
+    ("le-2m-tx", packet::Phys::Le2MTx),
+    ("le-2m-rx", packet::Phys::Le2MRx),
+    ("le-coded-tx", packet::Phys::LeCodedTx),
+    ("le-coded-rx", packet::Phys::LeCodedRx),
+];
+
+/// One of [`PHY_NAMES`].
+#[derive(Debug, Clone)]
+struct PhyName(packet::Phys);
+
+impl FromStr for PhyName {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match PHY_NAMES.iter().find(|(name, _)| *name == s) {
+            Some((_, phy)) => Ok(Self(*phy)),
+            None => Err(format!(
+                "unknown PHY {:?}, expected one of {}",
+                s,
+                PHY_NAMES
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+/// Names of [`PHY_NAMES`], `-` if none.
+fn format_phys(phys: &packet::Phys) -> String {
+    let names = PHY_NAMES
+        .iter()
+        .filter(|(_, phy)| phys.contains(*phy))
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>();
+    if names.is_empty() {
+        return "-".into();
+    }
+    names.join(" ")
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Output {
+    Text,
+    Json,
+}
+
+impl FromStr for Output {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            v => Err(v.into()),
+        }
+    }
+}
+
+impl Output {
+    /// Print reply of `C` by `text`, or as JSON envelope.
+    fn print<C>(
+        self,
+        index: Option<u16>,
+        reply: &C::Reply,
+        text: impl FnOnce(&C::Reply),
+    ) -> anyhow::Result<()>
+    where
+        C: CommandRequest,
+        C::Reply: Serialize,
+    {
+        match self {
+            Self::Text => text(reply),
+            Self::Json => outln!("{}", envelope::<C>(index, reply)?),
+        }
+        Ok(())
+    }
+}
+
+/// `{ "command": .., "index": .., "result": .. }`
+///
+/// `index` is `null` for commands not bound to a controller.
+fn envelope<C>(index: Option<u16>, reply: &C::Reply) -> anyhow::Result<serde_json::Value>
+where
+    C: CommandRequest,
+    C::Reply: Serialize,
+{
+    Ok(serde_json::json!({
+        "command": C::NAME,
+        "index": index,
+        "result": serde_json::to_value(reply)?,
+    }))
+}
+
 #[derive(Debug)]
 enum Discoerable {
     On,
@@ -1231,54 +1887,236 @@ impl FromStr for AddressType {
     }
 }
 
+/// PIN bytes, zero padded.
+#[derive(Debug, Clone)]
+struct PinCode {
+    len: u8,
+    code: [u8; 16],
+}
+
+impl FromStr for PinCode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s.as_bytes();
+        if bytes.len() > 16 {
+            return Err(format!("PIN is {} bytes, up to 16", bytes.len()));
+        }
+        let mut code = [0; 16];
+        code[..bytes.len()].copy_from_slice(bytes);
+        Ok(Self {
+            len: bytes.len() as u8,
+            code,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 struct HexBinary(Vec<u8>);
 
 impl FromStr for HexBinary {
     type Err = anyhow::Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.len().is_multiple_of(2) {
+            anyhow::bail!("odd number of hex digits: {}", s);
+        }
         let v = (0..s.len())
-            .map(|i| u8::from_str_radix(&s[i..i + 1], 16))
+            .step_by(2)
+            .map(|i| u8::from_str_radix(s.get(i..i + 2).unwrap_or("-"), 16))
             .collect::<Result<Vec<u8>, _>>()?;
         Ok(Self(v))
     }
 }
 
-/*
-#[derive(Debug)]
-struct CoD(btmgmt::ClassOfDevice);
-
-impl FromStr for CoD {
-type Err = String;
-fn from_str(s: &str) -> Result<Self, Self::Err> {
-let maybe_values = [s.get(0..2), s.get(2..4), s.get(4..6)]
-.iter()
-.map(|e| e.and_then(|v| u8::from_str(v).ok()))
-.collect::<Option<Vec<_>>>();
-if let Some(&[i1, i2, i3]) = maybe_values.as_deref() {
-Ok(Self([i1, i2, i3].into()))
-} else {
-Err("invalid format".into())
+fn hex(v: &[u8]) -> String {
+    v.iter().map(|b| format!("{:02x}", b)).collect()
 }
+
+/// Space separated, in the order of the bitmask.
+fn format_address_types(types: &packet::AddressTypes) -> String {
+    [
+        packet::AddressType::BrEdr,
+        packet::AddressType::LePublic,
+        packet::AddressType::LeRandom,
+    ]
+    .iter()
+    .filter(|t| types.iter().any(|included| included == *t))
+    .map(|t| format!("{:?}", t))
+    .collect::<Vec<_>>()
+    .join(" ")
 }
+
+/// Decimal, or hex with `0x` prefix.
+fn parse_u16(s: &str) -> Result<u16, std::num::ParseIntError> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
 }
-*/
 
 fn handle_event(index: packet::ControllerIndex, event: Event) {
-    println!("{:?} {:?}", index, event);
+    outln!("{}", format_event(&index, &event));
+}
+
+/// Row of `controller ls`. Unknown type / bus are `-`.
+fn format_summary(summary: &btmgmt::ControllerSummary) -> String {
+    let controller_type = match summary.controller_type() {
+        Some(packet::ControllerType::PrimaryController) => "primary",
+        Some(packet::ControllerType::UnconfiguredController) => "unconfigured",
+        Some(packet::ControllerType::AlternateMacPhyController) => "amp",
+        None => "-",
+    };
+    let bus = summary
+        .bus()
+        .map(|bus| format!("{:?}", bus).to_lowercase())
+        .unwrap_or_else(|| "-".into());
+    let powered = summary
+        .current_settings()
+        .contains(packet::Settings::Powered);
+    format!(
+        "{:<6} {:<17} {:<12} {:<7} {:<7} {:<7} {:<40} {}",
+        u16::from(summary.index().clone()),
+        summary.address().to_string(),
+        controller_type,
+        bus,
+        summary.bluetooth_version(),
+        if powered { "yes" } else { "no" },
+        summary.manufacturer().to_string(),
+        summary.name().to_string_lossy()
+    )
+}
+
+/// Event for monitoring. Discovering is one line per transport.
+fn format_event(index: &packet::ControllerIndex, event: &Event) -> String {
+    match event {
+        Event::Discovering(discovering) => {
+            let state = if *discovering.discovering() {
+                "started"
+            } else {
+                "stopped"
+            };
+            let mut lines = vec![];
+            if discovering.is_bredr() {
+                lines.push(format!("{:?} discovering (BR/EDR) {}", index, state));
+            }
+            if discovering.is_le() {
+                lines.push(format!("{:?} discovering (LE) {}", index, state));
+            }
+            if lines.is_empty() {
+                lines.push(format!("{:?} {:?}", index, event));
+            }
+            lines.join("\n")
+        }
+        Event::DeviceConnected(connected) => {
+            let mut line = format!("{:?} connected {}", index, connected.address());
+            if let Some(name) = connected.name() {
+                line.push_str(&format!(" {:?}", name));
+            }
+            if !connected.flags().is_empty() {
+                line.push_str(&format!(" {:?}", connected.flags()));
+            }
+            line
+        }
+        Event::UserConfirmationRequest(request) => {
+            let addr = request.address();
+            let mut line = format!("{:?} confirm {}", index, addr);
+            if let packet::ConfirmHint::Full = request.confirm_hint() {
+                line.push_str(&format!(" {:06}", u32::from_le_bytes(*request.value())));
+            }
+            line.push_str(&format!(
+                " (device confirm -a {} {})",
+                addr.clone().into_bd_addr(),
+                address_flag(&addr)
+            ));
+            line
+        }
+        Event::UserPasskeyRequest(request) => {
+            let addr = request.address();
+            format!(
+                "{:?} passkey requested {} (device passkey -a {} {} <passkey>)",
+                index,
+                addr,
+                addr.clone().into_bd_addr(),
+                address_flag(&addr)
+            )
+        }
+        event => format!("{:?} {:?}", index, event),
+    }
+}
+
+/// `device` subcommand flag of the address type.
+fn address_flag(addr: &packet::Address) -> &'static str {
+    match addr.address_type() {
+        packet::AddressType::BrEdr => "-b",
+        packet::AddressType::LePublic => "-l",
+        packet::AddressType::LeRandom => "-r",
+    }
+}
+
+/// Map error to exit code. (see sysexits.h)
+fn exit_code(err: &anyhow::Error) -> i32 {
+    let err = match err.downcast_ref::<btmgmt::Error>() {
+        Some(err) => err,
+        None => return 1,
+    };
+    match err {
+        btmgmt::Error::NoAdapters => 4,
+        btmgmt::Error::NotSupportedByKernel(..) => 69, // EX_UNAVAILABLE
+        err if err.is_permission_denied() => 77,       // EX_NOPERM
+        btmgmt::Error::Io(err) if err.kind() == std::io::ErrorKind::PermissionDenied => 77, // EX_NOPERM
+        btmgmt::Error::Io(..) | btmgmt::Error::Closed => 74, // EX_IOERR
+        btmgmt::Error::Timeout => 75,                        // EX_TEMPFAIL
+        err if err.is_retryable() => 75,                     // EX_TEMPFAIL
+        btmgmt::Error::Pack(..)
+        | btmgmt::Error::Unexpected(..)
+        | btmgmt::Error::HasRemaining(..) => 76, // EX_PROTOCOL
+        _ => 1,
+    }
+}
+
+fn print_supported_commands(reply: &command::ReadManagementSupportedCommandsReply) {
+    outln!("commands");
+    for command in reply.commands() {
+        let name = command::all_commands()
+            .iter()
+            .find(|(code, _)| code == command)
+            .map_or("?", |(_, name)| name);
+        outln!("  {:#06x} {}", command.clone() as u16, name);
+    }
+    outln!("events");
+    for event in reply.events() {
+        let name = event::all_events()
+            .iter()
+            .find(|(code, _)| code == event)
+            .map_or("?", |(_, name)| name);
+        outln!("  {:#06x} {}", event.clone() as u16, name);
+    }
 }
 
 #[tokio::main(flavor = "current_thread")]
-async fn main() -> anyhow::Result<()> {
+async fn main() {
     pretty_env_logger::init();
 
     let opt = Opt::parse();
+    if let Err(err) = run(opt).await {
+        match err.downcast_ref::<btmgmt::Error>() {
+            Some(btmgmt::Error::NoAdapters) => eprintln!("{}", err),
+            _ => eprintln!("Error: {:?}", err),
+        }
+        std::process::exit(exit_code(&err));
+    }
+}
 
+async fn run(opt: Opt) -> anyhow::Result<()> {
     let index = opt.index;
+    let output = opt.output;
     let listen = opt.listen || opt.command.is_none();
 
     let client = Client::open()?;
 
+    if let Some(Command::Repl { index: repl_index }) = opt.command {
+        return repl::run(&client, repl_index.unwrap_or(index), output).await;
+    }
+
     let mut events = client.events().await;
     let listen_task = tokio::spawn(async move {
         while let Some((index, event)) = events.next().await {
@@ -1287,46 +2125,1132 @@ async fn main() -> anyhow::Result<()> {
     });
 
     if let Some(command) = opt.command {
-        match command {
-            Command::Version => {
-                let reply = client
-                    .call(None, command::ReadManagementVersionInformation)
-                    .await?;
-                println!("{}.{}", reply.version(), reply.revision());
+        if let Err(err) = dispatch(&client, command, index, output).await {
+            return Err(no_adapters(&client, err).await);
+        }
+    }
+
+    if listen {
+        listen_task.await?;
+    }
+
+    Ok(())
+}
+
+async fn dispatch(
+    client: &Client,
+    command: Command,
+    index: u16,
+    output: Output,
+) -> anyhow::Result<()> {
+    match command {
+        Command::Version => {
+            let reply = client
+                .call(None, command::ReadManagementVersionInformation)
+                .await?;
+            output.print::<command::ReadManagementVersionInformation>(None, &reply, |reply| {
+                outln!("{}.{}", reply.version(), reply.revision());
+            })?;
+        }
+
+        Command::SupportedCommands => {
+            let reply = client
+                .call(None, command::ReadManagementSupportedCommands)
+                .await?;
+            output.print::<command::ReadManagementSupportedCommands>(
+                None,
+                &reply,
+                print_supported_commands,
+            )?;
+        }
+
+        Command::Controller { command } => {
+            command
+                .unwrap_or_default()
+                .proc(client, index, output)
+                .await?
+        }
+        Command::Key { command } => command.proc(client, index).await?,
+        Command::Connection { command } => {
+            command
+                .unwrap_or_default()
+                .proc(client, index, output)
+                .await?
+        }
+        Command::Discovery { command } => command.proc(client, index).await?,
+        Command::Configuration { command } => command.proc(client, index, output).await?,
+        Command::Advertise { command } => command.proc(client, index, output).await?,
+        Command::Device { command } => command.proc(client, index).await?,
+        Command::Oob { command } => command.proc(client, index, output).await?,
+        Command::Repl { .. } => anyhow::bail!("already in repl"),
+    };
+    Ok(())
+}
+
+/// Replace `InvalidIndex` by [`btmgmt::Error::NoAdapters`] if there is no controller at all.
+async fn no_adapters(client: &Client, err: anyhow::Error) -> anyhow::Error {
+    let invalid_index = matches!(
+        err.downcast_ref::<btmgmt::Error>()
+            .and_then(btmgmt::Error::status),
+        Some(packet::ErrorCode::InvalidIndex)
+    );
+    if invalid_index {
+        if let Ok(list) = client.call(None, command::ReadControllerIndexList).await {
+            if list.iter().next().is_none() {
+                return btmgmt::Error::NoAdapters.into();
             }
+        }
+    }
+    err
+}
 
-            Command::SupportedCommands => {
-                let reply = client
-                    .call(None, command::ReadManagementSupportedCommands)
-                    .await?;
-                println!("commands");
-                for command in reply.commands() {
-                    println!("  {:?}", command);
-                }
-                println!("events");
-                for event in reply.events() {
-                    println!("  {:?}", event);
-                }
+#[cfg(test)]
+mod tests {
+    use btmgmt::packet::Unpack;
+    use serde_json::json;
+
+    use super::*;
+
+    /// Envelope of canned reply parameters.
+    fn json<C>(index: Option<u16>, mut params: &[u8]) -> serde_json::Value
+    where
+        C: CommandRequest,
+        C::Reply: Serialize,
+    {
+        let reply = C::Reply::unpack(&mut params).unwrap();
+        assert!(params.is_empty());
+        envelope::<C>(index, &reply).unwrap()
+    }
+
+    #[test]
+    fn test_version() {
+        let value = json::<command::ReadManagementVersionInformation>(None, &[0x01, 0x13, 0x00]);
+        assert_eq!(
+            json!({
+                "command": "ReadManagementVersionInformation",
+                "index": null,
+                "result": { "version": 1, "revision": 19 },
+            }),
+            value
+        );
+    }
+
+    #[test]
+    fn test_supported_commands() {
+        let value = json::<command::ReadManagementSupportedCommands>(
+            None,
+            &[0x02, 0x00, 0x01, 0x00, 0x01, 0x00, 0x04, 0x00, 0x06, 0x00],
+        );
+        assert_eq!("ReadManagementSupportedCommands", value["command"]);
+        assert_eq!(
+            json!({
+                "commands": ["ReadManagementVersionInformation", "ReadControllerInformation"],
+                "events": ["NewSettings"],
+            }),
+            value["result"]
+        );
+    }
+
+    #[test]
+    fn test_controller_show() {
+        let (bytes, _) = btmgmt::packet::fixtures::controller_information_reply();
+        let value = json::<command::ReadControllerInformation>(Some(0), &bytes[9..]);
+        assert_eq!("ReadControllerInformation", value["command"]);
+        assert_eq!(0, value["index"]);
+        let result = &value["result"];
+        assert_eq!("00:11:22:33:44:55", result["address"]);
+        assert_eq!(9, result["bluetooth_version"]);
+        assert_eq!(2, result["manufacturer"]);
+        assert_eq!(
+            json!(["Powered", "BasicRateEnhancedDataRate", "LowEnergy"]),
+            result["current_settings"]
+        );
+        assert_eq!("btmgmt", result["name"]);
+        assert_eq!("bt", result["short_name"]);
+    }
+
+    #[test]
+    fn test_controller_config_info() {
+        let value = json::<command::ReadControllerConfigurationInformation>(
+            Some(0),
+            &[0x02, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00],
+        );
+        assert_eq!(
+            json!({
+                "command": "ReadControllerConfigurationInformation",
+                "index": 0,
+                "result": {
+                    "manufacture": 2,
+                    "supported_options": ["ExternalConfiguration", "BluetoothPublicAddressConfiguration"],
+                    "missing_options": ["BluetoothPublicAddressConfiguration"],
+                },
+            }),
+            value
+        );
+    }
+
+    #[test]
+    fn test_controller_ls() {
+        let value =
+            json::<command::ReadControllerIndexList>(None, &[0x02, 0x00, 0x00, 0x00, 0x01, 0x00]);
+        assert_eq!(
+            json!({ "command": "ReadControllerIndexList", "index": null, "result": [0, 1] }),
+            value
+        );
+    }
+
+    #[test]
+    fn test_controller_ls_extended() {
+        let value = json::<command::ReadExtendedControllerIndexList>(
+            None,
+            &[0x01, 0x00, 0x00, 0x00, 0x00, 0x01],
+        );
+        assert_eq!("ReadExtendedControllerIndexList", value["command"]);
+        assert_eq!(json!([[0, "PrimaryController", "Usb"]]), value["result"]);
+    }
+
+    #[test]
+    fn test_connection_ls() {
+        let value = json::<command::GetConnections>(
+            Some(0),
+            &[0x01, 0x00, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x01],
+        );
+        assert_eq!("GetConnections", value["command"]);
+        assert_eq!(
+            json!([{ "address": "00:11:22:33:44:55", "address_type": "LePublic" }]),
+            value["result"]
+        );
+    }
+
+    #[test]
+    fn test_configuration_system_get() {
+        let value = json::<command::ReadDefaultSystemConfiguration>(
+            Some(0),
+            &[0x0A, 0x00, 0x02, 0xA0, 0x00],
+        );
+        assert_eq!("ReadDefaultSystemConfiguration", value["command"]);
+        assert_eq!(
+            json!([{ "LEAdvertisementMinInterval": 160 }]),
+            value["result"]
+        );
+    }
+
+    #[test]
+    fn test_configuration_system_get_raw() {
+        let value = json::<command::ReadDefaultSystemConfiguration>(
+            Some(0),
+            &[0x0A, 0x00, 0x02, 0xA0, 0x00, 0x00, 0x01, 0x02, 0x12, 0x34],
+        );
+        assert_eq!(
+            json!([
+                { "LEAdvertisementMinInterval": 160 },
+                { "Raw": { "param_type": 256, "value": [0x12, 0x34] } },
+            ]),
+            value["result"]
+        );
+    }
+
+    #[test]
+    fn test_configuration_system_set_raw_args() {
+        let opts = Opt::try_parse_from([
+            "btmgmt",
+            "configuration",
+            "system",
+            "set-raw",
+            "--type",
+            "0x0100",
+            "--value",
+            "1234",
+        ])
+        .unwrap();
+        let debug = format!("{:?}", opts);
+        assert!(debug.contains("param_type: 256"), "{}", debug);
+        assert!(debug.contains("[18, 52]"), "{}", debug);
+
+        assert!("123".parse::<HexBinary>().is_err());
+        assert!("zz".parse::<HexBinary>().is_err());
+    }
+
+    #[test]
+    fn test_time_flags_help() {
+        for args in [
+            &["btmgmt", "controller", "discoverable", "--help"][..],
+            &["btmgmt", "advertise", "add", "--help"],
+        ] {
+            let help = Opt::try_parse_from(args).unwrap_err().to_string();
+            assert!(help.contains("Timeout in seconds"), "{}", help);
+        }
+    }
+
+    #[test]
+    fn test_cod_raw_args() {
+        for arg in ["5A020C", "0x5a020c"] {
+            let opts = Opt::try_parse_from(["btmgmt", "controller", "cod-raw", arg]).unwrap();
+            let debug = format!("{:?}", opts);
+            assert!(debug.contains("ClassOfDevice([12, 2, 90])"), "{}", debug);
+        }
+
+        for arg in ["5a02", "0x5a02", "5a020g"] {
+            let err = Opt::try_parse_from(["btmgmt", "controller", "cod-raw", arg])
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("expected six hex digits"), "{}", err);
+        }
+    }
+
+    const LINK_KEYS: &str = "# address keytype hex16 pinlen
+00:11:22:33:44:55 4 00112233445566778899aabbccddeeff 0
+
+AA:BB:CC:DD:EE:FF 0x00 ffeeddccbbaa99887766554433221100 4
+";
+
+    const LONG_TERM_KEYS: &str = "# address addrtype keytype master|slave encsize ediv hex8 hex16
+00:11:22:33:44:55 le_public 1 master 16 0x1234 0011223344556677 00112233445566778899aabbccddeeff
+
+AA:BB:CC:DD:EE:FF le_random 0x00 slave 7 0 ffeeddccbbaa9988 00000000000000000000000000000000
+";
+
+    #[test]
+    fn test_parse_long_term_keys() {
+        let keys = parse_long_term_keys(LONG_TERM_KEYS).unwrap();
+        assert_eq!(2, keys.len());
+        assert_eq!(
+            "00:11:22:33:44:55"
+                .parse::<packet::BdAddr>()
+                .unwrap()
+                .to_le_public_addr(),
+            keys[0].address()
+        );
+        assert!(matches!(
+            keys[0].key_type(),
+            packet::LongTermKeyType::AuthenticatedKey
+        ));
+        assert!(*keys[0].master());
+        assert!(!*keys[1].master());
+        assert_eq!(16, *keys[0].encryption_size());
+        assert_eq!(0x1234, *keys[0].encryption_diversifier());
+        assert_eq!(
+            &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77],
+            keys[0].random_number()
+        );
+        assert_eq!(0xFF, keys[0].value()[15]);
+        assert_eq!(0xFF, keys[1].random_number()[0]);
+        assert_eq!(
+            "AA:BB:CC:DD:EE:FF"
+                .parse::<packet::BdAddr>()
+                .unwrap()
+                .to_le_random_addr(),
+            keys[1].address()
+        );
+
+        for (text, expected) in [
+            (
+                "00:11:22:33:44:55 le_public 1 central 16 0 0011223344556677 00112233445566778899aabbccddeeff",
+                "line 1: expected master or slave",
+            ),
+            (
+                "00:11:22:33:44:55 le_public 1 master 16 0 00112233445566 00112233445566778899aabbccddeeff",
+                "line 1: expected 8 bytes",
+            ),
+            (
+                "00:11:22:33:44:55 le_public 1 master 16 0 0011223344556677 00112233445566778899aabbccddee",
+                "line 1: expected 16 bytes",
+            ),
+            (
+                "\n00:11:22:33:44:55 le_public 1 master 16 0 0011223344556677",
+                "line 2: missing value column",
+            ),
+            (
+                "00:11:22:33:44:55 le_public 1 master",
+                "line 1: missing encryption_size column",
+            ),
+            ("00:11:22:33:44:55", "line 1: missing address_type column"),
+            (
+                "00:11:22:33:44:55 bredr_le 1 master",
+                "line 1: invalid address type",
+            ),
+            (
+                "00:11:22:33:44:55 le_public 1 master 16 0 0011223344556677 00000000000000000000000000000000",
+                "line 1: key is all zeros",
+            ),
+            (
+                "00:11:22:33:44:55 le_public 1 master 16 0 0011223344556677 00112233445566778899aabbccddeeff 0",
+                "line 1: unexpected field",
+            ),
+        ] {
+            let err = format!("{:#}", parse_long_term_keys(text).unwrap_err());
+            assert!(err.starts_with(expected), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_parse_link_keys() {
+        let keys = parse_link_keys(LINK_KEYS).unwrap();
+        assert_eq!(2, keys.len());
+        assert_eq!(
+            "00:11:22:33:44:55"
+                .parse::<packet::BdAddr>()
+                .unwrap()
+                .to_br_edr_addr(),
+            keys[0].address()
+        );
+        assert!(matches!(
+            keys[0].key_type(),
+            packet::LinkKeyType::UnauthenticatedCombinationkeyfromP192
+        ));
+        assert_eq!(0x00, keys[0].value()[0]);
+        assert_eq!(0xFF, keys[1].value()[0]);
+        assert_eq!(4, *keys[1].pin_length());
+        assert!(parse_link_keys("").unwrap().is_empty());
+
+        for (text, expected) in [
+            (
+                "00:11:22:33:44:5g 4 00112233445566778899aabbccddeeff 0",
+                "line 1: invalid address",
+            ),
+            (
+                "\n00:11:22:33:44:55 4 00112233445566778899aabbccddee 0",
+                "line 2: expected 16 bytes",
+            ),
+            (
+                "00:11:22:33:44:55 4 00112233445566778899aabbccddeeff00 0",
+                "line 1: expected 16 bytes",
+            ),
+            (
+                "00:11:22:33:44:55 4 00112233445566778899aabbccddeefg 0",
+                "line 1: invalid digit",
+            ),
+            (
+                "00:11:22:33:44:55 9 00112233445566778899aabbccddeeff 0",
+                "line 1:",
+            ),
+            (
+                "00:11:22:33:44:55 4 00112233445566778899aabbccddeeff",
+                "line 1: expected `address",
+            ),
+        ] {
+            let err = format!("{:#}", parse_link_keys(text).unwrap_err());
+            assert!(err.starts_with(expected), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_add_ext_args() {
+        let opts = Opt::try_parse_from([
+            "btmgmt",
+            "adv",
+            "add-ext",
+            "--tx-power",
+            "-4",
+            "--phy",
+            "coded",
+        ])
+        .unwrap();
+        let debug = format!("{:?}", opts);
+        assert!(debug.contains("tx_power: Some(-4)"), "{}", debug);
+        assert!(debug.contains("phy: Some(LeCoded)"), "{}", debug);
+
+        // both intervals, or none.
+        assert!(
+            Opt::try_parse_from(["btmgmt", "adv", "add-ext", "--min-interval", "160"]).is_err()
+        );
+        assert!(Opt::try_parse_from(["btmgmt", "adv", "add-ext", "--phy", "3m"]).is_err());
+    }
+
+    #[test]
+    fn test_pincode_args() {
+        let opt = Opt::try_parse_from([
+            "btmgmt",
+            "device",
+            "pincode",
+            "-a",
+            "00:11:22:33:44:55",
+            "-b",
+            "0000",
+        ])
+        .unwrap();
+        match opt.command {
+            Some(Command::Device {
+                command: DeviceCommand::Pincode { pin, .. },
+            }) => {
+                assert_eq!(4, pin.len);
+                assert_eq!(b"0000", &pin.code[..4]);
+                assert_eq!([0; 12], pin.code[4..]);
             }
+            v => panic!("{:?}", v),
+        }
+
+        let pin = "1".repeat(16);
+        assert!(Opt::try_parse_from([
+            "btmgmt",
+            "device",
+            "pincode",
+            "-a",
+            "00:11:22:33:44:55",
+            &pin
+        ])
+        .is_ok());
+        let long = "1".repeat(17);
+        let err = Opt::try_parse_from([
+            "btmgmt",
+            "device",
+            "pincode",
+            "-a",
+            "00:11:22:33:44:55",
+            &long,
+        ])
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("PIN is 17 bytes, up to 16"), "{}", err);
+    }
 
-            Command::Controller { command } => {
-                command.unwrap_or_default().proc(&client, index).await?
+    #[tokio::test]
+    async fn test_address_type_flags() {
+        let mock = btmgmt::testing::MockClient::new(vec![]);
+        for (flags, expected) in [
+            (&["-b"][..], packet::AddressType::BrEdr),
+            (&["-l"][..], packet::AddressType::LePublic),
+            (&["-r"][..], packet::AddressType::LeRandom),
+            (&["-l", "-r"][..], packet::AddressType::LeRandom),
+        ] {
+            let args = ["btmgmt", "device", "remove", "-a", "00:11:22:33:44:55"];
+            let opt = Opt::try_parse_from(args.iter().chain(flags)).unwrap();
+            let address = match opt.command {
+                Some(Command::Device {
+                    command: DeviceCommand::Remove { address },
+                }) => address,
+                v => panic!("{:?}", v),
+            };
+            let addr_type = address_type(&address, mock.client(), 0).await.unwrap();
+            assert_eq!(expected, addr_type, "{:?}", flags);
+        }
+        // not inferred.
+        assert!(mock.calls().is_empty());
+
+        let args = [
+            "btmgmt",
+            "device",
+            "remove",
+            "-a",
+            "00:11:22:33:44:55",
+            "-b",
+            "-l",
+        ];
+        assert!(Opt::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_phy_names() {
+        let opt = Opt::try_parse_from([
+            "btmgmt",
+            "controller",
+            "phy",
+            "set",
+            "--phy",
+            "le-1m-tx",
+            "--phy",
+            "le-2m-rx",
+        ])
+        .unwrap();
+        match opt.command {
+            Some(Command::Controller {
+                command:
+                    Some(ControllerCommand::Phy {
+                        command: Some(PhyCommand::Set { phys }),
+                    }),
+            }) => {
+                let phys = phys.iter().fold(packet::Phys::empty(), |acc, p| acc | p.0);
+                assert_eq!(packet::Phys::Le1MTx | packet::Phys::Le2MRx, phys);
+                assert_eq!("le-1m-tx le-2m-rx", format_phys(&phys));
             }
-            Command::Key { .. } => todo!(),
-            Command::Connection { command } => {
-                command.unwrap_or_default().proc(&client, index).await?
+            v => panic!("{:?}", v),
+        }
+
+        let all = PHY_NAMES
+            .iter()
+            .fold(packet::Phys::empty(), |acc, (_, p)| acc | *p);
+        assert_eq!(packet::Phys::all(), all);
+        assert_eq!("-", format_phys(&packet::Phys::empty()));
+
+        let err = Opt::try_parse_from(["btmgmt", "controller", "phy", "set", "--phy", "le-3m"])
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("unknown PHY \"le-3m\", expected one of br-1m-1slot,"),
+            "{}",
+            err
+        );
+        assert!(err.contains("le-coded-rx"), "{}", err);
+        assert!(Opt::try_parse_from(["btmgmt", "controller", "phy", "set"]).is_err());
+    }
+
+    #[test]
+    fn test_name_args() {
+        fn name_args(args: &[&str]) -> (packet::Name, Option<packet::ShortName>) {
+            let args = ["btmgmt", "controller", "name"].iter().chain(args);
+            match Opt::try_parse_from(args).unwrap().command {
+                Some(Command::Controller {
+                    command: Some(ControllerCommand::Name { name, short_name }),
+                }) => (name, short_name),
+                v => panic!("{:?}", v),
             }
-            Command::Discovery { command } => command.proc(&client, index).await?,
-            Command::Configuration { command } => command.proc(&client, index).await?,
-            Command::Advertise { command } => command.proc(&client, index).await?,
-            Command::Device { command } => command.proc(&client, index).await?,
-            Command::Oob { command } => command.proc(&client, index).await?,
-        };
+        }
+
+        let name = "a".repeat(248);
+        let (parsed, short_name) = name_args(&[&name, "bt"]);
+        assert_eq!(parsed, name.as_str());
+        assert_eq!(short_name.unwrap(), "bt");
+
+        let long = format!("{}😀", "a".repeat(246));
+        let (parsed, short_name) = name_args(&[&long, "ショート名前"]);
+        assert_eq!(parsed, "a".repeat(246).as_str());
+        assert_eq!(short_name.unwrap(), "ショー");
     }
 
-    if listen {
-        listen_task.await?;
+    #[test]
+    fn test_oob_add_length() {
+        let err = length(16)("112233").unwrap_err();
+        assert_eq!(
+            Some(&LengthError {
+                expected: 16,
+                actual: 3
+            }),
+            err.downcast_ref()
+        );
+        assert!(length(16)(&"11".repeat(16)).is_ok());
+
+        let valid = "00".repeat(16);
+        for arg in [
+            "--hash192",
+            "--randomizer192",
+            "--hash256",
+            "--randomizer256",
+        ] {
+            let mut args = vec!["btmgmt", "oob", "add", "--address", "00:11:22:33:44:55"];
+            for name in ["--hash192", "--randomizer192"] {
+                if name != arg {
+                    args.extend([name, &valid]);
+                }
+            }
+            args.extend([arg, "112233"]);
+            let err = Opt::try_parse_from(args).unwrap_err().to_string();
+            assert!(
+                err.contains("expected 16 bytes (32 hex chars), got 3"),
+                "{}",
+                err
+            );
+        }
     }
 
-    Ok(())
+    #[test]
+    fn test_format_discovering() {
+        let index = packet::ControllerIndex::from(0);
+        let bytes = [0x13, 0x00, 0x00, 0x00, 0x02, 0x00, 0x07, 0x01];
+        let (_, event) = event::unpack_events(&mut &bytes[..]).unwrap();
+        assert_eq!(
+            "ControllerId(0) discovering (BR/EDR) started\n\
+            ControllerId(0) discovering (LE) started",
+            format_event(&index, &event)
+        );
+
+        let bytes = [0x13, 0x00, 0x00, 0x00, 0x02, 0x00, 0x06, 0x00];
+        let (_, event) = event::unpack_events(&mut &bytes[..]).unwrap();
+        assert_eq!(
+            "ControllerId(0) discovering (LE) stopped",
+            format_event(&index, &event)
+        );
+    }
+
+    #[test]
+    fn test_format_device_connected() {
+        let index = packet::ControllerIndex::from(0);
+        let (bytes, _) = packet::fixtures::device_connected();
+        let (_, event) = event::unpack_events(&mut &bytes[..]).unwrap();
+        assert_eq!(
+            "ControllerId(0) connected 00:1a:7d:da:71:13 \"JBL Flip 5\" InitiatedConnection",
+            format_event(&index, &event)
+        );
+    }
+
+    #[test]
+    fn test_format_pairing_requests() {
+        let index = packet::ControllerIndex::from(0);
+        let bytes = [
+            0x0F, 0x00, 0x00, 0x00, 0x0C, 0x00, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x01, 0x00,
+            0x40, 0xE2, 0x01, 0x00,
+        ];
+        let (_, event) = event::unpack_events(&mut &bytes[..]).unwrap();
+        assert_eq!(
+            "ControllerId(0) confirm 00:11:22:33:44:55 123456 (device confirm -a 00:11:22:33:44:55 -l)",
+            format_event(&index, &event)
+        );
+
+        let bytes = [
+            0x10, 0x00, 0x00, 0x00, 0x07, 0x00, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x02,
+        ];
+        let (_, event) = event::unpack_events(&mut &bytes[..]).unwrap();
+        assert_eq!(
+            "ControllerId(0) passkey requested 00:11:22:33:44:55 \
+            (device passkey -a 00:11:22:33:44:55 -r <passkey>)",
+            format_event(&index, &event)
+        );
+    }
+
+    #[test]
+    fn test_configuration_runtime_get() {
+        let value = json::<command::ReadDefaultRuntimeConfiguration>(Some(0), &[]);
+        assert_eq!(
+            json!({ "command": "ReadDefaultRuntimeConfiguration", "index": 0, "result": [] }),
+            value
+        );
+    }
+
+    #[test]
+    fn test_advertise_features() {
+        let value = json::<command::ReadAdvertisingFeature>(
+            Some(0),
+            &[0x03, 0x00, 0x00, 0x00, 0x1F, 0x1F, 0x05, 0x01, 0x01],
+        );
+        assert_eq!("ReadAdvertisingFeature", value["command"]);
+        assert_eq!(
+            json!({
+                "supported_flags": ["SwitchIntoConnectableMode", "AdvertiseAsDiscoverable"],
+                "max_adv_data_len": 31,
+                "max_scan_resp_len": 31,
+                "max_instances": 5,
+                "instances": [1],
+            }),
+            value["result"]
+        );
+    }
+
+    #[test]
+    fn test_oob_read() {
+        let mut params = vec![0x11; 16];
+        params.extend([0x22; 16]);
+        let value = json::<command::ReadLocalOutOfBandData>(Some(0), &params);
+        assert_eq!("ReadLocalOutOfBandData", value["command"]);
+        assert_eq!(json!(vec![0x11; 16]), value["result"]["hash192"]);
+        assert_eq!(json!(vec![0x22; 16]), value["result"]["randomizer192"]);
+        assert!(value["result"]["hash256"].is_null());
+    }
+
+    #[test]
+    fn test_oob_read_extended() {
+        let value = json::<command::ReadLocalOutOfBandExtendedData>(
+            Some(0),
+            &[0x06, 0x03, 0x00, 0x02, 0x01, 0x06],
+        );
+        assert_eq!("ReadLocalOutOfBandExtendedData", value["command"]);
+        assert_eq!(
+            json!({ "address_type": ["LePublic", "LeRandom"], "eir_data": [2, 1, 6] }),
+            value["result"]
+        );
+    }
+
+    #[test]
+    fn test_output() {
+        assert!(matches!("json".parse(), Ok(Output::Json)));
+        assert!(matches!("text".parse(), Ok(Output::Text)));
+        assert!("yaml".parse::<Output>().is_err());
+    }
+
+    #[test]
+    fn test_exit_code_no_adapters() {
+        let err = anyhow::Error::from(btmgmt::Error::NoAdapters);
+        assert_eq!(4, exit_code(&err));
+        assert_eq!("no Bluetooth adapters found", err.to_string());
+    }
+
+    #[test]
+    fn test_exit_code_status() {
+        let err = btmgmt::Error::from(packet::ErrorCode::PermissionDenied);
+        assert_eq!(77, exit_code(&err.into()));
+        let err = btmgmt::Error::from(packet::ErrorCode::Busy);
+        assert_eq!(75, exit_code(&err.into()));
+        let err = btmgmt::Error::from(packet::ErrorCode::Failed);
+        assert_eq!(1, exit_code(&err.into()));
+    }
+
+    /// Reply parameters by opcode.
+    pub(crate) type Replies = Vec<(u16, Vec<u8>)>;
+
+    /// Reply parameters to `C`.
+    pub(crate) fn reply<C: CommandRequest>(params: impl Into<Vec<u8>>) -> (u16, Vec<u8>) {
+        (C::CODE as u16, params.into())
+    }
+
+    /// Client against the kernel answering by `replies`. Dropped with the returned task.
+    ///
+    /// Commands without a reply fail by `UnknownCommand`.
+    pub(crate) fn client(replies: Replies) -> (Client, tokio::task::JoinHandle<()>) {
+        let (client, peer) = Client::pair();
+        let kernel = tokio::spawn(async move {
+            let mut buf = [0; 1024];
+            loop {
+                peer.recv(&mut buf).await.unwrap();
+                let opcode = u16::from_le_bytes([buf[0], buf[1]]);
+                let event = match replies.iter().find(|(code, _)| *code == opcode) {
+                    Some((_, params)) => {
+                        let mut event = vec![0x01, 0x00, buf[2], buf[3]];
+                        event.extend(((params.len() + 3) as u16).to_le_bytes());
+                        event.extend(opcode.to_le_bytes());
+                        event.push(0x00);
+                        event.extend(params);
+                        event
+                    }
+                    None => {
+                        let mut event = vec![0x02, 0x00, buf[2], buf[3], 0x03, 0x00];
+                        event.extend(opcode.to_le_bytes());
+                        event.push(0x01);
+                        event
+                    }
+                };
+                peer.send(&event).await.unwrap();
+            }
+        });
+        (client, kernel)
+    }
+
+    /// Output of `args` in text, against the kernel answering by `replies`.
+    async fn text_output(args: &[&str], replies: Replies) -> String {
+        let opt =
+            Opt::try_parse_from(std::iter::once("btmgmt").chain(args.iter().copied())).unwrap();
+        let (client, kernel) = client(replies);
+        let command = opt.command.unwrap();
+        let (result, output) =
+            out::capture(dispatch(&client, command, opt.index, opt.output)).await;
+        kernel.abort();
+        result.unwrap_or_else(|err| panic!("{:?}: {:?}", args, err));
+        output
+    }
+
+    #[tokio::test]
+    async fn test_text_snapshots() {
+        let (info, _) = packet::fixtures::controller_information_reply();
+        let info = info[9..].to_vec();
+        let mut oob = vec![0x11; 16];
+        oob.extend([0x22; 16]);
+        let link_keys =
+            std::env::temp_dir().join(format!("btmgmt-link-keys-{}", std::process::id()));
+        std::fs::write(&link_keys, LINK_KEYS).unwrap();
+        let key_link = ["key", "link", "--file", link_keys.to_str().unwrap()];
+        let long_term_keys =
+            std::env::temp_dir().join(format!("btmgmt-long-term-keys-{}", std::process::id()));
+        std::fs::write(&long_term_keys, LONG_TERM_KEYS).unwrap();
+        let key_ltk = ["key", "ltk", "--file", long_term_keys.to_str().unwrap()];
+        let mut name = b"btmgmt".to_vec();
+        name.resize(249 + 11, 0);
+        let cases: Vec<(&str, &[&str], Replies)> = vec![
+            (
+                "version",
+                &["version"],
+                vec![reply::<command::ReadManagementVersionInformation>([
+                    0x01, 0x13, 0x00,
+                ])],
+            ),
+            (
+                "supported_commands",
+                &["supported-commands"],
+                vec![reply::<command::ReadManagementSupportedCommands>([
+                    0x02, 0x00, 0x01, 0x00, 0x01, 0x00, 0x04, 0x00, 0x06, 0x00,
+                ])],
+            ),
+            (
+                "controller_show",
+                &["controller", "show"],
+                vec![reply::<command::ReadControllerInformation>(info.clone())],
+            ),
+            (
+                "controller",
+                &["controller"],
+                vec![reply::<command::ReadControllerInformation>(info.clone())],
+            ),
+            (
+                "controller_ls",
+                &["controller", "ls"],
+                vec![
+                    reply::<command::ReadExtendedControllerIndexList>([
+                        0x01, 0x00, 0x00, 0x00, 0x00, 0x01,
+                    ]),
+                    reply::<command::ReadControllerInformation>(info.clone()),
+                ],
+            ),
+            (
+                "controller_ls_unconfigured",
+                &["controller", "ls"],
+                vec![
+                    reply::<command::ReadExtendedControllerIndexList>([
+                        0x02, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0x00, 0x01, 0x06,
+                    ]),
+                    reply::<command::ReadControllerInformation>(info.clone()),
+                ],
+            ),
+            (
+                "controller_config_info",
+                &["controller", "config-info"],
+                vec![reply::<command::ReadControllerConfigurationInformation>([
+                    0x02, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+                ])],
+            ),
+            (
+                "connection_ls",
+                &["connection", "ls"],
+                vec![reply::<command::GetConnections>([
+                    0x01, 0x00, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x01,
+                ])],
+            ),
+            (
+                "connection",
+                &["connection"],
+                vec![reply::<command::GetConnections>([
+                    0x02, 0x00, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x00, 0x66, 0x55, 0x44, 0x33,
+                    0x22, 0xC1, 0x02,
+                ])],
+            ),
+            (
+                "configuration_system_get",
+                &["configuration", "system", "get"],
+                vec![reply::<command::ReadDefaultSystemConfiguration>([
+                    0x0A, 0x00, 0x02, 0xA0, 0x00, 0x00, 0x01, 0x02, 0x12, 0x34,
+                ])],
+            ),
+            (
+                "configuration_runtime_get",
+                &["configuration", "runtime", "get"],
+                vec![reply::<command::ReadDefaultRuntimeConfiguration>([
+                    0x00, 0x00, 0x02, 0xB8, 0x0B,
+                ])],
+            ),
+            (
+                "configuration_runtime_get_values",
+                &["configuration", "runtime", "get"],
+                vec![reply::<command::ReadDefaultRuntimeConfiguration>([
+                    0x00, 0x00, 0x02, 0xE8, 0x03, 0x01, 0x00, 0x01, 0x01,
+                ])],
+            ),
+            (
+                "configuration_runtime_set",
+                &[
+                    "configuration",
+                    "runtime",
+                    "set",
+                    "--le-auto-reconnect-timeout",
+                    "1000",
+                ],
+                vec![reply::<command::SetDefaultRuntimeConfiguration>([])],
+            ),
+            (
+                "advertise_features",
+                &["advertise", "features"],
+                vec![reply::<command::ReadAdvertisingFeature>([
+                    0x03, 0x00, 0x00, 0x00, 0x1F, 0x1F, 0x05, 0x01, 0x01,
+                ])],
+            ),
+            (
+                "advertise_features_none",
+                &["advertise", "features"],
+                vec![reply::<command::ReadAdvertisingFeature>([
+                    0x00, 0x00, 0x00, 0x00, 0x1F, 0x1F, 0x01, 0x00,
+                ])],
+            ),
+            (
+                "cod_raw",
+                &["controller", "cod-raw", "5a020c"],
+                vec![reply::<command::SetDeviceClass>([0x0C, 0x02, 0x5A])],
+            ),
+            (
+                "advertise_add_ext",
+                &[
+                    "advertise",
+                    "add-ext",
+                    "--min-interval",
+                    "160",
+                    "--max-interval",
+                    "240",
+                    "--adv-data",
+                    "020106",
+                ],
+                vec![
+                    reply::<command::AddExtendedAdvertisingParameters>([0x01, 0xF9, 0xFB, 0xFB]),
+                    reply::<command::AddExtendedAdvertisingData>([0x01]),
+                ],
+            ),
+            (
+                "key_link",
+                &key_link,
+                vec![reply::<command::LoadLinkKeys>([])],
+            ),
+            (
+                "key_ltk",
+                &key_ltk,
+                vec![reply::<command::LoadLongTermKey>([])],
+            ),
+            (
+                "controller_name",
+                &["controller", "name", "btmgmt"],
+                vec![reply::<command::SetLocalName>(name)],
+            ),
+            (
+                "device_pincode",
+                &["device", "pincode", "-a", "00:11:22:33:44:55", "-b", "0000"],
+                vec![reply::<command::PinCodeReply>([
+                    0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x00,
+                ])],
+            ),
+            (
+                "device_pincode_negative",
+                &[
+                    "device",
+                    "pincode-negative",
+                    "-a",
+                    "00:11:22:33:44:55",
+                    "-b",
+                ],
+                vec![reply::<command::PinCodeNegativeReply>([
+                    0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x00,
+                ])],
+            ),
+            (
+                "device_confirm",
+                &["device", "confirm", "-a", "00:11:22:33:44:55", "-l"],
+                vec![reply::<command::UserConfirmationReply>([
+                    0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x01,
+                ])],
+            ),
+            (
+                "device_confirm_negative",
+                &[
+                    "device",
+                    "confirm-negative",
+                    "-a",
+                    "00:11:22:33:44:55",
+                    "-l",
+                ],
+                vec![reply::<command::UserConfirmationNegativeReply>([
+                    0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x01,
+                ])],
+            ),
+            (
+                "device_passkey",
+                &[
+                    "device",
+                    "passkey",
+                    "-a",
+                    "00:11:22:33:44:55",
+                    "-l",
+                    "123456",
+                ],
+                vec![reply::<command::UserPasskeyReply>([
+                    0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x01,
+                ])],
+            ),
+            (
+                "device_passkey_negative",
+                &[
+                    "device",
+                    "passkey-negative",
+                    "-a",
+                    "00:11:22:33:44:55",
+                    "-l",
+                ],
+                vec![reply::<command::UserPasskeyNegativeReply>([
+                    0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x01,
+                ])],
+            ),
+            (
+                "device_flags_get",
+                &["device", "flags", "get", "-a", "00:11:22:33:44:55", "-l"],
+                vec![reply::<command::GetDeviceFlag>([
+                    0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+                    0x00, 0x00,
+                ])],
+            ),
+            (
+                "device_flags_get_bredr",
+                &["device", "flags", "get", "-a", "00:11:22:33:44:55"],
+                vec![
+                    reply::<command::ReadControllerInformation>(info),
+                    reply::<command::GetDeviceFlag>([
+                        0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01,
+                        0x00, 0x00, 0x00,
+                    ]),
+                ],
+            ),
+            (
+                "device_flags_set",
+                &[
+                    "device",
+                    "flags",
+                    "set",
+                    "-a",
+                    "00:11:22:33:44:55",
+                    "-l",
+                    "--remote-wakeup",
+                    "on",
+                ],
+                vec![
+                    reply::<command::GetDeviceFlag>([
+                        0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00,
+                        0x00, 0x00, 0x00,
+                    ]),
+                    reply::<command::SetDeviceFlag>([0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x01]),
+                ],
+            ),
+            (
+                "controller_phy",
+                &["controller", "phy"],
+                vec![reply::<command::GetPhyConfiguration>([
+                    0xFF, 0x7F, 0x00, 0x00, 0x00, 0x7E, 0x00, 0x00, 0xFF, 0x06, 0x00, 0x00,
+                ])],
+            ),
+            (
+                "controller_phy_set",
+                &[
+                    "controller",
+                    "phy",
+                    "set",
+                    "--phy",
+                    "le-1m-tx",
+                    "--phy",
+                    "le-1m-rx",
+                    "--phy",
+                    "le-2m-tx",
+                ],
+                vec![reply::<command::SetPhyConfiguration>([])],
+            ),
+            (
+                "oob_read",
+                &["oob", "read"],
+                vec![reply::<command::ReadLocalOutOfBandData>(oob)],
+            ),
+            (
+                "oob_read_extended",
+                &["oob", "read", "--extended", "--le"],
+                vec![reply::<command::ReadLocalOutOfBandExtendedData>([
+                    0x06, 0x03, 0x00, 0x02, 0x01, 0x06,
+                ])],
+            ),
+            (
+                "oob_read_extended_bredr",
+                &["oob", "read", "--extended", "--bredr"],
+                vec![reply::<command::ReadLocalOutOfBandExtendedData>([
+                    0x01, 0x05, 0x00, 0x04, 0x0D, 0x0C, 0x02, 0x5A,
+                ])],
+            ),
+        ];
+
+        for (name, args, replies) in cases {
+            let output = text_output(args, replies).await;
+            insta::with_settings!({
+                prepend_module_to_snapshot => false,
+                omit_expression => true,
+                description => args.join(" "),
+            }, {
+                insta::assert_snapshot!(name, output);
+            });
+        }
+        std::fs::remove_file(&link_keys).unwrap();
+        std::fs::remove_file(&long_term_keys).unwrap();
+    }
 }
@@ -0,0 +1,49 @@
+//! Standard output of commands.
+//!
+//! Tests capture it instead, for snapshots of the text output.
+
+use std::fmt;
+
+/// `println!` for command output.
+macro_rules! outln {
+    ($($arg:tt)*) => {
+        $crate::out::write_line(format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+thread_local! {
+    static CAPTURE: std::cell::RefCell<Option<String>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+pub(crate) fn write_line(args: fmt::Arguments<'_>) {
+    #[cfg(test)]
+    {
+        use std::fmt::Write as _;
+
+        let captured = CAPTURE.with(|capture| match capture.borrow_mut().as_mut() {
+            Some(buf) => {
+                writeln!(buf, "{}", args).unwrap();
+                true
+            }
+            None => false,
+        });
+        if captured {
+            return;
+        }
+    }
+    println!("{}", args);
+}
+
+/// Run `fut` with output captured. Only for a current thread runtime.
+#[cfg(test)]
+pub(crate) async fn capture<F>(fut: F) -> (F::Output, String)
+where
+    F: std::future::Future,
+{
+    CAPTURE.with(|capture| *capture.borrow_mut() = Some(String::new()));
+    let output = fut.await;
+    let captured = CAPTURE.with(|capture| capture.borrow_mut().take().unwrap());
+    (output, captured)
+}
@@ -0,0 +1,296 @@
+//! Interactive mode. Commands are read line by line against one client.
+use std::future::Future;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use btmgmt::client::Client;
+use btmgmt::event::Event;
+use btmgmt::packet;
+use clap::{ErrorKind, Parser};
+use futures::stream::{self, Stream, StreamExt};
+use rustyline::error::ReadlineError;
+use rustyline::{DefaultEditor, ExternalPrinter};
+
+use super::{dispatch, Command, Output};
+
+const PROMPT: &str = "btmgmt> ";
+
+const BUILTINS: &str = "\
+builtins:
+    events on|off    Print incoming events or not
+    quit             Leave (also exit or Ctrl-D)";
+
+/// One line of input, parsed with the command line grammar.
+#[derive(Debug, Parser)]
+#[clap(name = "", no_binary_name = true)]
+struct ReplLine {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug)]
+enum Line {
+    Empty,
+    Quit,
+    Events(bool),
+    Command(Command),
+}
+
+/// Split like a shell does: whitespaces separate words, quotes and backslashes escape.
+fn split(line: &str) -> anyhow::Result<Vec<String>> {
+    let mut words = vec![];
+    let mut word = None::<String>;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => anyhow::bail!("unterminated single quote"),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => word.extend(['\\', c]),
+                            None => anyhow::bail!("unterminated double quote"),
+                        },
+                        Some(c) => word.push(c),
+                        None => anyhow::bail!("unterminated double quote"),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => anyhow::bail!("trailing backslash"),
+            },
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+fn parse(line: &str) -> anyhow::Result<Line> {
+    let words = split(line)?;
+    let words = words.iter().map(String::as_str).collect::<Vec<_>>();
+    Ok(match &words[..] {
+        [] => Line::Empty,
+        ["quit" | "exit"] => Line::Quit,
+        ["events", "on"] => Line::Events(true),
+        ["events", "off"] => Line::Events(false),
+        ["events", ..] => anyhow::bail!("usage: events on|off"),
+        ["repl", ..] => anyhow::bail!("already in repl"),
+        words => Line::Command(ReplLine::try_parse_from(words)?.command),
+    })
+}
+
+/// Run lines until `quit` or the end of input. Commands are handed to `exec`.
+///
+/// Errors of lines are printed, not returned.
+async fn run_lines<S, F, Fut>(mut lines: S, events: &AtomicBool, mut exec: F)
+where
+    S: Stream<Item = io::Result<String>> + Unpin,
+    F: FnMut(Command) -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    loop {
+        let line = match lines.next().await {
+            Some(Ok(line)) => line,
+            Some(Err(err)) => {
+                eprintln!("Error: {}", err);
+                break;
+            }
+            None => break,
+        };
+
+        match parse(&line) {
+            Ok(Line::Empty) => {}
+            Ok(Line::Quit) => break,
+            Ok(Line::Events(on)) => events.store(on, Ordering::Relaxed),
+            Ok(Line::Command(command)) => {
+                if let Err(err) = exec(command).await {
+                    eprintln!("Error: {:?}", err);
+                }
+            }
+            Err(err) => match err.downcast_ref::<clap::Error>() {
+                Some(err) if err.kind() == ErrorKind::DisplayHelp => {
+                    err.print().ok();
+                    println!("\n{}", BUILTINS);
+                }
+                Some(err) => {
+                    err.print().ok();
+                }
+                None => eprintln!("Error: {}", err),
+            },
+        }
+    }
+}
+
+/// Lines read by `editor` on a blocking thread, with in-memory history.
+///
+/// Ctrl-C discards the line being edited. The prompt is shown only on a terminal.
+fn editor_lines(editor: DefaultEditor) -> impl Stream<Item = io::Result<String>> + Unpin {
+    Box::pin(stream::unfold(Some(editor), |editor| async move {
+        let mut editor = editor?;
+        let (editor, line) = tokio::task::spawn_blocking(move || {
+            let line = editor.readline(PROMPT);
+            (editor, line)
+        })
+        .await
+        .expect("readline panicked");
+        match line {
+            Ok(line) => Some((Ok(line), Some(editor))),
+            Err(ReadlineError::Interrupted) => Some((Ok(String::new()), Some(editor))),
+            Err(ReadlineError::Eof) => None,
+            Err(err) => Some((Err(io::Error::other(err)), None)),
+        }
+    }))
+}
+
+/// Print above the line being edited if `printer`, or to stdout.
+fn print_event(
+    index: packet::ControllerIndex,
+    event: Event,
+    printer: &mut Option<Box<dyn ExternalPrinter + Send>>,
+) {
+    let text = super::format_event(&index, &event)
+        .lines()
+        .map(|line| format!("event: {}\n", line))
+        .collect::<String>();
+    match printer {
+        Some(printer) => {
+            if let Err(err) = printer.print(text) {
+                eprintln!("Error: {}", err);
+            }
+        }
+        None => print!("{}", text),
+    }
+}
+
+/// Read commands from stdin against `client`.
+pub(crate) async fn run(client: &Client, index: u16, output: Output) -> anyhow::Result<()> {
+    let prompt = io::stdin().is_terminal() && io::stdout().is_terminal();
+    let config = rustyline::Config::builder().auto_add_history(true).build();
+    let mut editor = DefaultEditor::with_config(config)?;
+    let mut printer = None::<Box<dyn ExternalPrinter + Send>>;
+    if prompt {
+        printer = Some(Box::new(editor.create_external_printer()?));
+        println!("btmgmt repl. 'help' for commands, 'quit' to leave.");
+    }
+
+    let events = Arc::new(AtomicBool::new(true));
+    let mut subscribe = client.events().await;
+    let listen_task = tokio::spawn({
+        let events = events.clone();
+        async move {
+            while let Some((index, event)) = subscribe.next().await {
+                if events.load(Ordering::Relaxed) {
+                    print_event(index, event, &mut printer);
+                }
+            }
+        }
+    });
+
+    run_lines(editor_lines(editor), &events, |command| {
+        dispatch(client, command, index, output)
+    })
+    .await;
+    listen_task.abort();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use btmgmt::command;
+
+    use super::*;
+    use crate::out;
+    use crate::tests::{client, reply, Replies};
+
+    #[test]
+    fn test_split() {
+        assert_eq!(
+            vec!["oob", "add", "--address", "00:11:22:33:44:55"],
+            split("  oob add\t--address 00:11:22:33:44:55 ").unwrap()
+        );
+        assert_eq!(
+            vec!["name", "my device", "a\"b", "x y", ""],
+            split(r#"name "my device" "a\"b" x\ y ''"#).unwrap()
+        );
+        assert!(split("name 'unterminated").is_err());
+        assert!(split("name \"unterminated").is_err());
+    }
+
+    fn lines(script: &[&str]) -> impl Stream<Item = io::Result<String>> + Unpin {
+        let lines = script.iter().map(|line| Ok(line.to_string()));
+        stream::iter(lines.collect::<Vec<_>>())
+    }
+
+    fn version() -> Replies {
+        vec![reply::<command::ReadManagementVersionInformation>([
+            0x01, 0x13, 0x00,
+        ])]
+    }
+
+    #[tokio::test]
+    async fn test_run_lines() {
+        let script = [
+            "version",
+            "",
+            "events off",
+            "no-such-command",
+            // no reply. failed commands do not stop the loop.
+            "configuration system set-raw --type 0x0100 --value 1234",
+            "events on",
+            "repl",
+            "version",
+            "quit",
+            "version",
+        ];
+        let (client, kernel) = client(version());
+        let events = AtomicBool::new(true);
+        let observed = RefCell::new(vec![]);
+
+        let ((), output) = out::capture(run_lines(lines(&script), &events, |command| {
+            observed.borrow_mut().push(events.load(Ordering::Relaxed));
+            dispatch(&client, command, 0, Output::Text)
+        }))
+        .await;
+        kernel.abort();
+
+        assert_eq!("1.19\n1.19\n", output);
+        assert_eq!(vec![true, false, true], observed.into_inner());
+        assert!(events.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_run_lines_end_of_input() {
+        let (client, kernel) = client(version());
+        let events = AtomicBool::new(true);
+        let ((), output) =
+            out::capture(run_lines(lines(&["help", "version"]), &events, |command| {
+                dispatch(&client, command, 0, Output::Text)
+            }))
+            .await;
+        kernel.abort();
+        assert_eq!("1.19\n", output);
+
+        let err = parse("help").unwrap_err();
+        let err = err.downcast_ref::<clap::Error>().unwrap();
+        assert_eq!(ErrorKind::DisplayHelp, err.kind());
+        assert!(err.to_string().contains("supported-commands"), "{}", err);
+    }
+}
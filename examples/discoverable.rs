@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+use btmgmt::packet::Discoverable;
+use btmgmt::*;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    pretty_env_logger::init();
+
+    let client = Client::open().unwrap();
+    let index = client.wait_for_controller(None).await.unwrap();
+    let adapter = client.adapter(index);
+
+    // Limited discoverable for 30 seconds, then back to the previous state.
+    let window = adapter
+        .discoverable_window(Discoverable::Limited, Duration::from_secs(30))
+        .await
+        .unwrap();
+    println!("discoverable (previously {})", window.previous());
+
+    let end = window.closed().await.unwrap();
+    println!("{:?}", end);
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use prometheus::{Encoder, IntCounter, IntCounterVec, Opts, Registry, TextEncoder};
+
+use btmgmt::command::*;
+use btmgmt::*;
+
+struct Metrics {
+    events_total: IntCounterVec,
+    commands_total: IntCounter,
+    command_errors_total: IntCounterVec,
+    decode_failures_total: IntCounter,
+}
+
+impl Metrics {
+    fn register(registry: &Registry) -> prometheus::Result<Self> {
+        let metrics = Self {
+            events_total: IntCounterVec::new(
+                Opts::new("btmgmt_events_total", "Received mgmt events."),
+                &["code"],
+            )?,
+            commands_total: IntCounter::new("btmgmt_commands_total", "Sent mgmt commands.")?,
+            command_errors_total: IntCounterVec::new(
+                Opts::new("btmgmt_command_errors_total", "Non-success replies."),
+                &["status"],
+            )?,
+            decode_failures_total: IntCounter::new(
+                "btmgmt_decode_failures_total",
+                "Packets failed to decode.",
+            )?,
+        };
+        registry.register(Box::new(metrics.events_total.clone()))?;
+        registry.register(Box::new(metrics.commands_total.clone()))?;
+        registry.register(Box::new(metrics.command_errors_total.clone()))?;
+        registry.register(Box::new(metrics.decode_failures_total.clone()))?;
+        Ok(metrics)
+    }
+
+    /// Scrape client snapshot. Counters only move forward, so increment by the difference.
+    fn update(&self, stats: &stats::ClientStats) {
+        fn sync(counter: &IntCounter, value: u64) {
+            counter.inc_by(value.saturating_sub(counter.get()));
+        }
+
+        for (code, n) in stats.events_total.iter() {
+            let code = format!("{:#06x}", code);
+            sync(&self.events_total.with_label_values(&[&code]), n);
+        }
+        sync(&self.commands_total, stats.commands_total);
+        for (status, n) in stats.command_errors.iter() {
+            let status = format!("{:#04x}", status);
+            sync(&self.command_errors_total.with_label_values(&[&status]), n);
+        }
+        sync(&self.decode_failures_total, stats.decode_failures);
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    pretty_env_logger::init();
+
+    let registry = Registry::new();
+    let metrics = Metrics::register(&registry).unwrap();
+
+    let client = Client::open().unwrap();
+    let mut events = client.events().await;
+    tokio::spawn(async move { while events.next().await.is_some() {} });
+
+    for _ in 0..3 {
+        client
+            .call(None, ReadManagementVersionInformation)
+            .await
+            .unwrap();
+
+        metrics.update(&client.stats());
+        let mut buf = vec![];
+        TextEncoder::new()
+            .encode(&registry.gather(), &mut buf)
+            .unwrap();
+        println!("{}", String::from_utf8(buf).unwrap());
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
@@ -1,4 +1,6 @@
-pub use btmgmt_packet_macros::{commands, events};
+#[doc(hidden)]
+pub use btmgmt_packet_macros::commands_enum;
+pub use btmgmt_packet_macros::{collect_commands, commands, events};
 pub mod pack;
 
 pub mod helper {
@@ -1,6 +1,8 @@
 use std::io;
 
 mod imp;
+#[cfg(feature = "lenient")]
+pub mod lenient;
 pub use btmgmt_packet_macros::{Pack, Unpack};
 
 #[derive(Debug, thiserror::Error)]
@@ -13,6 +15,41 @@ pub enum Error {
 
     #[error("unexpected value {0}")]
     UnexpectedValue(String),
+
+    /// Fewer items than the count prefix.
+    #[error("truncated: {expected} items expected, {actual} available")]
+    Truncated { expected: usize, actual: usize },
+
+    /// Value out of the enum. `field` is set by the innermost struct containing it.
+    #[error("invalid {name} value {value:#04x}{}", field_suffix(.field))]
+    InvalidEnumValue {
+        name: &'static str,
+        field: Option<&'static str>,
+        value: u64,
+    },
+}
+
+fn field_suffix(field: &Option<&'static str>) -> String {
+    field.map(|f| format!(" in {}", f)).unwrap_or_default()
+}
+
+impl Error {
+    /// Set the field of [`Error::InvalidEnumValue`], unless set.
+    #[doc(hidden)]
+    pub fn in_field(self, name: &'static str) -> Self {
+        match self {
+            Self::InvalidEnumValue {
+                name: ty,
+                field: None,
+                value,
+            } => Self::InvalidEnumValue {
+                name: ty,
+                field: Some(name),
+                value,
+            },
+            err => err,
+        }
+    }
 }
 
 pub type Result<R> = std::result::Result<R, Error>;
@@ -21,6 +58,24 @@ pub trait Pack {
     fn pack<W>(&self, write: &mut W) -> Result<()>
     where
         W: io::Write;
+
+    /// Packed length in bytes, used as the capacity of buffers.
+    fn packed_len_hint(&self) -> usize {
+        0
+    }
+
+    /// Pack into a new `Vec`.
+    ///
+    /// Writing to memory does not fail, so this panics only if the value is rejected by
+    /// [`Pack::pack`], a bug of the caller. Lengths are validated on construction;
+    /// overflowing lengths are caught by debug assertions.
+    fn pack_to_vec(&self) -> Vec<u8> {
+        let mut b = Vec::with_capacity(self.packed_len_hint());
+        if let Err(err) = self.pack(&mut b) {
+            panic!("failed to pack: {}", err);
+        }
+        b
+    }
 }
 
 pub trait Unpack: Sized {
@@ -52,6 +52,10 @@ impl Pack for bool {
     {
         (if *self { 1u8 } else { 0u8 }).pack(write)
     }
+
+    fn packed_len_hint(&self) -> usize {
+        1
+    }
 }
 
 impl Unpack for bool {
@@ -70,6 +74,10 @@ impl Pack for u8 {
     {
         self.to_le_bytes().pack(write)
     }
+
+    fn packed_len_hint(&self) -> usize {
+        std::mem::size_of::<Self>()
+    }
 }
 
 impl Unpack for u8 {
@@ -82,6 +90,29 @@ impl Unpack for u8 {
     }
 }
 
+impl Pack for i8 {
+    fn pack<W>(&self, write: &mut W) -> Result<()>
+    where
+        W: io::Write,
+    {
+        self.to_le_bytes().pack(write)
+    }
+
+    fn packed_len_hint(&self) -> usize {
+        std::mem::size_of::<Self>()
+    }
+}
+
+impl Unpack for i8 {
+    fn unpack<R>(read: &mut R) -> Result<Self>
+    where
+        R: io::Read,
+    {
+        let v = <[u8; 1]>::unpack(read)?;
+        Ok(Self::from_le_bytes(v))
+    }
+}
+
 impl Pack for u16 {
     fn pack<W>(&self, write: &mut W) -> Result<()>
     where
@@ -89,6 +120,10 @@ impl Pack for u16 {
     {
         self.to_le_bytes().pack(write)
     }
+
+    fn packed_len_hint(&self) -> usize {
+        std::mem::size_of::<Self>()
+    }
 }
 
 impl Unpack for u16 {
@@ -108,6 +143,10 @@ impl Pack for u32 {
     {
         self.to_le_bytes().pack(write)
     }
+
+    fn packed_len_hint(&self) -> usize {
+        std::mem::size_of::<Self>()
+    }
 }
 
 impl Unpack for u32 {
@@ -120,6 +159,29 @@ impl Unpack for u32 {
     }
 }
 
+impl Pack for u64 {
+    fn pack<W>(&self, write: &mut W) -> Result<()>
+    where
+        W: io::Write,
+    {
+        self.to_le_bytes().pack(write)
+    }
+
+    fn packed_len_hint(&self) -> usize {
+        std::mem::size_of::<Self>()
+    }
+}
+
+impl Unpack for u64 {
+    fn unpack<R>(read: &mut R) -> Result<Self>
+    where
+        R: io::Read,
+    {
+        let v = <[u8; 8]>::unpack(read)?;
+        Ok(Self::from_le_bytes(v))
+    }
+}
+
 impl Pack for u128 {
     fn pack<W>(&self, write: &mut W) -> Result<()>
     where
@@ -127,6 +189,10 @@ impl Pack for u128 {
     {
         self.to_le_bytes().pack(write)
     }
+
+    fn packed_len_hint(&self) -> usize {
+        std::mem::size_of::<Self>()
+    }
 }
 
 impl Unpack for u128 {
@@ -153,6 +219,10 @@ where
             Ok(())
         }
     }
+
+    fn packed_len_hint(&self) -> usize {
+        self.as_ref().map_or(0, T::packed_len_hint)
+    }
 }
 
 impl<T> Unpack for Option<T>
@@ -179,7 +249,11 @@ where
     where
         W: io::Write,
     {
-        T::pack(&*self, write)
+        T::pack(self, write)
+    }
+
+    fn packed_len_hint(&self) -> usize {
+        T::packed_len_hint(self)
     }
 }
 
@@ -203,12 +277,21 @@ where
     where
         W: io::Write,
     {
+        debug_assert!(
+            self.len() <= u16::MAX as usize,
+            "too many items {}",
+            self.len()
+        );
         (self.len() as u16).pack(write)?;
         for item in self {
             item.pack(write)?;
         }
         Ok(())
     }
+
+    fn packed_len_hint(&self) -> usize {
+        2 + self.iter().map(T::packed_len_hint).sum::<usize>()
+    }
 }
 
 impl<T> Unpack for Vec<T>
@@ -219,8 +302,21 @@ where
     where
         R: io::Read,
     {
-        let len = u16::unpack(read)?;
-        (0..len).map(|_| T::unpack(read)).collect()
+        let len = u16::unpack(read)? as usize;
+        let mut v = Vec::with_capacity(len);
+        for actual in 0..len {
+            match T::unpack(read) {
+                Ok(item) => v.push(item),
+                Err(Error::NoDataAvailable) => {
+                    return Err(Error::Truncated {
+                        expected: len,
+                        actual,
+                    })
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(v)
     }
 }
 
@@ -232,6 +328,10 @@ impl Pack for Box<[u8]> {
         write.write_all(self)?;
         Ok(())
     }
+
+    fn packed_len_hint(&self) -> usize {
+        self.len()
+    }
 }
 
 impl Unpack for Box<[u8]> {
@@ -253,6 +353,10 @@ impl<const N: usize> Pack for [u8; N] {
         write.write_all(&self[..])?;
         Ok(())
     }
+
+    fn packed_len_hint(&self) -> usize {
+        N
+    }
 }
 
 impl<const N: usize> Unpack for [u8; N] {
@@ -280,6 +384,10 @@ where
         p2.pack(write)?;
         Ok(())
     }
+
+    fn packed_len_hint(&self) -> usize {
+        self.0.packed_len_hint() + self.1.packed_len_hint()
+    }
 }
 
 impl<P1, P2> Unpack for (P1, P2)
@@ -311,6 +419,10 @@ where
         p3.pack(write)?;
         Ok(())
     }
+
+    fn packed_len_hint(&self) -> usize {
+        self.0.packed_len_hint() + self.1.packed_len_hint() + self.2.packed_len_hint()
+    }
 }
 
 impl<P1, P2, P3> Unpack for (P1, P2, P3)
@@ -332,6 +444,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::let_unit_value, clippy::unit_cmp)]
     fn test_unit() {
         let mut b = vec![];
 
@@ -350,12 +463,29 @@ mod tests {
             let mut b = vec![];
             test.pack(&mut b).unwrap();
             assert_eq!(b, buf);
+            assert_eq!(b, test.pack_to_vec());
+            assert_eq!(b.len(), test.packed_len_hint());
 
             let v = bool::unpack(&mut &b[..]).unwrap();
             assert_eq!(v, test);
         }
     }
 
+    #[test]
+    fn test_i8() {
+        let tests = [(-127i8, &[0x81]), (0x7F, &[0x7F])];
+
+        for (test, buf) in tests {
+            let mut b = vec![];
+            test.pack(&mut b).unwrap();
+            assert_eq!(b, buf);
+            assert_eq!(b.len(), test.packed_len_hint());
+
+            let v = i8::unpack(&mut &b[..]).unwrap();
+            assert_eq!(v, test);
+        }
+    }
+
     #[test]
     fn test_u16() {
         let tests = [(0x00FF, &[0xFF, 0x00])];
@@ -364,6 +494,8 @@ mod tests {
             let mut b = vec![];
             test.pack(&mut b).unwrap();
             assert_eq!(b, buf);
+            assert_eq!(b, test.pack_to_vec());
+            assert_eq!(b.len(), test.packed_len_hint());
 
             let v = u16::unpack(&mut &b[..]).unwrap();
             assert_eq!(v, test);
@@ -378,12 +510,33 @@ mod tests {
             let mut b = vec![];
             test.pack(&mut b).unwrap();
             assert_eq!(b, buf);
+            assert_eq!(b, test.pack_to_vec());
+            assert_eq!(b.len(), test.packed_len_hint());
 
             let v = u32::unpack(&mut &b[..]).unwrap();
             assert_eq!(v, test);
         }
     }
 
+    #[test]
+    fn test_u64() {
+        let tests = [(
+            0x00FFFFFFFFFFFF01,
+            &[0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00],
+        )];
+
+        for (test, buf) in tests {
+            let mut b = vec![];
+            test.pack(&mut b).unwrap();
+            assert_eq!(b, buf);
+            assert_eq!(b, test.pack_to_vec());
+            assert_eq!(b.len(), test.packed_len_hint());
+
+            let v = u64::unpack(&mut &b[..]).unwrap();
+            assert_eq!(v, test);
+        }
+    }
+
     #[test]
     fn test_u128() {
         let tests = [(
@@ -398,6 +551,8 @@ mod tests {
             let mut b = vec![];
             test.pack(&mut b).unwrap();
             assert_eq!(b, buf);
+            assert_eq!(b, test.pack_to_vec());
+            assert_eq!(b.len(), test.packed_len_hint());
 
             let v = u128::unpack(&mut &b[..]).unwrap();
             assert_eq!(v, test);
@@ -412,6 +567,8 @@ mod tests {
             let mut b = vec![];
             test.pack(&mut b).unwrap();
             assert_eq!(b, buf);
+            assert_eq!(b, test.pack_to_vec());
+            assert_eq!(b.len(), test.packed_len_hint());
 
             let v = <Option<u8>>::unpack(&mut &b[..]).unwrap();
             assert_eq!(v, test);
@@ -426,6 +583,8 @@ mod tests {
             let mut b = vec![];
             test.pack(&mut b).unwrap();
             assert_eq!(b, buf);
+            assert_eq!(b, test.pack_to_vec());
+            assert_eq!(b.len(), test.packed_len_hint());
 
             let v = <Box<u8>>::unpack(&mut &b[..]).unwrap();
             assert_eq!(v, test);
@@ -440,6 +599,8 @@ mod tests {
             let mut b = vec![];
             test.pack(&mut b).unwrap();
             assert_eq!(b, buf);
+            assert_eq!(b, test.pack_to_vec());
+            assert_eq!(b.len(), test.packed_len_hint());
 
             let v = <Vec<u8>>::unpack(&mut &b[..]).unwrap();
             assert_eq!(v, test);
@@ -454,6 +615,8 @@ mod tests {
             let mut b = vec![];
             test.pack(&mut b).unwrap();
             assert_eq!(b, buf);
+            assert_eq!(b, test.pack_to_vec());
+            assert_eq!(b.len(), test.packed_len_hint());
 
             let v = <Box<[u8]>>::unpack(&mut &b[..]).unwrap();
             assert_eq!(v, test);
@@ -468,6 +631,8 @@ mod tests {
             let mut b = vec![];
             test.pack(&mut b).unwrap();
             assert_eq!(b, buf);
+            assert_eq!(b, test.pack_to_vec());
+            assert_eq!(b.len(), test.packed_len_hint());
 
             let v = <(bool, u8)>::unpack(&mut &b[..]).unwrap();
             assert_eq!(v, test);
@@ -482,6 +647,8 @@ mod tests {
             let mut b = vec![];
             test.pack(&mut b).unwrap();
             assert_eq!(b, buf);
+            assert_eq!(b, test.pack_to_vec());
+            assert_eq!(b.len(), test.packed_len_hint());
 
             let v = <(bool, u8, u8)>::unpack(&mut &b[..]).unwrap();
             assert_eq!(v, test);
@@ -490,7 +657,7 @@ mod tests {
 
     #[test]
     fn test_unexpected_eof() {
-        let b = vec![0x01];
+        let b = [0x01];
         let r = <[u8; 2]>::unpack(&mut &b[..]);
         assert!(matches!(r, Err(Error::Io(io::Error { .. }))))
     }
@@ -504,7 +671,7 @@ mod tests {
                     self.0 = true;
                     Err(io::Error::new(io::ErrorKind::Interrupted, "int"))
                 } else {
-                    Err(io::Error::new(io::ErrorKind::Other, "other"))
+                    Err(io::Error::other("other"))
                 }
             }
         }
@@ -0,0 +1,161 @@
+//! Field-wise decoding which keeps going on errors. (feature `lenient`)
+use std::fmt;
+
+use super::*;
+
+/// Decoded field value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldValue {
+    /// Decoded value, formatted by `Debug`.
+    Decoded(String),
+    /// Bytes failed to decode. (e.g. unknown enum value)
+    Raw(Box<[u8]>),
+}
+
+/// Field of partially decoded packet. `None` for truncated field.
+pub type Field = (&'static str, Option<FieldValue>);
+
+/// Error of a field.
+#[derive(Debug, thiserror::Error)]
+#[error("{field} at offset {offset}: {error}")]
+pub struct FieldError {
+    pub offset: usize,
+    pub field: &'static str,
+    #[source]
+    pub error: Error,
+}
+
+/// Decode fields one by one, recording values and errors.
+#[derive(Debug)]
+pub struct Decoder<'a> {
+    data: &'a [u8],
+    offset: usize,
+    end: usize,
+    truncated: bool,
+    fields: Vec<Field>,
+    errors: Vec<FieldError>,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: 0,
+            end: data.len(),
+            truncated: false,
+            fields: vec![],
+            errors: vec![],
+        }
+    }
+
+    /// Current offset.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Limit the rest to `len` bytes. Records an error for `field` if fewer bytes remain.
+    pub fn limit(&mut self, field: &'static str, len: usize) {
+        let available = self.data.len() - self.offset;
+        if available < len {
+            self.error(
+                field,
+                io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("{} bytes expected, {} available", len, available),
+                )
+                .into(),
+            );
+        } else {
+            self.end = self.offset + len;
+        }
+    }
+
+    fn error(&mut self, field: &'static str, error: Error) {
+        self.errors.push(FieldError {
+            offset: self.offset,
+            field,
+            error,
+        });
+    }
+
+    /// Decode a value without recording it as a field.
+    ///
+    /// `Err(Some(..))` holds bytes consumed until the error, `Err(None)` means truncated.
+    pub fn value<T>(&mut self, field: &'static str) -> std::result::Result<T, Option<Box<[u8]>>>
+    where
+        T: Unpack,
+    {
+        if self.truncated {
+            return Err(None);
+        }
+
+        let data = &self.data[self.offset..self.end];
+        let mut rest = data;
+        let result = T::unpack(&mut rest);
+        let consumed = data.len() - rest.len();
+        match result {
+            Ok(value) => {
+                self.offset += consumed;
+                Ok(value)
+            }
+            Err(error @ (Error::NoDataAvailable | Error::Truncated { .. })) => {
+                self.error(field, error);
+                self.truncated = true;
+                Err(None)
+            }
+            Err(Error::Io(error)) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                self.error(field, error.into());
+                self.truncated = true;
+                Err(None)
+            }
+            Err(error) => {
+                self.error(field, error);
+                self.offset += consumed;
+                Err(Some(data[..consumed].into()))
+            }
+        }
+    }
+
+    /// Decode a field.
+    pub fn field<T>(&mut self, field: &'static str) -> Option<T>
+    where
+        T: Unpack + fmt::Debug,
+    {
+        match self.value::<T>(field) {
+            Ok(value) => {
+                let decoded = FieldValue::Decoded(format!("{:?}", value));
+                self.fields.push((field, Some(decoded)));
+                Some(value)
+            }
+            Err(raw) => {
+                self.fields.push((field, raw.map(FieldValue::Raw)));
+                None
+            }
+        }
+    }
+
+    /// Record the rest as a raw field.
+    pub fn rest(&mut self, field: &'static str) {
+        let rest = self.data[self.offset..self.end].into();
+        self.offset = self.end;
+        self.fields.push((field, Some(FieldValue::Raw(rest))));
+    }
+
+    /// Fields and errors. Records an error for bytes left undecoded.
+    pub fn finish(mut self) -> (Vec<Field>, Vec<FieldError>) {
+        if !self.truncated && self.offset < self.data.len() {
+            let remaining = self.data.len() - self.offset;
+            self.error(
+                "..",
+                Error::UnexpectedValue(format!("{} bytes remaining", remaining)),
+            );
+        }
+        (self.fields, self.errors)
+    }
+}
+
+/// Lenient counterpart of [`Unpack`], derived with it.
+pub trait UnpackLenient {
+    /// Decode fields into `decoder`.
+    fn unpack_lenient(decoder: &mut Decoder<'_>);
+}
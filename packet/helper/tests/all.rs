@@ -11,5 +11,6 @@ fn test() {
     t.pass("tests/iter_newtype.rs");
     t.pass("tests/iter_newtype_hashset.rs");
     t.pass("tests/iter_newtype_generics.rs");
+    t.pass("tests/iter_newtype_constructors.rs");
     t.pass("tests/newtype.rs");
 }
@@ -15,6 +15,8 @@ fn main() {
     let mut b = vec![];
     let v = Bitflags::A | Bitflags::B;
     v.pack(&mut b).unwrap();
+    assert_eq!(b, v.pack_to_vec());
+    assert_eq!(b.len(), v.packed_len_hint());
     assert_eq!(b, &[0x01, 0x00]);
     assert_eq!(&v.bits().to_le_bytes()[..], &[0x01, 0x00]);
 
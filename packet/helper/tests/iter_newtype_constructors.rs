@@ -0,0 +1,27 @@
+use std::collections::HashSet;
+
+use btmgmt_packet_helper::helper::IterNewtype;
+
+#[derive(IterNewtype)]
+#[iter_newtype(constructors)]
+struct MyNewType(Vec<u8>);
+
+#[derive(IterNewtype)]
+#[iter_newtype(item = u8, into_iter = ::std::collections::hash_set::IntoIter<u8>, constructors, no_iter_mut)]
+struct MySet(HashSet<u8>);
+
+fn main() {
+    let v = MyNewType::new(vec![2, 0, 1]);
+    assert_eq!(3, v.len());
+    assert!(!v.is_empty());
+    assert_eq!(vec![2, 0, 1], v.iter().copied().collect::<Vec<_>>());
+
+    // inherent, without FromIterator in scope.
+    let v = MyNewType::from_iter([5, 4]);
+    assert_eq!(vec![5, 4], v.into_iter().collect::<Vec<_>>());
+    assert!(MyNewType::new(vec![]).is_empty());
+
+    let s = MySet::from_iter([1, 1, 2]);
+    assert_eq!(2, s.len());
+    assert!(MySet::new(vec![]).is_empty());
+}
@@ -10,6 +10,8 @@ fn main() {
     let mut b = vec![];
     let v = Tuple(0x0123, true);
     v.pack(&mut b).unwrap();
+    assert_eq!(b, v.pack_to_vec());
+    assert_eq!(b.len(), v.packed_len_hint());
     assert_eq!(b, &[0x23, 0x01, 0x01]);
 
     let v2 = Tuple::unpack(&mut &b[..]).unwrap();
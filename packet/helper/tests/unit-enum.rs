@@ -14,6 +14,8 @@ fn main() {
     let mut b = vec![];
     let v = Enum::A;
     v.pack(&mut b).unwrap();
+    assert_eq!(b, v.pack_to_vec());
+    assert_eq!(b.len(), v.packed_len_hint());
     assert_eq!(b, &[0x0F, 0x00]);
 
     let v2 = Enum::unpack(&mut &b[..]).unwrap();
@@ -10,6 +10,8 @@ fn main() {
     let mut b = vec![];
     let v = Unit;
     v.pack(&mut b).unwrap();
+    assert_eq!(b, v.pack_to_vec());
+    assert_eq!(b.len(), v.packed_len_hint());
     assert!(b.is_empty());
 
     let v2 = Unit::unpack(&mut &b[..]).unwrap();
@@ -0,0 +1,35 @@
+//! Doc aliases, for searching the docs by the code or the name in `mgmt-api.txt`.
+use syn::{parse_quote, Attribute, Ident, Lit, Meta};
+
+/// Name in `mgmt-api.txt`, from the first doc line without `suffix`. (e.g. `"Add Advertising"`)
+pub fn spec_name(docs: &[Attribute], suffix: &str) -> Option<String> {
+    let first = docs.iter().find_map(|attr| match attr.parse_meta() {
+        Ok(Meta::NameValue(meta)) if meta.path.is_ident("doc") => match meta.lit {
+            Lit::Str(lit) => Some(lit.value()),
+            _ => None,
+        },
+        _ => None,
+    })?;
+    let name = first.trim();
+    let name = name.strip_suffix(suffix).unwrap_or(name).trim_end();
+    // only plain names. the first line may be a sentence.
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '/' || c == '-')
+    {
+        return None;
+    }
+    Some(name.to_owned())
+}
+
+/// `#[doc(alias = "0x003E")]`, and `#[doc(alias = "Add Advertising")]` with the name
+/// unless it is `ident` already.
+pub fn aliases(ident: &Ident, code: u16, spec_name: Option<&str>) -> Vec<Attribute> {
+    let code = format!("{:#06X}", code).replacen("0X", "0x", 1);
+    let mut result: Vec<Attribute> = vec![parse_quote!(#[doc(alias = #code)])];
+    if let Some(name) = spec_name.filter(|name| ident != name) {
+        result.push(parse_quote!(#[doc(alias = #name)]));
+    }
+    result
+}
@@ -0,0 +1,107 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{
+    Attribute, Data, DeriveInput, Fields, GenericArgument, Path, PathArguments, Token, Type,
+};
+
+/// true if `#[derive(..)]` in `attrs` lists `name`.
+pub fn derives(attrs: &[Attribute], name: &str) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("derive"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .any(|path| path.segments.last().is_some_and(|s| s.ident == name))
+}
+
+/// true if `ty` is `Box<[u8]>`.
+fn is_bytes(ty: &Type) -> bool {
+    let segment = match ty {
+        Type::Path(ty) => ty.path.segments.last(),
+        _ => None,
+    };
+    let args = match segment {
+        Some(segment) if segment.ident == "Box" => &segment.arguments,
+        _ => return false,
+    };
+    matches!(
+        args,
+        PathArguments::AngleBracketed(args)
+            if matches!(args.args.first(), Some(GenericArgument::Type(Type::Slice(..))))
+    )
+}
+
+/// true if `ty` is `Vec<T>`.
+fn is_vec(ty: &Type) -> bool {
+    match ty {
+        Type::Path(ty) => ty.path.segments.last().is_some_and(|s| s.ident == "Vec"),
+        _ => false,
+    }
+}
+
+/// Lengths of `Vec<T>` and `Box<[u8]>` are bounded by `crate::arbitrary_impl`.
+fn field(ty: &Type) -> TokenStream {
+    if is_bytes(ty) {
+        quote! { crate::arbitrary_impl::bytes(u)? }
+    } else if is_vec(ty) {
+        quote! { crate::arbitrary_impl::vec(u)? }
+    } else {
+        quote! { ::arbitrary::Arbitrary::arbitrary(u)? }
+    }
+}
+
+/// `arbitrary::Arbitrary`, generated only with the `arbitrary` feature of the deriving crate.
+///
+/// Structs with only `bits` are taken as bitflags, and are limited to the known bits.
+/// Enums choose one of the variants. Generic types are not supported.
+pub fn derive(item: &DeriveInput) -> TokenStream {
+    if !item.generics.params.is_empty() {
+        return TokenStream::new();
+    }
+
+    let ident = &item.ident;
+    let body = match &item.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Unit => quote! { { let _ = u; Self } },
+            Fields::Unnamed(fields) => {
+                let fields = fields.unnamed.iter().map(|f| field(&f.ty));
+                quote! { Self( #( #fields, )* ) }
+            }
+            Fields::Named(fields)
+                if fields.named.len() == 1 && fields.named[0].ident.as_ref().unwrap() == "bits" =>
+            {
+                quote! { Self::from_bits_truncate(::arbitrary::Arbitrary::arbitrary(u)?) }
+            }
+            Fields::Named(fields) => {
+                let names = fields.named.iter().map(|f| &f.ident);
+                let fields = fields.named.iter().map(|f| field(&f.ty));
+                quote! { Self { #( #names: #fields, )* } }
+            }
+        },
+        Data::Enum(data) => {
+            let len = data.variants.len();
+            let index = (0..len).map(proc_macro2::Literal::usize_unsuffixed);
+            let variants = data.variants.iter().map(|v| &v.ident);
+            quote! {
+                match u.choose_index(#len)? {
+                    #( #index => Self::#variants, )*
+                    _ => unreachable!(),
+                }
+            }
+        }
+        Data::Union(..) => return TokenStream::new(),
+    };
+
+    quote! {
+        #[cfg(feature = "arbitrary")]
+        impl<'a> ::arbitrary::Arbitrary<'a> for #ident {
+            fn arbitrary(u: &mut ::arbitrary::Unstructured<'a>) -> ::arbitrary::Result<Self> {
+                Ok(#body)
+            }
+        }
+    }
+}
@@ -1,13 +1,18 @@
-use proc_macro2::TokenStream;
-use quote::ToTokens;
+use std::collections::HashMap;
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
 use syn::parse::{Parse, ParseStream};
-use syn::{parse_quote, Attribute, Expr, Ident, Item, ItemMod, Token};
+use syn::{parse_quote, Attribute, Expr, Ident, Item, ItemMod, LitStr, Token};
+
+use crate::{alias, arbitrary, registry};
 
 #[derive(Debug)]
 struct Args {
     name: Ident,
     codes: Ident,
     trait_: Ident,
+    group: bool,
 }
 
 impl Parse for Args {
@@ -15,6 +20,7 @@ impl Parse for Args {
         let mut name = None;
         let mut codes = None;
         let mut trait_ = None;
+        let mut group = false;
 
         while input.peek(Ident) || input.peek(Token![trait]) {
             if input.peek(Ident) {
@@ -28,6 +34,7 @@ impl Parse for Args {
                         input.parse::<Token![=]>()?;
                         codes = Some(input.parse()?);
                     }
+                    "group" => group = true,
                     unknown => return Err(input.error(format!("unknown name {}", unknown))),
                 }
             } else if input.peek(Token![trait]) {
@@ -48,6 +55,7 @@ impl Parse for Args {
                 name,
                 codes,
                 trait_,
+                group,
             })
         } else {
             Err(input.error("no name, code or trait found."))
@@ -134,6 +142,12 @@ fn collect_targets(items: &mut Vec<Item>) -> syn::Result<Vec<Target>> {
                     .filter(|a| a.path.is_ident("doc"))
                     .cloned()
                     .collect::<Vec<_>>();
+                let name = alias::spec_name(&docs, " Command");
+                item.attrs.extend(alias::aliases(
+                    &item.ident,
+                    registry::code(&attr.code)?,
+                    name.as_deref(),
+                ));
                 result.push(Target(item.ident.clone(), attr, docs));
             }
         }
@@ -142,14 +156,11 @@ fn collect_targets(items: &mut Vec<Item>) -> syn::Result<Vec<Target>> {
     Ok(result)
 }
 
-fn apply(attr: Args, item: &mut ItemMod) -> syn::Result<()> {
-    let docs = item
-        .attrs
-        .iter()
-        .filter(|a| a.path.is_ident("doc"))
-        .cloned()
-        .collect::<Vec<_>>();
-
+/// Adds impls for commands, and the command enum, codes and trait into the module.
+///
+/// With `group`, adds `__commands!` passing the commands to [`commands_enum`] instead.
+/// `__commands!` is chained through the groups given to [`collect_commands`].
+fn apply(attr: Args, item: &mut ItemMod) -> syn::Result<TokenStream> {
     let contents = if let Some((_, contents)) = &mut item.content {
         contents
     } else {
@@ -159,6 +170,7 @@ fn apply(attr: Args, item: &mut ItemMod) -> syn::Result<()> {
     let name = &attr.name;
     let trait_ = &attr.trait_;
     let codes = &attr.codes;
+    let structs = registry::struct_fields(contents);
     let targets = collect_targets(contents)?;
 
     if targets.is_empty() {
@@ -168,6 +180,22 @@ fn apply(attr: Args, item: &mut ItemMod) -> syn::Result<()> {
         ));
     }
 
+    // replies. commands get it from `Pack`.
+    let replies = contents
+        .iter()
+        .filter_map(|item| match item {
+            Item::Struct(item)
+                if arbitrary::derives(&item.attrs, "Unpack")
+                    && !arbitrary::derives(&item.attrs, "Pack") =>
+            {
+                Some(arbitrary::derive(&item.clone().into()))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    contents.extend(replies.into_iter().map(Item::Verbatim));
+
+    let mut entries = vec![];
     for target in &targets {
         let ident = target.ident();
         let reply = target.reply();
@@ -175,6 +203,7 @@ fn apply(attr: Args, item: &mut ItemMod) -> syn::Result<()> {
         contents.push(parse_quote! {
             impl #trait_ for #ident {
                 const CODE: #codes = #codes::#ident;
+                const NAME: &'static str = stringify!(#ident);
                 type Reply = #reply;
             }
         });
@@ -186,32 +215,231 @@ fn apply(attr: Args, item: &mut ItemMod) -> syn::Result<()> {
                 }
             }
         });
+
+        let reply_fields = structs
+            .get(&reply.to_string())
+            .map_or_else(|| "null".into(), registry::fields);
+        let json = format!(
+            r#"{{"code":{},"name":"{}","params":{},"reply":{{"name":"{}","fields":{}}},"since":null}}"#,
+            registry::code(target.val())?,
+            ident,
+            registry::fields(&structs[&ident.to_string()]),
+            reply,
+            reply_fields,
+        );
+        entries.push(Entry {
+            ident: ident.clone(),
+            val: target.val().clone(),
+            json: LitStr::new(&json, ident.span()),
+            docs: target.docs().to_vec(),
+        });
+    }
+
+    if !attr.group {
+        let generated = apply_enum(EnumInput {
+            args: attr,
+            entries,
+        })?;
+        contents.push(Item::Verbatim(generated));
+        return Ok(quote! { #item });
+    }
+
+    let entries = entries
+        .iter()
+        .map(|entry| {
+            let Entry {
+                ident,
+                val,
+                json,
+                docs,
+            } = entry;
+            quote! { { #ident #val #json [ #( #docs )* ] } }
+        })
+        .collect::<Vec<_>>();
+    Ok(quote! {
+        #item
+
+        #[doc(hidden)]
+        macro_rules! __commands {
+            ([$next:ident $(, $rest:ident)*] $args:tt [$($acc:tt)*]) => {
+                self::$next::__commands! { [$($rest),*] $args [$($acc)* #( #entries )*] }
+            };
+            ([] $args:tt [$($acc:tt)*]) => {
+                ::btmgmt_packet_helper::commands_enum! { $args [$($acc)* #( #entries )*] }
+            };
+        }
+        #[doc(hidden)]
+        pub(super) use __commands;
+    })
+}
+
+pub fn commands(attr: TokenStream, input: TokenStream) -> TokenStream {
+    let mut item = match syn::parse2::<ItemMod>(input) {
+        Ok(item) => item,
+        Err(err) => return err.to_compile_error(),
+    };
+
+    let attr = match syn::parse2::<Args>(attr) {
+        Ok(attr) => attr,
+        Err(err) => return err.to_compile_error(),
+    };
+
+    apply(attr, &mut item).unwrap_or_else(|err| err.to_compile_error())
+}
+
+/// `name = .., trait = .., codes = .., groups = [..]`
+struct CollectArgs {
+    args: TokenStream,
+    groups: Vec<Ident>,
+}
+
+impl Parse for CollectArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = TokenStream::new();
+        let mut groups = None;
+        while !input.is_empty() {
+            if input.peek(Ident) && input.fork().parse::<Ident>()? == "groups" {
+                input.parse::<Ident>()?;
+                input.parse::<Token![=]>()?;
+                let content;
+                syn::bracketed!(content in input);
+                groups = Some(
+                    content
+                        .parse_terminated::<_, Token![,]>(Ident::parse)?
+                        .into_iter()
+                        .collect::<Vec<_>>(),
+                );
+                if input.peek(Token![,]) {
+                    input.parse::<Token![,]>()?;
+                }
+            } else {
+                args.extend(Some(input.parse::<TokenTree>()?));
+            }
+        }
+        // validate
+        syn::parse2::<Args>(args.clone())?;
+        match groups {
+            Some(groups) if !groups.is_empty() => Ok(Self { args, groups }),
+            _ => Err(input.error("no groups found.")),
+        }
+    }
+}
+
+/// Collects commands of the groups, and generates the command enum, codes and trait.
+pub fn collect_commands(input: TokenStream) -> TokenStream {
+    let CollectArgs { args, groups } = match syn::parse2::<CollectArgs>(input) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error(),
+    };
+    let first = &groups[0];
+    let rest = &groups[1..];
+    quote! {
+        self::#first::__commands! { [#( #rest ),*] { #args } [] }
+    }
+}
+
+struct Entry {
+    ident: Ident,
+    val: Expr,
+    json: LitStr,
+    docs: Vec<Attribute>,
+}
+
+impl Parse for Entry {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        syn::braced!(content in input);
+        let ident = content.parse()?;
+        let val = content.parse()?;
+        let json = content.parse()?;
+        let docs;
+        syn::bracketed!(docs in content);
+        let docs = docs.call(Attribute::parse_outer)?;
+        Ok(Self {
+            ident,
+            val,
+            json,
+            docs,
+        })
+    }
+}
+
+struct EnumInput {
+    args: Args,
+    entries: Vec<Entry>,
+}
+
+impl Parse for EnumInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let args;
+        syn::braced!(args in input);
+        let args = args.parse()?;
+        let entries;
+        syn::bracketed!(entries in input);
+        let mut result = vec![];
+        while !entries.is_empty() {
+            result.push(entries.parse()?);
+        }
+        Ok(Self {
+            args,
+            entries: result,
+        })
+    }
+}
+
+fn apply_enum(input: EnumInput) -> syn::Result<TokenStream> {
+    let EnumInput { args, mut entries } = input;
+    let name = &args.name;
+    let trait_ = &args.trait_;
+    let codes = &args.codes;
+
+    let mut seen = HashMap::new();
+    for entry in &entries {
+        let code = registry::code(&entry.val)?;
+        if let Some(other) = seen.insert(code, &entry.ident) {
+            return Err(syn::Error::new_spanned(
+                &entry.ident,
+                format!("code {:#06x} is used by {} too.", code, other),
+            ));
+        }
     }
 
-    let idents = targets.iter().map(Target::ident).collect::<Vec<_>>();
-    let vals = targets.iter().map(Target::val).collect::<Vec<_>>();
-    let tdocs = targets.iter().map(Target::docs).collect::<Vec<_>>();
+    // in code order, regardless of the groups.
+    entries.sort_by_key(|e| registry::code(&e.val).unwrap_or_default());
 
-    contents.push(parse_quote! {
+    let idents = entries.iter().map(|e| &e.ident).collect::<Vec<_>>();
+    let vals = entries.iter().map(|e| &e.val).collect::<Vec<_>>();
+    let tdocs = entries.iter().map(|e| &e.docs).collect::<Vec<_>>();
+    let spec_names = entries
+        .iter()
+        .map(|e| alias::spec_name(&e.docs, " Command").unwrap_or_else(|| e.ident.to_string()))
+        .collect::<Vec<_>>();
+    let registry = format!(
+        "[{}]",
+        entries
+            .iter()
+            .map(|e| e.json.value())
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+
+    Ok(quote! {
         /// Represents a management api command.
         pub trait #trait_: ::std::convert::Into<#name> {
             /// Command code.
             const CODE: #codes;
-            /// Return type for this command.
-            type Reply: ::btmgmt_packet_helper::pack::Unpack;
+            /// Command name. (e.g. `"SetPowered"`)
+            const NAME: &'static str;
+            /// Return type for this command. Sendable across threads like the command.
+            type Reply: ::btmgmt_packet_helper::pack::Unpack + ::std::marker::Send + ::std::marker::Sync;
         }
-    });
 
-    contents.push(parse_quote! {
-        #( #docs )*
         /// This struct internal use only.
         #[derive(Debug)]
         pub enum #name {
             #( #( #tdocs )* #idents(#idents), )*
         }
-    });
 
-    contents.push(parse_quote! {
         impl #name {
             #[doc(hidden)]
             pub fn code(&self) -> #codes {
@@ -220,41 +448,74 @@ fn apply(attr: Args, item: &mut ItemMod) -> syn::Result<()> {
                 }
             }
 
+            /// Command name. (e.g. `"SetPowered"`)
+            pub fn name(&self) -> &'static str {
+                match self {
+                    #( Self::#idents(..) => <#idents as #trait_>::NAME, )*
+                }
+            }
+
             #[doc(hidden)]
             pub fn pack_inner<W>(&self, write: &mut W) -> ::btmgmt_packet_helper::pack::Result<()> where W: ::std::io::Write {
                 match self {
                     #( Self::#idents(inner) => inner.pack(write), )*
                 }
             }
+
+            #[doc(hidden)]
+            pub fn packed_len_hint(&self) -> usize {
+                match self {
+                    #( Self::#idents(inner) => inner.packed_len_hint(), )*
+                }
+            }
+        }
+
+        /// All commands with names. (in code order)
+        pub fn all_commands() -> &'static [(#codes, &'static str)] {
+            &[ #( (#codes::#idents, stringify!(#idents)), )* ]
         }
-    });
 
-    contents.push(parse_quote! {
+        /// Command code by the code, or by the name in `mgmt-api.txt` or of the command,
+        /// ignoring case. (e.g. `"0x003E"`, `"62"`, `"Add Advertising"` or `"addadvertising"`)
+        pub fn lookup(name_or_code: &str) -> ::std::option::Option<#codes> {
+            const NAMES: &[(#codes, &str, &str)] = &[ #( (#codes::#idents, stringify!(#idents), #spec_names), )* ];
+
+            let input = name_or_code.trim();
+            let code = match input.strip_prefix("0x").or_else(|| input.strip_prefix("0X")) {
+                Some(hex) => u16::from_str_radix(hex, 16).ok(),
+                None => input.parse::<u16>().ok(),
+            };
+            if let Some(code) = code {
+                return NAMES
+                    .iter()
+                    .find(|(c, ..)| c.clone() as u16 == code)
+                    .map(|(c, ..)| c.clone());
+            }
+
+            let lower = input.to_ascii_lowercase();
+            let name = lower.strip_suffix(" command").unwrap_or(&lower);
+            NAMES
+                .iter()
+                .find(|(_, ident, spec)| name.eq_ignore_ascii_case(ident) || name.eq_ignore_ascii_case(spec))
+                .map(|(c, ..)| c.clone())
+        }
+
+        #[doc(hidden)]
+        pub const REGISTRY_JSON: &str = #registry;
+
         /// Command Code
+        #[cfg_attr(feature = "serde", derive(::serde::Serialize))]
         #[derive(Debug, Clone, PartialEq, Eq, Hash, ::btmgmt_packet_helper::pack::Pack, ::btmgmt_packet_helper::pack::Unpack)]
         #[pack(u16)]
         pub enum #codes {
             #( #( #tdocs )* #idents = #vals, )*
         }
-    });
-
-    Ok(())
+    })
 }
 
-pub fn commands(attr: TokenStream, input: TokenStream) -> TokenStream {
-    let mut item = match syn::parse2::<ItemMod>(input) {
-        Ok(item) => item,
-        Err(err) => return err.to_compile_error(),
-    };
-
-    let attr = match syn::parse2::<Args>(attr) {
-        Ok(attr) => attr,
-        Err(err) => return err.to_compile_error(),
-    };
-
-    if let Err(err) = apply(attr, &mut item) {
-        return err.to_compile_error();
-    };
-
-    item.to_token_stream()
+/// Terminal of `__commands!` chain.
+pub fn commands_enum(input: TokenStream) -> TokenStream {
+    syn::parse2::<EnumInput>(input)
+        .and_then(apply_enum)
+        .unwrap_or_else(|err| err.to_compile_error())
 }
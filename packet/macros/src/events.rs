@@ -3,6 +3,8 @@ use quote::ToTokens;
 use syn::parse::{Parse, ParseStream};
 use syn::{parse_quote, Attribute, Expr, Ident, Item, ItemMod, Token};
 
+use crate::{alias, arbitrary, registry};
+
 #[derive(Debug)]
 struct Args {
     name: Ident,
@@ -79,6 +81,12 @@ fn collect_targets(items: &mut Vec<Item>) -> syn::Result<Vec<Target>> {
                     .filter(|a| a.path.is_ident("doc"))
                     .cloned()
                     .collect::<Vec<_>>();
+                let name = alias::spec_name(&docs, " Event");
+                item.attrs.extend(alias::aliases(
+                    &item.ident,
+                    registry::code(&val)?,
+                    name.as_deref(),
+                ));
                 result.push(Target(item.ident.clone(), val, docs));
             }
         }
@@ -101,6 +109,7 @@ fn apply(attr: Args, item: &mut ItemMod) -> syn::Result<()> {
         return Err(syn::Error::new_spanned(item, "no mod body found."));
     };
 
+    let structs = registry::struct_fields(contents);
     let targets = collect_targets(contents)?;
     let events = targets.iter().map(Target::ident).collect::<Vec<_>>();
     let vals = targets.iter().map(Target::val).collect::<Vec<_>>();
@@ -109,6 +118,18 @@ fn apply(attr: Args, item: &mut ItemMod) -> syn::Result<()> {
     let name = &attr.name;
     let codes = &attr.codes;
 
+    // packed back for round trips of arbitrary events. `Pack` derives `Arbitrary` too.
+    for item in contents.iter_mut() {
+        if let Item::Struct(item) = item {
+            if arbitrary::derives(&item.attrs, "Unpack") && !arbitrary::derives(&item.attrs, "Pack")
+            {
+                item.attrs.push(parse_quote! {
+                    #[cfg_attr(feature = "arbitrary", derive(::btmgmt_packet_helper::pack::Pack))]
+                });
+            }
+        }
+    }
+
     for event in &events {
         contents.push(parse_quote! {
             impl ::std::convert::From<#event> for #name {
@@ -118,6 +139,32 @@ fn apply(attr: Args, item: &mut ItemMod) -> syn::Result<()> {
             }
         });
 
+        contents.push(parse_quote! {
+            /// The event itself is returned for other variants.
+            impl ::std::convert::TryFrom<#name> for #event {
+                type Error = #name;
+                fn try_from(v: #name) -> Result<Self, Self::Error> {
+                    match v {
+                        #name::#event(v) => Ok(v),
+                        v => Err(v),
+                    }
+                }
+            }
+        });
+
+        contents.push(parse_quote! {
+            /// By reference, for matching without taking the event.
+            impl<'a> ::std::convert::TryFrom<&'a #name> for &'a #event {
+                type Error = &'a #name;
+                fn try_from(v: &'a #name) -> Result<Self, Self::Error> {
+                    match v {
+                        #name::#event(v) => Ok(v),
+                        v => Err(v),
+                    }
+                }
+            }
+        });
+
         contents.push(parse_quote! {
             impl #event {
                 pub const CODE: #codes = #codes::#event;
@@ -145,8 +192,73 @@ fn apply(attr: Args, item: &mut ItemMod) -> syn::Result<()> {
         }
     });
 
+    contents.push(parse_quote! {
+        #[cfg(feature = "lenient")]
+        impl #name {
+            #[doc(hidden)]
+            pub fn unpack_lenient_inner(code: &#codes, decoder: &mut ::btmgmt_packet_helper::pack::lenient::Decoder<'_>) {
+                use ::btmgmt_packet_helper::pack::lenient::UnpackLenient;
+                match code {
+                    #( #codes::#events => #events::unpack_lenient(decoder), )*
+                }
+            }
+        }
+    });
+
+    let index = (0..events.len()).map(proc_macro2::Literal::usize_unsuffixed);
+    let len = events.len();
+    contents.push(parse_quote! {
+        /// One of the known events. Never [`Self::Unknown`].
+        #[cfg(feature = "arbitrary")]
+        impl<'a> ::arbitrary::Arbitrary<'a> for #name {
+            fn arbitrary(u: &mut ::arbitrary::Unstructured<'a>) -> ::arbitrary::Result<Self> {
+                Ok(match u.choose_index(#len)? {
+                    #( #index => #name::#events(u.arbitrary()?), )*
+                    _ => unreachable!(),
+                })
+            }
+        }
+    });
+
+    contents.push(parse_quote! {
+        #[cfg(feature = "arbitrary")]
+        impl #name {
+            #[doc(hidden)]
+            pub fn pack_inner<W>(&self, write: &mut W) -> ::btmgmt_packet_helper::pack::Result<u16> where W: ::std::io::Write {
+                use ::btmgmt_packet_helper::pack::Pack;
+                match self {
+                    #( #name::#events(v) => v.pack(write).map(|_| #codes::#events as u16), )*
+                    #name::Unknown(code, data) => data.pack(write).map(|_| *code),
+                }
+            }
+        }
+    });
+
+    let mut entries = vec![];
+    for target in &targets {
+        entries.push(format!(
+            r#"{{"code":{},"name":"{}","fields":{},"since":null}}"#,
+            registry::code(target.val())?,
+            target.ident(),
+            registry::fields(&structs[&target.ident().to_string()]),
+        ));
+    }
+    let registry = format!("[{}]", entries.join(","));
+    contents.push(parse_quote! {
+        #[doc(hidden)]
+        pub const REGISTRY_JSON: &str = #registry;
+    });
+
+    contents.push(parse_quote! {
+        /// All events with names. (in declaration order)
+        pub fn all_events() -> &'static [(#codes, &'static str)] {
+            &[ #( (#codes::#events, stringify!(#events)), )* ]
+        }
+    });
+
     contents.push(parse_quote! {
         /// Command Code
+        #[cfg_attr(feature = "serde", derive(::serde::Serialize))]
         #[derive(Debug, Clone, PartialEq, Eq, Hash, ::btmgmt_packet_helper::pack::Pack, ::btmgmt_packet_helper::pack::Unpack)]
         #[pack(u16)]
         pub enum #codes {
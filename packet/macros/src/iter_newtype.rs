@@ -23,29 +23,39 @@ fn assert(item: &DeriveInput) -> syn::Result<()> {
 
 struct Conf {
     iter_mut: bool,
+    constructors: bool,
     item: Type,
     into_iter: Type,
 }
 
-impl Parse for Conf {
+#[derive(Default)]
+struct Attrs {
+    iter_mut: Option<bool>,
+    constructors: bool,
+    item: Option<Type>,
+    into_iter: Option<Type>,
+}
+
+impl Parse for Attrs {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let mut iter_mut = true;
-        let mut item = None;
-        let mut into_iter = None;
+        let mut attrs = Attrs::default();
 
         while input.peek(Ident) {
             let name = input.parse::<Ident>()?;
             match name.to_string().as_str() {
                 "item" => {
                     input.parse::<Token![=]>()?;
-                    item = Some(input.parse::<Type>()?);
+                    attrs.item = Some(input.parse::<Type>()?);
                 }
                 "into_iter" => {
                     input.parse::<Token![=]>()?;
-                    into_iter = Some(input.parse::<Type>()?);
+                    attrs.into_iter = Some(input.parse::<Type>()?);
                 }
                 "no_iter_mut" => {
-                    iter_mut = false;
+                    attrs.iter_mut = Some(false);
+                }
+                "constructors" => {
+                    attrs.constructors = true;
                 }
                 unknown => return Err(input.error(format!("unknown attribute {}", unknown))),
             }
@@ -55,15 +65,10 @@ impl Parse for Conf {
             }
         }
 
-        if let (Some(item), Some(into_iter)) = (item, into_iter) {
-            Ok(Conf {
-                iter_mut,
-                item,
-                into_iter,
-            })
-        } else {
-            Err(input.error("item or into_iter not found."))
+        if attrs.item.is_some() != attrs.into_iter.is_some() {
+            return Err(input.error("item or into_iter not found."));
         }
+        Ok(attrs)
     }
 }
 
@@ -81,13 +86,25 @@ impl<'ast, 'b> Visit<'ast> for FindFirstGenericParam<'b> {
 }
 
 fn detect_conf(item: &DeriveInput) -> syn::Result<Conf> {
+    let mut attrs = Attrs::default();
     for attr in &item.attrs {
         if attr.path.is_ident("iter_newtype") {
-            let conf = attr.parse_args::<Conf>()?;
-            return Ok(conf);
+            attrs = attr.parse_args::<Attrs>()?;
+            break;
         }
     }
 
+    let iter_mut = attrs.iter_mut.unwrap_or(true);
+    let constructors = attrs.constructors;
+    if let (Some(item), Some(into_iter)) = (attrs.item, attrs.into_iter) {
+        return Ok(Conf {
+            iter_mut,
+            constructors,
+            item,
+            into_iter,
+        });
+    }
+
     match &item.data {
         Data::Struct(DataStruct {
             fields: Fields::Unnamed(f),
@@ -98,7 +115,8 @@ fn detect_conf(item: &DeriveInput) -> syn::Result<Conf> {
             FindFirstGenericParam(&mut ty).visit_field(f);
             if let Some(ty) = ty {
                 return Ok(Conf {
-                    iter_mut: true,
+                    iter_mut,
+                    constructors,
                     into_iter: parse_quote! { ::std::vec::IntoIter<#ty> },
                     item: ty,
                 });
@@ -129,6 +147,24 @@ fn derive(input: TokenStream) -> syn::Result<TokenStream> {
         TokenStream::new()
     };
 
+    // Vec and Remaining keep the order. The kernel processes items in that order.
+    let constructors = if conf.constructors {
+        parse_quote! {
+            /// Items are sent in the order of `items`.
+            pub fn new(items: ::std::vec::Vec<#item>) -> Self {
+                Self(::std::iter::FromIterator::from_iter(items))
+            }
+
+            /// Items are sent in the iteration order. Same as [`FromIterator`](::std::iter::FromIterator).
+            #[allow(clippy::should_implement_trait)]
+            pub fn from_iter<I>(iter: I) -> Self where I: ::std::iter::IntoIterator<Item = #item> {
+                Self(::std::iter::FromIterator::from_iter(iter))
+            }
+        }
+    } else {
+        TokenStream::new()
+    };
+
     Ok(parse_quote! {
         impl #impl_generics ::std::iter::IntoIterator for #ident #type_generics #where_clause {
             type Item = #item;
@@ -152,6 +188,16 @@ fn derive(input: TokenStream) -> syn::Result<TokenStream> {
         }
 
         impl #impl_generics #ident #type_generics #where_clause {
+            #constructors
+
+            pub fn len(&self) -> usize {
+                self.0.len()
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.0.is_empty()
+            }
+
             pub fn iter(&self) -> impl std::iter::Iterator<Item = &#item> {
                 self.0.iter()
             }
@@ -1,10 +1,13 @@
 use proc_macro::TokenStream;
 
+mod alias;
+mod arbitrary;
 mod commands;
 mod events;
 mod iter_newtype;
 mod newtype;
 mod pack;
+mod registry;
 mod unpack;
 
 #[proc_macro_derive(Pack, attributes(pack))]
@@ -22,6 +25,17 @@ pub fn commands(attr: TokenStream, input: TokenStream) -> TokenStream {
     commands::commands(attr.into(), input.into()).into()
 }
 
+#[proc_macro]
+pub fn collect_commands(input: TokenStream) -> TokenStream {
+    commands::collect_commands(input.into()).into()
+}
+
+#[doc(hidden)]
+#[proc_macro]
+pub fn commands_enum(input: TokenStream) -> TokenStream {
+    commands::commands_enum(input.into()).into()
+}
+
 #[proc_macro_attribute]
 pub fn events(attr: TokenStream, input: TokenStream) -> TokenStream {
     events::events(attr.into(), input.into()).into()
@@ -28,6 +28,10 @@ fn derive_tuple(item: &DeriveInput, data: &DataStruct) -> syn::Result<TokenStrea
                 #( self.#fields.pack(write)?; )*
                 Ok(())
             }
+
+            fn packed_len_hint(&self) -> usize {
+                0 #( + self.#fields.packed_len_hint() )*
+            }
         }
     };
     Ok(code)
@@ -48,6 +52,10 @@ fn derive_standard(item: &DeriveInput, data: &DataStruct) -> syn::Result<TokenSt
                 #( self.#fields.pack(write)?; )*
                 Ok(())
             }
+
+            fn packed_len_hint(&self) -> usize {
+                0 #( + self.#fields.packed_len_hint() )*
+            }
         }
     };
     Ok(code)
@@ -87,6 +95,10 @@ fn derive_enum(item: &DeriveInput, data: &DataEnum) -> syn::Result<TokenStream>
                 };
                 v.pack(write)
             }
+
+            fn packed_len_hint(&self) -> usize {
+                ::std::mem::size_of::<#ty>()
+            }
         }
     };
     Ok(code)
@@ -94,33 +106,30 @@ fn derive_enum(item: &DeriveInput, data: &DataEnum) -> syn::Result<TokenStream>
 
 fn derive(input: TokenStream) -> syn::Result<TokenStream> {
     let input = syn::parse2::<DeriveInput>(input)?;
-    match &input.data {
+    let mut code = match &input.data {
         Data::Struct(
-            data
-            @ DataStruct {
+            data @ DataStruct {
                 fields: Fields::Unit,
                 ..
             },
         ) => derive_unit(&input, data),
         Data::Struct(
-            data
-            @
-            DataStruct {
+            data @ DataStruct {
                 fields: Fields::Unnamed(..),
                 ..
             },
         ) => derive_tuple(&input, data),
         Data::Struct(
-            data
-            @
-            DataStruct {
+            data @ DataStruct {
                 fields: Fields::Named(..),
                 ..
             },
         ) => derive_standard(&input, data),
         Data::Enum(data) => derive_enum(&input, data),
         _ => todo!(),
-    }
+    }?;
+    code.extend(crate::arbitrary::derive(&input));
+    Ok(code)
 }
 
 pub fn pack(input: TokenStream) -> TokenStream {
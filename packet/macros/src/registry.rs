@@ -0,0 +1,64 @@
+//! JSON metadata of commands and events. (see `btmgmt_packet::registry_json`)
+use std::collections::HashMap;
+
+use quote::ToTokens;
+use syn::{Expr, ExprLit, Fields, Item, Lit, Type};
+
+/// Fields of structs in the module, by name.
+pub fn struct_fields(items: &[Item]) -> HashMap<String, Fields> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Struct(item) => Some((item.ident.to_string(), item.fields.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Value of an integer literal code.
+pub fn code(expr: &Expr) -> syn::Result<u16> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => lit.base10_parse(),
+        _ => Err(syn::Error::new_spanned(
+            expr,
+            "code must be an integer literal.",
+        )),
+    }
+}
+
+/// `[{"name": .., "type": ..}, ..]`. Tuple fields are named by position.
+pub fn fields(fields: &Fields) -> String {
+    let fields = fields
+        .iter()
+        .enumerate()
+        .map(|(n, field)| {
+            let name = match &field.ident {
+                Some(ident) => ident.to_string(),
+                None => n.to_string(),
+            };
+            format!(r#"{{"name":"{}","type":"{}"}}"#, name, type_name(&field.ty))
+        })
+        .collect::<Vec<_>>();
+    format!("[{}]", fields.join(","))
+}
+
+/// Type as written, relative to the crate root. (e.g. `Box<[u8]>`)
+fn type_name(ty: &Type) -> String {
+    let tokens = ty.to_token_stream().to_string();
+    let mut name = String::with_capacity(tokens.len());
+    let mut chars = tokens.chars().peekable();
+    while let Some(c) = chars.next() {
+        // keep spaces between words only. (e.g. `dyn Trait`)
+        if c == ' ' {
+            let before = name.chars().last().is_some_and(char::is_alphanumeric);
+            let after = chars.peek().is_some_and(|c| c.is_alphanumeric());
+            if !(before && after) {
+                continue;
+            }
+        }
+        name.push(c);
+    }
+    name.replace("super::", "").replace("crate::", "")
+}
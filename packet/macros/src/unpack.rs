@@ -1,9 +1,39 @@
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{Data, DataEnum, DataStruct, DeriveInput, Fields, Ident};
+use syn::{Data, DataEnum, DataStruct, DeriveInput, Fields, Ident, LitStr};
 
-fn derive_unit(item: &DeriveInput, _: &DataStruct) -> syn::Result<TokenStream> {
+/// `UnpackLenient` decoding each field, generated only with the `lenient` feature of the
+/// deriving crate. Generic structs are not supported.
+fn derive_lenient(item: &DeriveInput, data: &DataStruct) -> TokenStream {
+    if !item.generics.params.is_empty() {
+        return TokenStream::new();
+    }
+
+    let ident = &item.ident;
+    let names = data
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(n, f)| match &f.ident {
+            Some(ident) => LitStr::new(&ident.to_string(), ident.span()),
+            None => LitStr::new(&n.to_string(), ident.span()),
+        })
+        .collect::<Vec<_>>();
+    let types = data.fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+
+    quote! {
+        #[cfg(feature = "lenient")]
+        impl ::btmgmt_packet_helper::pack::lenient::UnpackLenient for #ident {
+            fn unpack_lenient(decoder: &mut ::btmgmt_packet_helper::pack::lenient::Decoder<'_>) {
+                #( decoder.field::<#types>(#names); )*
+            }
+        }
+    }
+}
+
+fn derive_unit(item: &DeriveInput, data: &DataStruct) -> syn::Result<TokenStream> {
     let ident = &item.ident;
+    let lenient = derive_lenient(item, data);
 
     let code = quote! {
         impl ::btmgmt_packet_helper::pack::Unpack for #ident {
@@ -11,6 +41,8 @@ fn derive_unit(item: &DeriveInput, _: &DataStruct) -> syn::Result<TokenStream> {
                 Ok(Self)
             }
         }
+
+        #lenient
     };
     Ok(code)
 }
@@ -21,6 +53,7 @@ fn derive_tuple(item: &DeriveInput, data: &DataStruct) -> syn::Result<TokenStrea
         .map(|_| TokenStream::new())
         .collect::<Vec<_>>();
     let (impl_generics, type_generics, where_clause) = item.generics.split_for_impl();
+    let lenient = derive_lenient(item, data);
 
     let code = quote! {
         impl #impl_generics ::btmgmt_packet_helper::pack::Unpack for #ident #type_generics #where_clause {
@@ -30,6 +63,8 @@ fn derive_tuple(item: &DeriveInput, data: &DataStruct) -> syn::Result<TokenStrea
                 ))
             }
         }
+
+        #lenient
     };
     Ok(code)
 }
@@ -42,15 +77,19 @@ fn derive_standard(item: &DeriveInput, data: &DataStruct) -> syn::Result<TokenSt
         .map(|f| f.ident.as_ref().unwrap())
         .collect::<Vec<_>>();
     let (impl_generics, type_generics, where_clause) = item.generics.split_for_impl();
+    let lenient = derive_lenient(item, data);
 
     let code = quote! {
         impl #impl_generics ::btmgmt_packet_helper::pack::Unpack for #ident #type_generics #where_clause {
             fn unpack<R>(read: &mut R) -> ::btmgmt_packet_helper::pack::Result<Self> where R: ::std::io::Read {
                 Ok(Self {
-                    #( #fields: ::btmgmt_packet_helper::pack::Unpack::unpack(read)?, )*
+                    #( #fields: ::btmgmt_packet_helper::pack::Unpack::unpack(read)
+                    .map_err(|e: ::btmgmt_packet_helper::pack::Error| e.in_field(stringify!(#fields)))?, )*
                 })
             }
         }
+
+        #lenient
     };
     Ok(code)
 }
@@ -88,7 +127,11 @@ fn derive_enum(item: &DeriveInput, data: &DataEnum) -> syn::Result<TokenStream>
                 #( const #fields: #ty = #ident::#fields as #ty; )*
                 Ok(match #ty::unpack(read)? {
                     #( #fields => Self::#fields, )*
-                    unknown => return Err(::btmgmt_packet_helper::pack::Error::UnexpectedValue(format!("{}", unknown))),
+                    unknown => return Err(::btmgmt_packet_helper::pack::Error::InvalidEnumValue {
+                        name: stringify!(#ident),
+                        field: None,
+                        value: unknown as u64,
+                    }),
                 })
             }
         }
@@ -100,24 +143,19 @@ fn derive(input: TokenStream) -> syn::Result<TokenStream> {
     let input = syn::parse2::<DeriveInput>(input)?;
     match &input.data {
         Data::Struct(
-            data
-            @ DataStruct {
+            data @ DataStruct {
                 fields: Fields::Unit,
                 ..
             },
         ) => derive_unit(&input, data),
         Data::Struct(
-            data
-            @
-            DataStruct {
+            data @ DataStruct {
                 fields: Fields::Unnamed(..),
                 ..
             },
         ) => derive_tuple(&input, data),
         Data::Struct(
-            data
-            @
-            DataStruct {
+            data @ DataStruct {
                 fields: Fields::Named(..),
                 ..
             },
@@ -0,0 +1,669 @@
+//! Advertising data and [`AddAdvertising`] builder.
+use crate::command::AddAdvertising;
+use crate::{
+    AdvDataScanResp, AdvertiseInstance, AdvertisingFlag, Discoverable, LengthTooLong, Seconds,
+};
+
+/// AD type: Flags
+pub const AD_TYPE_FLAGS: u8 = 0x01;
+/// AD type: Shortened Local Name
+pub const AD_TYPE_SHORT_NAME: u8 = 0x08;
+/// AD type: Complete Local Name
+pub const AD_TYPE_COMPLETE_NAME: u8 = 0x09;
+/// AD type: Tx Power Level
+pub const AD_TYPE_TX_POWER: u8 = 0x0A;
+/// AD type: Appearance
+pub const AD_TYPE_APPEARANCE: u8 = 0x19;
+
+/// Max length of legacy advertising data and scan response.
+pub const MAX_AD_LENGTH: usize = 31;
+
+/// Flags AD: LE Limited Discoverable Mode
+const LE_AD_LIMITED: u8 = 0x01;
+/// Flags AD: LE General Discoverable Mode
+const LE_AD_GENERAL: u8 = 0x02;
+/// Flags AD: BR/EDR Not Supported
+const LE_AD_NO_BREDR: u8 = 0x04;
+
+/// Longest name the kernel puts in as is. (`HCI_MAX_SHORT_NAME_LENGTH`)
+const MAX_SHORT_NAME_LENGTH: usize = 10;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AdvertisingParamsError {
+    #[error("malformed AD structure at offset {0}")]
+    Malformed(usize),
+
+    #[error("scan response contains local name (AD type {0:#04x}), but kernel appends it")]
+    LocalNameConflict(u8),
+
+    #[error("advertising data contains tx power (AD type {0:#04x}), but kernel appends it")]
+    TxPowerConflict(u8),
+
+    #[error(transparent)]
+    LengthTooLong(#[from] LengthTooLong),
+}
+
+/// AD structures in advertising data / EIR. Yields `(AD type, data)`.
+///
+/// Stops at zero length (significant part ended).
+/// Yields [`AdvertisingParamsError::Malformed`] once for a truncated structure.
+#[derive(Debug, Clone)]
+pub struct AdStructures<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> AdStructures<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+}
+
+impl<'a> Iterator for AdStructures<'a> {
+    type Item = Result<(u8, &'a [u8]), AdvertisingParamsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.data[self.offset..];
+        let len = *rest.first()? as usize;
+        if len == 0 {
+            self.offset = self.data.len();
+            return None;
+        }
+        if rest.len() < len + 1 {
+            let offset = self.offset;
+            self.offset = self.data.len();
+            return Some(Err(AdvertisingParamsError::Malformed(offset)));
+        }
+        self.offset += len + 1;
+        Some(Ok((rest[1], &rest[2..len + 1])))
+    }
+}
+
+fn find(data: &[u8], types: &[u8]) -> Result<Option<u8>, AdvertisingParamsError> {
+    for item in AdStructures::new(data) {
+        let (ty, _) = item?;
+        if types.contains(&ty) {
+            return Ok(Some(ty));
+        }
+    }
+    Ok(None)
+}
+
+fn push_ad(data: &mut Vec<u8>, ty: u8, value: &[u8]) {
+    data.push(value.len() as u8 + 1);
+    data.push(ty);
+    data.extend(value);
+}
+
+/// Controller state the kernel fills advertising fields from.
+/// See [`AdvertisingParams::predict`].
+#[derive(Debug, Clone)]
+pub struct AdvertisingContext {
+    name: Vec<u8>,
+    short_name: Vec<u8>,
+    appearance: u16,
+    discoverable: Discoverable,
+    bredr_enabled: bool,
+    tx_power: Option<i8>,
+}
+
+impl Default for AdvertisingContext {
+    fn default() -> Self {
+        Self {
+            name: vec![],
+            short_name: vec![],
+            appearance: 0,
+            discoverable: Discoverable::Disable,
+            bredr_enabled: false,
+            tx_power: None,
+        }
+    }
+}
+
+impl AdvertisingContext {
+    /// Local name, without the terminating NUL.
+    pub fn name<N: Into<Vec<u8>>>(mut self, name: N) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Short local name, without the terminating NUL.
+    pub fn short_name<N: Into<Vec<u8>>>(mut self, short_name: N) -> Self {
+        self.short_name = short_name.into();
+        self
+    }
+
+    pub fn appearance(mut self, appearance: u16) -> Self {
+        self.appearance = appearance;
+        self
+    }
+
+    /// Discoverable setting, for managed flags without discoverable instance flags.
+    pub fn discoverable(mut self, discoverable: Discoverable) -> Self {
+        self.discoverable = discoverable;
+        self
+    }
+
+    /// BR/EDR enabled. Otherwise the flags tell BR/EDR not supported.
+    pub fn bredr_enabled(mut self, bredr_enabled: bool) -> Self {
+        self.bredr_enabled = bredr_enabled;
+        self
+    }
+
+    /// Advertising tx power, if known.
+    pub fn tx_power(mut self, tx_power: Option<i8>) -> Self {
+        self.tx_power = tx_power;
+        self
+    }
+
+    /// Local name field appended to data of `len` bytes. (`eir_append_local_name`)
+    fn local_name(&self, len: usize) -> Vec<u8> {
+        let mut field = vec![];
+        if MAX_AD_LENGTH.saturating_sub(len) < MAX_SHORT_NAME_LENGTH + 2 {
+            return field;
+        }
+        if !self.name.is_empty() && self.name.len() <= MAX_SHORT_NAME_LENGTH {
+            push_ad(&mut field, AD_TYPE_COMPLETE_NAME, &self.name);
+        } else if !self.short_name.is_empty() {
+            let len = self.short_name.len().min(MAX_SHORT_NAME_LENGTH);
+            push_ad(&mut field, AD_TYPE_SHORT_NAME, &self.short_name[..len]);
+        } else if !self.name.is_empty() {
+            push_ad(
+                &mut field,
+                AD_TYPE_SHORT_NAME,
+                &self.name[..MAX_SHORT_NAME_LENGTH],
+            );
+        }
+        field
+    }
+}
+
+/// Fields added by the kernel per the instance flags, as in `net/bluetooth/eir.c`.
+///
+/// Advertising data is `flags, data, tx power`, and scan response is
+/// `appearance, data, local name`.
+struct KernelFields {
+    flags: Vec<u8>,
+    tx_power: Vec<u8>,
+    appearance: Vec<u8>,
+    /// Reserved for the local name. The field itself depends on the space left.
+    local_name: usize,
+}
+
+impl KernelFields {
+    fn new(flags: AdvertisingFlag, context: &AdvertisingContext) -> Self {
+        let mut result = Self {
+            flags: vec![],
+            tx_power: vec![],
+            appearance: vec![],
+            local_name: 0,
+        };
+
+        let mut ad_flags = 0;
+        if flags.contains(AdvertisingFlag::AdvertiseAsDiscoverable) {
+            ad_flags |= LE_AD_GENERAL;
+        }
+        if flags.contains(AdvertisingFlag::AdvertiseAsLimitedDiscoverable) {
+            ad_flags |= LE_AD_LIMITED;
+        }
+        if ad_flags != 0 || flags.contains(AdvertisingFlag::AddFlagsFieldToAdvData) {
+            if ad_flags == 0 {
+                ad_flags = match context.discoverable {
+                    Discoverable::Disable => 0,
+                    Discoverable::General => LE_AD_GENERAL,
+                    Discoverable::Limited => LE_AD_LIMITED,
+                };
+            }
+            if !context.bredr_enabled {
+                ad_flags |= LE_AD_NO_BREDR;
+            }
+            if ad_flags != 0 {
+                push_ad(&mut result.flags, AD_TYPE_FLAGS, &[ad_flags]);
+            }
+        }
+        if flags.contains(AdvertisingFlag::AddTxPowerFieldToAdvData) {
+            if let Some(tx_power) = context.tx_power {
+                push_ad(
+                    &mut result.tx_power,
+                    AD_TYPE_TX_POWER,
+                    &tx_power.to_le_bytes(),
+                );
+            }
+        }
+        if flags.contains(AdvertisingFlag::AddAppearanceFieldToScanResp) {
+            push_ad(
+                &mut result.appearance,
+                AD_TYPE_APPEARANCE,
+                &context.appearance.to_le_bytes(),
+            );
+        }
+        if flags.contains(AdvertisingFlag::AddLocalNameInScanResp) {
+            result.local_name = context.local_name(0).len();
+        }
+        result
+    }
+}
+
+/// Bytes of user data fitting with the kernel fields. See [`AdvertisingParams::budget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdvertisingBudget {
+    adv_data: usize,
+    scan_resp: usize,
+}
+
+impl AdvertisingBudget {
+    /// Bytes available for advertising data.
+    pub fn adv_data(&self) -> usize {
+        self.adv_data
+    }
+
+    /// Bytes available for scan response.
+    pub fn scan_resp(&self) -> usize {
+        self.scan_resp
+    }
+}
+
+/// Advertising data and scan response expected on air. See [`AdvertisingParams::predict`].
+///
+/// Predicted from the request and the controller state, not captured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PredictedAdvertising {
+    adv_data: Vec<u8>,
+    scan_resp: Vec<u8>,
+    tx_power_unknown: bool,
+}
+
+impl PredictedAdvertising {
+    pub fn adv_data(&self) -> &[u8] {
+        &self.adv_data
+    }
+
+    pub fn scan_resp(&self) -> &[u8] {
+        &self.scan_resp
+    }
+
+    /// The kernel adds the tx power, but it is unknown, so the field is missing in
+    /// [`Self::adv_data`].
+    pub fn tx_power_unknown(&self) -> bool {
+        self.tx_power_unknown
+    }
+}
+
+/// Builder for [`AddAdvertising`].
+///
+/// Fields managed by the kernel (see [`Self::kernel_appends_local_name`] and
+/// [`Self::kernel_appends_tx_power`]) must not be included in the user-supplied data.
+/// Some kernels send duplicated fields, others reply `InvalidParameters`,
+/// so [`Self::build`] rejects them.
+#[derive(Debug, Clone)]
+pub struct AdvertisingParams {
+    instance: u8,
+    flags: AdvertisingFlag,
+    duration: Seconds,
+    timeout: Seconds,
+    adv_data: Vec<u8>,
+    scan_resp: Vec<u8>,
+}
+
+impl AdvertisingParams {
+    pub fn new(instance: u8) -> Self {
+        Self {
+            instance,
+            flags: AdvertisingFlag::empty(),
+            duration: Seconds::default(),
+            timeout: Seconds::default(),
+            adv_data: vec![],
+            scan_resp: vec![],
+        }
+    }
+
+    pub fn instance(mut self, instance: u8) -> Self {
+        self.instance = instance;
+        self
+    }
+
+    pub fn flags(mut self, flags: AdvertisingFlag) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Rotation time in seconds, `0` for the kernel default.
+    pub fn duration<S: Into<Seconds>>(mut self, duration: S) -> Self {
+        self.duration = duration.into();
+        self
+    }
+
+    /// Lifetime in seconds, `0` for none.
+    pub fn timeout<S: Into<Seconds>>(mut self, timeout: S) -> Self {
+        self.timeout = timeout.into();
+        self
+    }
+
+    pub fn adv_data<D: Into<Vec<u8>>>(mut self, adv_data: D) -> Self {
+        self.adv_data = adv_data.into();
+        self
+    }
+
+    pub fn scan_resp<D: Into<Vec<u8>>>(mut self, scan_resp: D) -> Self {
+        self.scan_resp = scan_resp.into();
+        self
+    }
+
+    /// Let the kernel append the local name to scan response.
+    /// ([`AdvertisingFlag::AddLocalNameInScanResp`])
+    pub fn kernel_appends_local_name(mut self, enable: bool) -> Self {
+        self.flags
+            .set(AdvertisingFlag::AddLocalNameInScanResp, enable);
+        self
+    }
+
+    /// Let the kernel append the tx power to advertising data.
+    /// ([`AdvertisingFlag::AddTxPowerFieldToAdvData`])
+    pub fn kernel_appends_tx_power(mut self, enable: bool) -> Self {
+        self.flags
+            .set(AdvertisingFlag::AddTxPowerFieldToAdvData, enable);
+        self
+    }
+
+    /// Bytes of the advertising data and scan response left for the data, after the
+    /// fields the kernel adds per the flags. (`tlv_data_max_len`)
+    ///
+    /// The tx power field is counted even if unknown, as the kernel does.
+    pub fn budget(&self, context: &AdvertisingContext) -> AdvertisingBudget {
+        // the tx power is not known to be invalid here.
+        let tx_power = context.tx_power.or(Some(0));
+        let fields = KernelFields::new(self.flags, &context.clone().tx_power(tx_power));
+        AdvertisingBudget {
+            adv_data: MAX_AD_LENGTH.saturating_sub(fields.flags.len() + fields.tx_power.len()),
+            scan_resp: MAX_AD_LENGTH.saturating_sub(fields.appearance.len() + fields.local_name),
+        }
+    }
+
+    /// Predict advertising data and scan response on air, with the fields the kernel
+    /// adds per the flags from `context`.
+    ///
+    /// Follows `net/bluetooth/eir.c` of Linux 6.x for legacy advertising. Older kernels
+    /// differ in details. (e.g. a terminating NUL in the local name)
+    pub fn predict(&self, context: &AdvertisingContext) -> PredictedAdvertising {
+        let fields = KernelFields::new(self.flags, context);
+
+        let mut adv_data = fields.flags;
+        adv_data.extend(&self.adv_data);
+        adv_data.extend(fields.tx_power);
+
+        let mut scan_resp = fields.appearance;
+        scan_resp.extend(&self.scan_resp);
+        if self.flags.contains(AdvertisingFlag::AddLocalNameInScanResp) {
+            scan_resp.extend(context.local_name(scan_resp.len()));
+        }
+
+        PredictedAdvertising {
+            adv_data,
+            scan_resp,
+            tx_power_unknown: self
+                .flags
+                .contains(AdvertisingFlag::AddTxPowerFieldToAdvData)
+                && context.tx_power.is_none(),
+        }
+    }
+
+    /// Validate and build command.
+    pub fn build(self) -> Result<AddAdvertising, AdvertisingParamsError> {
+        if self.flags.contains(AdvertisingFlag::AddLocalNameInScanResp) {
+            if let Some(ty) = find(
+                &self.scan_resp,
+                &[AD_TYPE_SHORT_NAME, AD_TYPE_COMPLETE_NAME],
+            )? {
+                return Err(AdvertisingParamsError::LocalNameConflict(ty));
+            }
+        }
+        if self
+            .flags
+            .contains(AdvertisingFlag::AddTxPowerFieldToAdvData)
+        {
+            if let Some(ty) = find(&self.adv_data, &[AD_TYPE_TX_POWER])? {
+                return Err(AdvertisingParamsError::TxPowerConflict(ty));
+            }
+        }
+
+        Ok(AddAdvertising::new(
+            AdvertiseInstance::new(self.instance),
+            self.flags,
+            self.duration,
+            self.timeout,
+            AdvDataScanResp::try_new(self.adv_data, self.scan_resp)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::pack_command;
+    use crate::ControllerIndex;
+
+    fn ad(ty: u8, data: &[u8]) -> Vec<u8> {
+        let mut b = vec![data.len() as u8 + 1, ty];
+        b.extend(data);
+        b
+    }
+
+    #[test]
+    fn test_ad_structures() {
+        let mut data = ad(0x01, &[0x06]);
+        data.extend(ad(AD_TYPE_COMPLETE_NAME, b"test"));
+        data.extend([0x00, 0x00]); // padding
+
+        let items = AdStructures::new(&data)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            vec![(0x01, &[0x06][..]), (AD_TYPE_COMPLETE_NAME, &b"test"[..])],
+            items
+        );
+
+        let items = AdStructures::new(&[0x02, 0x01, 0x06, 0x05, 0x09]).collect::<Vec<_>>();
+        assert!(matches!(
+            items[..],
+            [Ok(..), Err(AdvertisingParamsError::Malformed(3))]
+        ));
+    }
+
+    #[test]
+    fn test_local_name_conflict() {
+        let err = AdvertisingParams::new(1)
+            .scan_resp(ad(AD_TYPE_SHORT_NAME, b"bt"))
+            .kernel_appends_local_name(true)
+            .build()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            AdvertisingParamsError::LocalNameConflict(0x08)
+        ));
+
+        // name in advertising data is not managed by the flag.
+        AdvertisingParams::new(1)
+            .adv_data(ad(AD_TYPE_COMPLETE_NAME, b"bt"))
+            .kernel_appends_local_name(true)
+            .build()
+            .unwrap();
+
+        AdvertisingParams::new(1)
+            .scan_resp(ad(AD_TYPE_SHORT_NAME, b"bt"))
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_tx_power_conflict() {
+        let mut adv_data = ad(0x01, &[0x06]);
+        adv_data.extend(ad(AD_TYPE_TX_POWER, &[0x00]));
+        let err = AdvertisingParams::new(1)
+            .adv_data(adv_data.clone())
+            .kernel_appends_tx_power(true)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, AdvertisingParamsError::TxPowerConflict(0x0A)));
+
+        let err = AdvertisingParams::new(1)
+            .adv_data(adv_data)
+            .flags(AdvertisingFlag::AddTxPowerFieldToAdvData)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, AdvertisingParamsError::TxPowerConflict(..)));
+    }
+
+    #[test]
+    fn test_build() {
+        let adv_data = ad(0x01, &[0x06]);
+        let command = AdvertisingParams::new(1)
+            .adv_data(adv_data.clone())
+            .kernel_appends_local_name(true)
+            .kernel_appends_tx_power(true)
+            .duration(2)
+            .build()
+            .unwrap();
+
+        let mut b = vec![];
+        pack_command(&ControllerIndex::ControllerId(0), &command.into(), &mut b).unwrap();
+        let mut expected = vec![0x3E, 0x00, 0x00, 0x00, 0x0E, 0x00];
+        expected.extend([0x01]); // instance
+        expected.extend([0x50, 0x00, 0x00, 0x00]); // flags
+        expected.extend([0x02, 0x00, 0x00, 0x00]); // duration, timeout
+        expected.extend([0x03, 0x00]); // lengths
+        expected.extend(&adv_data);
+        assert_eq!(expected, b);
+
+        // round trip through the parser.
+        let items = AdStructures::new(&b[expected.len() - adv_data.len()..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(vec![(0x01, &[0x06][..])], items);
+    }
+
+    /// On-air data of three flag combinations, as btmon shows for
+    /// LE Set Advertising Data / LE Set Scan Response Data.
+    #[test]
+    fn test_predict() {
+        // Flags: 0x06 (LE General Discoverable Mode, BR/EDR Not Supported)
+        // 16-bit Service UUIDs (complete): Battery Service (0x180f)
+        // TX power: -7 dBm
+        let predicted = AdvertisingParams::new(1)
+            .flags(AdvertisingFlag::AddFlagsFieldToAdvData)
+            .kernel_appends_tx_power(true)
+            .adv_data(ad(0x03, &[0x0F, 0x18]))
+            .predict(
+                &AdvertisingContext::default()
+                    .discoverable(Discoverable::General)
+                    .tx_power(Some(-7)),
+            );
+        assert_eq!(
+            [0x02, 0x01, 0x06, 0x03, 0x03, 0x0F, 0x18, 0x02, 0x0A, 0xF9],
+            predicted.adv_data()
+        );
+        assert!(predicted.scan_resp().is_empty());
+        assert!(!predicted.tx_power_unknown());
+
+        // Flags: 0x02 (LE General Discoverable Mode)
+        // Name (complete): btmgmt
+        let predicted = AdvertisingParams::new(1)
+            .flags(AdvertisingFlag::AdvertiseAsDiscoverable)
+            .kernel_appends_local_name(true)
+            .predict(
+                &AdvertisingContext::default()
+                    .name("btmgmt")
+                    .bredr_enabled(true),
+            );
+        assert_eq!([0x02, 0x01, 0x02], predicted.adv_data());
+        let mut scan_resp = vec![0x07, 0x09];
+        scan_resp.extend(b"btmgmt");
+        assert_eq!(scan_resp, predicted.scan_resp());
+
+        // Flags: 0x05 (LE Limited Discoverable Mode, BR/EDR Not Supported)
+        // Appearance: Keyboard (0x03c1)
+        // Company: not assigned (65535)
+        // Name (short): btmgmt-per
+        let predicted = AdvertisingParams::new(1)
+            .flags(
+                AdvertisingFlag::AdvertiseAsLimitedDiscoverable
+                    | AdvertisingFlag::AddAppearanceFieldToScanResp,
+            )
+            .kernel_appends_local_name(true)
+            .scan_resp(ad(0xFF, &[0xFF, 0xFF, 0x01]))
+            .predict(
+                &AdvertisingContext::default()
+                    .name("btmgmt-peripheral")
+                    .appearance(0x03C1)
+                    .discoverable(Discoverable::General),
+            );
+        assert_eq!([0x02, 0x01, 0x05], predicted.adv_data());
+        let mut scan_resp = vec![0x03, 0x19, 0xC1, 0x03, 0x04, 0xFF, 0xFF, 0xFF, 0x01];
+        scan_resp.extend([0x0B, 0x08]);
+        scan_resp.extend(b"btmgmt-per");
+        assert_eq!(scan_resp, predicted.scan_resp());
+    }
+
+    #[test]
+    fn test_predict_unknown() {
+        // no flags field: not discoverable, BR/EDR enabled.
+        let params = AdvertisingParams::new(1)
+            .flags(AdvertisingFlag::AddFlagsFieldToAdvData)
+            .kernel_appends_tx_power(true)
+            .kernel_appends_local_name(true)
+            .scan_resp(vec![0xAA; 20]);
+        let context = AdvertisingContext::default()
+            .name("btmgmt")
+            .short_name("bt")
+            .bredr_enabled(true);
+        let predicted = params.predict(&context);
+        assert!(predicted.adv_data().is_empty());
+        assert!(predicted.tx_power_unknown());
+        // no space left for the name.
+        assert_eq!(&[0xAA; 20][..], predicted.scan_resp());
+
+        let predicted = params.predict(&context.clone().name("btmgmt-peripheral"));
+        assert_eq!(&[0xAA; 20][..], predicted.scan_resp());
+        let predicted = params
+            .clone()
+            .scan_resp(vec![])
+            .predict(&context.name("btmgmt-peripheral"));
+        assert_eq!([0x03, 0x08, b'b', b't'], predicted.scan_resp());
+    }
+
+    #[test]
+    fn test_budget() {
+        let context = AdvertisingContext::default().name("btmgmt");
+        let params = AdvertisingParams::new(1);
+        assert_eq!(31, params.budget(&context).adv_data());
+        assert_eq!(31, params.budget(&context).scan_resp());
+
+        let params = params
+            .flags(
+                AdvertisingFlag::AddFlagsFieldToAdvData
+                    | AdvertisingFlag::AddAppearanceFieldToScanResp,
+            )
+            .kernel_appends_tx_power(true)
+            .kernel_appends_local_name(true);
+        let budget = params.budget(&context);
+        assert_eq!(25, budget.adv_data());
+        assert_eq!(31 - 4 - 8, budget.scan_resp());
+
+        let predicted = params
+            .adv_data(vec![0xAA; budget.adv_data()])
+            .scan_resp(vec![0xBB; budget.scan_resp()])
+            .predict(&context.tx_power(Some(0)));
+        assert_eq!(31, predicted.adv_data().len());
+        // the name is reserved by its length, but added only with room for a
+        // shortened name left.
+        assert_eq!(31 - 8, predicted.scan_resp().len());
+    }
+
+    #[test]
+    fn test_build_too_long() {
+        let err = AdvertisingParams::new(1)
+            .adv_data(vec![0; 256])
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, AdvertisingParamsError::LengthTooLong(..)));
+    }
+}
@@ -0,0 +1,177 @@
+//! `arbitrary::Arbitrary` for types not derivable. (feature `arbitrary`)
+//!
+//! Values are valid on the wire: addresses have a known random address type, names are
+//! NUL terminated, bitflags are of the known bits and lengths are bounded.
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use super::*;
+
+/// Max length of bytes such as EIR data. Within the 8-bit length fields.
+const MAX_BYTES: usize = 255;
+
+/// Max number of items of lists.
+const MAX_ITEMS: usize = 16;
+
+#[doc(hidden)]
+pub fn bytes(u: &mut Unstructured<'_>) -> Result<Box<[u8]>> {
+    let len = u.int_in_range(0..=MAX_BYTES)?.min(u.len());
+    Ok(u.bytes(len)?.into())
+}
+
+#[doc(hidden)]
+pub fn vec<'a, T: Arbitrary<'a>>(u: &mut Unstructured<'a>) -> Result<Vec<T>> {
+    let len = u.int_in_range(0..=MAX_ITEMS)?;
+    (0..len).map(|_| u.arbitrary()).collect()
+}
+
+/// Any address of [`InternalAddressType`], paired or not.
+///
+/// The two most significant bits `0b10` are reserved for random addresses, and not generated.
+impl<'a> Arbitrary<'a> for WrappedAddress {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut b = <[u8; 6]>::arbitrary(u)?;
+        if b[5] & 0xC0 == 0x80 {
+            b[5] |= 0xC0;
+        }
+        Ok(Self(b.into()))
+    }
+}
+
+/// Known types only.
+impl<'a> Arbitrary<'a> for InternalAddressType {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.choose_index(3)? {
+            0 => Self::BrEdr,
+            1 => Self::LePublic,
+            _ => Self::LeRandom,
+        })
+    }
+}
+
+/// [`Address`] is foreign, so for lists of [`command::GetConnectionsReply`] only.
+fn address(u: &mut Unstructured<'_>) -> Result<Address> {
+    let address = WrappedAddress::arbitrary(u)?;
+    let address_type = InternalAddressType::arbitrary(u)?;
+    Ok(join(&address_type, &address))
+}
+
+impl<'a> Arbitrary<'a> for command::GetConnectionsReply {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let len = u.int_in_range(0..=MAX_ITEMS)?;
+        (0..len).map(|_| address(u)).collect()
+    }
+}
+
+/// Known types only.
+impl<'a> Arbitrary<'a> for AddressTypes {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let types = [
+            AddressType::BrEdr,
+            AddressType::LePublic,
+            AddressType::LeRandom,
+        ];
+        let mut result = Self::default();
+        for ty in types {
+            if u.arbitrary()? {
+                result.0.insert(ty);
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// [`ControllerIndex::NonController`] for `0xFFFF`, as on the wire.
+impl<'a> Arbitrary<'a> for ControllerIndex {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(u16::arbitrary(u)?.into())
+    }
+}
+
+impl<'a> Arbitrary<'a> for Uuid {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self(uuid::Uuid::from_u128(u.arbitrary()?)))
+    }
+}
+
+/// NUL terminated within `N` bytes, without NUL in the middle, and padded by NUL.
+impl<'a, const N: usize> Arbitrary<'a> for FixedLengthName<N> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let len = u.int_in_range(0..=N - 1)?;
+        let name = (0..len)
+            .map(|_| u.int_in_range(1..=u8::MAX))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::new(name).expect("no NUL and shorter than N"))
+    }
+}
+
+impl<'a, L> Arbitrary<'a> for VariableLengthBytes<L> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self(bytes(u)?, PhantomData))
+    }
+}
+
+impl<'a> Arbitrary<'a> for AdvDataScanResp {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self(bytes(u)?, bytes(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for AdvertiseInstances {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self(vec(u)?))
+    }
+}
+
+impl<'a, T: Arbitrary<'a>> Arbitrary<'a> for Remaining<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self(vec(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for CommandsEvents {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            commands: vec(u)?,
+            events: vec(u)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{pack_events, unpack_events, Event};
+
+    /// xorshift64, for reproducible inputs.
+    fn input(seed: &mut u64) -> Vec<u8> {
+        (0..512)
+            .map(|_| {
+                *seed ^= *seed << 13;
+                *seed ^= *seed >> 7;
+                *seed ^= *seed << 17;
+                *seed as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_events_round_trip() {
+        let mut seed = 0x2545_F491_4F6C_DD1D;
+        for _ in 0..1000 {
+            let input = input(&mut seed);
+            let mut u = Unstructured::new(&input);
+            let index = ControllerIndex::arbitrary(&mut u).unwrap();
+            let event = Event::arbitrary(&mut u).unwrap();
+
+            let mut packed = vec![];
+            pack_events(&index, &event, &mut packed).unwrap();
+            let (unpacked_index, unpacked) = unpack_events(&mut &packed[..])
+                .unwrap_or_else(|err| panic!("{:?}: {}", event, err));
+            assert!(!matches!(unpacked, Event::Unknown(..)), "{:?}", event);
+
+            let mut repacked = vec![];
+            pack_events(&unpacked_index, &unpacked, &mut repacked).unwrap();
+            assert_eq!(packed, repacked, "{:?}", event);
+        }
+    }
+}
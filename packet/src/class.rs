@@ -0,0 +1,166 @@
+//! Assigned numbers of the Class of Device fields, for [`ClassOfDevice::from_parts`](super::ClassOfDevice::from_parts).
+//!
+//! Minor classes depend on the major class. The peripheral, LAN and imaging minor
+//! classes are bit fields, not enumerated here.
+use std::convert::TryFrom;
+
+use bitflags::bitflags;
+
+macro_rules! device_class {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$vmeta:meta])* $variant:ident = $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$vmeta])* $variant = $value,)*
+        }
+
+        /// From the field value. Unassigned values are returned as the error.
+        impl TryFrom<u8> for $name {
+            type Error = u8;
+            fn try_from(v: u8) -> Result<Self, Self::Error> {
+                match v {
+                    $($value => Ok(Self::$variant),)*
+                    _ => Err(v),
+                }
+            }
+        }
+
+        impl From<$name> for u8 {
+            fn from(v: $name) -> Self {
+                v as u8
+            }
+        }
+    };
+}
+
+device_class! {
+    /// Major Device Class. (CoD bits 8-12)
+    pub enum MajorDeviceClass {
+        Miscellaneous = 0x00,
+        Computer = 0x01,
+        Phone = 0x02,
+        LanNetworkAccessPoint = 0x03,
+        AudioVideo = 0x04,
+        Peripheral = 0x05,
+        Imaging = 0x06,
+        Wearable = 0x07,
+        Toy = 0x08,
+        Health = 0x09,
+        Uncategorized = 0x1F,
+    }
+}
+
+device_class! {
+    /// Minor Device Class of [`MajorDeviceClass::Computer`].
+    pub enum MinorDeviceClassComputer {
+        Uncategorized = 0x00,
+        Desktop = 0x01,
+        Server = 0x02,
+        Laptop = 0x03,
+        HandheldPcPda = 0x04,
+        PalmSizePcPda = 0x05,
+        Wearable = 0x06,
+        Tablet = 0x07,
+    }
+}
+
+device_class! {
+    /// Minor Device Class of [`MajorDeviceClass::Phone`].
+    pub enum MinorDeviceClassPhone {
+        Uncategorized = 0x00,
+        Cellular = 0x01,
+        Cordless = 0x02,
+        Smartphone = 0x03,
+        WiredModemOrVoiceGateway = 0x04,
+        CommonIsdnAccess = 0x05,
+    }
+}
+
+device_class! {
+    /// Minor Device Class of [`MajorDeviceClass::AudioVideo`].
+    pub enum MinorDeviceClassAudioVideo {
+        Uncategorized = 0x00,
+        WearableHeadset = 0x01,
+        HandsFree = 0x02,
+        Microphone = 0x04,
+        Loudspeaker = 0x05,
+        Headphones = 0x06,
+        PortableAudio = 0x07,
+        CarAudio = 0x08,
+        SetTopBox = 0x09,
+        HifiAudio = 0x0A,
+        Vcr = 0x0B,
+        VideoCamera = 0x0C,
+        Camcorder = 0x0D,
+        VideoMonitor = 0x0E,
+        VideoDisplayAndLoudspeaker = 0x0F,
+        VideoConferencing = 0x10,
+        GamingToy = 0x12,
+    }
+}
+
+device_class! {
+    /// Minor Device Class of [`MajorDeviceClass::Wearable`].
+    pub enum MinorDeviceClassWearable {
+        Wristwatch = 0x01,
+        Pager = 0x02,
+        Jacket = 0x03,
+        Helmet = 0x04,
+        Glasses = 0x05,
+        Pin = 0x06,
+    }
+}
+
+device_class! {
+    /// Minor Device Class of [`MajorDeviceClass::Toy`].
+    pub enum MinorDeviceClassToy {
+        Robot = 0x01,
+        Vehicle = 0x02,
+        Doll = 0x03,
+        Controller = 0x04,
+        Game = 0x05,
+    }
+}
+
+device_class! {
+    /// Minor Device Class of [`MajorDeviceClass::Health`].
+    pub enum MinorDeviceClassHealth {
+        Undefined = 0x00,
+        BloodPressureMonitor = 0x01,
+        Thermometer = 0x02,
+        WeighingScale = 0x03,
+        GlucoseMeter = 0x04,
+        PulseOximeter = 0x05,
+        HeartPulseRateMonitor = 0x06,
+        HealthDataDisplay = 0x07,
+        StepCounter = 0x08,
+        BodyCompositionAnalyzer = 0x09,
+        PeakFlowMonitor = 0x0A,
+        MedicationMonitor = 0x0B,
+        KneeProsthesis = 0x0C,
+        AnkleProsthesis = 0x0D,
+        GenericHealthManager = 0x0E,
+        PersonalMobilityDevice = 0x0F,
+    }
+}
+
+bitflags! {
+    /// Major Service Classes. (CoD bits 13-23, shifted to bit 0)
+    pub struct ServiceClasses: u16 {
+        const LimitedDiscoverableMode = 1 << 0;
+        const LeAudio = 1 << 1;
+        const Positioning = 1 << 3;
+        const Networking = 1 << 4;
+        const Rendering = 1 << 5;
+        const Capturing = 1 << 6;
+        const ObjectTransfer = 1 << 7;
+        const Audio = 1 << 8;
+        const Telephony = 1 << 9;
+        const Information = 1 << 10;
+    }
+}
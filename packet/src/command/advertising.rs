@@ -0,0 +1,237 @@
+//! Advertising commands.
+use super::*;
+
+#[commands(name = Command, trait = CommandRequest, codes = CommandCode, group)]
+mod imp {
+    use super::*;
+
+    /// Set Advertising Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0029, reply = SetAdvertisingReply)]
+    pub struct SetAdvertising(super::Advertising);
+
+    /// Reply for [`SetAdvertising`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetAdvertisingReply(super::Settings);
+
+    /// Read Advertising Features Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x003D, reply = ReadAdvertisingFeatureReply)]
+    pub struct ReadAdvertisingFeature;
+
+    /// Reply for [`ReadAdvertisingFeature`]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[derive(Debug, Unpack, Getters)]
+    #[getset(get = "pub")]
+    pub struct ReadAdvertisingFeatureReply {
+        supported_flags: super::AdvertisingFlag,
+        max_adv_data_len: u8,
+        max_scan_resp_len: u8,
+        max_instances: u8,
+        instances: super::AdvertiseInstances,
+    }
+
+    impl ReadAdvertisingFeatureReply {
+        /// Take the fields apart.
+        pub fn into_parts(self) -> ReadAdvertisingFeatureParts {
+            self.into()
+        }
+    }
+
+    /// Fields of [`ReadAdvertisingFeatureReply`], for destructuring.
+    #[derive(Debug)]
+    pub struct ReadAdvertisingFeatureParts {
+        pub supported_flags: super::AdvertisingFlag,
+        pub max_adv_data_len: u8,
+        pub max_scan_resp_len: u8,
+        pub max_instances: u8,
+        pub instances: super::AdvertiseInstances,
+    }
+
+    impl From<ReadAdvertisingFeatureReply> for ReadAdvertisingFeatureParts {
+        fn from(v: ReadAdvertisingFeatureReply) -> Self {
+            Self {
+                supported_flags: v.supported_flags,
+                max_adv_data_len: v.max_adv_data_len,
+                max_scan_resp_len: v.max_scan_resp_len,
+                max_instances: v.max_instances,
+                instances: v.instances,
+            }
+        }
+    }
+
+    /// Add Advertising Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Getters)]
+    #[command(code = 0x003E, reply = AddAdvertisingReply)]
+    #[getset(get = "pub")]
+    pub struct AddAdvertising {
+        instance: super::AdvertiseInstance,
+        flags: super::AdvertisingFlag,
+        duration: super::Seconds,
+        timeout: super::Seconds,
+        adv_data_scan_resp: super::AdvDataScanResp,
+    }
+
+    impl AddAdvertising {
+        /// `duration` of each rotation and `timeout` of the instance in seconds, `0` for
+        /// the default and none.
+        pub fn new<D, T>(
+            instance: super::AdvertiseInstance,
+            flags: super::AdvertisingFlag,
+            duration: D,
+            timeout: T,
+            adv_data_scan_resp: super::AdvDataScanResp,
+        ) -> Self
+        where
+            D: Into<super::Seconds>,
+            T: Into<super::Seconds>,
+        {
+            Self {
+                instance,
+                flags,
+                duration: duration.into(),
+                timeout: timeout.into(),
+                adv_data_scan_resp,
+            }
+        }
+    }
+
+    /// Reply for [`AddAdvertising`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct AddAdvertisingReply(super::AdvertiseInstance);
+
+    /// Remove Advertising Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x003F, reply = RemoveAdvertisingReply)]
+    pub struct RemoveAdvertising(super::AdvertiseInstance);
+
+    /// Reply for [`RemoveAdvertising`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct RemoveAdvertisingReply(super::AdvertiseInstance);
+
+    /// Get Advertising Size Information Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, New)]
+    #[command(code = 0x0040, reply = GetAdvertisingSizeInformationReply)]
+    pub struct GetAdvertisingSizeInformation {
+        instance: super::AdvertiseInstance,
+        flags: super::AdvertisingFlag,
+    }
+
+    /// Reply for [`GetAdvertisingSizeInformation`]
+    #[derive(Debug, Unpack, Getters)]
+    #[getset(get = "pub")]
+    pub struct GetAdvertisingSizeInformationReply {
+        instance: super::AdvertiseInstance,
+        flags: super::AdvertisingFlag,
+        max_adv_data_len: u8,
+        max_scan_resp_len: u8,
+    }
+
+    /// Add Extended Advertising Parameters Command
+    ///
+    /// Followed by [`AddExtendedAdvertisingData`] for the instance. The PHYs are chosen by
+    /// the `SecondaryChannelWith` flags: LE Coded on both channels with
+    /// `SecondaryChannelWithLeCoded`, otherwise LE 1M on the primary channel.
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0054, reply = AddExtendedAdvertisingParametersReply)]
+    pub struct AddExtendedAdvertisingParameters {
+        instance: super::AdvertiseInstance,
+        flags: super::AdvertisingFlag,
+        duration: super::Seconds,
+        timeout: super::Seconds,
+        min_interval: u32,
+        max_interval: u32,
+        tx_power: i8,
+    }
+
+    impl AddExtendedAdvertisingParameters {
+        /// Parameters left to the kernel defaults, until set by the methods.
+        pub fn new(instance: super::AdvertiseInstance, flags: super::AdvertisingFlag) -> Self {
+            Self {
+                instance,
+                flags,
+                duration: Default::default(),
+                timeout: Default::default(),
+                min_interval: 0,
+                max_interval: 0,
+                tx_power: 0,
+            }
+        }
+
+        /// Duration of each rotation in seconds.
+        pub fn duration<D: Into<super::Seconds>>(mut self, duration: D) -> Self {
+            self.duration = duration.into();
+            self.flags |= super::AdvertisingFlag::ParamDuration;
+            self
+        }
+
+        /// Timeout of the instance in seconds.
+        pub fn timeout<T: Into<super::Seconds>>(mut self, timeout: T) -> Self {
+            self.timeout = timeout.into();
+            self.flags |= super::AdvertisingFlag::ParamTimeout;
+            self
+        }
+
+        /// Advertising intervals in 0.625 ms units.
+        pub fn interval(mut self, min_interval: u32, max_interval: u32) -> Self {
+            self.min_interval = min_interval;
+            self.max_interval = max_interval;
+            self.flags |= super::AdvertisingFlag::ParamIntervals;
+            self
+        }
+
+        /// Tx power in dBm. `127` for no preference.
+        pub fn tx_power(mut self, tx_power: i8) -> Self {
+            self.tx_power = tx_power;
+            self.flags |= super::AdvertisingFlag::ParamTxPower;
+            self
+        }
+    }
+
+    /// Reply for [`AddExtendedAdvertisingParameters`]
+    #[derive(Debug, Unpack, Getters)]
+    #[getset(get = "pub")]
+    pub struct AddExtendedAdvertisingParametersReply {
+        instance: super::AdvertiseInstance,
+        /// Selected tx power in dBm.
+        tx_power: i8,
+        max_adv_data_len: u8,
+        max_scan_resp_len: u8,
+    }
+
+    /// Add Extended Advertising Data Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Getters, New)]
+    #[command(code = 0x0055, reply = AddExtendedAdvertisingDataReply)]
+    #[getset(get = "pub")]
+    pub struct AddExtendedAdvertisingData {
+        instance: super::AdvertiseInstance,
+        adv_data_scan_resp: super::AdvDataScanResp,
+    }
+
+    /// Reply for [`AddExtendedAdvertisingData`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct AddExtendedAdvertisingDataReply(super::AdvertiseInstance);
+}
+
+pub use imp::*;
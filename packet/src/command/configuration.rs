@@ -0,0 +1,197 @@
+//! Controller configuration, security and experimental feature commands.
+use super::*;
+
+#[commands(name = Command, trait = CommandRequest, codes = CommandCode, group)]
+mod imp {
+    use super::*;
+
+    /// Read Unconfigured Controller Index List Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0036, reply = ReadUnconfiguredControllerIndexListReply)]
+    pub struct ReadUnconfiguredControllerIndexList;
+
+    /// Reply for [`ReadUnconfiguredControllerIndexList`]
+    #[derive(Debug, Unpack, IterNewtype)]
+    pub struct ReadUnconfiguredControllerIndexListReply(Vec<ControllerIndex>);
+
+    /// Read Controller Configuration Information Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0037, reply = ReadControllerConfigurationInformationReply)]
+    pub struct ReadControllerConfigurationInformation;
+
+    /// Reply for [`ReadControllerConfigurationInformation`]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[derive(Debug, Unpack, Getters)]
+    #[getset(get = "pub")]
+    pub struct ReadControllerConfigurationInformationReply {
+        manufacture: u16,
+        supported_options: super::ControllerConfigurationOption,
+        missing_options: super::ControllerConfigurationOption,
+    }
+
+    impl ReadControllerConfigurationInformationReply {
+        /// true if a public address can be set by [`SetPublicAddress`].
+        pub fn can_set_public_address(&self) -> bool {
+            self.supported_options
+                .contains(super::ControllerConfigurationOption::BluetoothPublicAddressConfiguration)
+        }
+
+        /// true if a public address must be set before the controller is usable.
+        pub fn needs_public_address(&self) -> bool {
+            self.missing_options
+                .contains(super::ControllerConfigurationOption::BluetoothPublicAddressConfiguration)
+        }
+
+        /// true if external configuration must be completed. See [`SetExternalConfiguration`].
+        pub fn needs_external_config(&self) -> bool {
+            self.missing_options
+                .contains(super::ControllerConfigurationOption::ExternalConfiguration)
+        }
+
+        /// true if no option is missing, that is, the controller is configured.
+        pub fn is_fully_configured(&self) -> bool {
+            self.missing_options.is_empty()
+        }
+    }
+
+    /// Set External Configuration Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0038, reply = SetExternalConfigurationReply)]
+    pub struct SetExternalConfiguration(bool);
+
+    /// Reply for [`SetExternalConfiguration`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetExternalConfigurationReply(super::ControllerConfigurationOption);
+
+    /// Set Public Address Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0039, reply = SetPublicAddressReply)]
+    pub struct SetPublicAddress(super::WrappedAddress);
+
+    impl SetPublicAddress {
+        pub fn new(addr: bdaddr::PublicDeviceAddress) -> Self {
+            Self(super::WrappedAddress(Address::from(addr).into_bd_addr()))
+        }
+    }
+
+    /// Reply for [`SetPublicAddress`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetPublicAddressReply(super::ControllerConfigurationOption);
+
+    /// Read Security Information Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0048, reply = ReadSecurityInformationReply)]
+    pub struct ReadSecurityInformation;
+
+    /// Reply for [`ReadSecurityInformation`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct ReadSecurityInformationReply(super::VariableLengthBytes);
+
+    /// Read Experimental Features Information Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0049, reply = ReadExperimentalFeaturesInformationReply)]
+    pub struct ReadExperimentalFeaturesInformation;
+
+    /// Reply for [`ReadExperimentalFeaturesInformation`]
+    #[derive(Debug, Unpack, IterNewtype)]
+    pub struct ReadExperimentalFeaturesInformationReply(
+        pub(crate) Vec<(super::Uuid, super::FeatureFlags)>,
+    );
+
+    /// Set Experimental Feature Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, New)]
+    #[command(code = 0x004A, reply = SetExperimentalFeatureReply)]
+    pub struct SetExperimentalFeature {
+        uuid: super::Uuid,
+        action: super::FeatureAction,
+    }
+
+    /// Reply for [`SetExperimentalFeature`]
+    #[derive(Debug, Unpack, Getters)]
+    #[getset(get = "pub")]
+    pub struct SetExperimentalFeatureReply {
+        uuid: super::Uuid,
+        flags: super::FeatureFlags,
+    }
+
+    /// Read Default System Configuration Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x004B, reply = ReadDefaultSystemConfigurationReply)]
+    pub struct ReadDefaultSystemConfiguration;
+
+    /// Reply for [`ReadDefaultSystemConfiguration`]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[derive(Debug, Unpack, IterNewtype)]
+    pub struct ReadDefaultSystemConfigurationReply(
+        super::Remaining<super::SystemConfigurationParameter>,
+    );
+
+    /// Set Default System Configuration Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, IterNewtype)]
+    #[iter_newtype(constructors)]
+    #[command(code = 0x004C, reply = SetDefaultSystemConfigurationReply)]
+    pub struct SetDefaultSystemConfiguration(super::Remaining<super::SystemConfigurationParameter>);
+
+    /// Reply for [`SetDefaultSystemConfiguration`]
+    #[derive(Debug, Unpack)]
+    pub struct SetDefaultSystemConfigurationReply;
+
+    /// Read Default Runtime Configuration Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x004D, reply = ReadDefaultRuntimeConfigurationReply)]
+    pub struct ReadDefaultRuntimeConfiguration;
+
+    /// Reply for [`ReadDefaultRuntimeConfiguration`]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[derive(Debug, Unpack, IterNewtype)]
+    pub struct ReadDefaultRuntimeConfigurationReply(
+        super::Remaining<super::RuntimeConfigurationParameter>,
+    );
+
+    /// Set Default Runtime Configuration Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, IterNewtype)]
+    #[iter_newtype(constructors)]
+    #[command(code = 0x004E, reply = SetDefaultRuntimeConfigurationReply)]
+    pub struct SetDefaultRuntimeConfiguration(
+        super::Remaining<super::RuntimeConfigurationParameter>,
+    );
+
+    /// Reply for [`SetDefaultRuntimeConfiguration`]
+    #[derive(Debug, Unpack)]
+    pub struct SetDefaultRuntimeConfigurationReply;
+}
+
+pub use imp::*;
@@ -0,0 +1,214 @@
+//! Controller index and information commands.
+use super::*;
+
+#[commands(name = Command, trait = CommandRequest, codes = CommandCode, group)]
+mod imp {
+    use super::*;
+
+    /// Read Management Version Information Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Default, Pack)]
+    #[command(code = 0x0001, reply = ReadManagementVersionInformationReply)]
+    pub struct ReadManagementVersionInformation;
+
+    /// Reply for [`ReadManagementVersionInformation`]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[derive(Debug, Unpack, Getters)]
+    #[getset(get = "pub")]
+    pub struct ReadManagementVersionInformationReply {
+        pub(crate) version: u8,
+        pub(crate) revision: u16,
+    }
+
+    /// Read Management Supported Commands Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Default, Pack)]
+    #[command(code = 0x0002, reply = ReadManagementSupportedCommandsReply)]
+    pub struct ReadManagementSupportedCommands;
+
+    /// Reply for [`ReadManagementSupportedCommands`]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct ReadManagementSupportedCommandsReply(super::CommandsEvents);
+
+    /// Read Controller Index List Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Default, Pack)]
+    #[command(code = 0x0003, reply = ReadControllerIndexListReply)]
+    pub struct ReadControllerIndexList;
+
+    /// Reply for [`ReadControllerIndexList`]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[derive(Debug, Unpack, IterNewtype)]
+    pub struct ReadControllerIndexListReply(Vec<ControllerIndex>);
+
+    /// Read Controller Information Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Default, Pack)]
+    #[command(code = 0x0004, reply = ReadControllerInformationReply)]
+    pub struct ReadControllerInformation;
+
+    /// Reply for [`ReadControllerInformation`]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[derive(Debug, Unpack, Getters)]
+    pub struct ReadControllerInformationReply {
+        pub(crate) address: super::WrappedAddress,
+        #[getset(get = "pub")]
+        pub(crate) bluetooth_version: u8,
+        #[getset(get = "pub")]
+        pub(crate) manufacturer: u16,
+        #[getset(get = "pub")]
+        pub(crate) supported_settings: super::Settings,
+        #[getset(get = "pub")]
+        pub(crate) current_settings: super::Settings,
+        #[getset(get = "pub")]
+        pub(crate) class_of_device: super::ClassOfDevice,
+        #[getset(get = "pub")]
+        pub(crate) name: super::Name,
+        #[getset(get = "pub")]
+        pub(crate) short_name: super::ShortName,
+    }
+
+    impl ReadControllerInformationReply {
+        pub fn address(&self) -> &BdAddr {
+            &self.address.0
+        }
+
+        /// Capabilities decoded from [`Self::supported_settings`].
+        pub fn capabilities(&self) -> super::Capabilities {
+            (&self.supported_settings).into()
+        }
+
+        /// Take the fields apart.
+        pub fn into_parts(self) -> ReadControllerInformationParts {
+            self.into()
+        }
+    }
+
+    /// Fields of [`ReadControllerInformationReply`], for destructuring.
+    ///
+    /// ```
+    /// use btmgmt_packet::command::{ReadControllerInformationParts, ReadControllerInformationReply};
+    /// use btmgmt_packet::Unpack;
+    ///
+    /// let mut b = vec![0; 280];
+    /// b[..6].copy_from_slice(&[0x55, 0x44, 0x33, 0x22, 0x11, 0x00]);
+    /// b[20..23].copy_from_slice(b"bt\0");
+    /// let reply = ReadControllerInformationReply::unpack(&mut &b[..])?;
+    ///
+    /// let ReadControllerInformationParts { address, name, .. } = reply.into();
+    /// assert_eq!("00:11:22:33:44:55", address.to_string());
+    /// assert_eq!("bt", name.to_string_lossy());
+    /// # Ok::<_, btmgmt_packet::pack::Error>(())
+    /// ```
+    #[derive(Debug)]
+    pub struct ReadControllerInformationParts {
+        pub address: BdAddr,
+        pub bluetooth_version: u8,
+        pub manufacturer: u16,
+        pub supported_settings: super::Settings,
+        pub current_settings: super::Settings,
+        pub class_of_device: super::ClassOfDevice,
+        pub name: super::Name,
+        pub short_name: super::ShortName,
+    }
+
+    impl From<ReadControllerInformationReply> for ReadControllerInformationParts {
+        fn from(v: ReadControllerInformationReply) -> Self {
+            Self {
+                address: v.address.0,
+                bluetooth_version: v.bluetooth_version,
+                manufacturer: v.manufacturer,
+                supported_settings: v.supported_settings,
+                current_settings: v.current_settings,
+                class_of_device: v.class_of_device,
+                name: v.name,
+                short_name: v.short_name,
+            }
+        }
+    }
+
+    /// Read Management Version Information Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x003C, reply = ReadExtendedControllerIndexListReply)]
+    pub struct ReadExtendedControllerIndexList;
+
+    /// Reply for [`ReadExtendedControllerIndexList`]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[derive(Debug, Unpack, IterNewtype)]
+    pub struct ReadExtendedControllerIndexListReply(
+        Vec<(ControllerIndex, super::ControllerType, super::ControllerBus)>,
+    );
+
+    /// Read Extended Controller Information Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0042, reply = ReadExtendedControllerInformationReply)]
+    pub struct ReadExtendedControllerInformation;
+
+    /// Reply for [`ReadExtendedControllerInformation`]
+    #[derive(Debug, Unpack, Getters)]
+    pub struct ReadExtendedControllerInformationReply {
+        address: super::WrappedAddress,
+        #[getset(get = "pub")]
+        bluetooth_version: u8,
+        #[getset(get = "pub")]
+        manufacturer: u16,
+        #[getset(get = "pub")]
+        supported_settings: super::Settings,
+        #[getset(get = "pub")]
+        current_settings: super::Settings,
+        #[getset(get = "pub")]
+        eir_data: super::VariableLengthBytes,
+    }
+
+    impl ReadExtendedControllerInformationReply {
+        pub fn address(&self) -> &BdAddr {
+            &self.address.0
+        }
+
+        /// Take the fields apart.
+        pub fn into_parts(self) -> ReadExtendedControllerInformationParts {
+            self.into()
+        }
+    }
+
+    /// Fields of [`ReadExtendedControllerInformationReply`], for destructuring.
+    #[derive(Debug)]
+    pub struct ReadExtendedControllerInformationParts {
+        pub address: BdAddr,
+        pub bluetooth_version: u8,
+        pub manufacturer: u16,
+        pub supported_settings: super::Settings,
+        pub current_settings: super::Settings,
+        pub eir_data: Box<[u8]>,
+    }
+
+    impl From<ReadExtendedControllerInformationReply> for ReadExtendedControllerInformationParts {
+        fn from(v: ReadExtendedControllerInformationReply) -> Self {
+            Self {
+                address: v.address.0,
+                bluetooth_version: v.bluetooth_version,
+                manufacturer: v.manufacturer,
+                supported_settings: v.supported_settings,
+                current_settings: v.current_settings,
+                eir_data: v.eir_data.0,
+            }
+        }
+    }
+}
+
+pub use imp::*;
@@ -0,0 +1,124 @@
+//! Discovery commands.
+use super::*;
+
+#[commands(name = Command, trait = CommandRequest, codes = CommandCode, group)]
+mod imp {
+    use super::*;
+
+    /// Start Discovery Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0023, reply = StartDiscoveryReply)]
+    pub struct StartDiscovery(super::AddressTypes);
+
+    /// Reply for [`StartDiscovery`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct StartDiscoveryReply(super::AddressTypes);
+
+    /// Stop Discovery Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0024, reply = StopDiscoveryReply)]
+    pub struct StopDiscovery(super::AddressTypes);
+
+    /// Reply for [`StopDiscovery`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct StopDiscoveryReply(super::AddressTypes);
+
+    /// Confirm Name Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0025, reply = ConfirmNameReply)]
+    pub struct ConfirmName {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+        name_known: bool,
+    }
+
+    impl ConfirmName {
+        pub fn new<A: IntoAddress>(addr: A, name_known: bool) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+                name_known,
+            }
+        }
+    }
+
+    /// Reply for [`ConfirmName`]
+    #[derive(Debug, Unpack)]
+    pub struct ConfirmNameReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl ConfirmNameReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Set Scan Parameters Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, New)]
+    #[command(code = 0x002C, reply = SetScanParametersReply)]
+    pub struct SetScanParameters {
+        interval: u16,
+        window: u16,
+    }
+
+    /// Reply for [`SetScanParameters`]
+    #[derive(Debug, Unpack)]
+    pub struct SetScanParametersReply;
+
+    /// Start Service Discovery Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, New)]
+    #[command(code = 0x003A, reply = StartServiceDiscoveryReply)]
+    pub struct StartServiceDiscovery {
+        address_type: super::AddressTypes,
+        rssi_threshold: u8,
+        uuids: Vec<super::Uuid>,
+    }
+
+    impl StartServiceDiscovery {
+        /// Filter by 16-bit service UUIDs. See [`super::Uuid::from_u16`].
+        pub fn with_16bit_uuids(
+            address_type: super::AddressTypes,
+            rssi_threshold: u8,
+            shorts: &[u16],
+        ) -> Self {
+            let uuids = shorts.iter().map(|s| super::Uuid::from_u16(*s)).collect();
+            Self::new(address_type, rssi_threshold, uuids)
+        }
+    }
+
+    /// Reply for [`StartServiceDiscovery`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct StartServiceDiscoveryReply(super::AddressTypes);
+
+    /// Start Limited Discovery Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0041, reply = StartLimitedDiscoveryReply)]
+    pub struct StartLimitedDiscovery(super::AddressTypes);
+
+    /// Reply for [`StartLimitedDiscovery`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct StartLimitedDiscoveryReply(super::AddressTypes);
+}
+
+pub use imp::*;
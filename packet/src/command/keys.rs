@@ -0,0 +1,65 @@
+//! Key loading commands.
+use super::*;
+
+#[commands(name = Command, trait = CommandRequest, codes = CommandCode, group)]
+mod imp {
+    use super::*;
+
+    /// Load Link Keys Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, New)]
+    #[command(code = 0x0012, reply = LoadLinkKeysReply)]
+    pub struct LoadLinkKeys {
+        debug_keys: bool,
+        keys: Vec<super::LinkKey>,
+    }
+
+    /// Reply for [`LoadLinkKeys`]
+    #[derive(Debug, Unpack)]
+    pub struct LoadLinkKeysReply;
+
+    /// Load Long Term Keys Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, IterNewtype)]
+    #[iter_newtype(constructors)]
+    #[command(code = 0x0013, reply = LoadLongTermKeyReply)]
+    pub struct LoadLongTermKey(Vec<super::LongTermKey>);
+
+    /// Reply for [`LoadLongTermKey`]
+    #[derive(Debug, Unpack)]
+    pub struct LoadLongTermKeyReply;
+
+    /// Load Identity Resolving Keys Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    ///
+    /// Keys are checked by [`IdentityResolvingKey::new`](super::IdentityResolvingKey::new).
+    #[derive(Debug, Pack, IterNewtype)]
+    #[iter_newtype(constructors)]
+    #[command(code = 0x0030, reply = LoadIdentityResolvingKeysReply)]
+    pub struct LoadIdentityResolvingKeys(Vec<super::IdentityResolvingKey>);
+
+    /// Reply for [`LoadIdentityResolvingKeys`]
+    #[derive(Debug, Unpack)]
+    pub struct LoadIdentityResolvingKeysReply;
+
+    /// Load Blocked Keys Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, IterNewtype)]
+    #[iter_newtype(constructors)]
+    #[command(code = 0x0046, reply = LoadBlockedKeysReply)]
+    pub struct LoadBlockedKeys(Vec<super::BlockedKey>);
+
+    /// Reply for [`LoadBlockedKeys`]
+    #[derive(Debug, Unpack)]
+    pub struct LoadBlockedKeysReply;
+}
+
+pub use imp::*;
@@ -0,0 +1,112 @@
+//! Mesh commands.
+use super::*;
+
+#[commands(name = Command, trait = CommandRequest, codes = CommandCode, group)]
+mod imp {
+    use super::*;
+
+    /// Set Mesh Receiver Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0057, reply = SetMeshReceiverReply)]
+    pub struct SetMeshReceiver {
+        enable: bool,
+        window: u16,
+        period: u16,
+        ad_types: super::VariableLengthBytes<u8>,
+    }
+
+    impl SetMeshReceiver {
+        /// Receive advertisements of `ad_types` as Mesh Device Found. Empty for all.
+        pub fn new<T: Into<Box<[u8]>>>(
+            enable: bool,
+            window: u16,
+            period: u16,
+            ad_types: T,
+        ) -> Self {
+            Self {
+                enable,
+                window,
+                period,
+                ad_types: super::VariableLengthBytes(ad_types.into(), PhantomData),
+            }
+        }
+    }
+
+    /// Reply for [`SetMeshReceiver`]
+    #[derive(Debug, Unpack)]
+    pub struct SetMeshReceiverReply;
+
+    /// Read Mesh Features Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0058, reply = ReadMeshFeaturesReply)]
+    pub struct ReadMeshFeatures;
+
+    /// Reply for [`ReadMeshFeatures`]
+    #[derive(Debug, Unpack, Getters)]
+    #[getset(get = "pub")]
+    pub struct ReadMeshFeaturesReply {
+        index: super::ControllerIndex,
+        max_handles: u8,
+        /// Handles of the pending transmissions.
+        used_handles: super::VariableLengthBytes<u8>,
+    }
+
+    /// Mesh Send Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0059, reply = MeshSendReply)]
+    pub struct MeshSend {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+        instant: u64,
+        delay: u16,
+        count: u8,
+        data: super::VariableLengthBytes<u8>,
+    }
+
+    impl MeshSend {
+        /// Send `data`, up to 31 bytes, `count` times after `delay` milliseconds from
+        /// `instant`. (as Mesh Device Found reports)
+        pub fn new<A, T>(addr: A, instant: u64, delay: u16, count: u8, data: T) -> Self
+        where
+            A: IntoAddress,
+            T: Into<Box<[u8]>>,
+        {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+                instant,
+                delay,
+                count,
+                data: super::VariableLengthBytes(data.into(), PhantomData),
+            }
+        }
+    }
+
+    /// Reply for [`MeshSend`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct MeshSendReply(super::MeshHandle);
+
+    /// Mesh Send Cancel Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x005A, reply = MeshSendCancelReply)]
+    pub struct MeshSendCancel(super::MeshHandle);
+
+    /// Reply for [`MeshSendCancel`]
+    #[derive(Debug, Unpack)]
+    pub struct MeshSendCancelReply;
+}
+
+pub use imp::*;
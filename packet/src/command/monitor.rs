@@ -0,0 +1,53 @@
+//! Advertisement monitor commands.
+use super::*;
+
+#[commands(name = Command, trait = CommandRequest, codes = CommandCode, group)]
+mod imp {
+    use super::*;
+
+    /// Read Advertisement Monitor Features Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0051, reply = ReadAdvertisementMonitorFeaturesReply)]
+    pub struct ReadAdvertisementMonitorFeatures;
+
+    /// Reply for [`ReadAdvertisementMonitorFeatures`]
+    #[derive(Debug, Unpack, Getters)]
+    #[getset(get = "pub")]
+    pub struct ReadAdvertisementMonitorFeaturesReply {
+        supported_features: super::AdvertisementMonitorFeatures,
+        enabled_features: super::AdvertisementMonitorFeatures,
+        max_num_handle: u16,
+        max_num_pattern: u8,
+        handles: Vec<super::AdvertisementMonitorHandle>,
+    }
+
+    /// Add Advertisement Patterns Monitor Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, IterNewtype)]
+    #[iter_newtype(constructors)]
+    #[command(code = 0x0052, reply = AddAdvertisementPatternsMonitorReply)]
+    pub struct AddAdvertisementPatternsMonitor(Vec<super::AdvertisementPattern>);
+
+    /// Reply for [`AddAdvertisementPatternsMonitor`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct AddAdvertisementPatternsMonitorReply(super::AdvertisementMonitorHandle);
+
+    /// Remove Advertisement Monitor Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0053, reply = RemoveAdvertisementPatternsMonitorReply)]
+    pub struct RemoveAdvertisementPatternsMonitor(super::AdvertisementMonitorHandle);
+
+    /// Reply for [`RemoveAdvertisementPatternsMonitor`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct RemoveAdvertisementPatternsMonitorReply(super::AdvertisementMonitorHandle);
+}
+
+pub use imp::*;
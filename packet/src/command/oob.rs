@@ -0,0 +1,127 @@
+//! Out of band data commands.
+use super::*;
+
+#[commands(name = Command, trait = CommandRequest, codes = CommandCode, group)]
+mod imp {
+    use super::*;
+
+    /// Read Local Out Of Band Data Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0020, reply = ReadLocalOutOfBandDataReply)]
+    pub struct ReadLocalOutOfBandData;
+
+    /// Reply for [`ReadLocalOutOfBandData`]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[derive(Debug, Unpack, Getters)]
+    #[getset(get = "pub")]
+    pub struct ReadLocalOutOfBandDataReply {
+        hash192: [u8; 16],
+        randomizer192: [u8; 16],
+        hash256: Option<[u8; 16]>,
+        randomizer256: Option<[u8; 16]>,
+    }
+
+    /// Add Remote Out Of Band Data Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0021, reply = AddRemoteOutOfBandDataReply)]
+    pub struct AddRemoteOutOfBandData {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+        hash192: [u8; 16],
+        randomizer192: [u8; 16],
+        hash256: Option<[u8; 16]>,
+        randomizer256: Option<[u8; 16]>,
+    }
+
+    impl AddRemoteOutOfBandData {
+        pub fn new<A: IntoAddress>(
+            addr: A,
+            hash192: [u8; 16],
+            randomizer192: [u8; 16],
+            hash256: Option<[u8; 16]>,
+            randomizer256: Option<[u8; 16]>,
+        ) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+                hash192,
+                randomizer192,
+                hash256,
+                randomizer256,
+            }
+        }
+    }
+
+    /// Reply for [`AddRemoteOutOfBandData`]
+    #[derive(Debug, Unpack)]
+    pub struct AddRemoteOutOfBandDataReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl AddRemoteOutOfBandDataReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Remove Remote Out Of Band Data Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0022, reply = RemoveRemoteOutOfBandDataReply)]
+    pub struct RemoveRemoteOutOfBandData {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl RemoveRemoteOutOfBandData {
+        pub fn new<A: IntoAddress>(addr: A) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+            }
+        }
+    }
+
+    /// Reply for [`RemoveRemoteOutOfBandData`]
+    #[derive(Debug, Unpack)]
+    pub struct RemoveRemoteOutOfBandDataReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl RemoveRemoteOutOfBandDataReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Read Local Out Of Band Extended Data Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x003B, reply = ReadLocalOutOfBandExtendedDataReply)]
+    pub struct ReadLocalOutOfBandExtendedData(super::AddressTypes);
+
+    /// Reply for [`ReadLocalOutOfBandExtendedData`]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[derive(Debug, Unpack, Getters)]
+    #[getset(get = "pub")]
+    pub struct ReadLocalOutOfBandExtendedDataReply {
+        address_type: super::AddressTypes,
+        eir_data: super::VariableLengthBytes,
+    }
+}
+
+pub use imp::*;
@@ -0,0 +1,702 @@
+//! Connection, pairing and device list commands.
+use super::*;
+
+#[commands(name = Command, trait = CommandRequest, codes = CommandCode, group)]
+mod imp {
+    use super::*;
+
+    /// Disconnect Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0014, reply = DisconnectReply)]
+    pub struct Disconnect {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl Disconnect {
+        pub fn new<A: IntoAddress>(addr: A) -> Self {
+            let (address, address_type) = super::split(addr);
+            Self {
+                address,
+                address_type,
+            }
+        }
+    }
+
+    /// Reply for [`Disconnect`]
+    #[derive(Debug, Unpack)]
+    pub struct DisconnectReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl DisconnectReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Get Connections Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0015, reply = GetConnectionsReply)]
+    pub struct GetConnections;
+
+    /// Reply for [`GetConnections`]
+    #[derive(Debug, IterNewtype)]
+    pub struct GetConnectionsReply(Vec<Address>);
+
+    impl Unpack for GetConnectionsReply {
+        fn unpack<R>(read: &mut R) -> crate::pack::Result<Self>
+        where
+            R: io::Read,
+        {
+            let inner = Vec::<(super::WrappedAddress, super::InternalAddressType)>::unpack(read)?;
+            let inner = inner
+                .into_iter()
+                .map(|(addr, ty)| join(&ty, &addr))
+                .collect::<Vec<_>>();
+            Ok(Self(inner))
+        }
+    }
+
+    /// PIN Code Reply Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0016, reply = PinCodeReplyReply)]
+    pub struct PinCodeReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+        pin_length: u8,
+        pin_code: [u8; 16],
+    }
+
+    impl PinCodeReply {
+        pub fn new<A: IntoAddress>(addr: A, pin_length: u8, pin_code: [u8; 16]) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+                pin_length,
+                pin_code,
+            }
+        }
+    }
+
+    /// Reply for [`PinCodeReply`]
+    #[derive(Debug, Unpack)]
+    pub struct PinCodeReplyReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl PinCodeReplyReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// PIN Code Negative Reply Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0017, reply = PinCodeNegativeReplyReply)]
+    pub struct PinCodeNegativeReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl PinCodeNegativeReply {
+        pub fn new<A: IntoAddress>(addr: A) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+            }
+        }
+    }
+
+    /// Reply for [`PinCodeNegativeReply`]
+    #[derive(Debug, Unpack)]
+    pub struct PinCodeNegativeReplyReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl PinCodeNegativeReplyReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Set IO Capability Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0018, reply = SetIoCapabilityReply)]
+    pub struct SetIoCapability(super::IoCapability);
+
+    /// Reply for [`SetIoCapability`]
+    #[derive(Debug, Unpack)]
+    pub struct SetIoCapabilityReply;
+
+    /// Read Management Version Information Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0019, reply = PairDeviceReply)]
+    pub struct PairDevice {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+        io_capability: super::IoCapability,
+    }
+
+    impl PairDevice {
+        pub fn new<A: IntoAddress>(addr: A, io_capability: super::IoCapability) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+                io_capability,
+            }
+        }
+    }
+
+    /// Reply for [`PairDevice`]
+    #[derive(Debug, Unpack)]
+    pub struct PairDeviceReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl PairDeviceReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Cancel Pair Device Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x001A, reply = CancelPairDeviceReply)]
+    pub struct CancelPairDevice {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl CancelPairDevice {
+        pub fn new<A: IntoAddress>(addr: A) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+            }
+        }
+    }
+
+    /// Reply for [`CancelPairDevice`]
+    #[derive(Debug, Unpack)]
+    pub struct CancelPairDeviceReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl CancelPairDeviceReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Unpair Device Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x001B, reply = UnpairDeviceReply)]
+    pub struct UnpairDevice {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+        disconnect: bool,
+    }
+
+    impl UnpairDevice {
+        pub fn new<A: IntoAddress>(addr: A, disconnect: bool) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+                disconnect,
+            }
+        }
+    }
+
+    /// Reply for [`UnpairDevice`]
+    #[derive(Debug, Unpack)]
+    pub struct UnpairDeviceReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl UnpairDeviceReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// User Confirmation Reply Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x001C, reply = UserConfirmationReplyReply)]
+    pub struct UserConfirmationReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl UserConfirmationReply {
+        pub fn new<A: IntoAddress>(addr: A) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+            }
+        }
+    }
+
+    /// Reply for [`UserConfirmationReply`]
+    #[derive(Debug, Unpack)]
+    pub struct UserConfirmationReplyReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl UserConfirmationReplyReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// User Confirmation Negative Reply Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x001D, reply = UserConfirmationNegativeReplyReply)]
+    pub struct UserConfirmationNegativeReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl UserConfirmationNegativeReply {
+        pub fn new<A: IntoAddress>(addr: A) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+            }
+        }
+    }
+
+    /// Reply for [`UserConfirmationNegativeReply`]
+    #[derive(Debug, Unpack)]
+    pub struct UserConfirmationNegativeReplyReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl UserConfirmationNegativeReplyReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// User Passkey Reply Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x001E, reply = UserPasskeyReplyReply)]
+    pub struct UserPasskeyReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+        passkey: u32,
+    }
+
+    impl UserPasskeyReply {
+        pub fn new<A: IntoAddress>(addr: A, passkey: u32) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+                passkey,
+            }
+        }
+    }
+
+    /// Reply for [`UserPasskeyReply`]
+    #[derive(Debug, Unpack)]
+    pub struct UserPasskeyReplyReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl UserPasskeyReplyReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// User Passkey Negative Reply Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x001F, reply = UserPasskeyNegativeReplyReply)]
+    pub struct UserPasskeyNegativeReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl UserPasskeyNegativeReply {
+        pub fn new<A: IntoAddress>(addr: A) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+            }
+        }
+    }
+
+    /// Reply for [`UserPasskeyNegativeReply`]
+    #[derive(Debug, Unpack)]
+    pub struct UserPasskeyNegativeReplyReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl UserPasskeyNegativeReplyReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Block Device Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0026, reply = BlockDeviceReply)]
+    pub struct BlockDevice {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl BlockDevice {
+        pub fn new<A: IntoAddress>(addr: A) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+            }
+        }
+    }
+
+    /// Reply for [`BlockDevice`]
+    #[derive(Debug, Unpack)]
+    pub struct BlockDeviceReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl BlockDeviceReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Unblock Device Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0027, reply = UnblockDeviceReply)]
+    pub struct UnblockDevice {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl UnblockDevice {
+        pub fn new<A: IntoAddress>(addr: A) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+            }
+        }
+    }
+
+    /// Reply for [`UnblockDevice`]
+    #[derive(Debug, Unpack)]
+    pub struct UnblockDeviceReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl UnblockDeviceReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Get Connection Information Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0031, reply = GetConnectionInformationReply)]
+    pub struct GetConnectionInformation {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl GetConnectionInformation {
+        pub fn new<A: IntoAddress>(addr: A) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+            }
+        }
+    }
+
+    /// Reply for [`GetConnectionInformation`]
+    #[derive(Debug, Unpack, Getters)]
+    pub struct GetConnectionInformationReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+        #[getset(get = "pub")]
+        rssi: u8,
+        #[getset(get = "pub")]
+        tx_power: u8,
+        #[getset(get = "pub")]
+        max_tx_power: u8,
+    }
+
+    impl GetConnectionInformationReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Get Clock Information Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0032, reply = GetClockInformationReply)]
+    pub struct GetClockInformation {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl GetClockInformation {
+        pub fn new<A: IntoAddress>(addr: A) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+            }
+        }
+    }
+
+    /// Reply for [`GetClockInformation`]
+    #[derive(Debug, Unpack, Getters)]
+    pub struct GetClockInformationReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+        #[getset(get = "pub")]
+        local_clock: u32,
+        #[getset(get = "pub")]
+        piconet_clock: u32,
+        #[getset(get = "pub")]
+        accuracy: u16,
+    }
+
+    impl GetClockInformationReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Add Device Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0033, reply = AddDeviceReply)]
+    pub struct AddDevice {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+        action: super::Action,
+    }
+
+    impl AddDevice {
+        pub fn new<A: IntoAddress>(addr: A, action: super::Action) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+                action,
+            }
+        }
+    }
+
+    /// Reply for [`AddDevice`]
+    #[derive(Debug, Unpack)]
+    pub struct AddDeviceReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl AddDeviceReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Remove Device Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0034, reply = RemoveDeviceReply)]
+    pub struct RemoveDevice {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl RemoveDevice {
+        pub fn new<A: IntoAddress>(addr: A) -> Self {
+            let (address, address_type) = split(addr);
+            Self {
+                address,
+                address_type,
+            }
+        }
+    }
+
+    /// Reply for [`RemoveDevice`]
+    #[derive(Debug, Unpack)]
+    pub struct RemoveDeviceReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl RemoveDeviceReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Load Connection Parameters Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, IterNewtype)]
+    #[iter_newtype(constructors)]
+    #[command(code = 0x0035, reply = LoadConnectionParametersReply)]
+    pub struct LoadConnectionParameters(Vec<super::ConnectionParameter>);
+
+    /// Reply for [`LoadConnectionParameters`]
+    #[derive(Debug, Unpack)]
+    pub struct LoadConnectionParametersReply;
+
+    /// Get Device Flags Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x004F, reply = GetDeviceFlagReply)]
+    pub struct GetDeviceFlag {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl GetDeviceFlag {
+        pub fn new<A: IntoAddress>(addr: A) -> Self {
+            let (address, address_type) = super::split(addr);
+            Self {
+                address,
+                address_type,
+            }
+        }
+    }
+
+    /// Reply for [`GetDeviceFlag`]
+    #[derive(Debug, Unpack, Getters)]
+    pub struct GetDeviceFlagReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+        #[getset(get = "pub")]
+        supported_flags: super::DeviceFlags,
+        #[getset(get = "pub")]
+        current_flags: super::DeviceFlags,
+    }
+
+    impl GetDeviceFlagReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Set Device Flags Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0050, reply = SetDeviceFlagReply)]
+    pub struct SetDeviceFlag {
+        address: super::WrappedAddress, // TODO typo
+        address_type: super::InternalAddressType,
+        current_flags: super::DeviceFlags,
+    }
+
+    impl SetDeviceFlag {
+        pub fn new<A: IntoAddress>(addr: A, current_flags: super::DeviceFlags) -> Self {
+            let (address, address_type) = super::split(addr);
+            Self {
+                address,
+                address_type,
+                current_flags,
+            }
+        }
+    }
+
+    /// Reply for [`SetDeviceFlag`]
+    #[derive(Debug, Unpack)]
+    pub struct SetDeviceFlagReply {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+    }
+
+    impl SetDeviceFlagReply {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+}
+
+pub use imp::*;
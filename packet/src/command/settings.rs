@@ -0,0 +1,362 @@
+//! Controller setting commands.
+use super::*;
+
+#[commands(name = Command, trait = CommandRequest, codes = CommandCode, group)]
+mod imp {
+    use super::*;
+
+    /// Set Powered Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0005, reply = SetPoweredReply)]
+    pub struct SetPowered(bool);
+
+    /// Reply for [`SetPowered`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetPoweredReply(super::Settings);
+
+    /// Set Discoverable Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0006, reply = SetDiscoverableReply)]
+    pub struct SetDiscoverable {
+        discoverable: super::Discoverable,
+        timeout: super::Seconds,
+    }
+
+    impl SetDiscoverable {
+        /// `timeout` in seconds, `0` for none.
+        pub fn new<T: Into<super::Seconds>>(discoverable: super::Discoverable, timeout: T) -> Self {
+            Self {
+                discoverable,
+                timeout: timeout.into(),
+            }
+        }
+    }
+
+    /// Reply for [`SetDiscoverable`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetDiscoverableReply(super::Settings);
+
+    /// Set Connectable Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0007, reply = SetConnectableReply)]
+    pub struct SetConnectable(bool);
+
+    /// Reply for [`SetConnectable`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetConnectableReply(super::Settings);
+
+    /// Set Fast Connectable Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0008, reply = SetFastConnectableReply)]
+    pub struct SetFastConnectable(bool);
+
+    /// Reply for [`SetFastConnectable`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetFastConnectableReply(super::Settings);
+
+    /// Set Bondable Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0009, reply = SetBondableReply)]
+    pub struct SetBondable(bool);
+
+    /// Reply for [`SetBondable`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetBondableReply(super::Settings);
+
+    /// Set Link Security Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x000A, reply = SetLinkSecurityReply)]
+    pub struct SetLinkSecurity(bool);
+
+    /// Reply for [`SetLinkSecurity`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetLinkSecurityReply(super::Settings);
+
+    /// Set Secure Simple Pairing Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x000B, reply = SetSecureSimplePairingReply)]
+    pub struct SetSecureSimplePairing(bool);
+
+    /// Reply for [`SetSecureSimplePairing`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetSecureSimplePairingReply(super::Settings);
+
+    /// Set High Speed Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x000C, reply = SetHighSpeedReply)]
+    pub struct SetHighSpeed(bool);
+
+    /// Reply for [`SetHighSpeed`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetHighSpeedReply(super::Settings);
+
+    /// Set Low Energy Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x000D, reply = SetLowEnergyReply)]
+    pub struct SetLowEnergy(bool);
+
+    /// Reply for [`SetLowEnergy`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetLowEnergyReply(super::Settings);
+
+    /// Set Device Class Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, New)]
+    #[command(code = 0x000E, reply = SetDeviceClassReply)]
+    pub struct SetDeviceClass {
+        major_class: u8,
+        minor_class: u8,
+    }
+
+    /// Reply for [`SetDeviceClass`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetDeviceClassReply(super::ClassOfDevice);
+
+    /// Set Local Name Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, New)]
+    #[command(code = 0x000F, reply = SetLocalNameReply)]
+    pub struct SetLocalName {
+        name: super::Name,
+        short_name: super::ShortName,
+    }
+
+    /// Reply for [`SetLocalName`]
+    #[derive(Debug, Unpack, Getters)]
+    #[getset(get = "pub")]
+    pub struct SetLocalNameReply {
+        name: super::Name,
+        short_name: super::ShortName,
+    }
+
+    /// Add UUID Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, New)]
+    #[command(code = 0x0010, reply = AddUuidReply)]
+    pub struct AddUuid {
+        uuid: super::Uuid,
+        svc_hint: u8,
+    }
+
+    /// Reply for [`AddUuid`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct AddUuidReply(super::ClassOfDevice);
+
+    /// Remove UUID Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0011, reply = RemoveUuidReply)]
+    pub struct RemoveUuid(super::Uuid);
+
+    /// Reply for [`RemoveUuid`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct RemoveUuidReply(super::ClassOfDevice);
+
+    /// Set Device ID Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0028, reply = SetDeviceIdReply)]
+    pub struct SetDeviceId {
+        pub source: super::DeviceIdSource,
+        pub vendor: u16,
+        pub product: u16,
+        pub version: u16,
+    }
+
+    /// Reply for [`SetDeviceId`]
+    #[derive(Debug, Unpack)]
+    pub struct SetDeviceIdReply;
+
+    /// Set BR/EDR Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x002A, reply = SetBrEdrReply)]
+    pub struct SetBrEdr(bool);
+
+    /// Reply for [`SetBrEdr`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetBrEdrReply(super::Settings);
+
+    /// Set Static Address Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x002B, reply = SetStaticAddressReply)]
+    pub struct SetStaticAddress(super::WrappedAddress);
+
+    impl SetStaticAddress {
+        pub fn new(addr: bdaddr::StaticDeviceAddress) -> Self {
+            let addr = bdaddr::RandomDeviceAddress::from(addr);
+            let addr = Address::from(addr).into_bd_addr();
+            Self(super::WrappedAddress(addr))
+        }
+
+        /// Clear the static address. (`00:00:00:00:00:00`)
+        ///
+        /// The controller falls back to its own static address, if any.
+        /// Only allowed while the controller is powered off.
+        pub fn clear() -> Self {
+            Self(super::WrappedAddress(BdAddr::from([0; 6])))
+        }
+    }
+
+    /// Reply for [`SetStaticAddress`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetStaticAddressReply(super::Settings);
+
+    /// Set Secure Connections Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x002D, reply = SetSecureConnectionsReply)]
+    pub struct SetSecureConnections(super::SecureConnections);
+
+    /// Reply for [`SetSecureConnections`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetSecureConnectionsReply(super::Settings);
+
+    /// Set Debug Keys Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x002E, reply = SetDebugKeysReply)]
+    pub struct SetDebugKeys(super::DebugKeys);
+
+    /// Reply for [`SetDebugKeys`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetDebugKeysReply(super::Settings);
+
+    /// Set Privacy Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x002F, reply = SetPrivacyReply)]
+    pub struct SetPrivacy {
+        privacy: super::Privacy,
+        identity_resolving_key: [u8; 16],
+    }
+
+    impl SetPrivacy {
+        /// Returns [`KeyError::AllZero`](super::KeyError::AllZero) if the key is all
+        /// zeros, unless disabling the privacy, where the key is not used.
+        pub fn new(
+            privacy: super::Privacy,
+            identity_resolving_key: [u8; 16],
+        ) -> Result<Self, super::KeyError> {
+            if !matches!(privacy, super::Privacy::Disable) {
+                super::check_key(&identity_resolving_key)?;
+            }
+            Ok(Self::new_unchecked(privacy, identity_resolving_key))
+        }
+
+        /// Command with any key, including all zeros. (e.g. test vectors)
+        pub fn new_unchecked(privacy: super::Privacy, identity_resolving_key: [u8; 16]) -> Self {
+            Self {
+                privacy,
+                identity_resolving_key,
+            }
+        }
+    }
+
+    /// Reply for [`SetPrivacy`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetPrivacyReply(super::Settings);
+
+    /// Set Appearance Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0043, reply = SetApperanceReply)]
+    pub struct SetApperance(u16);
+
+    /// Reply for [`SetApperance`]
+    #[derive(Debug, Unpack)]
+    pub struct SetApperanceReply;
+
+    /// Get PHY Configuration Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack)]
+    #[command(code = 0x0044, reply = GetPhyConfigurationReply)]
+    pub struct GetPhyConfiguration;
+
+    /// Reply for [`GetPhyConfiguration`]
+    #[derive(Debug, Unpack, Getters)]
+    #[getset(get = "pub")]
+    pub struct GetPhyConfigurationReply {
+        supported_phys: super::Phys,
+        configurable_phys: super::Phys,
+        selected_phys: super::Phys,
+    }
+
+    /// Set PHY Configuration Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0045, reply = SetPhyConfigurationReply)]
+    pub struct SetPhyConfiguration(super::Phys);
+
+    /// Reply for [`SetPhyConfiguration`]
+    #[derive(Debug, Unpack)]
+    pub struct SetPhyConfigurationReply;
+
+    /// Set Wideband Speech Command
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Pack, Newtype, New)]
+    #[command(code = 0x0047, reply = SetWidbandSpeechReply)]
+    pub struct SetWidbandSpeech(bool);
+
+    /// Reply for [`SetWidbandSpeech`]
+    #[derive(Debug, Unpack, Newtype)]
+    pub struct SetWidbandSpeechReply(super::Settings);
+}
+
+pub use imp::*;
@@ -0,0 +1,350 @@
+//! Typed records of EIR and advertising data. (e.g. [`DeviceFound`](crate::event::DeviceFound))
+//!
+//! ```
+//! use btmgmt_packet::eir::{iter_ad_records, CompleteLocalName, Flags};
+//! use std::convert::TryFrom;
+//!
+//! let data = [0x02, 0x01, 0x06, 0x05, 0x09, b'b', b't', b'm', b'g'];
+//! for record in iter_ad_records(&data) {
+//!     if let Ok(name) = CompleteLocalName::try_from(record) {
+//!         assert_eq!("btmg", name.to_string_lossy());
+//!     } else if let Ok(flags) = Flags::try_from(record) {
+//!         assert!(flags.contains(Flags::BrEdrNotSupported));
+//!     }
+//! }
+//! ```
+use std::convert::TryFrom;
+
+use bitflags::bitflags;
+
+use crate::advertising::{
+    AdStructures, AD_TYPE_COMPLETE_NAME, AD_TYPE_FLAGS, AD_TYPE_SHORT_NAME, AD_TYPE_TX_POWER,
+};
+use crate::{CompanyId, Uuid};
+
+/// AD type: Incomplete List of 16-bit Service UUIDs
+pub const AD_TYPE_UUID16_SOME: u8 = 0x02;
+/// AD type: Complete List of 16-bit Service UUIDs
+pub const AD_TYPE_UUID16_ALL: u8 = 0x03;
+/// AD type: Incomplete List of 32-bit Service UUIDs
+pub const AD_TYPE_UUID32_SOME: u8 = 0x04;
+/// AD type: Complete List of 32-bit Service UUIDs
+pub const AD_TYPE_UUID32_ALL: u8 = 0x05;
+/// AD type: Incomplete List of 128-bit Service UUIDs
+pub const AD_TYPE_UUID128_SOME: u8 = 0x06;
+/// AD type: Complete List of 128-bit Service UUIDs
+pub const AD_TYPE_UUID128_ALL: u8 = 0x07;
+/// AD type: Manufacturer Specific Data
+pub const AD_TYPE_MANUFACTURER_DATA: u8 = 0xFF;
+
+/// Record not of the typed wrapper.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AdRecordError {
+    #[error("unexpected AD type {0:#04x}")]
+    UnexpectedType(u8),
+
+    #[error("invalid length {len} of AD type {ad_type:#04x}")]
+    InvalidLength { ad_type: u8, len: usize },
+}
+
+/// AD structure, without the length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdRecord<'a> {
+    pub ad_type: u8,
+    pub data: &'a [u8],
+}
+
+/// Records of the length-type-value structures.
+///
+/// Ends at zero length, or at a truncated structure. See [`AdStructures`] to detect
+/// the latter.
+pub fn iter_ad_records(data: &[u8]) -> impl Iterator<Item = AdRecord<'_>> {
+    AdStructures::new(data)
+        .map_while(Result::ok)
+        .map(|(ad_type, data)| AdRecord { ad_type, data })
+}
+
+fn expect(record: &AdRecord<'_>, types: &[u8]) -> Result<(), AdRecordError> {
+    if types.contains(&record.ad_type) {
+        Ok(())
+    } else {
+        Err(AdRecordError::UnexpectedType(record.ad_type))
+    }
+}
+
+fn invalid_length(record: &AdRecord<'_>) -> AdRecordError {
+    AdRecordError::InvalidLength {
+        ad_type: record.ad_type,
+        len: record.data.len(),
+    }
+}
+
+macro_rules! local_name {
+    ($(#[$meta:meta])* $name:ident, $ad_type:expr) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct $name<'a>(&'a [u8]);
+
+        impl<'a> $name<'a> {
+            pub fn as_bytes(&self) -> &'a [u8] {
+                self.0
+            }
+
+            /// UTF-8, replacing invalid sequences.
+            pub fn to_string_lossy(&self) -> String {
+                String::from_utf8_lossy(self.0).into_owned()
+            }
+        }
+
+        impl<'a> TryFrom<AdRecord<'a>> for $name<'a> {
+            type Error = AdRecordError;
+            fn try_from(record: AdRecord<'a>) -> Result<Self, Self::Error> {
+                expect(&record, &[$ad_type])?;
+                Ok(Self(record.data))
+            }
+        }
+    };
+}
+
+local_name!(
+    /// Complete Local Name
+    CompleteLocalName,
+    AD_TYPE_COMPLETE_NAME
+);
+
+local_name!(
+    /// Shortened Local Name
+    ShortenedLocalName,
+    AD_TYPE_SHORT_NAME
+);
+
+/// List of 16-bit, 32-bit or 128-bit Service UUIDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServiceUuids<'a> {
+    ad_type: u8,
+    data: &'a [u8],
+}
+
+impl<'a> ServiceUuids<'a> {
+    /// true if the list is complete.
+    pub fn is_complete(&self) -> bool {
+        matches!(
+            self.ad_type,
+            AD_TYPE_UUID16_ALL | AD_TYPE_UUID32_ALL | AD_TYPE_UUID128_ALL
+        )
+    }
+
+    /// Bytes of each UUID. (2, 4 or 16)
+    pub fn width(&self) -> usize {
+        match self.ad_type {
+            AD_TYPE_UUID16_SOME | AD_TYPE_UUID16_ALL => 2,
+            AD_TYPE_UUID32_SOME | AD_TYPE_UUID32_ALL => 4,
+            _ => 16,
+        }
+    }
+
+    /// UUIDs, expanded with the Bluetooth Base UUID.
+    pub fn iter(&self) -> impl Iterator<Item = Uuid> + 'a {
+        self.data.chunks_exact(self.width()).map(|b| match *b {
+            [b0, b1] => Uuid::from_u16(u16::from_le_bytes([b0, b1])),
+            [b0, b1, b2, b3] => Uuid::from_u32(u32::from_le_bytes([b0, b1, b2, b3])),
+            _ => {
+                let mut v = [0; 16];
+                v.copy_from_slice(b);
+                Uuid::new(uuid::Uuid::from_u128(u128::from_le_bytes(v)))
+            }
+        })
+    }
+}
+
+impl<'a> TryFrom<AdRecord<'a>> for ServiceUuids<'a> {
+    type Error = AdRecordError;
+    fn try_from(record: AdRecord<'a>) -> Result<Self, Self::Error> {
+        expect(
+            &record,
+            &[
+                AD_TYPE_UUID16_SOME,
+                AD_TYPE_UUID16_ALL,
+                AD_TYPE_UUID32_SOME,
+                AD_TYPE_UUID32_ALL,
+                AD_TYPE_UUID128_SOME,
+                AD_TYPE_UUID128_ALL,
+            ],
+        )?;
+        let uuids = Self {
+            ad_type: record.ad_type,
+            data: record.data,
+        };
+        if !record.data.len().is_multiple_of(uuids.width()) {
+            return Err(invalid_length(&record));
+        }
+        Ok(uuids)
+    }
+}
+
+/// Manufacturer Specific Data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ManufacturerSpecificData<'a> {
+    company_id: CompanyId,
+    data: &'a [u8],
+}
+
+impl<'a> ManufacturerSpecificData<'a> {
+    pub fn company_id(&self) -> CompanyId {
+        self.company_id
+    }
+
+    /// Data after the company identifier.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl<'a> TryFrom<AdRecord<'a>> for ManufacturerSpecificData<'a> {
+    type Error = AdRecordError;
+    fn try_from(record: AdRecord<'a>) -> Result<Self, Self::Error> {
+        expect(&record, &[AD_TYPE_MANUFACTURER_DATA])?;
+        match record.data {
+            [b0, b1, data @ ..] => Ok(Self {
+                company_id: u16::from_le_bytes([*b0, *b1]).into(),
+                data,
+            }),
+            _ => Err(invalid_length(&record)),
+        }
+    }
+}
+
+/// Tx Power Level in dBm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxPower(pub i8);
+
+impl<'a> TryFrom<AdRecord<'a>> for TxPower {
+    type Error = AdRecordError;
+    fn try_from(record: AdRecord<'a>) -> Result<Self, Self::Error> {
+        expect(&record, &[AD_TYPE_TX_POWER])?;
+        match *record.data {
+            [v] => Ok(Self(v as i8)),
+            _ => Err(invalid_length(&record)),
+        }
+    }
+}
+
+bitflags! {
+    /// Flags, of the first octet.
+    pub struct Flags: u8 {
+        const LeLimitedDiscoverableMode = 1 << 0;
+        const LeGeneralDiscoverableMode = 1 << 1;
+        const BrEdrNotSupported = 1 << 2;
+        const SimultaneousLeBrEdrController = 1 << 3;
+    }
+}
+
+impl<'a> TryFrom<AdRecord<'a>> for Flags {
+    type Error = AdRecordError;
+    fn try_from(record: AdRecord<'a>) -> Result<Self, Self::Error> {
+        expect(&record, &[AD_TYPE_FLAGS])?;
+        match record.data.first() {
+            Some(v) => Ok(Self::from_bits_truncate(*v)),
+            None => Err(invalid_length(&record)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(ad_type: u8, data: &[u8]) -> AdRecord<'_> {
+        AdRecord { ad_type, data }
+    }
+
+    #[test]
+    fn test_iter_ad_records() {
+        let data = [
+            0x02, 0x01, 0x06, 0x03, 0x08, b'b', b't', 0x00, 0x02, 0x0A, 0x00,
+        ];
+        assert_eq!(
+            vec![record(0x01, &[0x06]), record(0x08, b"bt")],
+            iter_ad_records(&data).collect::<Vec<_>>()
+        );
+
+        // truncated.
+        let data = [0x02, 0x01, 0x06, 0x05, 0x09, b'b'];
+        assert_eq!(1, iter_ad_records(&data).count());
+        assert_eq!(0, iter_ad_records(&[]).count());
+    }
+
+    #[test]
+    fn test_names() {
+        let name = CompleteLocalName::try_from(record(0x09, b"btmgmt")).unwrap();
+        assert_eq!("btmgmt", name.to_string_lossy());
+        assert_eq!(b"btmgmt", name.as_bytes());
+        let name = ShortenedLocalName::try_from(record(0x08, &[b'b', 0xFF])).unwrap();
+        assert_eq!("b\u{FFFD}", name.to_string_lossy());
+        assert_eq!(
+            Err(AdRecordError::UnexpectedType(0x08)),
+            CompleteLocalName::try_from(record(0x08, b"bt"))
+        );
+    }
+
+    #[test]
+    fn test_service_uuids() {
+        let uuids = ServiceUuids::try_from(record(0x03, &[0x0F, 0x18, 0x0A, 0x18])).unwrap();
+        assert!(uuids.is_complete());
+        assert_eq!(
+            vec![
+                "0000180f-0000-1000-8000-00805f9b34fb",
+                "0000180a-0000-1000-8000-00805f9b34fb"
+            ],
+            uuids.iter().map(|u| u.to_string()).collect::<Vec<_>>()
+        );
+
+        let uuids = ServiceUuids::try_from(record(0x04, &[0x78, 0x56, 0x34, 0x12])).unwrap();
+        assert!(!uuids.is_complete());
+        assert_eq!(
+            vec!["12345678-0000-1000-8000-00805f9b34fb"],
+            uuids.iter().map(|u| u.to_string()).collect::<Vec<_>>()
+        );
+
+        let mut b = [0; 16];
+        b.copy_from_slice(&0x6e400001_b5a3_f393_e0a9_e50e24dcca9e_u128.to_le_bytes());
+        let uuids = ServiceUuids::try_from(record(0x07, &b)).unwrap();
+        assert_eq!(
+            vec!["6e400001-b5a3-f393-e0a9-e50e24dcca9e"],
+            uuids.iter().map(|u| u.to_string()).collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            Err(AdRecordError::InvalidLength {
+                ad_type: 0x03,
+                len: 3
+            }),
+            ServiceUuids::try_from(record(0x03, &[0x0F, 0x18, 0x0A]))
+        );
+    }
+
+    #[test]
+    fn test_manufacturer_data() {
+        let data = ManufacturerSpecificData::try_from(record(0xFF, &[0x59, 0x00, 0x01])).unwrap();
+        assert_eq!(CompanyId::from(0x0059), data.company_id());
+        assert_eq!(&[0x01], data.data());
+        assert!(ManufacturerSpecificData::try_from(record(0xFF, &[0x59])).is_err());
+    }
+
+    #[test]
+    fn test_tx_power_and_flags() {
+        assert_eq!(Ok(TxPower(-4)), TxPower::try_from(record(0x0A, &[0xFC])));
+        assert!(TxPower::try_from(record(0x0A, &[])).is_err());
+
+        let flags = Flags::try_from(record(0x01, &[0x06])).unwrap();
+        assert_eq!(
+            Flags::LeGeneralDiscoverableMode | Flags::BrEdrNotSupported,
+            flags
+        );
+        assert!(Flags::try_from(record(0x01, &[])).is_err());
+        assert_eq!(
+            Err(AdRecordError::UnexpectedType(0x0A)),
+            Flags::try_from(record(0x0A, &[0x06]))
+        );
+    }
+}
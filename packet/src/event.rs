@@ -4,6 +4,7 @@ use getset::Getters;
 use btmgmt_packet_helper::events;
 
 use super::*;
+use crate::advertising::AdStructures;
 pub use imp::*;
 
 /// Management API Events
@@ -108,8 +109,8 @@ mod imp {
     #[event(0x000A)]
     #[getset(get = "pub")]
     pub struct NewLongTermKey {
-        store_hint: bool,
-        key: super::LongTermKey,
+        pub(crate) store_hint: bool,
+        pub(crate) key: super::LongTermKey,
     }
 
     /// Device Connected Event
@@ -119,18 +120,36 @@ mod imp {
     #[derive(Debug, Clone, Unpack, Getters)]
     #[event(0x000B)]
     pub struct DeviceConnected {
-        address: super::WrappedAddress,
-        address_type: super::InternalAddressType,
+        pub(crate) address: super::WrappedAddress,
+        pub(crate) address_type: super::InternalAddressType,
         #[getset(get = "pub")]
-        flags: super::DeviceConnectFlags,
+        pub(crate) flags: super::DeviceConnectFlags,
         #[getset(get = "pub")]
-        eir_data: super::VariableLengthBytes,
+        pub(crate) eir_data: super::VariableLengthBytes,
     }
 
     impl DeviceConnected {
         pub fn address(&self) -> Address {
             join(&self.address_type, &self.address)
         }
+
+        /// AD structures of the EIR data. (e.g. name and class of the peer)
+        pub fn eir(&self) -> AdStructures<'_> {
+            AdStructures::new(&self.eir_data)
+        }
+
+        /// Complete, or else shortened, local name of the peer in the EIR data.
+        pub fn name(&self) -> Option<String> {
+            let mut shortened = None;
+            for (ty, data) in self.eir().flatten() {
+                match ty {
+                    0x09 => return Some(String::from_utf8_lossy(data).into_owned()),
+                    0x08 => shortened = Some(String::from_utf8_lossy(data).into_owned()),
+                    _ => {}
+                }
+            }
+            shortened
+        }
     }
 
     /// Device Disconnected Event
@@ -254,14 +273,14 @@ mod imp {
     #[derive(Debug, Clone, Unpack, Getters)]
     #[event(0x0012)]
     pub struct DeviceFound {
-        address: super::WrappedAddress,
-        address_type: super::InternalAddressType,
+        pub(crate) address: super::WrappedAddress,
+        pub(crate) address_type: super::InternalAddressType,
         #[getset(get = "pub")]
-        rssi: u8,
+        pub(crate) rssi: u8,
         #[getset(get = "pub")]
-        flags: super::DeviceConnectFlags,
+        pub(crate) flags: super::DeviceConnectFlags,
         #[getset(get = "pub")]
-        eir_data: super::VariableLengthBytes,
+        pub(crate) eir_data: super::VariableLengthBytes,
     }
 
     impl DeviceFound {
@@ -282,6 +301,20 @@ mod imp {
         discovering: bool,
     }
 
+    impl Discovering {
+        /// true if the transition is of BR/EDR discovery.
+        pub fn is_bredr(&self) -> bool {
+            self.address_type.iter().any(|ty| ty == &AddressType::BrEdr)
+        }
+
+        /// true if the transition is of LE discovery.
+        pub fn is_le(&self) -> bool {
+            self.address_type
+                .iter()
+                .any(|ty| matches!(ty, AddressType::LePublic | AddressType::LeRandom))
+        }
+    }
+
     /// Device Blocked Event
     ///
     /// see [bluez
@@ -634,6 +667,40 @@ mod imp {
             join(&self.address_type, &self.address)
         }
     }
+
+    /// Mesh Device Found Event
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Clone, Unpack, Getters)]
+    #[event(0x0031)]
+    pub struct MeshDeviceFound {
+        address: super::WrappedAddress,
+        address_type: super::InternalAddressType,
+        #[getset(get = "pub")]
+        rssi: u8,
+        /// Controller clock of the reception, for [`MeshSend`](crate::command::MeshSend).
+        #[getset(get = "pub")]
+        instant: u64,
+        #[getset(get = "pub")]
+        flags: super::DeviceConnectFlags,
+        #[getset(get = "pub")]
+        ad_data: super::VariableLengthBytes,
+    }
+
+    impl MeshDeviceFound {
+        pub fn address(&self) -> Address {
+            join(&self.address_type, &self.address)
+        }
+    }
+
+    /// Mesh Packet Transmit Complete Event
+    ///
+    /// see [bluez
+    /// docs/mgmt-api.txt](https://git.kernel.org/pub/scm/bluetooth/bluez.git/plain/doc/mgmt-api.txt)
+    #[derive(Debug, Clone, Unpack, Newtype)]
+    #[event(0x0032)]
+    pub struct MeshPacketComplete(super::MeshHandle);
 }
 
 #[doc(hidden)]
@@ -641,11 +708,284 @@ pub fn unpack_events<R>(read: &mut R) -> pack::Result<(ControllerIndex, Event)>
 where
     R: io::Read,
 {
-    let code = EventCode::unpack(read)?;
+    let raw_code = u16::unpack(read)?;
     let index = ControllerIndex::unpack(read)?;
-
     let data = <Vec<u8>>::unpack(read)?;
+
+    // Unknown event or reply for unknown command.
+    let code = match EventCode::unpack(&mut &raw_code.to_le_bytes()[..]) {
+        Ok(code) => code,
+        Err(..) => return Ok((index, Event::Unknown(raw_code, data.into()))),
+    };
+    if matches!(code, EventCode::CommandComplete | EventCode::CommandStatus)
+        && data.len() >= 2
+        && crate::command::CommandCode::unpack(&mut &data[..]).is_err()
+    {
+        return Ok((index, Event::Unknown(raw_code, data.into())));
+    }
+
     let events = Event::unpack_inner(code, &mut &data[..])?;
 
     Ok((index, events))
 }
+
+/// Pack an event as sent by the kernel. The inverse of unpacking.
+#[cfg(feature = "arbitrary")]
+pub fn pack_events<W>(index: &ControllerIndex, event: &Event, write: &mut W) -> pack::Result<()>
+where
+    W: io::Write,
+{
+    let mut data = vec![];
+    let code = event.pack_inner(&mut data)?;
+    code.pack(write)?;
+    index.pack(write)?;
+    data.pack(write)
+}
+
+/// Partially decoded event. See [`Event::unpack_lenient`].
+#[cfg(feature = "lenient")]
+#[derive(Debug, Clone)]
+pub struct PartialEvent {
+    /// Event code. `None` if truncated.
+    pub code: Option<u16>,
+    /// Name of the event if known.
+    pub name: Option<&'static str>,
+    /// Controller index. `None` if truncated.
+    pub index: Option<ControllerIndex>,
+    /// Event parameters. The whole parameters as `data` for unknown events.
+    pub fields: Vec<pack::lenient::Field>,
+}
+
+#[cfg(feature = "lenient")]
+impl Event {
+    /// Decode as much as possible, recording what went wrong per field.
+    ///
+    /// Fields with invalid values are kept as raw bytes, and truncated fields are `None`.
+    /// For diagnostic tools. Use [`unpack_events`] otherwise.
+    pub fn unpack_lenient(bytes: &[u8]) -> (PartialEvent, Vec<pack::lenient::FieldError>) {
+        let mut decoder = pack::lenient::Decoder::new(bytes);
+        let code = decoder.value::<u16>("code").ok();
+        let index = decoder.value::<ControllerIndex>("index").ok();
+        let known = code.and_then(|code| EventCode::unpack(&mut &code.to_le_bytes()[..]).ok());
+
+        if let Ok(len) = decoder.value::<u16>("length") {
+            decoder.limit("length", len as usize);
+            match &known {
+                Some(code) => Event::unpack_lenient_inner(code, &mut decoder),
+                None => decoder.rest("data"),
+            }
+        }
+
+        let name = known.and_then(|known| {
+            all_events()
+                .iter()
+                .find(|(code, _)| code == &known)
+                .map(|(_, name)| *name)
+        });
+        let (fields, errors) = decoder.finish();
+        let event = PartialEvent {
+            code,
+            name,
+            index,
+            fields,
+        };
+        (event, errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_events() {
+        let all = all_events();
+        assert!(!all.is_empty());
+        assert!(all.contains(&(EventCode::CommandComplete, "CommandComplete")));
+    }
+
+    #[test]
+    fn test_try_from_event() {
+        use std::convert::TryFrom;
+
+        let bytes = [0x04, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let (_, event) = unpack_events(&mut &bytes[..]).unwrap();
+        assert!(<&IndexAdded>::try_from(&event).is_ok());
+        assert!(<&IndexRemoved>::try_from(&event).is_err());
+        assert!(IndexAdded::try_from(event.clone()).is_ok());
+        assert!(matches!(
+            IndexRemoved::try_from(event),
+            Err(Event::IndexAdded(IndexAdded))
+        ));
+    }
+
+    #[test]
+    fn test_new_configuration_options() {
+        let bytes = [0x1F, 0x00, 0x00, 0x00, 0x04, 0x00, 0x02, 0x00, 0x00, 0x00];
+        let (index, event) = unpack_events(&mut &bytes[..]).unwrap();
+        assert_eq!(ControllerIndex::ControllerId(0), index);
+        match event {
+            Event::NewConfigurationOptions(options) => assert_eq!(
+                ControllerConfigurationOption::BluetoothPublicAddressConfiguration,
+                *options
+            ),
+            event => panic!("{:?}", event),
+        }
+    }
+
+    #[test]
+    fn test_class_of_device_changed() {
+        // computer / laptop, with audio and networking services
+        let bytes = [0x07, 0x00, 0x00, 0x00, 0x03, 0x00, 0x0C, 0x01, 0x22];
+        let (_, event) = unpack_events(&mut &bytes[..]).unwrap();
+        match event {
+            Event::ClassOfDeviceChanged(class) => {
+                assert_eq!(0x01, class.major_class());
+                assert_eq!(0x0C, class.minor_class());
+                assert_eq!(0x0110, class.service_classes());
+                assert_eq!(0x22010C, class.to_u32());
+            }
+            event => panic!("{:?}", event),
+        }
+    }
+
+    #[test]
+    fn test_mesh_events() {
+        let mut b = vec![0x31, 0x00, 0x00, 0x00, 0x19, 0x00];
+        b.extend([0x55, 0x44, 0x33, 0x22, 0x11, 0xC0, 0x02, 0xC4]);
+        b.extend([0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]);
+        b.extend([0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x02, 0x2A, 0x00]);
+        let (_, event) = unpack_events(&mut &b[..]).unwrap();
+        match event {
+            Event::MeshDeviceFound(found) => {
+                assert_eq!(
+                    "C0:11:22:33:44:55"
+                        .parse::<BdAddr>()
+                        .unwrap()
+                        .to_le_random_addr(),
+                    found.address()
+                );
+                assert_eq!(0xC4, *found.rssi());
+                assert_eq!(0x0102030405060708, *found.instant());
+                assert!(found.flags().is_empty());
+                assert_eq!(&[0x02, 0x2A, 0x00], &found.ad_data()[..]);
+            }
+            event => panic!("{:?}", event),
+        }
+
+        let b = [0x32, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02];
+        let (_, event) = unpack_events(&mut &b[..]).unwrap();
+        match event {
+            Event::MeshPacketComplete(handle) => assert_eq!(MeshHandle::new(2), *handle),
+            event => panic!("{:?}", event),
+        }
+    }
+
+    #[test]
+    fn test_discovering() {
+        // mask, BR/EDR, LE
+        for &(mask, bredr, le) in &[
+            (0x01, true, false),
+            (0x02, false, true),
+            (0x04, false, true),
+            (0x06, false, true),
+            (0x07, true, true),
+            (0x00, false, false),
+        ] {
+            for &on in &[0x00, 0x01] {
+                let b = [0x13, 0x00, 0x00, 0x00, 0x02, 0x00, mask, on];
+                let (_, event) = unpack_events(&mut &b[..]).unwrap();
+                let discovering = match event {
+                    Event::Discovering(discovering) => discovering,
+                    event => panic!("{:?}", event),
+                };
+                assert_eq!(on == 0x01, *discovering.discovering());
+                assert_eq!(bredr, discovering.is_bredr(), "{:#04x}", mask);
+                assert_eq!(le, discovering.is_le(), "{:#04x}", mask);
+            }
+        }
+    }
+
+    #[cfg(feature = "lenient")]
+    #[test]
+    fn test_unpack_lenient() {
+        use pack::lenient::FieldValue;
+
+        let (bytes, _) = crate::fixtures::device_found();
+        let (event, errors) = Event::unpack_lenient(&bytes);
+        assert_eq!(Some("DeviceFound"), event.name);
+        assert_eq!(Some(ControllerIndex::ControllerId(0)), event.index);
+        assert_eq!(5, event.fields.len());
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        // unknown address type: kept as is.
+        let mut corrupted = bytes.clone();
+        corrupted[12] = 0x07;
+        let (event, errors) = Event::unpack_lenient(&corrupted);
+        assert_eq!(
+            (
+                "address_type",
+                Some(FieldValue::Decoded("Unknown(7)".into()))
+            ),
+            event.fields[1]
+        );
+        assert_eq!(Some(FieldValue::Decoded("196".into())), event.fields[2].1); // rssi
+        assert!(event.fields[4].1.is_some()); // eir data
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        // bad disconnect reason.
+        let (event, errors) = Event::unpack_lenient(&[
+            0x0C, 0x00, 0x00, 0x00, 0x08, 0x00, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0xFF,
+        ]);
+        assert_eq!(Some("DeviceDisconnect"), event.name);
+        assert_eq!(
+            ("reason", Some(FieldValue::Raw([0xFF].into()))),
+            event.fields[2]
+        );
+        assert_eq!(1, errors.len());
+        assert_eq!(("reason", 13), (errors[0].field, errors[0].offset));
+
+        // truncated in the middle of flags.
+        let (event, errors) = Event::unpack_lenient(&bytes[..16]);
+        assert!(matches!(
+            event.fields[2],
+            ("rssi", Some(FieldValue::Decoded(..)))
+        ));
+        assert_eq!(("flags", None), event.fields[3]);
+        assert_eq!(("eir_data", None), event.fields[4]);
+        let errors = errors
+            .iter()
+            .map(|e| (e.field, e.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![("length", 6), ("flags", 14)], errors);
+
+        // reply for unknown command.
+        let (event, errors) =
+            Event::unpack_lenient(&[0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0xFF, 0xFF, 0x00, 0x01]);
+        assert_eq!(Some("CommandComplete"), event.name);
+        assert_eq!(
+            ("opcode", Some(FieldValue::Raw([0xFF, 0xFF].into()))),
+            event.fields[0]
+        );
+        assert!(matches!(
+            event.fields[1],
+            ("status", Some(FieldValue::Decoded(..)))
+        ));
+        assert_eq!(("opcode", 6), (errors[0].field, errors[0].offset));
+
+        // unknown event and header only.
+        let (event, errors) = Event::unpack_lenient(&[0xFF, 0x7F, 0x00, 0x00, 0x01, 0x00, 0xAA]);
+        assert_eq!((Some(0x7FFF), None), (event.code, event.name));
+        assert_eq!(
+            vec![("data", Some(FieldValue::Raw([0xAA].into())))],
+            event.fields
+        );
+        assert!(errors.is_empty());
+
+        let (event, errors) = Event::unpack_lenient(&[0x12, 0x00, 0x00]);
+        assert_eq!(Some(0x0012), event.code);
+        assert_eq!(None, event.index);
+        assert!(event.fields.is_empty());
+        assert_eq!("index", errors[0].field);
+    }
+}
@@ -0,0 +1,369 @@
+//! Sample packets for tests.
+//!
+//! Each function returns the bytes as read from the mgmt socket and the expected decoded value.
+use std::marker::PhantomData;
+
+use crate::command::{
+    AddUuid, ReadControllerInformationReply, ReadExperimentalFeaturesInformationReply,
+    ReadManagementVersionInformationReply, RemoveUuid, StartServiceDiscovery,
+};
+use crate::event::{DeviceConnected, DeviceFound, NewLongTermKey};
+use crate::*;
+
+/// Audio Sink `0000110b-0000-1000-8000-00805f9b34fb` on the wire, as bluetoothd sends it.
+///
+/// The bytes are reversed from the string form. The kernel takes the 16 bit
+/// value for EIR from bytes 12 and 13.
+const AUDIO_SINK: [u8; 16] = [
+    0xFB, 0x34, 0x9B, 0x5F, 0x80, 0x00, 0x00, 0x80, 0x00, 0x10, 0x00, 0x00, 0x0B, 0x11, 0x00, 0x00,
+];
+
+fn audio_sink() -> Uuid {
+    Uuid::from_u16(0x110B)
+}
+
+/// Add UUID command (index 0) for Audio Sink, as written to the mgmt socket.
+pub fn add_uuid() -> (Vec<u8>, AddUuid) {
+    let mut bytes = vec![0x10, 0x00, 0x00, 0x00, 0x11, 0x00];
+    bytes.extend(AUDIO_SINK);
+    bytes.extend([0x00]); // service hint
+    (bytes, AddUuid::new(audio_sink(), 0x00))
+}
+
+/// Remove UUID command (index 0) for Audio Sink, as written to the mgmt socket.
+pub fn remove_uuid() -> (Vec<u8>, RemoveUuid) {
+    let mut bytes = vec![0x11, 0x00, 0x00, 0x00, 0x10, 0x00];
+    bytes.extend(AUDIO_SINK);
+    (bytes, RemoveUuid::new(audio_sink()))
+}
+
+/// Start Service Discovery command (index 0) of BR/EDR for Audio Sink, as written
+/// to the mgmt socket.
+pub fn start_service_discovery() -> (Vec<u8>, StartServiceDiscovery) {
+    let mut bytes = vec![0x3A, 0x00, 0x00, 0x00, 0x14, 0x00];
+    bytes.extend([0x01]); // address type
+    bytes.extend([0x7F]); // rssi threshold
+    bytes.extend([0x01, 0x00]); // uuid count
+    bytes.extend(AUDIO_SINK);
+
+    let address_type = vec![AddressType::BrEdr].into_iter().collect();
+    let expected = StartServiceDiscovery::new(address_type, 0x7F, vec![audio_sink()]);
+    (bytes, expected)
+}
+
+/// Command Complete event for [`crate::command::ReadExperimentalFeaturesInformation`]
+/// (non-controller), listing the debug feature of the kernel as active.
+///
+/// The UUID bytes are `debug_uuid` of `net/bluetooth/mgmt.c`,
+/// for `d4992530-b9ec-469f-ab01-6c481c47da1c`.
+pub fn experimental_features_reply() -> (Vec<u8>, ReadExperimentalFeaturesInformationReply) {
+    let mut bytes = vec![0x01, 0x00, 0xFF, 0xFF, 0x19, 0x00, 0x49, 0x00, 0x00];
+    bytes.extend([0x01, 0x00]); // feature count
+    bytes.extend([
+        0x1C, 0xDA, 0x47, 0x1C, 0x48, 0x6C, 0x01, 0xAB, 0x9F, 0x46, 0xEC, 0xB9, 0x30, 0x25, 0x99,
+        0xD4,
+    ]);
+    bytes.extend([0x01, 0x00, 0x00, 0x00]); // flags
+
+    let uuid = "d4992530-b9ec-469f-ab01-6c481c47da1c".parse().unwrap();
+    let expected =
+        ReadExperimentalFeaturesInformationReply(vec![(uuid, FeatureFlags::FeatureActive)]);
+    (bytes, expected)
+}
+
+/// Command Complete event for [`crate::command::ReadManagementVersionInformation`].
+pub fn version_reply() -> (Vec<u8>, ReadManagementVersionInformationReply) {
+    let bytes = vec![
+        0x01, 0x00, 0xFF, 0xFF, 0x06, 0x00, 0x01, 0x00, 0x00, 0x01, 0x13, 0x00,
+    ];
+    let expected = ReadManagementVersionInformationReply {
+        version: 1,
+        revision: 0x0013,
+    };
+    (bytes, expected)
+}
+
+/// Command Complete event for [`crate::command::ReadControllerInformation`] (index 0).
+pub fn controller_information_reply() -> (Vec<u8>, ReadControllerInformationReply) {
+    let mut bytes = vec![0x01, 0x00, 0x00, 0x00, 0x1B, 0x01, 0x04, 0x00, 0x00];
+    bytes.extend([0x55, 0x44, 0x33, 0x22, 0x11, 0x00]); // address
+    bytes.extend([0x09]); // bluetooth version
+    bytes.extend([0x02, 0x00]); // manufacturer
+    bytes.extend([0xFF, 0xFF, 0x01, 0x00]); // supported settings
+    bytes.extend([0x81, 0x02, 0x00, 0x00]); // current settings
+    bytes.extend([0x0C, 0x01, 0x1C]); // class of device
+    let mut name = [0; 249];
+    name[..6].copy_from_slice(b"btmgmt");
+    bytes.extend(name);
+    let mut short_name = [0; 11];
+    short_name[..2].copy_from_slice(b"bt");
+    bytes.extend(short_name);
+
+    let expected = ReadControllerInformationReply {
+        address: "00:11:22:33:44:55".parse().unwrap(),
+        bluetooth_version: 0x09,
+        manufacturer: 0x0002,
+        supported_settings: Settings::from_bits_truncate(0x0001_FFFF),
+        current_settings: Settings::Powered
+            | Settings::BasicRateEnhancedDataRate
+            | Settings::LowEnergy,
+        class_of_device: [0x0C, 0x01, 0x1C].into(),
+        name: "btmgmt".parse().unwrap(),
+        short_name: "bt".parse().unwrap(),
+    };
+    (bytes, expected)
+}
+
+/// Device Found event (index 0) with flags and complete local name in EIR.
+pub fn device_found() -> (Vec<u8>, DeviceFound) {
+    let eir = [0x02, 0x01, 0x06, 0x05, 0x09, b't', b'e', b's', b't'];
+
+    let mut bytes = vec![0x12, 0x00, 0x00, 0x00, 0x17, 0x00];
+    bytes.extend([0x66, 0x55, 0x44, 0x33, 0x22, 0xC1]); // address
+    bytes.extend([0x02]); // address type
+    bytes.extend([0xC4]); // rssi
+    bytes.extend([0x00, 0x00, 0x00, 0x00]); // flags
+    bytes.extend([eir.len() as u8, 0x00]);
+    bytes.extend(eir);
+
+    let (address, address_type) = split(
+        "c1:22:33:44:55:66"
+            .parse::<BdAddr>()
+            .unwrap()
+            .to_le_random_addr(),
+    );
+    let expected = DeviceFound {
+        address,
+        address_type,
+        rssi: 0xC4,
+        flags: DeviceConnectFlags::empty(),
+        eir_data: VariableLengthBytes(eir.to_vec().into(), PhantomData),
+    };
+    (bytes, expected)
+}
+
+/// Device Connected event (index 0) of a BR/EDR speaker, with name and class in EIR,
+/// laid out as the kernel sends it.
+pub fn device_connected() -> (Vec<u8>, DeviceConnected) {
+    let mut eir = vec![0x0B, 0x09];
+    eir.extend(b"JBL Flip 5"); // complete local name
+    eir.extend([0x04, 0x0D, 0x14, 0x04, 0x24]); // class of device
+
+    let mut bytes = vec![0x0B, 0x00, 0x00, 0x00, 0x1E, 0x00];
+    bytes.extend([0x13, 0x71, 0xDA, 0x7D, 0x1A, 0x00]); // address
+    bytes.extend([0x00]); // address type
+    bytes.extend([0x08, 0x00, 0x00, 0x00]); // flags
+    bytes.extend([eir.len() as u8, 0x00]);
+    bytes.extend(&eir);
+
+    let (address, address_type) = split(
+        "00:1a:7d:da:71:13"
+            .parse::<BdAddr>()
+            .unwrap()
+            .to_br_edr_addr(),
+    );
+    let expected = DeviceConnected {
+        address,
+        address_type,
+        flags: DeviceConnectFlags::InitiatedConnection,
+        eir_data: VariableLengthBytes(eir.into(), PhantomData),
+    };
+    (bytes, expected)
+}
+
+/// New Long Term Key event (index 0).
+pub fn new_long_term_key() -> (Vec<u8>, NewLongTermKey) {
+    let random_number = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+    let value = [
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE,
+        0xFF,
+    ];
+
+    let mut bytes = vec![0x0A, 0x00, 0x00, 0x00, 0x25, 0x00];
+    bytes.extend([0x01]); // store hint
+    bytes.extend([0x55, 0x44, 0x33, 0x22, 0x11, 0x00]); // address
+    bytes.extend([0x01]); // address type
+    bytes.extend([0x01]); // key type
+    bytes.extend([0x01]); // master
+    bytes.extend([0x10]); // encryption size
+    bytes.extend([0x34, 0x12]); // encryption diversifier
+    bytes.extend(random_number);
+    bytes.extend(value);
+
+    let (address, address_type) = split(
+        "00:11:22:33:44:55"
+            .parse::<BdAddr>()
+            .unwrap()
+            .to_le_public_addr(),
+    );
+    let expected = NewLongTermKey {
+        store_hint: true,
+        key: LongTermKey {
+            address,
+            address_type,
+            key_type: LongTermKeyType::AuthenticatedKey,
+            master: true,
+            encryption_size: 0x10,
+            encryption_diversifier: 0x1234,
+            random_number,
+            value,
+        },
+    };
+    (bytes, expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::CommandRequest;
+    use crate::event::{self, Event};
+
+    fn unpack_reply<R: Unpack>(bytes: &[u8]) -> R {
+        let (_, event) = event::unpack_events(&mut &bytes[..]).unwrap();
+        match event {
+            Event::CommandComplete(comp) => R::unpack(&mut &comp.data()[..]).unwrap(),
+            event => panic!("{:?}", event),
+        }
+    }
+
+    #[test]
+    fn test_version_reply() {
+        let (bytes, expected) = version_reply();
+        let reply = unpack_reply::<
+            <crate::command::ReadManagementVersionInformation as CommandRequest>::Reply,
+        >(&bytes);
+        assert_eq!(format!("{:?}", expected), format!("{:?}", reply));
+    }
+
+    #[test]
+    fn test_controller_information_reply() {
+        let (bytes, expected) = controller_information_reply();
+        let reply = unpack_reply::<ReadControllerInformationReply>(&bytes);
+        assert_eq!(format!("{:?}", expected), format!("{:?}", reply));
+        assert_eq!(expected.address(), reply.address());
+    }
+
+    #[test]
+    fn test_unterminated_name() {
+        let (mut bytes, _) = controller_information_reply();
+        let name = 6 + 3 + 6 + 1 + 2 + 4 + 4 + 3;
+        bytes[name..name + 249].fill(b'a');
+        bytes[name + 249..].fill(b'b');
+
+        let reply = unpack_reply::<ReadControllerInformationReply>(&bytes);
+        assert!(!reply.name().is_valid());
+        assert_eq!("a".repeat(249), reply.name().to_string_lossy());
+        assert!(!reply.short_name().is_valid());
+        assert_eq!("b".repeat(11), reply.short_name().to_string_lossy());
+
+        let (bytes, _) = controller_information_reply();
+        let reply = unpack_reply::<ReadControllerInformationReply>(&bytes);
+        assert!(reply.name().is_valid());
+        assert!(reply.short_name().is_valid());
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let (bytes, _) = controller_information_reply();
+        let reply = unpack_reply::<ReadControllerInformationReply>(&bytes);
+        let capabilities = reply.capabilities();
+        assert!(capabilities.le);
+        assert!(capabilities.bredr);
+        assert!(capabilities.secure_connections);
+        assert!(capabilities.privacy);
+        assert!(capabilities.phy_configuration);
+        assert!(!capabilities.wideband_speech);
+
+        let capabilities = Capabilities::from(&(Settings::LowEnergy | Settings::Privacy));
+        assert_eq!(
+            Capabilities {
+                le: true,
+                privacy: true,
+                ..Default::default()
+            },
+            capabilities
+        );
+    }
+
+    #[test]
+    fn test_device_found() {
+        let (bytes, expected) = device_found();
+        let (index, event) = event::unpack_events(&mut &bytes[..]).unwrap();
+        assert_eq!(ControllerIndex::ControllerId(0), index);
+        match event {
+            Event::DeviceFound(event) => {
+                assert_eq!(format!("{:?}", expected), format!("{:?}", event));
+                assert_eq!(expected.address(), event.address());
+            }
+            event => panic!("{:?}", event),
+        }
+    }
+
+    #[test]
+    fn test_device_connected() {
+        let (bytes, expected) = device_connected();
+        let (_, event) = event::unpack_events(&mut &bytes[..]).unwrap();
+        match event {
+            Event::DeviceConnected(event) => {
+                assert_eq!(format!("{:?}", expected), format!("{:?}", event));
+                assert_eq!(expected.address(), event.address());
+                assert_eq!(&DeviceConnectFlags::InitiatedConnection, event.flags());
+                assert_eq!(Some("JBL Flip 5".into()), event.name());
+                let eir = event.eir().collect::<Result<Vec<_>, _>>().unwrap();
+                assert_eq!(
+                    vec![(0x09, &b"JBL Flip 5"[..]), (0x0D, &[0x14, 0x04, 0x24][..])],
+                    eir
+                );
+            }
+            event => panic!("{:?}", event),
+        }
+    }
+
+    fn packed(command: impl Into<crate::command::Command>) -> Vec<u8> {
+        let mut b = vec![];
+        crate::command::pack_command(&ControllerIndex::ControllerId(0), &command.into(), &mut b)
+            .unwrap();
+        b
+    }
+
+    #[test]
+    fn test_uuid_commands() {
+        let (bytes, command) = add_uuid();
+        assert_eq!(bytes, packed(command));
+        let (bytes, command) = remove_uuid();
+        assert_eq!(bytes, packed(command));
+        let (bytes, command) = start_service_discovery();
+        assert_eq!(bytes, packed(command));
+        let (bytes, _) = start_service_discovery();
+        assert_eq!(
+            bytes,
+            packed(StartServiceDiscovery::with_16bit_uuids(
+                vec![AddressType::BrEdr].into_iter().collect(),
+                0x7F,
+                &[0x110B]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_experimental_features_reply() {
+        let (bytes, expected) = experimental_features_reply();
+        let reply = unpack_reply::<ReadExperimentalFeaturesInformationReply>(&bytes);
+        assert_eq!(format!("{:?}", expected), format!("{:?}", reply));
+        let (uuid, flags) = reply.iter().next().unwrap();
+        assert_eq!("d4992530-b9ec-469f-ab01-6c481c47da1c", uuid.to_string());
+        assert_eq!(&FeatureFlags::FeatureActive, flags);
+    }
+
+    #[test]
+    fn test_new_long_term_key() {
+        let (bytes, expected) = new_long_term_key();
+        let (_, event) = event::unpack_events(&mut &bytes[..]).unwrap();
+        match event {
+            Event::NewLongTermKey(event) => {
+                assert_eq!(format!("{:?}", expected), format!("{:?}", event));
+            }
+            event => panic!("{:?}", event),
+        }
+    }
+}
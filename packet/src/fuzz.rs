@@ -0,0 +1,127 @@
+//! Robustness of [`Unpack`]: random bytes must decode to `Ok` or `Err`, never panic.
+//!
+//! Inputs are generated by a fixed seed PRNG, so failures are reproducible.
+//! Run with more iterations by `BTMGMT_FUZZ_ITERATIONS`.
+//!
+//! ```text
+//! BTMGMT_FUZZ_ITERATIONS=1000000 cargo test -p btmgmt-packet --release fuzz
+//! ```
+//!
+//! Inputs found to panic are added to [`CORPUS`].
+use crate::command::*;
+use crate::event::{self, EventCode};
+use crate::pack::Unpack;
+use crate::AdvDataScanResp;
+
+const DEFAULT_ITERATIONS: usize = 2000;
+
+/// Max length of generated input. Enough for names and EIR.
+const MAX_LEN: u64 = 300;
+
+/// Hand picked inputs around length fields.
+const CORPUS: &[&[u8]] = &[
+    &[],
+    &[0x00],
+    // adv data / scan resp length longer than data
+    &[0xFF, 0xFF],
+    &[0x02, 0x00, 0x01],
+    &[0x00, 0x02, 0x01],
+    // count longer than items
+    &[0xFF, 0xFF, 0x00],
+    &[0x01, 0x00, 0x01, 0x00],
+    // unterminated name
+    &[0x41; 260],
+    // EIR length longer than data
+    &[
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x09, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00,
+        0x00, 0x00, 0xFF, 0xFF, 0x02, 0x09,
+    ],
+];
+
+/// xorshift64
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn bytes(&mut self) -> Vec<u8> {
+        let len = self.next() % MAX_LEN;
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+}
+
+fn iterations() -> usize {
+    std::env::var("BTMGMT_FUZZ_ITERATIONS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(DEFAULT_ITERATIONS)
+}
+
+fn fuzz<T: Unpack>(seed: u64) {
+    for input in CORPUS {
+        let _ = T::unpack(&mut &input[..]);
+    }
+
+    let mut rng = Rng(seed);
+    for _ in 0..iterations() {
+        let input = rng.bytes();
+        let _ = T::unpack(&mut &input[..]);
+    }
+}
+
+macro_rules! fuzz_tests {
+    ($($name:ident: $ty:ty,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                fuzz::<$ty>(0x2545_F491_4F6C_DD1D);
+            }
+        )*
+    };
+}
+
+fuzz_tests! {
+    fuzz_adv_data_scan_resp: AdvDataScanResp,
+    fuzz_supported_commands: ReadManagementSupportedCommandsReply,
+    fuzz_controller_information: ReadControllerInformationReply,
+    fuzz_extended_controller_information: ReadExtendedControllerInformationReply,
+    fuzz_extended_controller_index_list: ReadExtendedControllerIndexListReply,
+    fuzz_connections: GetConnectionsReply,
+    fuzz_local_oob_extended_data: ReadLocalOutOfBandExtendedDataReply,
+    fuzz_advertising_feature: ReadAdvertisingFeatureReply,
+    fuzz_experimental_features: ReadExperimentalFeaturesInformationReply,
+    fuzz_security_information: ReadSecurityInformationReply,
+}
+
+/// Every event, with a well formed header.
+#[test]
+fn fuzz_events() {
+    let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+    for (code, _) in event::all_events() {
+        let code = code.clone() as u16;
+        let inputs = CORPUS
+            .iter()
+            .map(|input| input.to_vec())
+            .chain((0..iterations()).map(|_| rng.bytes()))
+            .collect::<Vec<_>>();
+        for params in inputs {
+            let mut input = code.to_le_bytes().to_vec();
+            input.extend(&(rng.next() as u16).to_le_bytes());
+            input.extend(&(params.len() as u16).to_le_bytes());
+            input.extend(&params);
+            let _ = event::unpack_events(&mut &input[..]);
+        }
+    }
+
+    // unknown codes and wrong lengths
+    for _ in 0..iterations() {
+        let input = rng.bytes();
+        let _ = event::unpack_events(&mut &input[..]);
+    }
+    let _ = EventCode::unpack(&mut &[0xFF, 0xFF][..]);
+}
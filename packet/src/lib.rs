@@ -11,6 +11,7 @@
 //!   ([LICENSE-APACHE](LICENSE-APACHE) or <http://www.apache.org/licenses/LICENSE-2.0>)
 //! * MIT license
 //!   ([LICENSE-MIT](LICENSE-MIT) or <http://opensource.org/licenses/MIT>)
+//!
 //! at your option.
 //!
 //! ## Contribution
@@ -24,9 +25,11 @@ use std::convert::{TryFrom, TryInto};
 use std::ffi::{CString, NulError};
 use std::fmt;
 use std::io;
+use std::iter::FromIterator;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
-use std::str::FromStr;
+use std::str::{self, FromStr, Utf8Error};
+use std::time::Duration;
 
 pub use bdaddr::{Address, AddressType, BdAddr};
 use bitflags::bitflags;
@@ -38,10 +41,72 @@ use helper::helper::{IterNewtype, Newtype};
 #[doc(hidden)]
 pub use helper::pack::{self, Pack, Unpack};
 
+#[cfg(feature = "serde")]
+mod serde_impl;
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impl;
+
+pub mod advertising;
+pub mod class;
 pub mod command;
+pub mod eir;
 pub mod event;
+#[cfg(any(test, feature = "test-fixtures"))]
+pub mod fixtures;
+#[cfg(test)]
+mod fuzz;
+mod registry;
+pub mod replay;
+
+pub use registry::registry_json;
+
+/// Address whose type is known from the Rust type.
+///
+/// Command constructors take any of these, setting the address type on the wire.
+/// BR/EDR addresses are built by [`BdAddr::to_br_edr_addr`].
+pub trait IntoAddress {
+    fn into_address(self) -> Address;
+}
+
+impl IntoAddress for Address {
+    fn into_address(self) -> Address {
+        self
+    }
+}
 
-fn split(addr: Address) -> (WrappedAddress, InternalAddressType) {
+impl IntoAddress for bdaddr::PublicDeviceAddress {
+    fn into_address(self) -> Address {
+        Address::LePublic(self)
+    }
+}
+
+impl IntoAddress for bdaddr::RandomDeviceAddress {
+    fn into_address(self) -> Address {
+        Address::LeRandom(self)
+    }
+}
+
+macro_rules! into_random_address {
+    ($($ty:ident),*) => {
+        $(
+            impl IntoAddress for bdaddr::$ty {
+                fn into_address(self) -> Address {
+                    Address::LeRandom(self.into())
+                }
+            }
+        )*
+    };
+}
+
+into_random_address!(
+    StaticDeviceAddress,
+    ResolvablePrivateAddress,
+    NonResolvablePrivateAddress
+);
+
+fn split<A: IntoAddress>(addr: A) -> (WrappedAddress, InternalAddressType) {
+    let addr = addr.into_address();
     let address_type = match &addr {
         Address::BrEdr(..) => InternalAddressType::BrEdr,
         Address::LePublic(..) => InternalAddressType::LePublic,
@@ -51,9 +116,12 @@ fn split(addr: Address) -> (WrappedAddress, InternalAddressType) {
     (address, address_type)
 }
 
+/// [`InternalAddressType::Unknown`] joins as BR/EDR. [`Address`] has no room for it.
 fn join(ty: &InternalAddressType, addr: &WrappedAddress) -> Address {
     match ty {
-        InternalAddressType::BrEdr => addr.0.clone().to_br_edr_addr(),
+        InternalAddressType::BrEdr | InternalAddressType::Unknown(..) => {
+            addr.0.clone().to_br_edr_addr()
+        }
         InternalAddressType::LePublic => addr.0.clone().to_le_public_addr(),
         InternalAddressType::LeRandom => addr.0.clone().to_le_random_addr(),
     }
@@ -77,6 +145,10 @@ impl Pack for WrappedAddress {
     {
         <[u8; 6]>::pack(&self.0.clone().into(), write)
     }
+
+    fn packed_len_hint(&self) -> usize {
+        6
+    }
 }
 
 impl Unpack for WrappedAddress {
@@ -94,6 +166,7 @@ impl fmt::Display for WrappedAddress {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq, Pack, Unpack)]
 #[pack(u8)]
 pub enum ErrorCode {
@@ -124,6 +197,25 @@ impl ErrorCode {
     pub fn success(&self) -> bool {
         self == &Self::Success
     }
+
+    /// Best-effort [`io::ErrorKind`] of the status. `Other` if nothing fits.
+    pub fn io_error_kind(&self) -> io::ErrorKind {
+        match self {
+            Self::UnknownCommand | Self::NotSupported => io::ErrorKind::Unsupported,
+            Self::NotConnected => io::ErrorKind::NotConnected,
+            Self::ConnectFailed => io::ErrorKind::ConnectionRefused,
+            Self::AuthenticationFailed | Self::PermissionDenied => io::ErrorKind::PermissionDenied,
+            Self::NotPaired | Self::InvalidIndex => io::ErrorKind::NotFound,
+            Self::NoResources => io::ErrorKind::OutOfMemory,
+            Self::Timeout => io::ErrorKind::TimedOut,
+            Self::AlreadyConnected | Self::AlreadyPaired => io::ErrorKind::AlreadyExists,
+            Self::Busy => io::ErrorKind::WouldBlock,
+            Self::InvalidParameters => io::ErrorKind::InvalidInput,
+            Self::Disconnected => io::ErrorKind::ConnectionAborted,
+            Self::NotPowered | Self::RfKilled => io::ErrorKind::NetworkDown,
+            Self::Success | Self::Failed | Self::Rejected | Self::Cancelled => io::ErrorKind::Other,
+        }
+    }
 }
 
 impl fmt::Display for ErrorCode {
@@ -207,6 +299,10 @@ impl Pack for ControllerIndex {
         u16::from(self.clone()).pack(write)?;
         Ok(())
     }
+
+    fn packed_len_hint(&self) -> usize {
+        2
+    }
 }
 
 impl Unpack for ControllerIndex {
@@ -218,6 +314,7 @@ impl Unpack for ControllerIndex {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, Getters)]
 #[getset(get = "pub")]
 pub struct CommandsEvents {
@@ -230,6 +327,8 @@ impl Pack for CommandsEvents {
     where
         W: io::Write,
     {
+        debug_assert!(self.commands.len() <= u16::MAX as usize);
+        debug_assert!(self.events.len() <= u16::MAX as usize);
         (self.commands.len() as u16).pack(write)?;
         (self.events.len() as u16).pack(write)?;
         for item in &self.commands {
@@ -240,6 +339,10 @@ impl Pack for CommandsEvents {
         }
         Ok(())
     }
+
+    fn packed_len_hint(&self) -> usize {
+        4 + (self.commands.len() + self.events.len()) * 2
+    }
 }
 
 impl Unpack for CommandsEvents {
@@ -259,24 +362,111 @@ impl Unpack for CommandsEvents {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Pack, Unpack)]
-#[pack(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 enum InternalAddressType {
-    BrEdr = 0,
-    LePublic = 1,
-    LeRandom = 2,
+    BrEdr,
+    LePublic,
+    LeRandom,
+    /// Not modeled, such as 3 of some kernel trees. Kept for re-packing as is.
+    Unknown(u8),
+}
+
+impl Pack for InternalAddressType {
+    fn pack<W>(&self, write: &mut W) -> pack::Result<()>
+    where
+        W: io::Write,
+    {
+        let v: u8 = match self {
+            Self::BrEdr => 0,
+            Self::LePublic => 1,
+            Self::LeRandom => 2,
+            Self::Unknown(v) => *v,
+        };
+        v.pack(write)
+    }
+
+    fn packed_len_hint(&self) -> usize {
+        1
+    }
+}
+
+impl Unpack for InternalAddressType {
+    fn unpack<R>(read: &mut R) -> pack::Result<Self>
+    where
+        R: io::Read,
+    {
+        Ok(match u8::unpack(read)? {
+            0 => Self::BrEdr,
+            1 => Self::LePublic,
+            2 => Self::LeRandom,
+            unknown => Self::Unknown(unknown),
+        })
+    }
+}
+
+/// Address types bitmask.
+///
+/// Unknown bits (3 to 7) are not listed as types, but kept by [`Self::unknown_bits`]
+/// and packed back as is.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AddressTypes(HashSet<AddressType>, u8);
+
+impl AddressTypes {
+    /// true if only LE address types are included.
+    ///
+    /// Discovery of LE only never asks for name confirmation. Discovery including
+    /// [`AddressType::BrEdr`] reports classic devices with [`DeviceConnectFlags::ConfirmName`],
+    /// which are answered by [`command::ConfirmName`].
+    pub fn is_le_only(&self) -> bool {
+        !self.0.is_empty() && !self.0.contains(&AddressType::BrEdr)
+    }
+
+    /// Bits not modeled by [`AddressType`]. 0 unless unpacked from such bits.
+    pub fn unknown_bits(&self) -> u8 {
+        self.1
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AddressType> {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for AddressTypes {
+    type Item = AddressType;
+    type IntoIter = std::collections::hash_set::IntoIter<AddressType>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl FromIterator<AddressType> for AddressTypes {
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = AddressType>,
+    {
+        Self(iter.into_iter().collect(), 0)
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, IterNewtype)]
-#[iter_newtype(item = AddressType, into_iter = ::std::collections::hash_set::IntoIter<AddressType>, no_iter_mut)]
-pub struct AddressTypes(HashSet<AddressType>);
+impl Extend<AddressType> for AddressTypes {
+    fn extend<T>(&mut self, iter: T)
+    where
+        T: IntoIterator<Item = AddressType>,
+    {
+        self.0.extend(iter)
+    }
+}
+
+const ADDRESS_TYPES_KNOWN: u8 = 0b111;
 
 impl Pack for AddressTypes {
     fn pack<W>(&self, write: &mut W) -> pack::Result<()>
     where
         W: io::Write,
     {
-        let mut v = 0u8;
+        let mut v = self.1 & !ADDRESS_TYPES_KNOWN;
         for item in &self.0 {
             v |= match item {
                 AddressType::BrEdr => 1 << 0,
@@ -286,6 +476,10 @@ impl Pack for AddressTypes {
         }
         v.pack(write)
     }
+
+    fn packed_len_hint(&self) -> usize {
+        1
+    }
 }
 
 impl Unpack for AddressTypes {
@@ -306,7 +500,7 @@ impl Unpack for AddressTypes {
             r.insert(AddressType::LeRandom);
         }
 
-        Ok(Self(r))
+        Ok(Self(r, v & !ADDRESS_TYPES_KNOWN))
     }
 }
 
@@ -334,18 +528,268 @@ bitflags! {
     }
 }
 
-#[derive(Debug, Clone, Pack, Unpack)]
+/// Controller capabilities decoded from supported [`Settings`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct Capabilities {
+    pub connectable: bool,
+    pub fast_connectable: bool,
+    pub discoverable: bool,
+    pub bondable: bool,
+    pub link_level_security: bool,
+    pub secure_simple_pairing: bool,
+    pub bredr: bool,
+    pub high_speed: bool,
+    pub le: bool,
+    pub advertising: bool,
+    pub secure_connections: bool,
+    pub debug_keys: bool,
+    pub privacy: bool,
+    pub controller_configuration: bool,
+    pub static_address: bool,
+    pub phy_configuration: bool,
+    pub wideband_speech: bool,
+}
+
+impl From<&Settings> for Capabilities {
+    fn from(v: &Settings) -> Self {
+        Self {
+            connectable: v.contains(Settings::Connectable),
+            fast_connectable: v.contains(Settings::FastConnectable),
+            discoverable: v.contains(Settings::Discoverable),
+            bondable: v.contains(Settings::Bondable),
+            link_level_security: v.contains(Settings::LinkLevelSecurity),
+            secure_simple_pairing: v.contains(Settings::SecureSimplePairing),
+            bredr: v.contains(Settings::BasicRateEnhancedDataRate),
+            high_speed: v.contains(Settings::HighSpeed),
+            le: v.contains(Settings::LowEnergy),
+            advertising: v.contains(Settings::Advertising),
+            secure_connections: v.contains(Settings::SecureConnections),
+            debug_keys: v.contains(Settings::DebugKeys),
+            privacy: v.contains(Settings::Privacy),
+            controller_configuration: v.contains(Settings::ControllerConfiguration),
+            static_address: v.contains(Settings::StaticAddress),
+            phy_configuration: v.contains(Settings::PhyConfiguration),
+            wideband_speech: v.contains(Settings::WidebandSpeech),
+        }
+    }
+}
+
+/// Single writable [`Settings`] flag, each set by its own command.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SettingFlag {
+    Powered,
+    Connectable,
+    FastConnectable,
+    /// General discoverable without timeout.
+    Discoverable,
+    Bondable,
+    LinkSecurity,
+    Ssp,
+    HighSpeed,
+    Le,
+    BrEdr,
+    /// Advertising without forcing connectable.
+    Advertising,
+    SecureConnections,
+    DebugKeys,
+    WidebandSpeech,
+}
+
+impl From<SettingFlag> for Settings {
+    fn from(v: SettingFlag) -> Self {
+        match v {
+            SettingFlag::Powered => Self::Powered,
+            SettingFlag::Connectable => Self::Connectable,
+            SettingFlag::FastConnectable => Self::FastConnectable,
+            SettingFlag::Discoverable => Self::Discoverable,
+            SettingFlag::Bondable => Self::Bondable,
+            SettingFlag::LinkSecurity => Self::LinkLevelSecurity,
+            SettingFlag::Ssp => Self::SecureSimplePairing,
+            SettingFlag::HighSpeed => Self::HighSpeed,
+            SettingFlag::Le => Self::LowEnergy,
+            SettingFlag::BrEdr => Self::BasicRateEnhancedDataRate,
+            SettingFlag::Advertising => Self::Advertising,
+            SettingFlag::SecureConnections => Self::SecureConnections,
+            SettingFlag::DebugKeys => Self::DebugKeys,
+            SettingFlag::WidebandSpeech => Self::WidebandSpeech,
+        }
+    }
+}
+
+/// Class of Device, in the wire order. (least significant byte first)
+///
+/// Displayed as the 24 bits value, `0x5a020c` for a smartphone, as bluez does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pack, Unpack)]
 pub struct ClassOfDevice([u8; 3]);
 
+impl ClassOfDevice {
+    /// From the fields, with the format bits cleared. Bits out of each field are ignored.
+    ///
+    /// ```
+    /// # use btmgmt_packet::ClassOfDevice;
+    /// use btmgmt_packet::class::{MajorDeviceClass, MinorDeviceClassPhone, ServiceClasses};
+    ///
+    /// let services = ServiceClasses::Networking | ServiceClasses::Capturing
+    ///     | ServiceClasses::ObjectTransfer | ServiceClasses::Telephony;
+    /// let class = ClassOfDevice::from_parts(
+    ///     services.bits(),
+    ///     MajorDeviceClass::Phone.into(),
+    ///     MinorDeviceClassPhone::Smartphone.into(),
+    /// );
+    /// assert_eq!("0x5a020c", class.to_string());
+    /// ```
+    pub fn from_parts(service_class: u16, major_device: u8, minor_device: u8) -> Self {
+        let v = u32::from(service_class & 0x07FF) << 13
+            | u32::from(major_device & 0x1F) << 8
+            | u32::from(minor_device & 0x3F) << 2;
+        let [b0, b1, b2, _] = v.to_le_bytes();
+        Self([b0, b1, b2])
+    }
+
+    /// Major service classes. (CoD bits 13-23, see [`class::ServiceClasses`])
+    pub fn service_class(&self) -> u16 {
+        u16::from(self.0[2]) << 3 | u16::from(self.0[1] >> 5)
+    }
+
+    /// Major device class. (CoD bits 8-12, see [`class::MajorDeviceClass`])
+    pub fn major_device(&self) -> u8 {
+        self.0[1] & 0x1F
+    }
+
+    /// Minor device class, interpreted by the major class. (CoD bits 2-7)
+    pub fn minor_device(&self) -> u8 {
+        self.0[0] >> 2
+    }
+
+    /// Minor device class, as given to `SetDeviceClass`. (format bits cleared)
+    pub fn minor_class(&self) -> u8 {
+        self.0[0] & 0xFC
+    }
+
+    /// Major device class, as given to `SetDeviceClass`.
+    pub fn major_class(&self) -> u8 {
+        self.major_device()
+    }
+
+    /// Same as [`Self::major_device`].
+    pub fn major_device_class(&self) -> u8 {
+        self.major_device()
+    }
+
+    /// Same as [`Self::minor_device`].
+    pub fn minor_device_class(&self) -> u8 {
+        self.minor_device()
+    }
+
+    /// Same as [`Self::service_class`]. The kernel owns them, from the registered UUIDs.
+    pub fn service_classes(&self) -> u16 {
+        self.service_class()
+    }
+
+    /// 24 bits value.
+    pub fn to_u24(&self) -> u32 {
+        u32::from_le_bytes([self.0[0], self.0[1], self.0[2], 0])
+    }
+
+    /// 24 bits value. Same as [`Self::to_u24`].
+    pub fn to_u32(&self) -> u32 {
+        self.to_u24()
+    }
+}
+
+/// From the wire order.
 impl From<[u8; 3]> for ClassOfDevice {
     fn from(v: [u8; 3]) -> Self {
-        Self(v) // FIXME reverse?
+        Self(v)
+    }
+}
+
+/// From the 24 bits value. Rejects values over 24 bits.
+impl TryFrom<u32> for ClassOfDevice {
+    type Error = ClassOfDeviceError;
+    fn try_from(v: u32) -> Result<Self, Self::Error> {
+        match v.to_le_bytes() {
+            [b0, b1, b2, 0] => Ok(Self([b0, b1, b2])),
+            _ => Err(ClassOfDeviceError::TooLarge(v)),
+        }
+    }
+}
+
+/// From six hex digits of the 24 bits value, optionally `0x` prefixed as displayed.
+/// (e.g. `5a020c` or `0x1F0100`)
+impl FromStr for ClassOfDevice {
+    type Err = ClassOfDeviceError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix("0x").unwrap_or(s);
+        if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ClassOfDeviceError::InvalidFormat(s.into()));
+        }
+        let v = u32::from_str_radix(hex, 16)
+            .map_err(|_| ClassOfDeviceError::InvalidFormat(s.into()))?;
+        Self::try_from(v)
     }
 }
 
+/// Rejected Class of Device.
+#[derive(Debug, thiserror::Error)]
+pub enum ClassOfDeviceError {
+    #[error("invalid class of device {0:?}: expected six hex digits (e.g. 5a020c or 0x5a020c)")]
+    InvalidFormat(String),
+
+    #[error("class of device {0:#x} exceeds 24 bits")]
+    TooLarge(u32),
+}
+
 impl fmt::Display for ClassOfDevice {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:02X}{:02X}{:02X}", self.0[0], self.0[1], self.0[2]) // FIXME reverse?
+        write!(f, "{:#08x}", self.to_u24())
+    }
+}
+
+/// Bluetooth SIG company identifier, as the manufacturer of Read Controller Information.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CompanyId(u16);
+
+impl CompanyId {
+    pub fn id(&self) -> u16 {
+        self.0
+    }
+
+    /// Name of well-known controller vendors. `None` for others.
+    pub fn name(&self) -> Option<&'static str> {
+        Some(match self.0 {
+            0x0002 => "Intel Corp.",
+            0x000A => "Qualcomm Technologies International, Ltd. (QTIL)",
+            0x000D => "Texas Instruments Inc.",
+            0x000F => "Broadcom Corporation",
+            0x001D => "Qualcomm",
+            0x0046 => "MediaTek, Inc.",
+            0x0059 => "Nordic Semiconductor ASA",
+            0x005D => "Realtek Semiconductor Corporation",
+            0x0131 => "Cypress Semiconductor",
+            0x05F1 => "The Linux Foundation",
+            _ => return None,
+        })
+    }
+}
+
+impl From<u16> for CompanyId {
+    fn from(v: u16) -> Self {
+        Self(v)
+    }
+}
+
+impl fmt::Display for CompanyId {
+    /// `Intel Corp. (0x0002)`, or `0x1234` if unknown.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{} ({:#06x})", name, self.0),
+            None => write!(f, "{:#06x}", self.0),
+        }
     }
 }
 
@@ -374,14 +818,64 @@ impl<const N: usize> FixedLengthName<N> {
         }
 
         let mut v = [0; N];
-        (&mut v[..b.len()]).copy_from_slice(b);
+        v[..b.len()].copy_from_slice(b);
         Ok(Self(Box::new(v)))
     }
 
+    /// Cut at the last char boundary within `N - 1` bytes, leaving room for the NUL.
+    ///
+    /// The name ends at the first NUL of `s`, if any.
+    pub fn new_truncate(s: &str) -> Self {
+        let s = s.split('\0').next().unwrap_or("");
+        let mut len = s.len().min(N.saturating_sub(1));
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+        let mut v = [0; N];
+        v[..len].copy_from_slice(&s.as_bytes()[..len]);
+        Self(Box::new(v))
+    }
+
+    /// true if NUL terminated within `N` bytes.
+    ///
+    /// The kernel always terminates, but a buggy controller may fill all bytes.
+    /// Unterminated names are truncated at `N` bytes by [`Self::to_string_lossy`].
+    pub fn is_valid(&self) -> bool {
+        self.0.contains(&0)
+    }
+
     pub fn to_string_lossy(&self) -> String {
         let b = self.0.split(|b| b == &0).next().unwrap_or(b"");
         CString::new(b).unwrap().to_string_lossy().to_string()
     }
+
+    /// Bytes before the NUL, as UTF-8.
+    pub fn as_str(&self) -> Result<&str, Utf8Error> {
+        str::from_utf8(self.as_bytes())
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self.0.split(|b| b == &0).next().unwrap_or(b"")
+    }
+}
+
+impl<const N: usize> PartialEq<str> for FixedLengthName<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl<const N: usize> PartialEq<&str> for FixedLengthName<N> {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+/// Invalid UTF-8 is replaced, as [`FixedLengthName::to_string_lossy`].
+impl<const N: usize> fmt::Display for FixedLengthName<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        String::from_utf8_lossy(self.as_bytes()).fmt(f)
+    }
 }
 
 impl<const N: usize> fmt::Debug for FixedLengthName<N> {
@@ -401,7 +895,8 @@ impl<const N: usize> FromStr for FixedLengthName<N> {
 pub type Name = FixedLengthName<249>;
 pub type ShortName = FixedLengthName<11>;
 
-#[derive(Debug, Pack, Unpack)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pack, Unpack)]
 #[pack(u8)]
 pub enum Discoverable {
     Disable = 0x00,
@@ -409,6 +904,43 @@ pub enum Discoverable {
     Limited = 0x02,
 }
 
+/// Seconds, as a 16 bit wire field. (e.g. the timeout of Set Discoverable)
+///
+/// Command fields in seconds take this, not a bare integer. `0` usually means no
+/// timeout; see each command.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Pack, Unpack, Newtype, New,
+)]
+pub struct Seconds(u16);
+
+impl Seconds {
+    pub fn to_duration(&self) -> Duration {
+        Duration::from_secs(self.0.into())
+    }
+}
+
+impl From<Seconds> for Duration {
+    fn from(v: Seconds) -> Self {
+        v.to_duration()
+    }
+}
+
+/// Rejects durations of fractional seconds or over 65535 seconds.
+impl TryFrom<Duration> for Seconds {
+    type Error = SecondsOutOfRange;
+    fn try_from(v: Duration) -> Result<Self, Self::Error> {
+        match u16::try_from(v.as_secs()) {
+            Ok(secs) if v.subsec_nanos() == 0 => Ok(Self(secs)),
+            _ => Err(SecondsOutOfRange(v)),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0:?} is not whole seconds up to 65535")]
+pub struct SecondsOutOfRange(Duration);
+
 #[derive(Debug, Clone, Default, Newtype, New)]
 pub struct Uuid(uuid::Uuid);
 
@@ -417,9 +949,14 @@ impl Pack for Uuid {
     where
         W: io::Write,
     {
-        self.0.to_u128_le().pack(read)?;
+        // little endian on the wire, the reverse of the string form. (as bluetoothd sends)
+        self.0.as_u128().pack(read)?;
         Ok(())
     }
+
+    fn packed_len_hint(&self) -> usize {
+        16
+    }
 }
 
 impl Unpack for Uuid {
@@ -427,7 +964,22 @@ impl Unpack for Uuid {
     where
         R: io::Read,
     {
-        Ok(Self(uuid::Uuid::from_u128_le(Unpack::unpack(read)?)))
+        Ok(Self(uuid::Uuid::from_u128(Unpack::unpack(read)?)))
+    }
+}
+
+impl Uuid {
+    /// Bluetooth Base UUID. (`00000000-0000-1000-8000-00805F9B34FB`)
+    pub const BASE: u128 = 0x0000_0000_0000_1000_8000_0080_5F9B_34FB;
+
+    /// Expand 16-bit assigned number. (e.g. `0x180F` Battery Service)
+    pub fn from_u16(short: u16) -> Self {
+        Self(uuid::Uuid::from_u128(Self::BASE | (short as u128) << 96))
+    }
+
+    /// Expand 32-bit assigned number.
+    pub fn from_u32(short: u32) -> Self {
+        Self(uuid::Uuid::from_u128(Self::BASE | (short as u128) << 96))
     }
 }
 
@@ -439,6 +991,7 @@ impl FromStr for Uuid {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Pack, Unpack)]
 #[pack(u8)]
 pub enum LinkKeyType {
@@ -453,6 +1006,7 @@ pub enum LinkKeyType {
     AuthenticatedCombinationkeyfromP256 = 0x08,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Pack, Unpack, Getters)]
 pub struct LinkKey {
     address: WrappedAddress,
@@ -460,6 +1014,7 @@ pub struct LinkKey {
     #[getset(get = "pub")]
     key_type: LinkKeyType,
     #[getset(get = "pub")]
+    #[cfg_attr(feature = "serde", serde(with = "serde_impl::hex"))]
     value: [u8; 16],
     #[getset(get = "pub")]
     pin_length: u8,
@@ -482,6 +1037,7 @@ impl LinkKey {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Pack, Unpack)]
 #[pack(u8)]
 pub enum LongTermKeyType {
@@ -492,9 +1048,37 @@ pub enum LongTermKeyType {
     DebugKeyP256 = 0x04,
 }
 
+impl LongTermKeyType {
+    /// true if the key was generated with MITM protection.
+    pub fn is_authenticated(&self) -> bool {
+        matches!(self, Self::AuthenticatedKey | Self::AuthenticatedP256Key)
+    }
+}
+
+/// Rejected key material.
 #[derive(Debug, thiserror::Error)]
-#[error("uninitialized field: {0:}")]
-pub struct LongTermKeyBuilderError(&'static str);
+pub enum KeyError {
+    /// All zeros, usually an uninitialized buffer.
+    #[error("key is all zeros")]
+    AllZero,
+}
+
+/// Err if `key` is all zeros.
+pub(crate) fn check_key(key: &[u8]) -> Result<(), KeyError> {
+    if key.iter().all(|b| *b == 0) {
+        return Err(KeyError::AllZero);
+    }
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LongTermKeyBuilderError {
+    #[error("uninitialized field: {0:}")]
+    Uninitialized(&'static str),
+
+    #[error(transparent)]
+    Key(#[from] KeyError),
+}
 
 #[derive(Clone, Default)]
 pub struct LongTermKeyBuilder {
@@ -536,42 +1120,54 @@ impl LongTermKeyBuilder {
         self.value = Some(value);
         self
     }
+    /// Build the key. An authenticated key type needs a non-zero value.
     pub fn build(&self) -> Result<LongTermKey, LongTermKeyBuilderError> {
+        let key = self.build_unchecked()?;
+        if key.key_type.is_authenticated() {
+            check_key(&key.value)?;
+        }
+        Ok(key)
+    }
+
+    /// Build the key without checking the value. (e.g. test vectors)
+    pub fn build_unchecked(&self) -> Result<LongTermKey, LongTermKeyBuilderError> {
         let address = if let Some(address) = &self.address {
             address.clone()
         } else {
-            return Err(LongTermKeyBuilderError("address"));
+            return Err(LongTermKeyBuilderError::Uninitialized("address"));
         };
         let key_type = if let Some(key_type) = &self.key_type {
             key_type.clone()
         } else {
-            return Err(LongTermKeyBuilderError("key_type"));
+            return Err(LongTermKeyBuilderError::Uninitialized("key_type"));
         };
         let master = if let Some(master) = self.master {
             master
         } else {
-            return Err(LongTermKeyBuilderError("master"));
+            return Err(LongTermKeyBuilderError::Uninitialized("master"));
         };
         let encryption_size = if let Some(encryption_size) = self.encryption_size {
             encryption_size
         } else {
-            return Err(LongTermKeyBuilderError("encryption_size"));
+            return Err(LongTermKeyBuilderError::Uninitialized("encryption_size"));
         };
         let encryption_diversifier =
             if let Some(encryption_diversifier) = self.encryption_diversifier {
                 encryption_diversifier
             } else {
-                return Err(LongTermKeyBuilderError("encryption_diversifier"));
+                return Err(LongTermKeyBuilderError::Uninitialized(
+                    "encryption_diversifier",
+                ));
             };
         let random_number = if let Some(random_number) = self.random_number {
             random_number
         } else {
-            return Err(LongTermKeyBuilderError("random_number"));
+            return Err(LongTermKeyBuilderError::Uninitialized("random_number"));
         };
         let value = if let Some(value) = self.value {
             value
         } else {
-            return Err(LongTermKeyBuilderError("value"));
+            return Err(LongTermKeyBuilderError::Uninitialized("value"));
         };
 
         let (address, address_type) = split(address);
@@ -588,6 +1184,11 @@ impl LongTermKeyBuilder {
     }
 }
 
+/// Key of Load Long Term Keys and New Long Term Key, 36 bytes on the wire.
+///
+/// `master` is named Central by newer mgmt-api.txt, with the same layout. BR/EDR
+/// addresses are kept as is, for keys derived from BR/EDR pairing.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Pack, Unpack, Getters)]
 pub struct LongTermKey {
     address: WrappedAddress,
@@ -601,8 +1202,10 @@ pub struct LongTermKey {
     #[getset(get = "pub")]
     encryption_diversifier: u16,
     #[getset(get = "pub")]
+    #[cfg_attr(feature = "serde", serde(with = "serde_impl::hex"))]
     random_number: [u8; 8],
     #[getset(get = "pub")]
+    #[cfg_attr(feature = "serde", serde(with = "serde_impl::hex"))]
     value: [u8; 16],
 }
 
@@ -612,16 +1215,25 @@ impl LongTermKey {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Pack, Unpack, Getters)]
 pub struct IdentityResolvingKey {
     address: WrappedAddress,
     address_type: InternalAddressType,
     #[getset(get = "pub")]
+    #[cfg_attr(feature = "serde", serde(with = "serde_impl::hex"))]
     value: [u8; 16],
 }
 
 impl IdentityResolvingKey {
-    pub fn new(addr: Address, value: [u8; 16]) -> Self {
+    /// Returns [`KeyError::AllZero`] if `value` is all zeros.
+    pub fn new(addr: Address, value: [u8; 16]) -> Result<Self, KeyError> {
+        check_key(&value)?;
+        Ok(Self::new_unchecked(addr, value))
+    }
+
+    /// Key of any value, including all zeros. (e.g. test vectors)
+    pub fn new_unchecked(addr: Address, value: [u8; 16]) -> Self {
         let (address, address_type) = split(addr);
         Self {
             address,
@@ -635,6 +1247,7 @@ impl IdentityResolvingKey {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Pack, Unpack)]
 #[pack(u8)]
 pub enum IoCapability {
@@ -645,6 +1258,7 @@ pub enum IoCapability {
     KeyboardDisplay = 4,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Pack, Unpack)]
 #[pack(u16)]
 pub enum DeviceIdSource {
@@ -653,6 +1267,7 @@ pub enum DeviceIdSource {
     UsbImplementersForum = 0x0002,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Pack, Unpack)]
 #[pack(u8)]
 pub enum Advertising {
@@ -661,6 +1276,7 @@ pub enum Advertising {
     Connectable = 0x02,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Pack, Unpack)]
 #[pack(u8)]
 pub enum SecureConnections {
@@ -669,6 +1285,7 @@ pub enum SecureConnections {
     Only = 0x02,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Pack, Unpack)]
 #[pack(u8)]
 pub enum DebugKeys {
@@ -677,6 +1294,7 @@ pub enum DebugKeys {
     ForEach = 0x02,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Pack, Unpack)]
 #[pack(u8)]
 pub enum Privacy {
@@ -685,6 +1303,7 @@ pub enum Privacy {
     Limited = 0x02,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Pack, Unpack)]
 #[pack(u8)]
 pub enum Action {
@@ -693,6 +1312,7 @@ pub enum Action {
     AutoConnect = 2,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Pack, Unpack, Getters)]
 pub struct ConnectionParameter {
     address: WrappedAddress,
@@ -703,6 +1323,7 @@ pub struct ConnectionParameter {
     max_connection_interval: u16,
     #[getset(get = "pub")]
     connection_latency: u16,
+    /// In units of 10 ms.
     #[getset(get = "pub")]
     supervision_timeout: u16,
 }
@@ -739,6 +1360,36 @@ bitflags! {
     }
 }
 
+impl fmt::Display for ControllerConfigurationOption {
+    /// Flag names separated by `, `, or `none`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("none");
+        }
+        let names = [
+            (Self::ExternalConfiguration, "ExternalConfiguration"),
+            (
+                Self::BluetoothPublicAddressConfiguration,
+                "BluetoothPublicAddressConfiguration",
+            ),
+        ];
+        let mut names = names
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| *name);
+        if let Some(name) = names.next() {
+            f.write_str(name)?;
+        }
+        for name in names {
+            write!(f, ", {}", name)?;
+        }
+        if !Self::all().contains(*self) {
+            write!(f, ", {:#x}", (*self - Self::all()).bits())?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct VariableLengthBytes<L = u16>(Box<[u8]>, PhantomData<L>);
 
@@ -747,10 +1398,19 @@ impl Pack for VariableLengthBytes<u16> {
     where
         W: io::Write,
     {
+        debug_assert!(
+            self.0.len() <= u16::MAX as usize,
+            "too long {}",
+            self.0.len()
+        );
         (self.0.len() as u16).pack(write)?;
         self.0.pack(write)?;
         Ok(())
     }
+
+    fn packed_len_hint(&self) -> usize {
+        2 + self.0.len()
+    }
 }
 
 impl Unpack for VariableLengthBytes<u16> {
@@ -762,7 +1422,7 @@ impl Unpack for VariableLengthBytes<u16> {
         let mut read = <&mut R as io::Read>::take(read, len as u64);
         let buf = <Box<[u8]>>::unpack(&mut read)?;
         if buf.len() != len {
-            return Err(io::Error::new(io::ErrorKind::Other, "few bytes.").into());
+            return Err(io::Error::other("few bytes.").into());
         }
 
         Ok(Self(buf, PhantomData))
@@ -774,10 +1434,19 @@ impl Pack for VariableLengthBytes<u8> {
     where
         W: io::Write,
     {
+        debug_assert!(
+            self.0.len() <= u8::MAX as usize,
+            "too long {}",
+            self.0.len()
+        );
         (self.0.len() as u8).pack(write)?;
         self.0.pack(write)?;
         Ok(())
     }
+
+    fn packed_len_hint(&self) -> usize {
+        1 + self.0.len()
+    }
 }
 
 impl Unpack for VariableLengthBytes<u8> {
@@ -789,7 +1458,7 @@ impl Unpack for VariableLengthBytes<u8> {
         let mut read = <&mut R as io::Read>::take(read, len as u64);
         let buf = <Box<[u8]>>::unpack(&mut read)?;
         if buf.len() != len {
-            return Err(io::Error::new(io::ErrorKind::Other, "few bytes.").into());
+            return Err(io::Error::other("few bytes.").into());
         }
 
         Ok(Self(buf, PhantomData))
@@ -840,6 +1509,7 @@ impl<L> DerefMut for VariableLengthBytes<L> {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Pack, Unpack)]
 #[pack(u8)]
 pub enum ControllerType {
@@ -848,6 +1518,7 @@ pub enum ControllerType {
     AlternateMacPhyController = 0x02,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Pack, Unpack)]
 #[pack(u8)]
 pub enum ControllerBus {
@@ -877,12 +1548,26 @@ bitflags! {
         const SecondaryChannelWithLe1M = 1 << 7;
         const SecondaryChannelWithLe2M = 1 << 8;
         const SecondaryChannelWithLeCoded = 1 << 9;
-    }
-}
-
-#[derive(Debug, Clone, Pack, Unpack, Newtype, New)]
+        /// Supported flag: the tx power can be set.
+        const CanSetTxPower = 1 << 10;
+        /// Supported flag: advertising is offloaded to the controller.
+        const HardwareOffload = 1 << 11;
+        /// The duration of Add Extended Advertising Parameters is given.
+        const ParamDuration = 1 << 12;
+        /// The timeout of Add Extended Advertising Parameters is given.
+        const ParamTimeout = 1 << 13;
+        /// The intervals of Add Extended Advertising Parameters are given.
+        const ParamIntervals = 1 << 14;
+        /// The tx power of Add Extended Advertising Parameters is given.
+        const ParamTxPower = 1 << 15;
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Pack, Unpack, Newtype, New)]
 pub struct AdvertiseInstance(u8);
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, IterNewtype)]
 pub struct AdvertiseInstances(Vec<AdvertiseInstance>);
 
@@ -891,12 +1576,21 @@ impl Pack for AdvertiseInstances {
     where
         W: io::Write,
     {
+        debug_assert!(
+            self.0.len() <= u8::MAX as usize,
+            "too many {}",
+            self.0.len()
+        );
         (self.0.len() as u8).pack(write)?;
         for item in &self.0 {
             item.pack(write)?;
         }
         Ok(())
     }
+
+    fn packed_len_hint(&self) -> usize {
+        1 + self.0.len()
+    }
 }
 
 impl Unpack for AdvertiseInstances {
@@ -904,11 +1598,18 @@ impl Unpack for AdvertiseInstances {
     where
         R: io::Read,
     {
+        // one byte per instance. read all at once to check the count.
         let len = u8::unpack(read)? as usize;
-        let v = (0..len)
-            .map(|_| Unpack::unpack(read))
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(Self(v))
+        let mut b = Vec::with_capacity(len);
+        let mut read = <&mut R as io::Read>::take(read, len as u64);
+        io::Read::read_to_end(&mut read, &mut b)?;
+        if b.len() < len {
+            return Err(pack::Error::Truncated {
+                expected: len,
+                actual: b.len(),
+            });
+        }
+        Ok(Self(b.into_iter().map(AdvertiseInstance).collect()))
     }
 }
 
@@ -916,7 +1617,7 @@ impl<'a> std::iter::IntoIterator for &'a AdvertiseInstances {
     type Item = &'a AdvertiseInstance;
     type IntoIter = std::slice::Iter<'a, AdvertiseInstance>;
     fn into_iter(self) -> Self::IntoIter {
-        (&self.0).iter()
+        self.0.iter()
     }
 }
 
@@ -924,14 +1625,32 @@ impl<'a> std::iter::IntoIterator for &'a AdvertiseInstances {
 pub struct AdvDataScanResp(Box<[u8]>, Box<[u8]>);
 
 impl AdvDataScanResp {
+    /// Panics if either is longer than 255 bytes. See [`Self::try_new`].
     pub fn new<A, S>(advdata: A, scanresp: S) -> Self
+    where
+        A: AsRef<[u8]>,
+        S: AsRef<[u8]>,
+    {
+        match Self::try_new(advdata, scanresp) {
+            Ok(v) => v,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Fails if either is longer than 255 bytes, the limit of the length fields.
+    pub fn try_new<A, S>(advdata: A, scanresp: S) -> Result<Self, LengthTooLong>
     where
         A: AsRef<[u8]>,
         S: AsRef<[u8]>,
     {
         let advdata = advdata.as_ref();
         let scanresp = scanresp.as_ref();
-        Self(advdata.into(), scanresp.into())
+        for len in [advdata.len(), scanresp.len()] {
+            if len > u8::MAX as usize {
+                return Err(LengthTooLong(u8::MAX as usize, len));
+            }
+        }
+        Ok(Self(advdata.into(), scanresp.into()))
     }
 }
 
@@ -940,12 +1659,18 @@ impl Pack for AdvDataScanResp {
     where
         W: io::Write,
     {
+        // validated by Self::try_new
+        debug_assert!(self.0.len() <= u8::MAX as usize && self.1.len() <= u8::MAX as usize);
         (self.0.len() as u8).pack(write)?;
         (self.1.len() as u8).pack(write)?;
         self.0.pack(write)?;
         self.1.pack(write)?;
         Ok(())
     }
+
+    fn packed_len_hint(&self) -> usize {
+        2 + self.0.len() + self.1.len()
+    }
 }
 
 impl Unpack for AdvDataScanResp {
@@ -960,13 +1685,13 @@ impl Unpack for AdvDataScanResp {
         let adv_data = <Box<[u8]>>::unpack(&mut read)?;
         let read = read.into_inner();
         if adv_data.len() != adv_data_len {
-            return Err(io::Error::new(io::ErrorKind::Other, "unexpected length.").into());
+            return Err(io::Error::other("unexpected length.").into());
         }
 
-        let mut read = <&mut R as io::Read>::take(read, adv_data_len as u64);
+        let mut read = <&mut R as io::Read>::take(read, scan_resp_len as u64);
         let scan_resp = <Box<[u8]>>::unpack(&mut read)?;
         if scan_resp.len() != scan_resp_len {
-            return Err(io::Error::new(io::ErrorKind::Other, "unexpected length.").into());
+            return Err(io::Error::other("unexpected length.").into());
         }
 
         Ok(Self(adv_data, scan_resp))
@@ -1004,6 +1729,7 @@ bitflags! {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Pack, Unpack)]
 #[pack(u8)]
 pub enum BlockedKeyType {
@@ -1012,10 +1738,12 @@ pub enum BlockedKeyType {
     IdentityResolvingKey = 0x02,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Pack, Unpack, New, Getters)]
 #[getset(get = "pub")]
 pub struct BlockedKey {
     key_type: BlockedKeyType,
+    #[cfg_attr(feature = "serde", serde(with = "serde_impl::hex"))]
     value: [u8; 16],
 }
 
@@ -1027,6 +1755,7 @@ bitflags! {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Pack, Unpack)]
 #[pack(u8)]
 pub enum FeatureAction {
@@ -1038,58 +1767,115 @@ macro_rules! configuration_parameter {
     (
         $(#[$attrs:meta])*
         $vis:vis enum $name:ident: $typename:ident {
-            $( $vname:ident($vty:ty) => $vcode:literal, )*
+            $( $(#[$vattrs:meta])* $vname:ident($vty:ty) => $vcode:literal, )*
         }
     ) => {
         $(#[$attrs])*
         $vis enum $name {
-            $( $vname($vty), )*
+            $( $(#[$vattrs])* $vname($vty), )*
+            /// Type not known to this crate, such as vendor specific ones.
+            ///
+            /// Packing fails for a known type. Use the typed variant instead.
+            Raw { param_type: u16, value: Vec<u8> },
         }
 
         #[derive(Debug, Clone)]
         $vis enum $typename {
             $( $vname, )*
+            Raw(u16),
         }
 
         impl $name {
-            #[allow(unreachable_patterns)]
             pub fn for_type(&self) -> $typename {
                 match self {
                     $( Self::$vname(..) => $typename::$vname,)*
-                    _ => unreachable!(),
+                    Self::Raw { param_type, .. } => $typename::Raw(*param_type),
                 }
             }
 
-            #[allow(unreachable_patterns)]
+            /// `None` for [`Self::Raw`].
             pub fn value_as_u16(&self) -> Option<u16> {
                 match self {
                     $( Self::$vname(v) => Some(*v),)* // FIXME
-                    _ => unreachable!(),
+                    Self::Raw { .. } => None,
+                }
+            }
+
+            /// Typed variant name of the type, if known.
+            #[allow(unreachable_patterns)]
+            pub fn known_type(param_type: u16) -> Option<&'static str> {
+                match param_type {
+                    $( $vcode => Some(stringify!($vname)), )*
+                    _ => None,
                 }
             }
         }
 
         impl Pack for $name {
-            #[allow(unreachable_code, unused_variables, unreachable_patterns)]
             fn pack<W>(&self, write: &mut W) -> pack::Result<()> where W: io::Write {
                 let (t, v) = match self {
                     $(Self::$vname(v) => ($vcode, VariableLengthBytes::from(*v)),)*
-                    _ => unreachable!(),
+                    Self::Raw { param_type, value } => {
+                        if let Some(known) = Self::known_type(*param_type) {
+                            return Err(pack::Error::UnexpectedValue(format!(
+                                "raw type {:#06x} is known, use {}::{}",
+                                param_type,
+                                stringify!($name),
+                                known
+                            )));
+                        }
+                        if value.len() > u8::MAX as usize {
+                            return Err(pack::Error::UnexpectedValue(format!(
+                                "raw value of {} bytes is longer than 255",
+                                value.len()
+                            )));
+                        }
+                        (*param_type, VariableLengthBytes(value.clone().into(), PhantomData))
+                    }
                 };
                 <u16 as Pack>::pack(&t, write)?;
                 <VariableLengthBytes<u8> as Pack>::pack(&v, write)?;
                 Ok(())
             }
+
+            fn packed_len_hint(&self) -> usize {
+                match self {
+                    $(Self::$vname(..) => 2 + 1 + 2,)*
+                    Self::Raw { value, .. } => 2 + 1 + value.len(),
+                }
+            }
+        }
+
+        /// Raw for unknown types only, as packing requires.
+        #[cfg(feature = "arbitrary")]
+        impl<'a> arbitrary::Arbitrary<'a> for $name {
+            fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+                let known: &[u16] = &[$($vcode),*];
+                let param_type = if !known.is_empty() && u.arbitrary()? {
+                    *u.choose(known)?
+                } else {
+                    u.arbitrary()?
+                };
+                Ok(match param_type {
+                    $($vcode => Self::$vname(u.arbitrary()?),)*
+                    param_type => Self::Raw {
+                        param_type,
+                        value: arbitrary_impl::bytes(u)?.into(),
+                    },
+                })
+            }
         }
 
         impl Unpack for $name {
-            #[allow(unreachable_code, unused_variables)]
             fn unpack<R>(read: &mut R) -> pack::Result<Self> where R: io::Read {
                 let t = u16::unpack(read)?;
                 let v = VariableLengthBytes::<u8>::unpack(read)?;
                 Ok(match t {
                     $($vcode => Self::$vname(v.try_into().map_err(|_| pack::Error::Io(io::Error::new(io::ErrorKind::Other, "unexpected data")))?),)*
-                    t => return Err(io::Error::new(io::ErrorKind::Other, format!("unknown type {}", t)).into()),
+                    param_type => Self::Raw {
+                        param_type,
+                        value: v.0.into(),
+                    },
                 })
             }
         }
@@ -1098,6 +1884,7 @@ macro_rules! configuration_parameter {
 
 configuration_parameter! {
     #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
     pub enum SystemConfigurationParameter: SystemConfigurationParameterType {
         BrEdrPageScanType(u16) => 0x0000,
         BrEdrPageScanInterval(u16) => 0x0001,
@@ -1132,10 +1919,15 @@ configuration_parameter! {
 
 configuration_parameter! {
     #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
     pub enum RuntimeConfigurationParameter: RuntimeConfigurationParameterType {
+        /// LE auto reconnection timeout, raw u16. mgmt-api.txt fixes no range; the
+        /// kernel rejects unsupported values with Invalid Parameters.
+        LeAutoReconnectTimeout(u16) => 0x0000,
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, Clone, IterNewtype)]
 pub struct Remaining<T>(Vec<T>);
 
@@ -1152,6 +1944,10 @@ where
         }
         Ok(())
     }
+
+    fn packed_len_hint(&self) -> usize {
+        self.0.iter().map(T::packed_len_hint).sum()
+    }
 }
 
 impl<T> Unpack for Remaining<T>
@@ -1187,15 +1983,23 @@ bitflags! {
     }
 }
 
-#[derive(Debug, Clone, Pack, Unpack, Newtype, New)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Pack, Unpack, Newtype, New)]
 pub struct AdvertisementMonitorHandle(u16);
 
+/// Handle of a packet queued by Mesh Send.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Pack, Unpack, Newtype, New)]
+pub struct MeshHandle(u8);
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Pack, Unpack, Getters)]
 #[getset(get = "pub")]
 pub struct AdvertisementPattern {
     ad_type: u8,
     offset: u8,
     length: u8,
+    #[cfg_attr(feature = "serde", serde(with = "serde_impl::hex"))]
     value: [u8; 31],
 }
 
@@ -1219,14 +2023,22 @@ impl AdvertisementPattern {
 }
 
 bitflags! {
+    /// Flags of Device Found and Device Connected events.
+    ///
+    /// Device Connected sets only `LegacyPairing` and `InitiatedConnection`.
     #[derive(Pack, Unpack)]
     pub struct DeviceConnectFlags: u32 {
         const ConfirmName = 1 << 0;
         const LegacyPairing = 1 << 1;
         const NotConnectable = 1 << 2;
+        /// The connection was initiated by this controller.
+        const InitiatedConnection = 1 << 3;
+        const NameRequestFailed = 1 << 4;
+        const ScanResponse = 1 << 5;
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Pack, Unpack)]
 #[pack(u8)]
 pub enum DeviceDisconnectReason {
@@ -1238,6 +2050,7 @@ pub enum DeviceDisconnectReason {
     ConnectionTerminatedByLocalHostForSuspend = 5,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Pack, Unpack)]
 #[pack(u8)]
 pub enum ConfirmHint {
@@ -1245,6 +2058,7 @@ pub enum ConfirmHint {
     Simple = 1,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Pack, Unpack)]
 #[pack(u8)]
 pub enum SignatureResolvingKeyType {
@@ -1254,6 +2068,7 @@ pub enum SignatureResolvingKeyType {
     AuthenticatedRemoteCsrk = 0x03,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Pack, Unpack, Getters)]
 pub struct SignatureResolvingKey {
     address: WrappedAddress,
@@ -1261,6 +2076,7 @@ pub struct SignatureResolvingKey {
     #[getset(get = "pub")]
     typ: SignatureResolvingKeyType,
     #[getset(get = "pub")]
+    #[cfg_attr(feature = "serde", serde(with = "serde_impl::hex"))]
     value: [u8; 16],
 }
 
@@ -1280,6 +2096,7 @@ impl SignatureResolvingKey {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Pack, Unpack)]
 #[pack(u8)]
 pub enum SuspendState {
@@ -1288,6 +2105,7 @@ pub enum SuspendState {
     PageScanAndOrPassiveScanning = 2,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Pack, Unpack)]
 #[pack(u8)]
 pub enum WakeReason {
@@ -1295,3 +2113,465 @@ pub enum WakeReason {
     WakeDueToUnexpectedEvent = 1,
     RemoteWakeDueToPeerDeviceConnection = 2,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_handles_as_keys() {
+        let instances = vec![1, 2, 1]
+            .into_iter()
+            .map(AdvertiseInstance::new)
+            .collect::<HashSet<_>>();
+        assert_eq!(2, instances.len());
+        assert!(instances.contains(&AdvertiseInstance::new(2)));
+
+        let monitors = vec![0x0001, 0x0002, 0x0001]
+            .into_iter()
+            .map(AdvertisementMonitorHandle::new)
+            .collect::<HashSet<_>>();
+        assert_eq!(2, monitors.len());
+        assert!(monitors.contains(&AdvertisementMonitorHandle::new(0x0001)));
+
+        assert!(AdvertiseInstance::new(1) < AdvertiseInstance::new(2));
+    }
+
+    #[test]
+    fn test_advertise_instances_truncated() {
+        let err = AdvertiseInstances::unpack(&mut &[0x05, 0x01, 0x02][..]).unwrap_err();
+        assert!(matches!(
+            err,
+            pack::Error::Truncated {
+                expected: 5,
+                actual: 2
+            }
+        ));
+
+        let instances = AdvertiseInstances::unpack(&mut &[0x02, 0x01, 0x02, 0xFF][..]).unwrap();
+        let instances = instances.into_iter().map(|i| *i).collect::<Vec<_>>();
+        assert_eq!(vec![1, 2], instances);
+    }
+
+    #[test]
+    fn test_vec_truncated() {
+        let err = <Vec<ControllerIndex>>::unpack(&mut &[0x03, 0x00, 0x01, 0x00][..]).unwrap_err();
+        assert!(matches!(
+            err,
+            pack::Error::Truncated {
+                expected: 3,
+                actual: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn test_controller_configuration_option_display() {
+        assert_eq!("none", ControllerConfigurationOption::empty().to_string());
+        assert_eq!(
+            "ExternalConfiguration, BluetoothPublicAddressConfiguration",
+            ControllerConfigurationOption::all().to_string()
+        );
+        let unknown = ControllerConfigurationOption::unpack(&mut &[0x06, 0, 0, 0][..]).unwrap();
+        assert_eq!(
+            "BluetoothPublicAddressConfiguration, 0x4",
+            unknown.to_string()
+        );
+    }
+
+    #[test]
+    fn test_uuid_little_endian() {
+        let bytes = [
+            0xFB, 0x34, 0x9B, 0x5F, 0x80, 0x00, 0x00, 0x80, 0x00, 0x10, 0x00, 0x00, 0x0F, 0x18,
+            0x00, 0x00,
+        ];
+        let uuid = Uuid::unpack(&mut &bytes[..]).unwrap();
+        assert_eq!("0000180f-0000-1000-8000-00805f9b34fb", uuid.to_string());
+        assert_eq!(Uuid::from_u16(0x180F).to_string(), uuid.to_string());
+    }
+
+    #[test]
+    fn test_uuid_kernel_vector() {
+        // `debug_uuid` of net/bluetooth/mgmt.c.
+        let bytes = [
+            0x1C, 0xDA, 0x47, 0x1C, 0x48, 0x6C, 0x01, 0xAB, 0x9F, 0x46, 0xEC, 0xB9, 0x30, 0x25,
+            0x99, 0xD4,
+        ];
+        let uuid = "d4992530-b9ec-469f-ab01-6c481c47da1c"
+            .parse::<Uuid>()
+            .unwrap();
+        assert_eq!(&bytes[..], &uuid.pack_to_vec()[..]);
+        assert_eq!(
+            uuid.to_string(),
+            Uuid::unpack(&mut &bytes[..]).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_is_le_only() {
+        let mut types = AddressTypes::default();
+        assert!(!types.is_le_only());
+        types.extend(vec![AddressType::LePublic, AddressType::LeRandom]);
+        assert!(types.is_le_only());
+        types.extend(vec![AddressType::BrEdr]);
+        assert!(!types.is_le_only());
+    }
+
+    /// Fails to compile if a packet type is not thread safe.
+    /// Every command reply is bound by [`command::CommandRequest::Reply`].
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        fn assert_reply<C: command::CommandRequest>() {
+            assert_send_sync::<C::Reply>();
+        }
+
+        assert_send_sync::<command::Command>();
+        assert_send_sync::<command::CommandCode>();
+        assert_send_sync::<event::Event>();
+        assert_send_sync::<event::EventCode>();
+        assert_send_sync::<replay::DecodedPacket>();
+        assert_send_sync::<replay::ReplayError>();
+        assert_send_sync::<pack::Error>();
+        assert_reply::<command::ReadExtendedControllerInformation>();
+    }
+
+    #[test]
+    fn test_address_type_unknown() {
+        let ty = InternalAddressType::unpack(&mut &[0x03][..]).unwrap();
+        assert_eq!(InternalAddressType::Unknown(3), ty);
+
+        // Long Term Key: address, address type, key type, central, enc size, ediv, rand, value
+        let mut b = vec![0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x03, 0x00, 0x01, 0x10];
+        b.extend([0x00; 2 + 8 + 16]);
+        let key = LongTermKey::unpack(&mut &b[..]).unwrap();
+        let mut packed = vec![];
+        key.pack(&mut packed).unwrap();
+        assert_eq!(b, packed);
+
+        // Device Connected: address, address type, flags, eir
+        let b = [0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x03, 0, 0, 0, 0, 0, 0];
+        let connected = event::DeviceConnected::unpack(&mut &b[..]).unwrap();
+        assert!(matches!(connected.address(), Address::BrEdr(..)));
+    }
+
+    #[test]
+    fn test_address_types_unknown_bits() {
+        let types = AddressTypes::unpack(&mut &[0xFA][..]).unwrap();
+        let mut expected = vec![AddressType::LePublic]
+            .into_iter()
+            .collect::<AddressTypes>();
+        assert_ne!(expected, types);
+        expected.1 = 0xF8;
+        assert_eq!(expected, types);
+        assert_eq!(0xF8, types.unknown_bits());
+        assert!(types.is_le_only());
+
+        for v in [0xFA, 0xFF, 0x00] {
+            let types = AddressTypes::unpack(&mut &[v][..]).unwrap();
+            let mut b = vec![];
+            types.pack(&mut b).unwrap();
+            assert_eq!(vec![v], b);
+        }
+    }
+
+    #[test]
+    fn test_configuration_parameter_raw() {
+        let b = [
+            0x0A, 0x00, 0x02, 0xA0, 0x00, // LEAdvertisementMinInterval
+            0x00, 0x01, 0x02, 0x12, 0x34, // vendor
+            0x1F, 0x00, 0x00, // unknown, empty
+        ];
+        let params = Remaining::<SystemConfigurationParameter>::unpack(&mut &b[..]).unwrap();
+        assert_eq!(
+            "[LEAdvertisementMinInterval(160), \
+            Raw { param_type: 256, value: [18, 52] }, \
+            Raw { param_type: 31, value: [] }]",
+            format!("{:?}", params.iter().collect::<Vec<_>>())
+        );
+        assert!(matches!(
+            params.iter().nth(1).unwrap().for_type(),
+            SystemConfigurationParameterType::Raw(0x0100)
+        ));
+        assert_eq!(None, params.iter().nth(1).unwrap().value_as_u16());
+
+        let command = params
+            .iter()
+            .cloned()
+            .collect::<command::SetDefaultSystemConfiguration>();
+        let mut packed = vec![];
+        command.pack(&mut packed).unwrap();
+        assert_eq!(&b[..], &packed[..]);
+    }
+
+    #[test]
+    fn test_runtime_configuration_parameter() {
+        let b = [
+            0x00, 0x00, 0x02, 0xE8, 0x03, // LeAutoReconnectTimeout
+            0x01, 0x00, 0x01, 0x01, // unknown
+        ];
+        let params = Remaining::<RuntimeConfigurationParameter>::unpack(&mut &b[..]).unwrap();
+        assert_eq!(
+            "[LeAutoReconnectTimeout(1000), Raw { param_type: 1, value: [1] }]",
+            format!("{:?}", params.iter().collect::<Vec<_>>())
+        );
+        assert_eq!(Some(1000), params.iter().next().unwrap().value_as_u16());
+
+        let command = params
+            .iter()
+            .cloned()
+            .collect::<command::SetDefaultRuntimeConfiguration>();
+        let mut packed = vec![];
+        command.pack(&mut packed).unwrap();
+        assert_eq!(&b[..], &packed[..]);
+    }
+
+    #[test]
+    fn test_configuration_parameter_raw_known() {
+        let param = SystemConfigurationParameter::Raw {
+            param_type: 0x000A,
+            value: vec![0xA0, 0x00],
+        };
+        let err = param.pack(&mut vec![]).unwrap_err();
+        assert_eq!(
+            "unexpected value raw type 0x000a is known, \
+            use SystemConfigurationParameter::LEAdvertisementMinInterval",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_adv_data_scan_resp_lengths() {
+        for (adv_data, scan_resp) in &[
+            (vec![0xAA; 10], vec![0xBB; 3]),
+            (vec![0xAA; 3], vec![0xBB; 10]),
+        ] {
+            let packed = AdvDataScanResp::new(adv_data, scan_resp).pack_to_vec();
+            let mut read = &packed[..];
+            let unpacked = AdvDataScanResp::unpack(&mut read).unwrap();
+            assert!(read.is_empty());
+            assert_eq!(&adv_data[..], &*unpacked.0);
+            assert_eq!(&scan_resp[..], &*unpacked.1);
+        }
+    }
+
+    #[test]
+    fn test_class_of_device() {
+        // smartphone, laptop and headset.
+        for (value, bytes, major, minor, services) in [
+            (0x5A020C, [0x0C, 0x02, 0x5A], 0x02, 0x03, 0x02D0),
+            (0x20010C, [0x0C, 0x01, 0x20], 0x01, 0x03, 0x0100),
+            (0x240404, [0x04, 0x04, 0x24], 0x04, 0x01, 0x0120),
+        ] {
+            let class = ClassOfDevice::try_from(value).unwrap();
+            assert_eq!(ClassOfDevice::from(bytes), class);
+            assert_eq!(bytes.to_vec(), class.pack_to_vec());
+            assert_eq!(value, class.to_u24());
+            assert_eq!(major, class.major_device_class());
+            assert_eq!(minor, class.minor_device_class());
+            assert_eq!(services, class.service_classes());
+        }
+        assert_eq!(
+            "0x5a020c",
+            ClassOfDevice::from([0x0C, 0x02, 0x5A]).to_string()
+        );
+        assert_eq!("0x000000", ClassOfDevice::from([0; 3]).to_string());
+        assert!(matches!(
+            ClassOfDevice::try_from(0xFF5A020C),
+            Err(ClassOfDeviceError::TooLarge(0xFF5A020C))
+        ));
+    }
+
+    #[test]
+    fn test_class_of_device_parts() {
+        use class::*;
+
+        let class = ClassOfDevice::from_parts(
+            ServiceClasses::ObjectTransfer.bits(),
+            MajorDeviceClass::Computer.into(),
+            MinorDeviceClassComputer::Laptop.into(),
+        );
+        assert_eq!(0x10010C, class.to_u24());
+        assert_eq!(ServiceClasses::ObjectTransfer.bits(), class.service_class());
+        assert_eq!(
+            Ok(MajorDeviceClass::Computer),
+            class.major_device().try_into()
+        );
+        assert_eq!(
+            Ok(MinorDeviceClassComputer::Laptop),
+            class.minor_device().try_into()
+        );
+
+        // all bits of each field, and no more.
+        let class = ClassOfDevice::from_parts(0xFFFF, 0xFF, 0xFF);
+        assert_eq!(0xFFFFFC, class.to_u24());
+        assert_eq!(
+            (0x07FF, 0x1F, 0x3F),
+            (
+                class.service_class(),
+                class.major_device(),
+                class.minor_device()
+            )
+        );
+        let class = ClassOfDevice::try_from(0x240404).unwrap();
+        assert_eq!(
+            class,
+            ClassOfDevice::from_parts(
+                class.service_class(),
+                class.major_device(),
+                class.minor_device()
+            )
+        );
+
+        assert_eq!(
+            Ok(MinorDeviceClassAudioVideo::WearableHeadset),
+            class.minor_device().try_into()
+        );
+        assert_eq!(Err(0x0A), MajorDeviceClass::try_from(0x0A));
+    }
+
+    #[test]
+    fn test_seconds() {
+        let seconds = Seconds::from(180);
+        assert_eq!(vec![0xB4, 0x00], seconds.pack_to_vec());
+        assert_eq!(Duration::from_secs(180), Duration::from(seconds));
+        assert_eq!(
+            seconds,
+            Seconds::try_from(Duration::from_secs(180)).unwrap()
+        );
+        assert!(Seconds::try_from(Duration::from_millis(1500)).is_err());
+        assert!(Seconds::try_from(Duration::from_secs(65536)).is_err());
+    }
+
+    #[test]
+    fn test_class_of_device_from_str() {
+        let class = "5a020c".parse::<ClassOfDevice>().unwrap();
+        assert_eq!(ClassOfDevice::from([0x0C, 0x02, 0x5A]), class);
+        assert_eq!(class, "5A020C".parse().unwrap());
+        // round trip, with or without the prefix.
+        assert_eq!(class, class.to_string().parse().unwrap());
+        assert_eq!(
+            class,
+            class.to_string().trim_start_matches("0x").parse().unwrap()
+        );
+        assert_eq!(
+            ClassOfDevice::try_from(0x1F0100).unwrap(),
+            "0x1F0100".parse().unwrap()
+        );
+
+        for s in [
+            "",
+            "5a020",
+            "5a020c0",
+            "0x5a02",
+            "0x0x5a020",
+            "0X5a020c",
+            "5a02g0",
+            "+5a020",
+            " 5a020",
+        ] {
+            assert!(
+                matches!(
+                    s.parse::<ClassOfDevice>(),
+                    Err(ClassOfDeviceError::InvalidFormat(..))
+                ),
+                "{:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn test_long_term_key_wire() {
+        let b = [
+            0x55, 0x44, 0x33, 0x22, 0x11, 0x00, // address
+            0x00, // BR/EDR
+            0x03, // AuthenticatedP256Key
+            0x01, // central
+            0x10, // encryption size
+            0x34, 0x12, // diversifier
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, // random number
+            0x0F, 0x0E, 0x0D, 0x0C, 0x0B, 0x0A, 0x09, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02,
+            0x01, 0x00, // value
+        ];
+        let key = LongTermKeyBuilder::default()
+            .address(
+                "00:11:22:33:44:55"
+                    .parse::<BdAddr>()
+                    .unwrap()
+                    .to_br_edr_addr(),
+            )
+            .key_type(LongTermKeyType::AuthenticatedP256Key)
+            .master(true)
+            .encryption_size(16)
+            .encryption_diversifier(0x1234)
+            .random_number([0, 1, 2, 3, 4, 5, 6, 7])
+            .value([15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0])
+            .build()
+            .unwrap();
+        assert_eq!(&b[..], &key.pack_to_vec()[..]);
+
+        let mut read = &b[..];
+        let key = LongTermKey::unpack(&mut read).unwrap();
+        assert!(read.is_empty());
+        assert_eq!(AddressType::BrEdr, key.address().address_type());
+        assert!(*key.master());
+        assert_eq!(0x1234, *key.encryption_diversifier());
+    }
+
+    #[test]
+    fn test_name_truncate() {
+        let name = ShortName::new_truncate("日本語です");
+        assert_eq!("日本語", name.as_str().unwrap());
+        assert!(name.is_valid());
+        assert_eq!(ShortName::new_truncate("ab😀😀😀"), *"ab😀😀");
+        assert_eq!(ShortName::new_truncate("abc😀😀"), "abc😀");
+        assert_eq!(ShortName::new_truncate("0123456789"), "0123456789");
+        assert_eq!(ShortName::new_truncate("0123456789a"), "0123456789");
+        assert_eq!(ShortName::new_truncate("ab\0cd"), "ab");
+        assert_eq!(ShortName::new_truncate(""), "");
+
+        let long = format!("{}😀", "a".repeat(247));
+        assert_eq!(Name::new_truncate(&long), "a".repeat(247).as_str());
+        let long = format!("{}漢", "a".repeat(245));
+        assert_eq!(Name::new_truncate(&long), long.as_str());
+        assert!(Name::new(Name::new_truncate(&"あ".repeat(100)).to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_name_str() {
+        let name = ShortName::new("名前").unwrap();
+        assert_eq!("名前", name.to_string());
+        assert!(name != "名");
+
+        let mut b = [0; 11];
+        b[..3].copy_from_slice(&[b'a', 0xE5, 0x90]);
+        let name = ShortName::unpack(&mut &b[..]).unwrap();
+        assert!(name.as_str().is_err());
+        assert_eq!("a\u{FFFD}", name.to_string());
+        assert_eq!(name.to_string_lossy(), name.to_string());
+    }
+
+    #[test]
+    fn test_length_too_long() {
+        assert!(AdvDataScanResp::try_new([0; 255], [0; 255]).is_ok());
+        let err = AdvDataScanResp::try_new([0; 256], []).unwrap_err();
+        assert_eq!("length too long 255 < 256", err.to_string());
+        assert!(AdvDataScanResp::try_new([], [0; 256]).is_err());
+
+        let param = SystemConfigurationParameter::Raw {
+            param_type: 0x0100,
+            value: vec![0; 256],
+        };
+        assert!(param.pack(&mut vec![]).is_err());
+    }
+
+    #[test]
+    fn test_company_id() {
+        assert_eq!("Intel Corp. (0x0002)", CompanyId::from(0x0002).to_string());
+        assert_eq!("0x1234", CompanyId::from(0x1234).to_string());
+        assert_eq!(None, CompanyId::from(0xFFFF).name());
+    }
+}
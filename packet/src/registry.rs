@@ -0,0 +1,136 @@
+//! Commands and events supported by this crate, as JSON.
+//!
+//! Generated by the `commands` / `events` macros from the declarations. Schema:
+//!
+//! ```text
+//! {
+//!   "version": 1,
+//!   "commands": [
+//!     {
+//!       "code": 5,
+//!       "name": "SetPowered",
+//!       "params": [{"name": "0", "type": "bool"}],
+//!       "reply": {"name": "SetPoweredReply", "fields": [{"name": "0", "type": "Settings"}]},
+//!       "since": null
+//!     }
+//!   ],
+//!   "events": [
+//!     {"code": 6, "name": "NewSettings", "fields": [..], "since": null}
+//!   ]
+//! }
+//! ```
+//!
+//! Tuple fields are named by position. Types are Rust types relative to the crate root.
+//! `since` is reserved for the mgmt API revision, and is `null` for now.
+//! A copy is checked in as `docs/registry.json`.
+use crate::{command, event};
+
+/// Schema version of [`registry_json`]. Bumped on incompatible changes.
+const VERSION: u32 = 1;
+
+/// Every command and event with code, name and fields. See [module docs](self).
+pub fn registry_json() -> String {
+    format!(
+        r#"{{"version":{},"commands":{},"events":{}}}"#,
+        VERSION,
+        command::REGISTRY_JSON,
+        event::REGISTRY_JSON
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::Value;
+
+    use super::*;
+
+    fn entries<'a>(registry: &'a Value, key: &str) -> Vec<(u64, &'a str)> {
+        registry[key]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| (e["code"].as_u64().unwrap(), e["name"].as_str().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_registry_json() {
+        let registry = serde_json::from_str::<Value>(&registry_json()).unwrap();
+        assert_eq!(1, registry["version"]);
+
+        let commands = entries(&registry, "commands");
+        assert_eq!(command::all_commands().len(), commands.len());
+        for (code, name) in command::all_commands() {
+            assert!(commands.contains(&(code.clone() as u64, *name)), "{}", name);
+        }
+        let events = entries(&registry, "events");
+        assert_eq!(event::all_events().len(), events.len());
+        for (code, name) in event::all_events() {
+            assert!(events.contains(&(code.clone() as u64, *name)), "{}", name);
+        }
+
+        let set_powered = &registry["commands"][4];
+        assert_eq!("SetPowered", set_powered["name"]);
+        assert_eq!("bool", set_powered["params"][0]["type"]);
+        assert_eq!("Settings", set_powered["reply"]["fields"][0]["type"]);
+        assert!(set_powered["since"].is_null());
+    }
+
+    /// `BTMGMT_UPDATE_REGISTRY=1 cargo test -p btmgmt-packet registry` to update.
+    /// Fields in seconds take [`crate::Seconds`], not a bare integer.
+    #[test]
+    fn test_time_field_units() {
+        // not in seconds.
+        const EXEMPT: &[(&str, &str)] = &[("NewConnectionParameter", "supervision_timeout")];
+        const BARE: &[&str] = &["u8", "u16", "u32", "u64"];
+
+        let registry = serde_json::from_str::<Value>(&registry_json()).unwrap();
+        let mut fields = vec![];
+        for command in registry["commands"].as_array().unwrap() {
+            fields.push((&command["name"], &command["params"]));
+            fields.push((&command["reply"]["name"], &command["reply"]["fields"]));
+        }
+        for event in registry["events"].as_array().unwrap() {
+            fields.push((&event["name"], &event["fields"]));
+        }
+
+        let mut offending = vec![];
+        for (owner, fields) in fields {
+            let owner = owner.as_str().unwrap();
+            for field in fields.as_array().unwrap() {
+                let name = field["name"].as_str().unwrap();
+                let ty = field["type"].as_str().unwrap();
+                if (name.contains("timeout") || name.contains("duration"))
+                    && BARE.contains(&ty)
+                    && !EXEMPT.contains(&(owner, name))
+                {
+                    offending.push(format!("{}.{}: {}", owner, name, ty));
+                }
+            }
+        }
+        assert!(offending.is_empty(), "use Seconds: {:?}", offending);
+
+        let set_discoverable = &registry["commands"][5];
+        assert_eq!("SetDiscoverable", set_discoverable["name"]);
+        assert_eq!("Seconds", set_discoverable["params"][1]["type"]);
+    }
+
+    #[test]
+    fn test_registry_checked_in() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../docs/registry.json");
+        let registry = serde_json::from_str::<Value>(&registry_json()).unwrap();
+        if std::env::var_os("BTMGMT_UPDATE_REGISTRY").is_some() {
+            let mut json = serde_json::to_string_pretty(&registry).unwrap();
+            json.push('\n');
+            std::fs::write(&path, json).unwrap();
+        }
+        let checked_in = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            registry,
+            serde_json::from_str::<Value>(&checked_in).unwrap(),
+            "docs/registry.json is outdated"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Decode captured mgmt traffic offline.
+//!
+//! Walks a concatenation of packets sent by the kernel (as captured on the mgmt channel),
+//! and decodes each of them. Command packets sent to the kernel are not decodable,
+//! because command parameters are only ever packed.
+use crate::command::CommandCode;
+use crate::event::{self, Event};
+use crate::{pack, ControllerIndex, ErrorCode};
+
+/// Length of packet header. (code, index, parameter length)
+const HEADER_LEN: usize = 6;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("truncated packet at offset {0}")]
+    Truncated(usize),
+
+    #[error("failed to decode packet at offset {offset}: {source}")]
+    Decode {
+        offset: usize,
+        #[source]
+        source: pack::Error,
+    },
+}
+
+/// Decoded packet.
+#[derive(Debug, Clone)]
+pub enum DecodedPacket {
+    /// Command Complete or Command Status. `params` is empty for Command Status.
+    Reply {
+        index: ControllerIndex,
+        opcode: CommandCode,
+        status: ErrorCode,
+        params: Box<[u8]>,
+    },
+
+    /// Any other event.
+    Event(ControllerIndex, Event),
+}
+
+/// Packets in captured traffic. See [`replay`].
+///
+/// A packet failed to decode yields [`ReplayError::Decode`] and decoding continues with
+/// the next one. A truncated packet yields [`ReplayError::Truncated`] once and ends.
+#[derive(Debug, Clone)]
+pub struct Replay<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for Replay<'a> {
+    type Item = Result<DecodedPacket, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.data[self.offset..];
+        if rest.is_empty() {
+            return None;
+        }
+
+        let offset = self.offset;
+        let len = match rest {
+            [_, _, _, _, lo, hi, ..] => HEADER_LEN + u16::from_le_bytes([*lo, *hi]) as usize,
+            _ => usize::MAX,
+        };
+        if rest.len() < len {
+            self.offset = self.data.len();
+            return Some(Err(ReplayError::Truncated(offset)));
+        }
+        self.offset += len;
+
+        let packet = match event::unpack_events(&mut &rest[..len]) {
+            Ok((index, Event::CommandComplete(comp))) => DecodedPacket::Reply {
+                index,
+                opcode: comp.opcode().clone(),
+                status: comp.status().clone(),
+                params: comp.data().clone(),
+            },
+            Ok((index, Event::CommandStatus(status))) => DecodedPacket::Reply {
+                index,
+                opcode: status.opcode,
+                status: status.status,
+                params: Default::default(),
+            },
+            Ok((index, event)) => DecodedPacket::Event(index, event),
+            Err(source) => return Some(Err(ReplayError::Decode { offset, source })),
+        };
+        Some(Ok(packet))
+    }
+}
+
+/// Decode concatenated packets sent by the kernel.
+pub fn replay(bytes: &[u8]) -> Replay<'_> {
+    Replay {
+        data: bytes,
+        offset: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay() {
+        let (mut bytes, _) = crate::fixtures::version_reply();
+        let (found, expected) = crate::fixtures::device_found();
+        bytes.extend(&found);
+
+        let packets = replay(&bytes).collect::<Result<Vec<_>, _>>().unwrap();
+        assert!(matches!(
+            &packets[..],
+            [
+                DecodedPacket::Reply {
+                    index: ControllerIndex::NonController,
+                    opcode: CommandCode::ReadManagementVersionInformation,
+                    status: ErrorCode::Success,
+                    ..
+                },
+                DecodedPacket::Event(ControllerIndex::ControllerId(0), Event::DeviceFound(..)),
+            ]
+        ));
+        if let DecodedPacket::Event(_, Event::DeviceFound(found)) = &packets[1] {
+            assert_eq!(expected.address(), found.address());
+        }
+    }
+
+    #[test]
+    fn test_replay_truncated() {
+        let (bytes, _) = crate::fixtures::device_found();
+        let mut blob = vec![0x02, 0x00, 0x00, 0x00, 0x02, 0x00, 0xFF, 0xFF]; // unknown opcode
+        blob.extend([0x12, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00]); // short device found
+        blob.extend(&bytes[..bytes.len() - 1]);
+
+        let results = replay(&blob).collect::<Vec<_>>();
+        assert!(matches!(
+            &results[..],
+            [
+                Ok(DecodedPacket::Event(_, Event::Unknown(0x0002, ..))),
+                Err(ReplayError::Decode { offset: 8, .. }),
+                Err(ReplayError::Truncated(15)),
+            ]
+        ));
+
+        let results = replay(&[0x12, 0x00, 0x00]).collect::<Vec<_>>();
+        assert!(matches!(&results[..], [Err(ReplayError::Truncated(0))]));
+    }
+}
@@ -0,0 +1,360 @@
+//! `serde` implementations for types not derivable. (feature `serde`)
+//!
+//! Types are deserialized from what they serialize to. Keys are deserialized as is,
+//! without the checks of their constructors.
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, SerializeSeq, SerializeStruct, Serializer};
+
+use super::*;
+
+/// Byte arrays as hex strings, in the wire order. (for `#[serde(with)]`)
+pub(crate) mod hex {
+    use std::convert::TryInto;
+
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::ser::Serializer;
+
+    pub(crate) fn serialize<S: Serializer, const N: usize>(
+        v: &[u8; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let hex = v.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        serializer.serialize_str(&hex)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        let invalid = || {
+            de::Error::invalid_value(de::Unexpected::Str(&hex), &&*format!("{} bytes in hex", N))
+        };
+        if hex.len() != N * 2 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let v = (0..N)
+            .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        Ok(v.try_into().unwrap())
+    }
+}
+
+/// Deserialize by `FromStr` from a string.
+fn from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let s = String::deserialize(deserializer)?;
+    s.parse().map_err(de::Error::custom)
+}
+
+impl Serialize for ControllerIndex {
+    /// Index number, or `null` for [`ControllerIndex::NonController`].
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::ControllerId(id) => serializer.serialize_some(id),
+            Self::NonController => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ControllerIndex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Option::<u16>::deserialize(deserializer).map(Self::from)
+    }
+}
+
+impl Serialize for WrappedAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for WrappedAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        from_str(deserializer)
+    }
+}
+
+impl Serialize for ClassOfDevice {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ClassOfDevice {
+    /// From the displayed value, `0x` prefixed.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        match s.strip_prefix("0x") {
+            Some(hex) => hex.parse().map_err(de::Error::custom),
+            None => Err(de::Error::invalid_value(
+                de::Unexpected::Str(&s),
+                &"0x prefixed hex",
+            )),
+        }
+    }
+}
+
+impl<const N: usize> Serialize for FixedLengthName<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string_lossy())
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for FixedLengthName<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        from_str(deserializer)
+    }
+}
+
+impl Serialize for Uuid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Uuid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        from_str(deserializer)
+    }
+}
+
+/// Serialize bitflags as the list of flag names.
+macro_rules! serde_flags {
+    ($($name:ident),*) => {
+        $(
+            impl Serialize for $name {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    let flags = (0..32)
+                        .filter_map(|bit| $name::from_bits(1 << bit))
+                        .filter(|flag| self.contains(*flag))
+                        .collect::<Vec<_>>();
+                    let mut seq = serializer.serialize_seq(Some(flags.len()))?;
+                    for flag in flags {
+                        seq.serialize_element(&format!("{:?}", flag))?;
+                    }
+                    seq.end()
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $name {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    let mut flags = Self::empty();
+                    for name in Vec::<String>::deserialize(deserializer)? {
+                        let flag = (0..32)
+                            .filter_map(|bit| $name::from_bits(1 << bit))
+                            .find(|flag| format!("{:?}", flag) == name)
+                            .ok_or_else(|| {
+                                de::Error::custom(format!(
+                                    "unknown {} flag {:?}",
+                                    stringify!($name),
+                                    name
+                                ))
+                            })?;
+                        flags |= flag;
+                    }
+                    Ok(flags)
+                }
+            }
+        )*
+    };
+}
+
+serde_flags!(
+    Settings,
+    AdvertisingFlag,
+    ControllerConfigurationOption,
+    Phys,
+    FeatureFlags,
+    DeviceFlags,
+    AdvertisementMonitorFeatures,
+    DeviceConnectFlags
+);
+
+impl Serialize for AddressTypes {
+    /// Sorted list of the type names.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut names = self
+            .0
+            .iter()
+            .map(|ty| format!("{:?}", ty))
+            .collect::<Vec<_>>();
+        names.sort();
+        serializer.collect_seq(names)
+    }
+}
+
+impl<'de> Deserialize<'de> for AddressTypes {
+    /// Unknown bits are not representable, and always 0.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut types = Self::default();
+        for name in Vec::<String>::deserialize(deserializer)? {
+            let ty = [
+                AddressType::BrEdr,
+                AddressType::LePublic,
+                AddressType::LeRandom,
+            ]
+            .iter()
+            .find(|ty| format!("{:?}", ty) == name)
+            .ok_or_else(|| de::Error::custom(format!("unknown address type {:?}", name)))?;
+            types.0.insert(ty.clone());
+        }
+        Ok(types)
+    }
+}
+
+impl<L> Serialize for VariableLengthBytes<L> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter())
+    }
+}
+
+struct AddressEntry<'a>(&'a Address);
+
+impl Serialize for AddressEntry<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (address, address_type) = split(self.0.clone());
+        let mut s = serializer.serialize_struct("Address", 2)?;
+        s.serialize_field("address", &address)?;
+        s.serialize_field("address_type", &address_type)?;
+        s.end()
+    }
+}
+
+impl Serialize for command::GetConnectionsReply {
+    /// List of `{ "address": .., "address_type": .. }`.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().map(AddressEntry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize() {
+        let (_, reply) = crate::fixtures::controller_information_reply();
+        let value = serde_json::to_value(&reply).unwrap();
+        assert_eq!("00:11:22:33:44:55", value["address"]);
+        assert_eq!("btmgmt", value["name"]);
+        assert_eq!(
+            serde_json::json!(["Powered", "BasicRateEnhancedDataRate", "LowEnergy"]),
+            value["current_settings"]
+        );
+
+        let value = serde_json::to_value(ControllerIndex::NonController).unwrap();
+        assert!(value.is_null());
+    }
+
+    fn round_trip<T>(v: &T) -> (serde_json::Value, T)
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+    {
+        let json = serde_json::to_string(v).unwrap();
+        (
+            serde_json::from_str(&json).unwrap(),
+            serde_json::from_str(&json).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_round_trip_keys() {
+        let addr = BdAddr::from([0x55, 0x44, 0x33, 0x22, 0x11, 0x00]);
+        let mut value = [0; 16];
+        value[0] = 0x01;
+        value[15] = 0xFF;
+
+        let link_key = LinkKey::new(
+            addr.clone().to_br_edr_addr(),
+            LinkKeyType::AuthenticatedCombinationkeyfromP256,
+            value,
+            4,
+        );
+        let (json, back) = round_trip(&link_key);
+        assert_eq!(
+            serde_json::json!({
+                "address": "00:11:22:33:44:55",
+                "address_type": "BrEdr",
+                "key_type": "AuthenticatedCombinationkeyfromP256",
+                "value": "010000000000000000000000000000ff",
+                "pin_length": 4,
+            }),
+            json
+        );
+        assert_eq!(link_key.pack_to_vec(), back.pack_to_vec());
+
+        let long_term_key = LongTermKeyBuilder::default()
+            .address(addr.clone().to_le_random_addr())
+            .key_type(LongTermKeyType::AuthenticatedP256Key)
+            .master(true)
+            .encryption_size(16)
+            .encryption_diversifier(0x1234)
+            .random_number([0xAB; 8])
+            .value(value)
+            .build()
+            .unwrap();
+        let (json, back) = round_trip(&long_term_key);
+        assert_eq!("abababababababab", json["random_number"]);
+        assert_eq!("LeRandom", json["address_type"]);
+        assert_eq!(long_term_key.pack_to_vec(), back.pack_to_vec());
+        assert_eq!(addr.to_le_random_addr(), back.address());
+
+        let irk =
+            IdentityResolvingKey::new(BdAddr::from([0; 6]).to_le_public_addr(), value).unwrap();
+        let (_, back) = round_trip(&irk);
+        assert_eq!(irk.pack_to_vec(), back.pack_to_vec());
+
+        let mut json = serde_json::to_value(&irk).unwrap();
+        json["value"] = "0102".into();
+        assert!(serde_json::from_value::<IdentityResolvingKey>(json.clone()).is_err());
+        json["value"] = "zz".repeat(16).into();
+        assert!(serde_json::from_value::<IdentityResolvingKey>(json).is_err());
+    }
+
+    #[test]
+    fn test_round_trip_values() {
+        let settings = Settings::Powered | Settings::LowEnergy;
+        let (json, back) = round_trip(&settings);
+        assert_eq!(serde_json::json!(["Powered", "LowEnergy"]), json);
+        assert_eq!(settings, back);
+        assert!(serde_json::from_str::<Settings>(r#"["Unknown"]"#).is_err());
+
+        let (json, back) = round_trip(&Phys::Le1MTx);
+        assert_eq!(serde_json::json!(["Le1MTx"]), json);
+        assert_eq!(Phys::Le1MTx, back);
+
+        let class = ClassOfDevice::from([0x0C, 0x02, 0x5A]);
+        let (json, back) = round_trip(&class);
+        assert_eq!("0x5a020c", json);
+        assert_eq!(class, back);
+
+        let uuid = Uuid::from_u16(0x180F);
+        let (json, back) = round_trip(&uuid);
+        assert_eq!("0000180f-0000-1000-8000-00805f9b34fb", json);
+        assert_eq!(uuid.0, back.0);
+
+        let mut types = AddressTypes::default();
+        types.extend(vec![AddressType::LePublic, AddressType::LeRandom]);
+        let (json, back) = round_trip(&types);
+        assert_eq!(serde_json::json!(["LePublic", "LeRandom"]), json);
+        assert_eq!(types, back);
+
+        for index in [
+            ControllerIndex::ControllerId(1),
+            ControllerIndex::NonController,
+        ] {
+            assert_eq!(index, round_trip(&index).1);
+        }
+        assert_eq!(ErrorCode::Busy, round_trip(&ErrorCode::Busy).1);
+        assert_eq!(Seconds::new(180), round_trip(&Seconds::new(180)).1);
+
+        let name = Name::new("btmgmt").unwrap();
+        assert_eq!("btmgmt", round_trip(&name).1.to_string_lossy());
+        assert!(serde_json::from_str::<ShortName>(r#""too long short name""#).is_err());
+    }
+}
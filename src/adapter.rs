@@ -0,0 +1,146 @@
+//! Controller handle.
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::lock::Mutex;
+
+use crate::client::{Client, Error, Result};
+use crate::command;
+use crate::packet::ControllerIndex;
+use crate::role::Role;
+
+mod advertising;
+mod class;
+mod debounced;
+mod discoverable;
+mod discovery;
+mod identity;
+mod keys;
+mod state;
+mod wake;
+
+pub(crate) use advertising::Allocators as AdvertisingAllocators;
+pub use advertising::{AdvertisingHandle, ConnectableConflict, ConnectableOutcome};
+pub use class::{StickyDeviceClass, REASSERT_INTERVAL};
+pub use debounced::{Coalesce, Debounced, DEFAULT_WINDOW};
+pub use discoverable::{DiscoverableGuard, WindowEnd};
+pub(crate) use discovery::Trackers as DiscoveryTrackers;
+pub use discovery::{DiscoveryOutcome, DiscoveryState};
+pub use identity::IdentityOutcome;
+pub use keys::{MAX_CONNECTION_PARAMETERS, MAX_LINK_KEYS, MAX_LONG_TERM_KEYS};
+pub use state::{
+    AdapterState, AdapterStateMachine, Transition, TransitionCause, TRANSITION_BACKLOG,
+};
+pub use wake::{WakeOutcome, WakeScanParams};
+
+/// Handle for a controller.
+///
+/// Binds [`Client`] and [`ControllerIndex`].
+pub struct Adapter {
+    client: Client,
+    index: ControllerIndex,
+    discovery: Arc<Mutex<Option<discovery::Tracker>>>,
+    advertising: Arc<Mutex<Option<advertising::Allocator>>>,
+    require_primary: bool,
+}
+
+impl Clone for Adapter {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            index: self.index.clone(),
+            discovery: self.discovery.clone(),
+            advertising: self.advertising.clone(),
+            require_primary: self.require_primary,
+        }
+    }
+}
+
+impl fmt::Debug for Adapter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Adapter")
+            .field("index", &self.index)
+            .field("require_primary", &self.require_primary)
+            .finish()
+    }
+}
+
+impl Adapter {
+    pub(crate) fn new(client: Client, index: ControllerIndex) -> Self {
+        Self {
+            discovery: client.discovery_trackers().get(&index),
+            advertising: client.advertising_allocators().get(&index),
+            client,
+            index,
+            require_primary: false,
+        }
+    }
+
+    /// Controller index.
+    pub fn index(&self) -> &ControllerIndex {
+        &self.index
+    }
+
+    /// Client.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Call mgmt API command for this controller.
+    pub fn call<C>(&self, command: C) -> impl Future<Output = Result<C::Reply>> + 'static
+    where
+        C: command::CommandRequest + 'static,
+        C::Reply: fmt::Debug,
+    {
+        self.client.call(self.index.clone(), command)
+    }
+
+    /// Refuse mutating helpers unless the client holds [`Role::Primary`] for this controller.
+    ///
+    /// Affects [`Debounced`] setters, discovery helpers, [`Adapter::add_advertising_auto`],
+    /// [`Adapter::set_connectable_checked`], [`Adapter::configure_wake`],
+    /// [`Adapter::discoverable_window`], [`Adapter::set_device_class_sticky`],
+    /// [`Adapter::set_identity`], the Load helpers (e.g. [`Adapter::load_long_term_keys`])
+    /// and commands of [`AdapterStateMachine`].
+    /// [`Adapter::call`] is not affected.
+    pub fn require_primary(mut self) -> Self {
+        self.require_primary = true;
+        self
+    }
+
+    /// Call mutating command, checking the role if required.
+    fn call_mutating<C>(&self, command: C) -> impl Future<Output = Result<C::Reply>> + 'static
+    where
+        C: command::CommandRequest + 'static,
+        C::Reply: fmt::Debug,
+    {
+        let check = self.check_primary();
+        let reply = self.call(command);
+        async move {
+            check?;
+            reply.await
+        }
+    }
+
+    fn check_primary(&self) -> Result<()> {
+        if self.require_primary && !self.client.holds_role(Role::Primary, &self.index) {
+            return Err(Error::RoleRequired {
+                role: Role::Primary,
+                scope: self.index.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Coalescing proxy for "last write wins" setters. (window: [`DEFAULT_WINDOW`])
+    pub fn debounced(&self) -> Debounced {
+        self.debounced_with_window(DEFAULT_WINDOW)
+    }
+
+    /// Coalescing proxy for "last write wins" setters.
+    pub fn debounced_with_window(&self, window: Duration) -> Debounced {
+        Debounced::new(self.clone(), window)
+    }
+}
@@ -0,0 +1,673 @@
+//! Advertising instance allocation.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use futures_util::future::FutureExt;
+use futures_util::lock::{Mutex, MutexGuard};
+use futures_util::stream::StreamExt;
+
+use super::Adapter;
+use crate::client::{Error, EventSubscribe, Result};
+use crate::command;
+use crate::event::Event;
+use crate::packet::advertising::{
+    AdStructures, AdvertisingContext, AdvertisingParams, PredictedAdvertising, AD_TYPE_APPEARANCE,
+    AD_TYPE_COMPLETE_NAME, AD_TYPE_SHORT_NAME,
+};
+use crate::packet::{AdvertisingFlag, ControllerIndex, Discoverable, ErrorCode, Settings};
+
+/// Advertising instances in use, tracked from Advertising Added / Removed events.
+pub(crate) struct Allocator {
+    events: EventSubscribe,
+    max_instances: u8,
+    pub(super) used: BTreeSet<u8>,
+    /// Instances added by this process.
+    pub(super) owned: BTreeMap<u8, Owned>,
+}
+
+/// Advertising instance added by this process.
+#[derive(Debug, Clone)]
+pub(super) struct Owned {
+    pub(super) flags: AdvertisingFlag,
+    /// Parameters with the instance set, to add it again.
+    pub(super) params: AdvertisingParams,
+}
+
+impl Allocator {
+    /// Consume received events.
+    pub(super) fn update(&mut self, index: &ControllerIndex) {
+        while let Some(item) = self.events.next().now_or_never() {
+            match item {
+                Some((i, Event::AdvertisingAdded(added))) if &i == index => {
+                    self.used.insert(**added);
+                }
+                Some((i, Event::AdvertisingRemoved(removed))) if &i == index => {
+                    self.used.remove(&**removed);
+                    self.owned.remove(&**removed);
+                }
+                Some(..) => {}
+                None => break,
+            }
+        }
+    }
+
+    /// Replace used instances by a Read Advertising Features reply.
+    fn refresh(
+        &mut self,
+        index: &ControllerIndex,
+        features: &command::ReadAdvertisingFeatureReply,
+    ) {
+        self.update(index);
+        self.used = features.instances().into_iter().map(|i| **i).collect();
+    }
+
+    /// Lowest free instance. (instances are numbered from 1)
+    fn free(&self) -> Result<u8> {
+        (1..=self.max_instances)
+            .find(|i| !self.used.contains(i))
+            .ok_or(Error::AdvertisingExhausted {
+                max_instances: self.max_instances,
+            })
+    }
+}
+
+/// Allocators of a client by controller, shared by its [`Adapter`]s.
+#[derive(Default)]
+pub(crate) struct Allocators(
+    std::sync::Mutex<HashMap<ControllerIndex, Arc<Mutex<Option<Allocator>>>>>,
+);
+
+impl Allocators {
+    pub(crate) fn get(&self, index: &ControllerIndex) -> Arc<Mutex<Option<Allocator>>> {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(index.clone())
+            .or_default()
+            .clone()
+    }
+}
+
+/// What [`Adapter::set_connectable_checked`] does on conflicting advertising instances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectableConflict {
+    /// Log a warning and proceed.
+    Warn,
+    /// Return [`Error::ConnectableAdvertising`] without sending the command.
+    Error,
+}
+
+/// Outcome of [`Adapter::set_connectable_checked`].
+#[derive(Debug, Clone)]
+pub struct ConnectableOutcome {
+    settings: Settings,
+    conflicting: Vec<u8>,
+    dropped: Vec<u8>,
+}
+
+impl ConnectableOutcome {
+    /// Current settings.
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Owned connectable instances active when disabling connectable.
+    pub fn conflicting(&self) -> &[u8] {
+        &self.conflicting
+    }
+
+    /// Owned instances the kernel dropped. Their handles do nothing on removal.
+    pub fn dropped(&self) -> &[u8] {
+        &self.dropped
+    }
+}
+
+/// Advertising instance added by [`Adapter::add_advertising_auto`].
+///
+/// Removed on drop, or explicitly by [`AdvertisingHandle::remove`].
+pub struct AdvertisingHandle {
+    adapter: Adapter,
+    instance: u8,
+    removed: bool,
+}
+
+impl fmt::Debug for AdvertisingHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdvertisingHandle")
+            .field("adapter", &self.adapter)
+            .field("instance", &self.instance)
+            .finish()
+    }
+}
+
+impl AdvertisingHandle {
+    /// Allocated instance.
+    pub fn instance(&self) -> u8 {
+        self.instance
+    }
+
+    /// Remove advertising and free the instance.
+    pub async fn remove(mut self) -> Result<()> {
+        self.removed = true;
+        self.adapter.remove_advertising(self.instance).await
+    }
+}
+
+impl Drop for AdvertisingHandle {
+    fn drop(&mut self) {
+        if self.removed {
+            return;
+        }
+        let adapter = self.adapter.clone();
+        let instance = self.instance;
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    if let Err(err) = adapter.remove_advertising(instance).await {
+                        log::warn!("failed to remove advertising {}: {}", instance, err);
+                    }
+                });
+            }
+            Err(..) => log::warn!("advertising {} leaked: no runtime", instance),
+        }
+    }
+}
+
+impl Adapter {
+    /// Allocator with the used instances read by Read Advertising Features.
+    async fn advertising_allocator(&self) -> Result<MutexGuard<'_, Option<Allocator>>> {
+        let mut allocator = self.advertising.lock().await;
+        // Subscribe before reading, not to miss changes in between.
+        let events = if allocator.is_none() {
+            let index = self.index.clone();
+            Some(
+                self.client
+                    .events_filtered(move |i, event| {
+                        i == &index
+                            && matches!(
+                                event,
+                                Event::AdvertisingAdded(..) | Event::AdvertisingRemoved(..)
+                            )
+                    })
+                    .await,
+            )
+        } else {
+            None
+        };
+        let features = self.call(command::ReadAdvertisingFeature).await?;
+        if let Some(events) = events {
+            *allocator = Some(Allocator {
+                events,
+                max_instances: *features.max_instances(),
+                used: Default::default(),
+                owned: Default::default(),
+            });
+        }
+        allocator.as_mut().unwrap().refresh(&self.index, &features);
+        Ok(allocator)
+    }
+
+    /// Add advertising with the lowest free instance.
+    ///
+    /// The instance set in `params` is ignored. Used instances are read by Read Advertising
+    /// Features just before adding. Another process may still add the same instance in
+    /// between. The kernel then updates that instance in place, and nothing tells which
+    /// process added it, so keep a single process managing advertising.
+    /// Returns [`Error::AdvertisingExhausted`] if no instance is free.
+    pub async fn add_advertising_auto(
+        &self,
+        params: AdvertisingParams,
+    ) -> Result<AdvertisingHandle> {
+        self.check_primary()?;
+        let mut allocator = self.advertising_allocator().await?;
+        let allocator = allocator.as_mut().unwrap();
+
+        let instance = allocator.free()?;
+        let params = params.instance(instance);
+        let command = params.clone().build()?;
+        let flags = *command.flags();
+        self.call(command).await?;
+
+        allocator.used.insert(instance);
+        allocator.owned.insert(instance, Owned { flags, params });
+        Ok(AdvertisingHandle {
+            adapter: self.clone(),
+            instance,
+            removed: false,
+        })
+    }
+
+    async fn remove_advertising(&self, instance: u8) -> Result<()> {
+        let mut allocator = self.advertising.lock().await;
+        if let Some(allocator) = allocator.as_mut() {
+            allocator.update(&self.index);
+            if allocator.owned.remove(&instance).is_none() {
+                // already removed by the kernel. may be reused by others.
+                return Ok(());
+            }
+            allocator.used.remove(&instance);
+        }
+        self.call(command::RemoveAdvertising::new(instance.into()))
+            .await?;
+        Ok(())
+    }
+
+    /// Set connectable, keeping track of advertising instances owned by this adapter.
+    ///
+    /// When disabling connectable, owned instances with
+    /// [`AdvertisingFlag::SwitchIntoConnectableMode`] are reported in the outcome, or
+    /// refused with [`Error::ConnectableAdvertising`] per `on_conflict`.
+    /// Some kernels drop or rewrite such instances instead of rejecting the command,
+    /// so afterwards owned instances are checked against the Read Advertising Features
+    /// reply, and the dropped ones are reported and forgotten.
+    pub async fn set_connectable_checked(
+        &self,
+        connectable: bool,
+        on_conflict: ConnectableConflict,
+    ) -> Result<ConnectableOutcome> {
+        self.check_primary()?;
+        let mut allocator = self.advertising.lock().await;
+
+        let mut conflicting = vec![];
+        if let (false, Some(allocator)) = (connectable, allocator.as_mut()) {
+            allocator.update(&self.index);
+            conflicting = allocator
+                .owned
+                .iter()
+                .filter(|(_, owned)| {
+                    owned
+                        .flags
+                        .contains(AdvertisingFlag::SwitchIntoConnectableMode)
+                })
+                .map(|(instance, _)| *instance)
+                .collect();
+        }
+        if !conflicting.is_empty() {
+            match on_conflict {
+                ConnectableConflict::Warn => log::warn!(
+                    "disabling connectable with connectable advertising {:?}",
+                    conflicting
+                ),
+                ConnectableConflict::Error => {
+                    return Err(Error::ConnectableAdvertising {
+                        instances: conflicting,
+                    })
+                }
+            }
+        }
+
+        let settings = *self.call(command::SetConnectable::new(connectable)).await?;
+
+        let mut dropped = vec![];
+        if let Some(allocator) = allocator.as_mut().filter(|a| !a.owned.is_empty()) {
+            let features = self.call(command::ReadAdvertisingFeature).await?;
+            allocator.refresh(&self.index, &features);
+            let used = &allocator.used;
+            dropped = allocator
+                .owned
+                .keys()
+                .filter(|instance| !used.contains(instance))
+                .copied()
+                .collect();
+            for instance in &dropped {
+                log::warn!("advertising {} dropped by the kernel", instance);
+                allocator.owned.remove(instance);
+            }
+        }
+
+        Ok(ConnectableOutcome {
+            settings,
+            conflicting,
+            dropped,
+        })
+    }
+
+    /// Advertising data and scan response of an instance added by
+    /// [`Adapter::add_advertising_auto`], predicted, not captured.
+    ///
+    /// The mgmt API has no command reading them back, so the kernel additions per the
+    /// flags are predicted from the current name, appearance and settings. (see
+    /// [`AdvertisingParams::predict`]) Limited discoverable is not told by the settings,
+    /// and taken as general. The tx power is not known, so the field is missing.
+    /// Returns [`Error::UnknownAdvertising`] for other instances.
+    pub async fn effective_advertising(&self, instance: u8) -> Result<PredictedAdvertising> {
+        let params = {
+            let mut allocator = self.advertising.lock().await;
+            let owned = allocator.as_mut().and_then(|allocator| {
+                allocator.update(&self.index);
+                allocator.owned.get(&instance)
+            });
+            match owned {
+                Some(owned) => owned.params.clone(),
+                None => return Err(Error::UnknownAdvertising { instance }),
+            }
+        };
+        let context = self.advertising_context().await?;
+        Ok(params.predict(&context))
+    }
+
+    async fn advertising_context(&self) -> Result<AdvertisingContext> {
+        fn settings_context(settings: &Settings) -> AdvertisingContext {
+            let discoverable = if settings.contains(Settings::Discoverable) {
+                Discoverable::General
+            } else {
+                Discoverable::Disable
+            };
+            AdvertisingContext::default()
+                .discoverable(discoverable)
+                .bredr_enabled(settings.contains(Settings::BasicRateEnhancedDataRate))
+        }
+
+        match self.call(command::ReadExtendedControllerInformation).await {
+            Ok(info) => {
+                let mut context = settings_context(info.current_settings());
+                for item in AdStructures::new(info.eir_data()) {
+                    context = match item? {
+                        (AD_TYPE_COMPLETE_NAME, name) => context.name(name),
+                        (AD_TYPE_SHORT_NAME, name) => context.short_name(name),
+                        (AD_TYPE_APPEARANCE, &[lo, hi]) => {
+                            context.appearance(u16::from_le_bytes([lo, hi]))
+                        }
+                        _ => context,
+                    };
+                }
+                Ok(context)
+            }
+            Err(Error::CommandStatus {
+                status: ErrorCode::UnknownCommand,
+                ..
+            }) => {
+                let info = self.call(command::ReadControllerInformation).await?;
+                Ok(settings_context(info.current_settings())
+                    .name(info.name().to_string_lossy())
+                    .short_name(info.short_name().to_string_lossy()))
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::UnixDatagram;
+
+    use super::*;
+    use crate::Client;
+
+    /// Read Advertising Features reply.
+    fn features(max_instances: u8, instances: &[u8]) -> Vec<u8> {
+        let len = 11 + instances.len() as u8;
+        let mut b = vec![0x01, 0x00, 0x00, 0x00, len, 0x00, 0x3D, 0x00, 0x00];
+        b.extend([0xFF, 0x03, 0x00, 0x00]); // supported flags
+        b.extend([0x1F, 0x1F, max_instances, instances.len() as u8]);
+        b.extend(instances);
+        b
+    }
+
+    async fn recv(peer: &UnixDatagram) -> Vec<u8> {
+        let mut buf = [0; 1024];
+        let n = peer.recv(&mut buf).await.unwrap();
+        buf[..n].to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_add_advertising_auto() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let kernel = async {
+            assert_eq!(
+                &[0x3D, 0x00, 0x00, 0x00, 0x00, 0x00],
+                &recv(&peer).await[..]
+            );
+            peer.send(&features(2, &[1])).await.unwrap();
+            let add = recv(&peer).await;
+            assert_eq!(&[0x3E, 0x00, 0x00, 0x00, 0x0B, 0x00, 0x02], &add[..7]);
+            peer.send(&[0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x3E, 0x00, 0x00, 0x02])
+                .await
+                .unwrap();
+        };
+        let (handle, _) = tokio::join!(
+            adapter.add_advertising_auto(AdvertisingParams::new(0)),
+            kernel
+        );
+        let handle = handle.unwrap();
+        assert_eq!(2, handle.instance());
+
+        // exhausted. no command other than reading sent.
+        let kernel = async {
+            recv(&peer).await;
+            peer.send(&features(2, &[1, 2])).await.unwrap();
+        };
+        let (err, _) = tokio::join!(
+            adapter.add_advertising_auto(AdvertisingParams::new(0)),
+            kernel
+        );
+        assert!(matches!(
+            err.unwrap_err(),
+            Error::AdvertisingExhausted { max_instances: 2 }
+        ));
+
+        let kernel = async {
+            assert_eq!(
+                &[0x3F, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02],
+                &recv(&peer).await[..]
+            );
+            peer.send(&[0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x3F, 0x00, 0x00, 0x02])
+                .await
+                .unwrap();
+        };
+        let (result, _) = tokio::join!(handle.remove(), kernel);
+        result.unwrap();
+        assert_eq!(
+            2,
+            adapter
+                .advertising
+                .lock()
+                .await
+                .as_ref()
+                .unwrap()
+                .free()
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_advertising_auto_shared() {
+        let (client, peer) = Client::pair();
+        let (first, second) = (client.adapter(0), client.adapter(0));
+
+        let kernel = async {
+            recv(&peer).await;
+            peer.send(&features(3, &[])).await.unwrap();
+            recv(&peer).await;
+            peer.send(&[0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x3E, 0x00, 0x00, 0x01])
+                .await
+                .unwrap();
+        };
+        let (handle, _) = tokio::join!(
+            first.add_advertising_auto(AdvertisingParams::new(0)),
+            kernel
+        );
+        let mut handle = handle.unwrap();
+        assert_eq!(1, handle.instance());
+
+        // another handle of the controller sees instance 1 owned.
+        let (added, _) = tokio::join!(
+            second.add_advertising_auto(AdvertisingParams::new(0)),
+            async {
+                recv(&peer).await;
+                // another process added instance 2 without the event received.
+                peer.send(&features(3, &[1, 2])).await.unwrap();
+                assert_eq!(0x03, recv(&peer).await[6]);
+                peer.send(&[0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x3E, 0x00, 0x00, 0x03])
+                    .await
+                    .unwrap();
+            }
+        );
+        let mut added = added.unwrap();
+        assert_eq!(3, added.instance());
+        assert!(second
+            .advertising
+            .lock()
+            .await
+            .as_ref()
+            .unwrap()
+            .owned
+            .contains_key(&1));
+        handle.removed = true; // kernel gone. skip removing on drop.
+        added.removed = true;
+    }
+
+    /// Add a connectable advertising as instance 1.
+    async fn add_connectable(adapter: &Adapter, peer: &UnixDatagram) -> AdvertisingHandle {
+        let kernel = async {
+            recv(peer).await;
+            peer.send(&features(2, &[])).await.unwrap();
+            recv(peer).await;
+            peer.send(&[0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x3E, 0x00, 0x00, 0x01])
+                .await
+                .unwrap();
+        };
+        let params = AdvertisingParams::new(0).flags(AdvertisingFlag::SwitchIntoConnectableMode);
+        let (handle, _) = tokio::join!(adapter.add_advertising_auto(params), kernel);
+        handle.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_effective_advertising() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        // not added. nothing sent.
+        let err = adapter.effective_advertising(1).await.unwrap_err();
+        assert!(matches!(err, Error::UnknownAdvertising { instance: 1 }));
+        assert!(!err.is_retryable());
+
+        let kernel = async {
+            recv(&peer).await;
+            peer.send(&features(2, &[])).await.unwrap();
+            recv(&peer).await;
+            peer.send(&[0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x3E, 0x00, 0x00, 0x01])
+                .await
+                .unwrap();
+        };
+        let params = AdvertisingParams::new(0)
+            .flags(
+                AdvertisingFlag::AddFlagsFieldToAdvData
+                    | AdvertisingFlag::AddAppearanceFieldToScanResp
+                    | AdvertisingFlag::AddLocalNameInScanResp,
+            )
+            .adv_data([0x03, 0xFF, 0x01, 0x02]);
+        let (handle, _) = tokio::join!(adapter.add_advertising_auto(params), kernel);
+        let mut handle = handle.unwrap();
+
+        let kernel = async {
+            assert_eq!(
+                &[0x42, 0x00, 0x00, 0x00, 0x00, 0x00],
+                &recv(&peer).await[..]
+            );
+            let mut reply = vec![0x01, 0x00, 0x00, 0x00, 0x20, 0x00, 0x42, 0x00, 0x00];
+            reply.extend([0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0x09, 0x02, 0x00]);
+            reply.extend([0xFF, 0xFF, 0x01, 0x00]); // supported settings
+            reply.extend([0x09, 0x02, 0x00, 0x00]); // powered, discoverable, le
+            reply.extend([0x0A, 0x00]);
+            reply.extend([0x05, 0x09, b't', b'e', b's', b't']);
+            reply.extend([0x03, 0x19, 0xC1, 0x03]);
+            peer.send(&reply).await.unwrap();
+        };
+        let (predicted, _) = tokio::join!(adapter.effective_advertising(1), kernel);
+        let predicted = predicted.unwrap();
+        // general discoverable, br/edr not supported.
+        assert_eq!(
+            &[0x02, 0x01, 0x06, 0x03, 0xFF, 0x01, 0x02],
+            predicted.adv_data()
+        );
+        assert_eq!(
+            &[0x03, 0x19, 0xC1, 0x03, 0x05, 0x09, b't', b'e', b's', b't'],
+            predicted.scan_resp()
+        );
+        assert!(!predicted.tx_power_unknown());
+        handle.removed = true;
+    }
+
+    #[tokio::test]
+    async fn test_set_connectable_checked_dropped() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+        let handle = add_connectable(&adapter, &peer).await;
+
+        // refused. no command sent.
+        let err = adapter
+            .set_connectable_checked(false, ConnectableConflict::Error)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ConnectableAdvertising { instances } if instances == [1]));
+
+        let kernel = async {
+            assert_eq!(
+                &[0x07, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00],
+                &recv(&peer).await[..]
+            );
+            peer.send(&[
+                0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0x07, 0x00, 0x00, 0x81, 0x02, 0x00, 0x00,
+            ])
+            .await
+            .unwrap();
+            // the kernel dropped the instance.
+            assert_eq!(
+                &[0x3D, 0x00, 0x00, 0x00, 0x00, 0x00],
+                &recv(&peer).await[..]
+            );
+            peer.send(&features(2, &[])).await.unwrap();
+        };
+        let (outcome, _) = tokio::join!(
+            adapter.set_connectable_checked(false, ConnectableConflict::Warn),
+            kernel
+        );
+        let outcome = outcome.unwrap();
+        assert!(!outcome.settings().contains(Settings::Connectable));
+        assert_eq!(&[1], outcome.conflicting());
+        assert_eq!(&[1], outcome.dropped());
+
+        // nothing to remove.
+        handle.remove().await.unwrap();
+        let mut buf = [0; 16];
+        assert_eq!(
+            std::io::ErrorKind::WouldBlock,
+            peer.try_recv(&mut buf).unwrap_err().kind()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_connectable_checked_rejected() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+        let mut handle = add_connectable(&adapter, &peer).await;
+
+        let kernel = async {
+            recv(&peer).await;
+            peer.send(&[0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x07, 0x00, 0x0B])
+                .await
+                .unwrap(); // rejected
+        };
+        let (result, _) = tokio::join!(
+            adapter.set_connectable_checked(false, ConnectableConflict::Warn),
+            kernel
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            Error::CommandStatus {
+                status: ErrorCode::Rejected,
+                ..
+            }
+        ));
+
+        // still owned.
+        let allocator = adapter.advertising.lock().await;
+        assert!(allocator.as_ref().unwrap().owned.contains_key(&1));
+        drop(allocator);
+        handle.removed = true;
+    }
+}
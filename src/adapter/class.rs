@@ -0,0 +1,235 @@
+//! Class of Device kept against kernel recomputation.
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_core::stream::Stream;
+use futures_util::future::FutureExt;
+use futures_util::stream::StreamExt;
+use tokio::time::{self, Instant, Sleep};
+
+use super::Adapter;
+use crate::client::{EventSubscribe, Result};
+use crate::command;
+use crate::event::Event;
+use crate::packet::ClassOfDevice;
+
+/// Minimum interval between re-asserts of [`StickyDeviceClass`].
+pub const REASSERT_INTERVAL: Duration = Duration::from_secs(1);
+
+type Reassert = Pin<Box<dyn Future<Output = Result<ClassOfDevice>> + Send>>;
+
+/// Major / minor device class re-asserted when the kernel changes it.
+///
+/// Yields every observed Class of Device: values of Class Of Device Changed events and
+/// replies of re-asserts. Re-asserts happen only while polled, at most once per
+/// [`REASSERT_INTERVAL`]. Service classes are left to the kernel.
+pub struct StickyDeviceClass {
+    adapter: Adapter,
+    events: EventSubscribe,
+    major: u8,
+    minor: u8,
+    class: ClassOfDevice,
+    last_reassert: Option<Instant>,
+    deferred: Option<Pin<Box<Sleep>>>,
+    reassert: Option<Reassert>,
+}
+
+impl fmt::Debug for StickyDeviceClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StickyDeviceClass")
+            .field("index", self.adapter.index())
+            .field("major", &self.major)
+            .field("minor", &self.minor)
+            .field("class", &self.class)
+            .finish()
+    }
+}
+
+impl StickyDeviceClass {
+    /// Class of Device last observed.
+    pub fn class(&self) -> &ClassOfDevice {
+        &self.class
+    }
+
+    fn drifted(&self) -> bool {
+        self.class.major_class() != self.major || self.class.minor_class() != self.minor
+    }
+
+    fn start_reassert(&mut self) {
+        log::debug!(
+            "re-assert device class {:#04x}/{:#04x}: {}",
+            self.major,
+            self.minor,
+            self.class
+        );
+        self.last_reassert = Some(Instant::now());
+        self.deferred = None;
+        let reply = self
+            .adapter
+            .call_mutating(command::SetDeviceClass::new(self.major, self.minor));
+        self.reassert = Some(Box::pin(async move { Ok(*reply.await?) }));
+    }
+}
+
+impl Stream for StickyDeviceClass {
+    type Item = Result<ClassOfDevice>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some(reassert) = &mut this.reassert {
+            if let Poll::Ready(result) = reassert.poll_unpin(cx) {
+                this.reassert = None;
+                if let Ok(class) = &result {
+                    this.class = *class;
+                }
+                return Poll::Ready(Some(result));
+            }
+        }
+
+        if let Some(deferred) = &mut this.deferred {
+            if deferred.poll_unpin(cx).is_ready() {
+                this.start_reassert();
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+        }
+
+        loop {
+            match this.events.poll_next_unpin(cx) {
+                Poll::Ready(Some((index, Event::ClassOfDeviceChanged(class))))
+                    if &index == this.adapter.index() =>
+                {
+                    this.class = *class;
+                    if !this.drifted() {
+                        this.deferred = None;
+                    } else if this.reassert.is_none() && this.deferred.is_none() {
+                        match this.last_reassert {
+                            Some(last) if last.elapsed() < REASSERT_INTERVAL => {
+                                log::warn!("device class changed again: {}", this.class);
+                                this.deferred =
+                                    Some(Box::pin(time::sleep_until(last + REASSERT_INTERVAL)));
+                            }
+                            _ => this.start_reassert(),
+                        }
+                        // poll the re-assert or the timer.
+                        cx.waker().wake_by_ref();
+                    }
+                    return Poll::Ready(Some(Ok(this.class)));
+                }
+                Poll::Ready(Some(..)) => {}
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Adapter {
+    /// Set the major / minor device class, re-asserting them when changed by the kernel.
+    ///
+    /// The kernel recomputes Class of Device on UUID changes. Poll the returned stream
+    /// to keep the class.
+    pub async fn set_device_class_sticky(&self, major: u8, minor: u8) -> Result<StickyDeviceClass> {
+        // Subscribe before setting, not to miss changes in between.
+        let events = self.client.events().await;
+        let class = *self
+            .call_mutating(command::SetDeviceClass::new(major, minor))
+            .await?;
+        Ok(StickyDeviceClass {
+            adapter: self.clone(),
+            events,
+            major,
+            minor,
+            class,
+            last_reassert: None,
+            deferred: None,
+            reassert: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::UnixDatagram;
+
+    use super::*;
+    use crate::Client;
+
+    /// computer / laptop
+    const MAJOR: u8 = 0x01;
+    const MINOR: u8 = 0x0C;
+
+    fn set_device_class() -> Vec<u8> {
+        vec![0x0E, 0x00, 0x00, 0x00, 0x02, 0x00, MAJOR, MINOR]
+    }
+
+    fn complete(class: [u8; 3]) -> Vec<u8> {
+        let mut b = vec![0x01, 0x00, 0x00, 0x00, 0x06, 0x00, 0x0E, 0x00, 0x00];
+        b.extend(class);
+        b
+    }
+
+    fn changed(class: [u8; 3]) -> Vec<u8> {
+        let mut b = vec![0x07, 0x00, 0x00, 0x00, 0x03, 0x00];
+        b.extend(class);
+        b
+    }
+
+    async fn expect(peer: &UnixDatagram, reply: Vec<u8>) {
+        let mut buf = [0; 64];
+        let n = peer.recv(&mut buf).await.unwrap();
+        assert_eq!(&set_device_class()[..], &buf[..n]);
+        peer.send(&reply).await.unwrap();
+    }
+
+    fn nothing_sent(peer: &UnixDatagram) {
+        let mut buf = [0; 64];
+        assert_eq!(
+            std::io::ErrorKind::WouldBlock,
+            peer.try_recv(&mut buf).unwrap_err().kind()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_set_device_class_sticky() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let (sticky, _) = tokio::join!(
+            adapter.set_device_class_sticky(MAJOR, MINOR),
+            expect(&peer, complete([MINOR, MAJOR, 0x00]))
+        );
+        let mut sticky = sticky.unwrap();
+
+        // service classes only: not a drift.
+        peer.send(&changed([MINOR, MAJOR, 0x20])).await.unwrap();
+        assert_eq!(0x20010C, sticky.next().await.unwrap().unwrap().to_u32());
+        nothing_sent(&peer);
+
+        // reverted to uncategorized, with the audio service.
+        peer.send(&changed([0x00, 0x00, 0x20])).await.unwrap();
+        let class = sticky.next().await.unwrap().unwrap();
+        assert_eq!(0x0100, class.service_classes());
+        assert_eq!(0x00, class.major_class());
+
+        let (class, _) = tokio::join!(sticky.next(), expect(&peer, complete([MINOR, MAJOR, 0x20])));
+        let class = class.unwrap().unwrap();
+        assert_eq!(0x20010C, class.to_u32());
+        assert_eq!(&class, sticky.class());
+        nothing_sent(&peer);
+
+        // again within the interval: deferred.
+        peer.send(&changed([0x00, 0x00, 0x20])).await.unwrap();
+        sticky.next().await.unwrap().unwrap();
+        tokio::task::yield_now().await;
+        nothing_sent(&peer);
+
+        let (class, _) = tokio::join!(sticky.next(), expect(&peer, complete([MINOR, MAJOR, 0x20])));
+        assert_eq!(0x20010C, class.unwrap().unwrap().to_u32());
+        nothing_sent(&peer);
+    }
+}
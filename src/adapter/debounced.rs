@@ -0,0 +1,239 @@
+//! Coalescing "last write wins" setters.
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+
+use futures_util::lock::Mutex;
+use tokio::time::{self, Instant};
+
+use super::Adapter;
+use crate::client::Result;
+use crate::command::{self, CommandCode, CommandRequest};
+use crate::packet::{Discoverable, Name, ShortName};
+
+/// Default window of [`Debounced`].
+pub const DEFAULT_WINDOW: Duration = Duration::from_millis(300);
+
+/// "Last write wins" setter command.
+///
+/// Only the last value matters, so successive calls can be coalesced.
+pub trait Coalesce: CommandRequest + Send + 'static {}
+
+impl Coalesce for command::SetLocalName {}
+impl Coalesce for command::SetDeviceClass {}
+impl Coalesce for command::SetApperance {}
+impl Coalesce for command::SetDiscoverable {}
+
+type Pending = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+struct Slot {
+    pending: Option<Pending>,
+    deadline: Instant,
+    running: bool,
+}
+
+struct Shared {
+    adapter: Adapter,
+    window: Duration,
+    slots: StdMutex<HashMap<CommandCode, Slot>>,
+    send: Mutex<()>,
+}
+
+/// Coalescing proxy for "last write wins" setters.
+///
+/// Successive calls to the same setter within the window are collapsed and
+/// only the last value is applied after the window elapsed.
+/// Failures of deferred commands are logged.
+#[derive(Clone)]
+pub struct Debounced {
+    shared: Arc<Shared>,
+}
+
+impl fmt::Debug for Debounced {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Debounced")
+            .field("adapter", &self.shared.adapter)
+            .field("window", &self.shared.window)
+            .finish()
+    }
+}
+
+impl Debounced {
+    pub(crate) fn new(adapter: Adapter, window: Duration) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                adapter,
+                window,
+                slots: Default::default(),
+                send: Mutex::new(()),
+            }),
+        }
+    }
+
+    /// Schedule command. Replaces the pending value for the same command.
+    pub fn set<C>(&self, command: C)
+    where
+        C: Coalesce,
+        C::Reply: fmt::Debug + Send,
+    {
+        let reply = self.shared.adapter.call_mutating(command);
+        let pending = Box::pin(async move { reply.await.map(drop) });
+        let deadline = Instant::now() + self.shared.window;
+
+        let mut slots = self.shared.slots.lock().unwrap();
+        let slot = slots.entry(C::CODE).or_insert_with(|| Slot {
+            pending: None,
+            deadline,
+            running: false,
+        });
+        slot.pending = Some(pending);
+        slot.deadline = deadline;
+        if !slot.running {
+            slot.running = true;
+            tokio::spawn(run(self.shared.clone(), C::CODE));
+        }
+    }
+
+    /// Schedule [`command::SetLocalName`].
+    pub fn set_local_name(&self, name: Name, short_name: ShortName) {
+        self.set(command::SetLocalName::new(name, short_name))
+    }
+
+    /// Schedule [`command::SetDeviceClass`].
+    pub fn set_device_class(&self, major_class: u8, minor_class: u8) {
+        self.set(command::SetDeviceClass::new(major_class, minor_class))
+    }
+
+    /// Schedule [`command::SetApperance`].
+    pub fn set_appearance(&self, appearance: u16) {
+        self.set(command::SetApperance::new(appearance))
+    }
+
+    /// Schedule [`command::SetDiscoverable`].
+    pub fn set_discoverable(&self, discoverable: Discoverable, timeout: u16) {
+        self.set(command::SetDiscoverable::new(discoverable, timeout))
+    }
+
+    /// Apply pending values immediately and wait for in-flight commands.
+    ///
+    /// Returns the first error if any.
+    pub async fn flush(&self) -> Result<()> {
+        let _send = self.shared.send.lock().await;
+        let pendings = {
+            let mut slots = self.shared.slots.lock().unwrap();
+            slots
+                .values_mut()
+                .filter_map(|slot| slot.pending.take())
+                .collect::<Vec<_>>()
+        };
+
+        let mut result = Ok(());
+        for pending in pendings {
+            if let Err(err) = pending.await {
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+        result
+    }
+}
+
+async fn run(shared: Arc<Shared>, code: CommandCode) {
+    loop {
+        let deadline = shared.slots.lock().unwrap()[&code].deadline;
+        time::sleep_until(deadline).await;
+
+        let _send = shared.send.lock().await;
+        let pending = {
+            let mut slots = shared.slots.lock().unwrap();
+            let slot = slots.get_mut(&code).unwrap();
+            if slot.pending.is_none() {
+                slot.running = false;
+                return;
+            }
+            if slot.deadline > Instant::now() {
+                continue;
+            }
+            slot.pending.take().unwrap()
+        };
+
+        if let Err(err) = pending.await {
+            log::warn!("failed to apply {:?}: {}", code, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use tokio::net::UnixDatagram;
+
+    use super::*;
+    use crate::Client;
+
+    fn name_of(packet: &[u8]) -> &[u8] {
+        let name = &packet[6..6 + 249];
+        let n = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+        &name[..n]
+    }
+
+    async fn reply_local_name(peer: &UnixDatagram) -> Vec<u8> {
+        let mut buf = [0; 1024];
+        let n = peer.recv(&mut buf).await.unwrap();
+        assert_eq!(&[0x0F, 0x00, 0x00, 0x00, 0x04, 0x01], &buf[..6]);
+
+        let mut reply = vec![0x01, 0x00, 0x00, 0x00, 0x07, 0x01, 0x0F, 0x00, 0x00];
+        reply.extend(&buf[6..n]);
+        peer.send(&reply).await.unwrap();
+        buf[..n].to_vec()
+    }
+
+    fn assert_no_packet(peer: &UnixDatagram) {
+        let mut buf = [0; 1024];
+        let err = peer.try_recv(&mut buf).unwrap_err();
+        assert_eq!(io::ErrorKind::WouldBlock, err.kind());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_coalesce() {
+        let (client, peer) = Client::pair();
+        let debounced = client.adapter(0).debounced();
+
+        for name in ["a", "ab", "abc"] {
+            debounced.set_local_name(name.parse().unwrap(), "".parse().unwrap());
+            time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_no_packet(&peer);
+
+        let packet = reply_local_name(&peer).await;
+        assert_eq!(b"abc", name_of(&packet));
+
+        time::sleep(DEFAULT_WINDOW * 2).await;
+        assert_no_packet(&peer);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_flush() {
+        let (client, peer) = Client::pair();
+        let debounced = client.adapter(0).debounced();
+
+        debounced.set_local_name("x".parse().unwrap(), "".parse().unwrap());
+        debounced.set_local_name("xy".parse().unwrap(), "".parse().unwrap());
+
+        let (result, packet) = tokio::join!(debounced.flush(), reply_local_name(&peer));
+        result.unwrap();
+        assert_eq!(b"xy", name_of(&packet));
+
+        // nothing left after the window.
+        time::sleep(DEFAULT_WINDOW * 2).await;
+        assert_no_packet(&peer);
+
+        // flush without pending values.
+        debounced.flush().await.unwrap();
+    }
+}
@@ -0,0 +1,338 @@
+//! Time limited discoverable window.
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use futures_util::stream::StreamExt;
+
+use super::Adapter;
+use crate::client::{Error, EventSubscribe, Result};
+use crate::command;
+use crate::event::Event;
+use crate::packet::{Discoverable, Settings};
+
+/// How a discoverable window ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowEnd {
+    /// Closed by the kernel when the timeout elapsed.
+    Expired,
+
+    /// The controller was powered off.
+    PoweredOff,
+
+    /// Closed by [`DiscoverableGuard::end`].
+    Ended,
+}
+
+/// Discoverable window opened by [`Adapter::discoverable_window`].
+///
+/// The previous discoverable state is restored when the window ends, or on drop.
+pub struct DiscoverableGuard {
+    adapter: Adapter,
+    events: EventSubscribe,
+    previous: bool,
+    current: Settings,
+    restored: bool,
+}
+
+impl std::fmt::Debug for DiscoverableGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiscoverableGuard")
+            .field("index", self.adapter.index())
+            .field("previous", &self.previous)
+            .field("current", &self.current)
+            .finish()
+    }
+}
+
+impl DiscoverableGuard {
+    /// true if discoverable before the window.
+    pub fn previous(&self) -> bool {
+        self.previous
+    }
+
+    /// Settings last reported.
+    pub fn current(&self) -> &Settings {
+        &self.current
+    }
+
+    /// Wait until the window is closed by the kernel, then restore the previous state.
+    pub async fn closed(mut self) -> Result<WindowEnd> {
+        let end = loop {
+            match self.events.next().await {
+                Some((index, Event::NewSettings(settings))) if &index == self.adapter.index() => {
+                    self.current = *settings;
+                    if !self.current.contains(Settings::Powered) {
+                        break WindowEnd::PoweredOff;
+                    }
+                    if !self.current.contains(Settings::Discoverable) {
+                        break WindowEnd::Expired;
+                    }
+                }
+                Some(..) => {}
+                None => return Err(Error::Closed),
+            }
+        };
+        self.restore().await?;
+        Ok(end)
+    }
+
+    /// Close the window early and restore the previous state.
+    pub async fn end(mut self) -> Result<WindowEnd> {
+        self.restore().await?;
+        Ok(WindowEnd::Ended)
+    }
+
+    async fn restore(&mut self) -> Result<()> {
+        self.restored = true;
+        let discoverable = self.current.contains(Settings::Discoverable);
+        self.adapter
+            .restore_discoverable(self.previous, discoverable)
+            .await
+    }
+}
+
+impl Drop for DiscoverableGuard {
+    fn drop(&mut self) {
+        if self.restored {
+            return;
+        }
+        let adapter = self.adapter.clone();
+        let previous = self.previous;
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    if let Err(err) = adapter.restore_discoverable(previous, true).await {
+                        log::warn!("failed to restore discoverable: {}", err);
+                    }
+                });
+            }
+            Err(..) => log::warn!("discoverable window left open: no runtime"),
+        }
+    }
+}
+
+impl Adapter {
+    /// Make the controller discoverable for `duration`, then restore the previous state.
+    ///
+    /// `duration` is rounded up to seconds. Returns [`Error::InvalidDiscoverableWindow`]
+    /// for [`Discoverable::Disable`] or a duration out of 1 to 65535 seconds.
+    /// If discoverable before, general discoverable without timeout is restored.
+    pub async fn discoverable_window(
+        &self,
+        mode: Discoverable,
+        duration: Duration,
+    ) -> Result<DiscoverableGuard> {
+        self.check_primary()?;
+        let seconds = duration.as_secs() + u64::from(duration.subsec_nanos() > 0);
+        let timeout = match (mode, seconds) {
+            (Discoverable::Disable, _) | (_, 0) => None,
+            (_, seconds) => u16::try_from(seconds).ok(),
+        };
+        let timeout = timeout.ok_or(Error::InvalidDiscoverableWindow { mode, duration })?;
+
+        // Subscribe before opening, not to miss the window closing.
+        let events = self.client.events().await;
+        let info = self.call(command::ReadControllerInformation).await?;
+        let previous = info.current_settings().contains(Settings::Discoverable);
+        let current = *self
+            .call(command::SetDiscoverable::new(mode, timeout))
+            .await?;
+
+        Ok(DiscoverableGuard {
+            adapter: self.clone(),
+            events,
+            previous,
+            current,
+            restored: false,
+        })
+    }
+
+    async fn restore_discoverable(&self, previous: bool, discoverable: bool) -> Result<()> {
+        let mode = if previous {
+            Discoverable::General
+        } else if discoverable {
+            Discoverable::Disable
+        } else {
+            return Ok(());
+        };
+        self.call(command::SetDiscoverable::new(mode, 0)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::UnixDatagram;
+
+    use super::*;
+    use crate::frames::{command, complete, event, kernel};
+    use crate::Client;
+
+    fn new_settings(settings: u32) -> Vec<u8> {
+        event(0x06, &settings.to_le_bytes())
+    }
+
+    /// Controller information with the current settings.
+    fn information(settings: u32) -> Vec<u8> {
+        let (mut b, _) = crate::packet::fixtures::controller_information_reply();
+        b[22..26].copy_from_slice(&settings.to_le_bytes());
+        b
+    }
+
+    const POWERED: u32 = 0x01;
+    const DISCOVERABLE: u32 = 0x08;
+
+    async fn open(
+        adapter: &Adapter,
+        peer: &UnixDatagram,
+        previous: u32,
+        mode: Discoverable,
+        duration: Duration,
+        params: &[u8],
+    ) -> DiscoverableGuard {
+        let kernel = kernel(
+            peer,
+            vec![
+                (command(0x04, &[]), information(previous)),
+                (
+                    command(0x06, params),
+                    complete(0x06, &(POWERED | DISCOVERABLE).to_le_bytes()),
+                ),
+            ],
+        );
+        let (guard, _) = tokio::join!(adapter.discoverable_window(mode, duration), kernel);
+        guard.unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_window_expired() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let guard = open(
+            &adapter,
+            &peer,
+            POWERED | DISCOVERABLE,
+            Discoverable::Limited,
+            Duration::from_millis(29_500),
+            &[0x02, 0x1E, 0x00],
+        )
+        .await;
+        assert!(guard.previous());
+
+        let kernel = async {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            peer.send(&new_settings(POWERED)).await.unwrap();
+            // general discoverable restored.
+            let restore = (
+                command(0x06, &[0x01, 0x00, 0x00]),
+                complete(0x06, &(POWERED | DISCOVERABLE).to_le_bytes()),
+            );
+            kernel(&peer, vec![restore]).await;
+        };
+        let (end, _) = tokio::join!(guard.closed(), kernel);
+        assert_eq!(WindowEnd::Expired, end.unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_window_ended() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let guard = open(
+            &adapter,
+            &peer,
+            POWERED,
+            Discoverable::General,
+            Duration::from_secs(60),
+            &[0x01, 0x3C, 0x00],
+        )
+        .await;
+        assert!(!guard.previous());
+
+        let restore = (
+            command(0x06, &[0x00, 0x00, 0x00]),
+            complete(0x06, &POWERED.to_le_bytes()),
+        );
+        let (end, _) = tokio::join!(guard.end(), kernel(&peer, vec![restore]));
+        assert_eq!(WindowEnd::Ended, end.unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_window_powered_off() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let guard = open(
+            &adapter,
+            &peer,
+            POWERED,
+            Discoverable::Limited,
+            Duration::from_secs(60),
+            &[0x02, 0x3C, 0x00],
+        )
+        .await;
+
+        let kernel = async {
+            // other controller
+            let mut other = new_settings(0);
+            other[2] = 0x01;
+            peer.send(&other).await.unwrap();
+            peer.send(&new_settings(0)).await.unwrap();
+        };
+        let (end, _) = tokio::join!(guard.closed(), kernel);
+        assert_eq!(WindowEnd::PoweredOff, end.unwrap());
+        // nothing to restore.
+        let mut buf = [0; 64];
+        tokio::task::yield_now().await;
+        assert_eq!(
+            std::io::ErrorKind::WouldBlock,
+            peer.try_recv(&mut buf).unwrap_err().kind()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_window_dropped() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let guard = open(
+            &adapter,
+            &peer,
+            POWERED,
+            Discoverable::Limited,
+            Duration::from_secs(60),
+            &[0x02, 0x3C, 0x00],
+        )
+        .await;
+        drop(guard);
+        let restore = (
+            command(0x06, &[0x00, 0x00, 0x00]),
+            complete(0x06, &POWERED.to_le_bytes()),
+        );
+        kernel(&peer, vec![restore]).await;
+    }
+
+    #[tokio::test]
+    async fn test_window_invalid() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        for &(mode, duration) in &[
+            (Discoverable::Limited, Duration::from_secs(0)),
+            (Discoverable::Limited, Duration::from_secs(65536)),
+            (Discoverable::Disable, Duration::from_secs(10)),
+        ] {
+            let err = adapter.discoverable_window(mode, duration).await;
+            assert!(matches!(
+                err.unwrap_err(),
+                Error::InvalidDiscoverableWindow { .. }
+            ));
+        }
+        let mut buf = [0; 64];
+        assert_eq!(
+            std::io::ErrorKind::WouldBlock,
+            peer.try_recv(&mut buf).unwrap_err().kind()
+        );
+    }
+}
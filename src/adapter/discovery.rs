@@ -0,0 +1,573 @@
+//! Idempotent discovery helpers.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures_util::future::FutureExt;
+use futures_util::lock::{Mutex, MutexGuard};
+use futures_util::stream::StreamExt;
+
+use super::Adapter;
+use crate::client::{Error, EventSubscribe, Result};
+use crate::command;
+use crate::event::{self, Event};
+use crate::packet::{AddressType, AddressTypes, ControllerIndex, ErrorCode};
+
+/// Outcome of [`Adapter::ensure_discovering`] / [`Adapter::ensure_not_discovering`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiscoveryOutcome {
+    /// Command sent and succeeded.
+    Performed,
+
+    /// Already in the requested state. Nothing changed.
+    Skipped,
+
+    /// Discovery for other address types is running. (started by someone else)
+    Conflict(AddressTypes),
+}
+
+/// Discovery state per transport, from Discovering events.
+///
+/// With interleaved discovery, the kernel may report transitions of BR/EDR and LE
+/// separately.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiscoveryState {
+    running: AddressTypes,
+}
+
+impl DiscoveryState {
+    /// Address types of running discovery.
+    pub fn address_types(&self) -> &AddressTypes {
+        &self.running
+    }
+
+    pub fn discovering_bredr(&self) -> bool {
+        self.running.iter().any(|ty| ty == &AddressType::BrEdr)
+    }
+
+    pub fn discovering_le(&self) -> bool {
+        self.running
+            .iter()
+            .any(|ty| matches!(ty, AddressType::LePublic | AddressType::LeRandom))
+    }
+
+    /// true if discovering on any transport.
+    pub fn discovering(&self) -> bool {
+        self.running.iter().next().is_some()
+    }
+
+    /// true if discovering for any of `address_types`.
+    fn running_any(&self, address_types: &AddressTypes) -> bool {
+        address_types
+            .iter()
+            .any(|ty| self.running.iter().any(|running| running == ty))
+    }
+
+    pub(super) fn apply(&mut self, discovering: &event::Discovering) {
+        if *discovering.discovering() {
+            self.start(discovering.address_type());
+        } else {
+            self.stop(discovering.address_type());
+        }
+    }
+
+    pub(super) fn start(&mut self, address_types: &AddressTypes) {
+        self.running.extend(address_types.iter().cloned());
+    }
+
+    pub(super) fn stop(&mut self, address_types: &AddressTypes) {
+        self.running = self
+            .running
+            .iter()
+            .filter(|ty| !address_types.iter().any(|t| &t == ty))
+            .cloned()
+            .collect();
+    }
+}
+
+/// Discovering state tracked from Discovering events.
+pub(crate) struct Tracker {
+    events: EventSubscribe,
+    state: Option<DiscoveryState>,
+}
+
+impl Tracker {
+    /// Consume received events.
+    fn update(&mut self, index: &ControllerIndex) -> Option<&DiscoveryState> {
+        while let Some(item) = self.events.next().now_or_never() {
+            match item {
+                Some((i, Event::Discovering(discovering))) if &i == index => {
+                    self.state
+                        .get_or_insert_with(Default::default)
+                        .apply(&discovering);
+                }
+                Some(..) => {}
+                None => break,
+            }
+        }
+        self.state.as_ref()
+    }
+}
+
+/// Trackers of a client by controller, shared by its [`Adapter`]s.
+#[derive(Default)]
+pub(crate) struct Trackers(std::sync::Mutex<HashMap<ControllerIndex, Arc<Mutex<Option<Tracker>>>>>);
+
+impl Trackers {
+    pub(crate) fn get(&self, index: &ControllerIndex) -> Arc<Mutex<Option<Tracker>>> {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(index.clone())
+            .or_default()
+            .clone()
+    }
+}
+
+impl Adapter {
+    async fn discovery_tracker(&self) -> MutexGuard<'_, Option<Tracker>> {
+        let mut tracker = self.discovery.lock().await;
+        if tracker.is_none() {
+            let index = self.index.clone();
+            // Subscribe before probing, not to miss transitions.
+            let events = self
+                .client
+                .events_filtered(move |i, event| {
+                    i == &index && matches!(event, Event::Discovering(..))
+                })
+                .await;
+            let state = self.probe_discovery().await;
+            *tracker = Some(Tracker { events, state });
+        }
+        tracker
+    }
+
+    /// Start Discovery without address types. The kernel checks busy before the types,
+    /// so nothing starts. `None` if running (for unknown types) or not answered.
+    async fn probe_discovery(&self) -> Option<DiscoveryState> {
+        match self
+            .call(command::StartDiscovery::new(AddressTypes::default()))
+            .await
+        {
+            Err(err)
+                if matches!(
+                    err.status(),
+                    Some(ErrorCode::InvalidParameters | ErrorCode::NotPowered)
+                ) =>
+            {
+                Some(DiscoveryState::default())
+            }
+            Err(err) if err.status() == Some(&ErrorCode::Busy) => None,
+            result => {
+                log::warn!("failed to probe discovery: {:?}", result);
+                None
+            }
+        }
+    }
+
+    /// Start discovery unless already discovering.
+    ///
+    /// `Busy` reply is treated as success if the discovery for same address types is running,
+    /// and returned as the error while the running types are unknown.
+    pub async fn ensure_discovering(
+        &self,
+        address_types: AddressTypes,
+    ) -> Result<DiscoveryOutcome> {
+        self.check_primary()?;
+        let mut tracker = self.discovery_tracker().await;
+        let tracker = tracker.as_mut().unwrap();
+
+        match tracker.update(&self.index) {
+            Some(state) if state.discovering() => {
+                return Ok(outcome_for(state.address_types(), &address_types));
+            }
+            _ => {}
+        }
+
+        match self
+            .call(command::StartDiscovery::new(address_types.clone()))
+            .await
+        {
+            Ok(..) => Ok(DiscoveryOutcome::Performed),
+            Err(err) if err.status() == Some(&ErrorCode::Busy) => {
+                match tracker.update(&self.index) {
+                    Some(state) if state.discovering() => {
+                        Ok(outcome_for(state.address_types(), &address_types))
+                    }
+                    _ => Err(err),
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Stop discovery unless not discovering.
+    ///
+    /// `Rejected` reply (not discovering) is treated as success.
+    pub async fn ensure_not_discovering(&self) -> Result<DiscoveryOutcome> {
+        self.check_primary()?;
+        let mut tracker = self.discovery_tracker().await;
+        let tracker = tracker.as_mut().unwrap();
+
+        let address_types = match tracker.update(&self.index) {
+            Some(state) if !state.discovering() => return Ok(DiscoveryOutcome::Skipped),
+            Some(state) => state.address_types().clone(),
+            None => {
+                let mut all = AddressTypes::default();
+                all.extend([
+                    AddressType::BrEdr,
+                    AddressType::LePublic,
+                    AddressType::LeRandom,
+                ]);
+                all
+            }
+        };
+
+        match self.call(command::StopDiscovery::new(address_types)).await {
+            Ok(..) => Ok(DiscoveryOutcome::Performed),
+            Err(err) if err.status() == Some(&ErrorCode::Rejected) => Ok(DiscoveryOutcome::Skipped),
+            Err(err) if err.status() == Some(&ErrorCode::InvalidParameters) => {
+                match tracker.update(&self.index) {
+                    Some(state) if state.discovering() => {
+                        Ok(DiscoveryOutcome::Conflict(state.address_types().clone()))
+                    }
+                    _ => Err(err),
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Discovery state from events received so far. `None` while unknown.
+    ///
+    /// Tracking starts with the first call of this or the discovery helpers on any
+    /// [`Adapter`] of the client for the controller, seeded by a probe to the kernel.
+    /// Unknown if discovery was running then, until the next Discovering event.
+    pub async fn discovery_state(&self) -> Option<DiscoveryState> {
+        let mut tracker = self.discovery_tracker().await;
+        tracker.as_mut().unwrap().update(&self.index).cloned()
+    }
+
+    /// Wait until discovery for any of `address_types` stops. (e.g. LE only)
+    ///
+    /// Returns immediately unless the discovery is tracked running.
+    pub async fn wait_discovery_stopped(
+        &self,
+        address_types: &AddressTypes,
+    ) -> Result<DiscoveryState> {
+        let (mut state, mut events) = {
+            let mut tracker = self.discovery_tracker().await;
+            let state = tracker.as_mut().unwrap().update(&self.index).cloned();
+            // Subscribe before releasing the tracker, not to miss transitions.
+            (state.unwrap_or_default(), self.client.events().await)
+        };
+        while state.running_any(address_types) {
+            match events.next().await {
+                Some((i, Event::Discovering(discovering))) if i == self.index => {
+                    state.apply(&discovering)
+                }
+                Some(..) => {}
+                None => return Err(Error::Closed),
+            }
+        }
+        Ok(state)
+    }
+}
+
+fn outcome_for(running: &AddressTypes, requested: &AddressTypes) -> DiscoveryOutcome {
+    if running == requested {
+        DiscoveryOutcome::Skipped
+    } else {
+        DiscoveryOutcome::Conflict(running.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use tokio::net::UnixDatagram;
+
+    use super::*;
+    use crate::role::Role;
+    use crate::{Client, Error};
+
+    fn le() -> AddressTypes {
+        let mut types = AddressTypes::default();
+        types.extend([AddressType::LePublic, AddressType::LeRandom]);
+        types
+    }
+
+    async fn kernel(peer: &UnixDatagram, expect: &[u8], replies: &[&[u8]]) {
+        let mut buf = [0; 1024];
+        let n = peer.recv(&mut buf).await.unwrap();
+        assert_eq!(expect, &buf[..n]);
+        for reply in replies {
+            peer.send(reply).await.unwrap();
+        }
+    }
+
+    fn assert_no_packet(peer: &UnixDatagram) {
+        let mut buf = [0; 1024];
+        let err = peer.try_recv(&mut buf).unwrap_err();
+        assert_eq!(io::ErrorKind::WouldBlock, err.kind());
+    }
+
+    const START_LE: &[u8] = &[0x23, 0x00, 0x00, 0x00, 0x01, 0x00, 0x06];
+    const PROBE: &[u8] = &[0x23, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00];
+
+    /// Answer the probe of a new tracker.
+    async fn probe(peer: &UnixDatagram, running: bool) {
+        let status = if running { 0x0A } else { 0x0D }; // busy / invalid parameters
+        let reply = [0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x23, 0x00, status, 0x00];
+        kernel(peer, PROBE, &[&reply]).await;
+    }
+
+    #[tokio::test]
+    async fn test_ensure_discovering_performed() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let (outcome, _) = tokio::join!(adapter.ensure_discovering(le()), async {
+            probe(&peer, false).await;
+            kernel(
+                &peer,
+                START_LE,
+                &[&[0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x23, 0x00, 0x00, 0x06]],
+            )
+            .await
+        });
+        assert_eq!(DiscoveryOutcome::Performed, outcome.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_ensure_discovering_skipped() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let (outcome, _) = tokio::join!(adapter.ensure_discovering(le()), async {
+            probe(&peer, true).await;
+            kernel(
+                &peer,
+                START_LE,
+                &[
+                    &[0x13, 0x00, 0x00, 0x00, 0x02, 0x00, 0x06, 0x01], // discovering
+                    &[0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x23, 0x00, 0x0A], // busy
+                ],
+            )
+            .await
+        });
+        assert_eq!(DiscoveryOutcome::Skipped, outcome.unwrap());
+
+        // tracked. no command sent.
+        let outcome = adapter.ensure_discovering(le()).await.unwrap();
+        assert_eq!(DiscoveryOutcome::Skipped, outcome);
+        assert_no_packet(&peer);
+    }
+
+    #[tokio::test]
+    async fn test_ensure_discovering_conflict() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let (outcome, _) = tokio::join!(adapter.ensure_discovering(le()), async {
+            probe(&peer, true).await;
+            kernel(
+                &peer,
+                START_LE,
+                &[
+                    &[0x13, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x01], // discovering (BR/EDR)
+                    &[0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x23, 0x00, 0x0A], // busy
+                ],
+            )
+            .await
+        });
+        let mut bredr = AddressTypes::default();
+        bredr.extend([AddressType::BrEdr]);
+        assert_eq!(DiscoveryOutcome::Conflict(bredr), outcome.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_ensure_discovering_busy_unknown() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        // running before tracked, and no Discovering event since.
+        let (outcome, _) = tokio::join!(adapter.ensure_discovering(le()), async {
+            probe(&peer, true).await;
+            kernel(
+                &peer,
+                START_LE,
+                &[&[0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x23, 0x00, 0x0A]], // busy
+            )
+            .await
+        });
+        let err = outcome.unwrap_err();
+        assert_eq!(Some(&ErrorCode::Busy), err.status());
+        assert_eq!(None, adapter.discovery_state().await);
+    }
+
+    #[tokio::test]
+    async fn test_tracker_shared_by_adapters() {
+        let (client, peer) = Client::pair();
+
+        let first = client.adapter(0);
+        let (outcome, _) = tokio::join!(first.ensure_discovering(le()), async {
+            probe(&peer, false).await;
+            kernel(
+                &peer,
+                START_LE,
+                &[
+                    &[0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x23, 0x00, 0x00, 0x06],
+                    &discovering(0x06, true),
+                ],
+            )
+            .await
+        });
+        assert_eq!(DiscoveryOutcome::Performed, outcome.unwrap());
+
+        // another handle: neither probed nor started again.
+        let adapter = client.adapter(0);
+        state_until(&adapter, DiscoveryState::discovering).await;
+        let outcome = adapter.ensure_discovering(le()).await.unwrap();
+        assert_eq!(DiscoveryOutcome::Skipped, outcome);
+        assert_no_packet(&peer);
+
+        // other controllers are tracked apart.
+        let mut probe1 = PROBE.to_vec();
+        probe1[2] = 0x01;
+        let reply = [0x01, 0x00, 0x01, 0x00, 0x04, 0x00, 0x23, 0x00, 0x0D, 0x00];
+        let other = client.adapter(1);
+        let replies: [&[u8]; 1] = [&reply];
+        let (state, _) = tokio::join!(other.discovery_state(), kernel(&peer, &probe1, &replies));
+        assert_eq!(Some(DiscoveryState::default()), state);
+    }
+
+    #[tokio::test]
+    async fn test_ensure_discovering_requires_primary() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0).require_primary();
+
+        let err = adapter.ensure_discovering(le()).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::RoleRequired {
+                role: Role::Primary,
+                ..
+            }
+        ));
+        let err = adapter.ensure_not_discovering().await.unwrap_err();
+        assert!(matches!(err, Error::RoleRequired { .. }));
+        assert_no_packet(&peer);
+
+        // read-only calls always work.
+        let (info, _) = crate::packet::fixtures::controller_information_reply();
+        let replies: [&[u8]; 1] = [&info];
+        let (reply, _) = tokio::join!(
+            adapter.call(command::ReadControllerInformation),
+            kernel(&peer, &[0x04, 0x00, 0x00, 0x00, 0x00, 0x00], &replies),
+        );
+        reply.unwrap();
+
+        let dir = std::env::temp_dir().join(format!("btmgmt-adapter-{}", std::process::id()));
+        let role = client.acquire_role_in(&dir, Role::Primary, 0).unwrap();
+        let (outcome, _) = tokio::join!(adapter.ensure_discovering(le()), async {
+            probe(&peer, false).await;
+            kernel(
+                &peer,
+                START_LE,
+                &[&[0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x23, 0x00, 0x00, 0x06]],
+            )
+            .await
+        });
+        assert_eq!(DiscoveryOutcome::Performed, outcome.unwrap());
+        drop(role);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_ensure_not_discovering_skipped() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        // probed not running.
+        let (outcome, _) = tokio::join!(adapter.ensure_not_discovering(), probe(&peer, false));
+        assert_eq!(DiscoveryOutcome::Skipped, outcome.unwrap());
+        assert_no_packet(&peer);
+    }
+
+    #[tokio::test]
+    async fn test_ensure_not_discovering_rejected() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        // probed running, but stopped before Stop Discovery.
+        let (outcome, _) = tokio::join!(adapter.ensure_not_discovering(), async {
+            probe(&peer, true).await;
+            kernel(
+                &peer,
+                &[0x24, 0x00, 0x00, 0x00, 0x01, 0x00, 0x07],
+                &[&[0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x24, 0x00, 0x0B]], // rejected
+            )
+            .await
+        });
+        assert_eq!(DiscoveryOutcome::Skipped, outcome.unwrap());
+    }
+
+    fn discovering(mask: u8, on: bool) -> Vec<u8> {
+        vec![0x13, 0x00, 0x00, 0x00, 0x02, 0x00, mask, on.into()]
+    }
+
+    /// State once `f` holds, polling until the socket is read.
+    async fn state_until(adapter: &Adapter, f: fn(&DiscoveryState) -> bool) -> DiscoveryState {
+        for _ in 0..100 {
+            match adapter.discovery_state().await {
+                Some(state) if f(&state) => return state,
+                _ => tokio::time::sleep(std::time::Duration::from_millis(1)).await,
+            }
+        }
+        panic!("not received");
+    }
+
+    #[tokio::test]
+    async fn test_discovery_state_per_transport() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+        let (state, _) = tokio::join!(adapter.discovery_state(), probe(&peer, false));
+        assert_eq!(Some(DiscoveryState::default()), state);
+
+        // interleaved, started at once.
+        peer.send(&discovering(0x07, true)).await.unwrap();
+        let state = state_until(&adapter, DiscoveryState::discovering).await;
+        assert!(state.discovering_bredr());
+        assert!(state.discovering_le());
+        assert!(state.discovering());
+
+        // BR/EDR phase ended.
+        peer.send(&discovering(0x01, false)).await.unwrap();
+        let bredr = vec![AddressType::BrEdr]
+            .into_iter()
+            .collect::<AddressTypes>();
+        let state = adapter.wait_discovery_stopped(&bredr).await.unwrap();
+        assert!(!state.discovering_bredr());
+        assert!(state.discovering_le());
+        assert_eq!(&le(), state.address_types());
+
+        // LE still running: wait for it.
+        let le = le();
+        let (state, _) = tokio::join!(adapter.wait_discovery_stopped(&le), async {
+            tokio::task::yield_now().await;
+            // other controller
+            let mut other = discovering(0x06, false);
+            other[2] = 0x01;
+            peer.send(&other).await.unwrap();
+            peer.send(&discovering(0x06, false)).await.unwrap();
+        });
+        let state = state.unwrap();
+        assert!(!state.discovering());
+
+        let state = adapter.discovery_state().await.unwrap();
+        assert!(!state.discovering());
+        // nothing running: no wait.
+        adapter.wait_discovery_stopped(&le).await.unwrap();
+    }
+}
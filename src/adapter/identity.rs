@@ -0,0 +1,356 @@
+//! Local name and appearance applied together.
+use super::advertising::{Allocator, Owned};
+use super::Adapter;
+use crate::client::{Error, Result};
+use crate::command;
+use crate::packet::advertising::{AdStructures, AD_TYPE_APPEARANCE, AD_TYPE_COMPLETE_NAME};
+use crate::packet::{AdvertisingFlag, Name, ShortName};
+
+/// Outcome of [`Adapter::set_identity`].
+#[derive(Debug, Clone)]
+pub struct IdentityOutcome {
+    previous_name: Name,
+    paused: Vec<u8>,
+}
+
+impl IdentityOutcome {
+    /// Local name before the change.
+    pub fn previous_name(&self) -> &Name {
+        &self.previous_name
+    }
+
+    /// Owned advertising instances removed during the change, and added again.
+    pub fn paused(&self) -> &[u8] {
+        &self.paused
+    }
+}
+
+impl Adapter {
+    /// Set the local name and the appearance, so that advertising never shows a mix of
+    /// old and new values.
+    ///
+    /// Owned advertising instances (see [`Adapter::add_advertising_auto`]) with the name or
+    /// the appearance appended by the kernel are removed during the change, and added
+    /// again afterwards. Instances of other processes are not touched.
+    /// Both values are read back from Read Extended Controller Information, returning
+    /// [`Error::Unexpected`] if not applied. On failure, the previous name is restored.
+    /// The short name is kept.
+    pub async fn set_identity(&self, name: &str, appearance: u16) -> Result<IdentityOutcome> {
+        self.check_primary()?;
+        let name = Name::new(name).map_err(Error::InvalidName)?;
+
+        // Hold the allocator, not to add or remove instances meanwhile.
+        let mut allocator = self.advertising.lock().await;
+        let info = self.call(command::ReadControllerInformation).await?;
+        let previous_name = info.name().clone();
+        let short_name = info.short_name().clone();
+
+        let mut paused = vec![];
+        let mut result = Ok(());
+        if let Some(allocator) = allocator.as_mut() {
+            result = self.pause_advertising(allocator, &mut paused).await;
+        }
+        if result.is_ok() {
+            result = self
+                .apply_identity(&previous_name, short_name, name, appearance)
+                .await;
+        }
+        if let Some(allocator) = allocator.as_mut() {
+            let resumed = self.resume_advertising(allocator, &paused).await;
+            result = result.and(resumed);
+        }
+        result?;
+
+        Ok(IdentityOutcome {
+            previous_name,
+            paused: paused.iter().map(|(instance, _)| *instance).collect(),
+        })
+    }
+
+    async fn pause_advertising(
+        &self,
+        allocator: &mut Allocator,
+        paused: &mut Vec<(u8, Owned)>,
+    ) -> Result<()> {
+        allocator.update(&self.index);
+        let appended =
+            AdvertisingFlag::AddLocalNameInScanResp | AdvertisingFlag::AddAppearanceFieldToScanResp;
+        let targets = allocator
+            .owned
+            .iter()
+            .filter(|(_, owned)| owned.flags.intersects(appended))
+            .map(|(instance, owned)| (*instance, owned.clone()))
+            .collect::<Vec<_>>();
+        for (instance, owned) in targets {
+            self.call(command::RemoveAdvertising::new(instance.into()))
+                .await?;
+            allocator.used.remove(&instance);
+            allocator.owned.remove(&instance);
+            paused.push((instance, owned));
+        }
+        Ok(())
+    }
+
+    /// Add paused instances again. Failed ones are forgotten, returning the first error.
+    async fn resume_advertising(
+        &self,
+        allocator: &mut Allocator,
+        paused: &[(u8, Owned)],
+    ) -> Result<()> {
+        allocator.update(&self.index);
+        let mut result = Ok(());
+        for (instance, owned) in paused {
+            if allocator.used.contains(instance) {
+                log::warn!("advertising {} taken while paused", instance);
+                result = result.and(Err(Error::Unexpected(format!(
+                    "advertising instance {} taken while paused",
+                    instance
+                ))));
+                continue;
+            }
+            let command = owned.params.clone().build()?;
+            match self.call(command).await {
+                Ok(..) => {
+                    allocator.used.insert(*instance);
+                    allocator.owned.insert(*instance, owned.clone());
+                }
+                Err(err) => {
+                    log::warn!("failed to add advertising {} again: {}", instance, err);
+                    result = result.and(Err(err));
+                }
+            }
+        }
+        result
+    }
+
+    /// Set both, restoring the previous name on failure.
+    async fn apply_identity(
+        &self,
+        previous_name: &Name,
+        short_name: ShortName,
+        name: Name,
+        appearance: u16,
+    ) -> Result<()> {
+        self.call(command::SetLocalName::new(name.clone(), short_name.clone()))
+            .await?;
+
+        let result = async {
+            self.call(command::SetApperance::new(appearance)).await?;
+            self.verify_identity(&name, appearance).await
+        }
+        .await;
+        if result.is_err() {
+            let restore = command::SetLocalName::new(previous_name.clone(), short_name);
+            if let Err(err) = self.call(restore).await {
+                log::warn!("failed to restore local name: {}", err);
+            }
+        }
+        result
+    }
+
+    async fn verify_identity(&self, name: &Name, appearance: u16) -> Result<()> {
+        let info = self
+            .call(command::ReadExtendedControllerInformation)
+            .await?;
+        let (mut current_name, mut current_appearance) = (None, None);
+        for item in AdStructures::new(info.eir_data()) {
+            match item? {
+                (AD_TYPE_COMPLETE_NAME, data) => current_name = Some(String::from_utf8_lossy(data)),
+                (AD_TYPE_APPEARANCE, &[lo, hi]) => {
+                    current_appearance = Some(u16::from_le_bytes([lo, hi]))
+                }
+                _ => {}
+            }
+        }
+
+        let expected = name.to_string_lossy();
+        if current_name.as_deref() != Some(&expected) || current_appearance != Some(appearance) {
+            return Err(Error::Unexpected(format!(
+                "identity {:?} / {:#06x} not applied: {:?} / {:?}",
+                expected, appearance, current_name, current_appearance
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::UnixDatagram;
+
+    use super::*;
+    use crate::adapter::AdvertisingHandle;
+    use crate::frames::{command, complete, recv, status};
+    use crate::packet::advertising::AdvertisingParams;
+    use crate::packet::ErrorCode;
+    use crate::Client;
+
+    /// Set Local Name parameters, also the reply.
+    fn local_name(name: &[u8]) -> Vec<u8> {
+        let mut b = vec![0; 249 + 11];
+        b[..name.len()].copy_from_slice(name);
+        b[249..251].copy_from_slice(b"bt");
+        b
+    }
+
+    /// Read Extended Controller Information reply.
+    fn extended_information(name: &[u8], appearance: u16) -> Vec<u8> {
+        let mut eir = vec![0x03, 0x19];
+        eir.extend(appearance.to_le_bytes());
+        eir.extend([name.len() as u8 + 1, 0x09]);
+        eir.extend(name);
+
+        let mut b = vec![0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x09, 0x02, 0x00];
+        b.extend([0xFF, 0xFF, 0x01, 0x00, 0x01, 0x02, 0x00, 0x00]);
+        b.extend((eir.len() as u16).to_le_bytes());
+        b.extend(eir);
+        complete(0x42, &b)
+    }
+
+    fn advertising(add: &[u8]) -> bool {
+        add[..7] == [0x3E, 0x00, 0x00, 0x00, 0x0B, 0x00, 0x01]
+    }
+
+    /// [`crate::frames::kernel`], but Add Advertising is checked by its instance only.
+    async fn kernel(peer: &UnixDatagram, exchanges: Vec<(Vec<u8>, Vec<u8>)>) {
+        for (request, reply) in exchanges {
+            let sent = recv(peer).await;
+            if request[0] == 0x3E {
+                assert!(advertising(&sent), "{:02x?}", sent);
+            } else {
+                assert_eq!(request, sent);
+            }
+            peer.send(&reply).await.unwrap();
+        }
+    }
+
+    fn nothing_sent(peer: &UnixDatagram) {
+        let mut buf = [0; 64];
+        assert_eq!(
+            std::io::ErrorKind::WouldBlock,
+            peer.try_recv(&mut buf).unwrap_err().kind()
+        );
+    }
+
+    /// Add instance 1 with the local name appended by the kernel.
+    async fn add_advertising(adapter: &Adapter, peer: &UnixDatagram) -> AdvertisingHandle {
+        let features = [0xFF, 0x03, 0x00, 0x00, 0x1F, 0x1F, 0x02, 0x00];
+        let exchanges = vec![
+            (command(0x3D, &[]), complete(0x3D, &features)),
+            (command(0x3E, &[]), complete(0x3E, &[0x01])),
+        ];
+        let params = AdvertisingParams::new(0).kernel_appends_local_name(true);
+        let (handle, _) = tokio::join!(
+            adapter.add_advertising_auto(params),
+            kernel(peer, exchanges)
+        );
+        handle.unwrap()
+    }
+
+    fn information() -> Vec<u8> {
+        crate::packet::fixtures::controller_information_reply().0
+    }
+
+    #[tokio::test]
+    async fn test_set_identity() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+        let handle = add_advertising(&adapter, &peer).await;
+
+        let exchanges = vec![
+            (command(0x04, &[]), information()),
+            (command(0x3F, &[0x01]), complete(0x3F, &[0x01])),
+            (
+                command(0x0F, &local_name(b"sensor")),
+                complete(0x0F, &local_name(b"sensor")),
+            ),
+            (command(0x43, &[0x41, 0x03]), complete(0x43, &[])),
+            (command(0x42, &[]), extended_information(b"sensor", 0x0341)),
+            (command(0x3E, &[]), complete(0x3E, &[0x01])),
+        ];
+        let (outcome, _) = tokio::join!(
+            adapter.set_identity("sensor", 0x0341),
+            kernel(&peer, exchanges)
+        );
+        let outcome = outcome.unwrap();
+        assert_eq!("btmgmt", outcome.previous_name().to_string_lossy());
+        assert_eq!(&[1], outcome.paused());
+        nothing_sent(&peer);
+
+        // still owned by the handle.
+        let (removed, _) = tokio::join!(
+            handle.remove(),
+            kernel(
+                &peer,
+                vec![(command(0x3F, &[0x01]), complete(0x3F, &[0x01]))]
+            )
+        );
+        removed.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_identity_appearance_failed() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+        let _handle = add_advertising(&adapter, &peer).await;
+
+        let exchanges = vec![
+            (command(0x04, &[]), information()),
+            (command(0x3F, &[0x01]), complete(0x3F, &[0x01])),
+            (
+                command(0x0F, &local_name(b"sensor")),
+                complete(0x0F, &local_name(b"sensor")),
+            ),
+            (command(0x43, &[0x41, 0x03]), status(0x43, 0x0B)),
+            // previous name restored, then advertising.
+            (
+                command(0x0F, &local_name(b"btmgmt")),
+                complete(0x0F, &local_name(b"btmgmt")),
+            ),
+            (command(0x3E, &[]), complete(0x3E, &[0x01])),
+        ];
+        let (err, _) = tokio::join!(
+            adapter.set_identity("sensor", 0x0341),
+            kernel(&peer, exchanges)
+        );
+        assert!(matches!(
+            err.unwrap_err(),
+            Error::CommandStatus {
+                status: ErrorCode::Rejected,
+                ..
+            }
+        ));
+        nothing_sent(&peer);
+    }
+
+    #[tokio::test]
+    async fn test_set_identity_not_applied() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let exchanges = vec![
+            (command(0x04, &[]), information()),
+            (
+                command(0x0F, &local_name(b"sensor")),
+                complete(0x0F, &local_name(b"sensor")),
+            ),
+            (command(0x43, &[0x41, 0x03]), complete(0x43, &[])),
+            // name reverted by someone.
+            (command(0x42, &[]), extended_information(b"btmgmt", 0x0341)),
+            (
+                command(0x0F, &local_name(b"btmgmt")),
+                complete(0x0F, &local_name(b"btmgmt")),
+            ),
+        ];
+        let (err, _) = tokio::join!(
+            adapter.set_identity("sensor", 0x0341),
+            kernel(&peer, exchanges)
+        );
+        assert!(matches!(err.unwrap_err(), Error::Unexpected(..)));
+        nothing_sent(&peer);
+
+        let err = adapter.set_identity(&"x".repeat(249), 0x0341).await;
+        assert!(matches!(err.unwrap_err(), Error::InvalidName(..)));
+        nothing_sent(&peer);
+    }
+}
@@ -0,0 +1,162 @@
+//! Loading key and parameter lists.
+//!
+//! Each Load command replaces the state in the kernel (`net/bluetooth/mgmt.c`):
+//! Load Link Keys and Load Long Term Keys clear all the stored keys first, and
+//! Load Connection Parameters clears the parameters not used for auto connection.
+//! Splitting a list over several calls would leave only the last part loaded, and
+//! no command adds a single key. Lists are limited by the 16 bit parameter length,
+//! so lists not fitting in one frame are refused instead.
+use super::Adapter;
+use crate::client::{Error, Result};
+use crate::command::{self, CommandCode};
+use crate::packet::{ConnectionParameter, LinkKey, LongTermKey};
+
+/// Max keys of Load Link Keys. (debug keys and count, and 25 bytes each)
+pub const MAX_LINK_KEYS: usize = (u16::MAX as usize - 3) / 25;
+
+/// Max keys of Load Long Term Keys. (count, and 36 bytes each)
+pub const MAX_LONG_TERM_KEYS: usize = (u16::MAX as usize - 2) / 36;
+
+/// Max parameters of Load Connection Parameters. (count, and 15 bytes each)
+pub const MAX_CONNECTION_PARAMETERS: usize = (u16::MAX as usize - 2) / 15;
+
+fn check_count(command: CommandCode, count: usize, max: usize) -> Result<()> {
+    if count > max {
+        return Err(Error::TooManyItems {
+            command,
+            count,
+            max,
+        });
+    }
+    Ok(())
+}
+
+impl Adapter {
+    /// Replace the stored link keys by `keys`.
+    ///
+    /// Returns [`Error::TooManyItems`] for more than [`MAX_LINK_KEYS`] keys, without
+    /// sending anything.
+    pub async fn load_link_keys(&self, debug_keys: bool, keys: Vec<LinkKey>) -> Result<()> {
+        check_count(CommandCode::LoadLinkKeys, keys.len(), MAX_LINK_KEYS)?;
+        self.call_mutating(command::LoadLinkKeys::new(debug_keys, keys))
+            .await?;
+        Ok(())
+    }
+
+    /// Replace the stored long term keys by `keys`.
+    ///
+    /// Returns [`Error::TooManyItems`] for more than [`MAX_LONG_TERM_KEYS`] keys, without
+    /// sending anything.
+    pub async fn load_long_term_keys(&self, keys: Vec<LongTermKey>) -> Result<()> {
+        check_count(CommandCode::LoadLongTermKey, keys.len(), MAX_LONG_TERM_KEYS)?;
+        self.call_mutating(command::LoadLongTermKey::new(keys))
+            .await?;
+        Ok(())
+    }
+
+    /// Replace the connection parameters by `params`. The kernel keeps the ones of
+    /// devices added for auto connection.
+    ///
+    /// Returns [`Error::TooManyItems`] for more than [`MAX_CONNECTION_PARAMETERS`]
+    /// parameters, without sending anything.
+    pub async fn load_connection_parameters(&self, params: Vec<ConnectionParameter>) -> Result<()> {
+        check_count(
+            CommandCode::LoadConnectionParameters,
+            params.len(),
+            MAX_CONNECTION_PARAMETERS,
+        )?;
+        self.call_mutating(command::LoadConnectionParameters::new(params))
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::pack::Pack;
+    use crate::packet::{BdAddr, LinkKeyType, LongTermKeyBuilder, LongTermKeyType};
+    use crate::Client;
+
+    fn long_term_key(n: u16) -> LongTermKey {
+        let [lo, hi] = n.to_le_bytes();
+        LongTermKeyBuilder::default()
+            .address(BdAddr::from([lo, hi, 0x33, 0x22, 0x11, 0x00]).to_le_public_addr())
+            .key_type(LongTermKeyType::AuthenticatedP256Key)
+            .master(false)
+            .encryption_size(16)
+            .encryption_diversifier(0)
+            .random_number([0; 8])
+            .value([0x01; 16])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_max_items() {
+        let addr = BdAddr::from([0x55, 0x44, 0x33, 0x22, 0x11, 0x00]);
+        let link_key = LinkKey::new(
+            addr.clone().to_br_edr_addr(),
+            LinkKeyType::Combinationkey,
+            [0x01; 16],
+            0,
+        );
+        let params = ConnectionParameter::new(addr.to_le_public_addr(), 0x18, 0x28, 0, 0x2A);
+        // one more item overflows the 16 bit length.
+        for (one, header, max) in [
+            (link_key.pack_to_vec().len(), 3, MAX_LINK_KEYS),
+            (long_term_key(0).pack_to_vec().len(), 2, MAX_LONG_TERM_KEYS),
+            (params.pack_to_vec().len(), 2, MAX_CONNECTION_PARAMETERS),
+        ] {
+            assert!(header + one * max <= u16::MAX as usize);
+            assert!(header + one * (max + 1) > u16::MAX as usize);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_long_term_keys_too_many() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let keys = (0..=MAX_LONG_TERM_KEYS as u16).map(long_term_key).collect();
+        let err = adapter.load_long_term_keys(keys).await.unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::TooManyItems {
+                    command: CommandCode::LoadLongTermKey,
+                    count: 1821,
+                    max: 1820,
+                }
+            ),
+            "{:?}",
+            err
+        );
+        assert!(!err.is_retryable());
+
+        // nothing sent.
+        let mut buf = [0; 8];
+        assert!(peer.try_recv(&mut buf).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_long_term_keys_max() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let keys = (0..MAX_LONG_TERM_KEYS as u16).map(long_term_key).collect();
+        let (result, _) = tokio::join!(adapter.load_long_term_keys(keys), async {
+            let mut buf = vec![0; 6 + u16::MAX as usize];
+            let n = peer.recv(&mut buf).await.unwrap();
+            assert_eq!(6 + 2 + 36 * MAX_LONG_TERM_KEYS, n);
+            assert_eq!([0x13, 0x00, 0x00, 0x00], buf[..4]);
+            assert_eq!(
+                MAX_LONG_TERM_KEYS as u16,
+                u16::from_le_bytes([buf[6], buf[7]])
+            );
+            let reply = [0x01, 0x00, 0x00, 0x00, 0x03, 0x00, 0x13, 0x00, 0x00];
+            peer.send(&reply).await.unwrap();
+        });
+        result.unwrap();
+    }
+}
@@ -0,0 +1,652 @@
+//! Modal state of discovery, pairing and suspend.
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_core::stream::Stream;
+use futures_util::future::FutureExt;
+use futures_util::stream::{self, StreamExt};
+use tokio::time::{self, Instant, Interval, MissedTickBehavior};
+
+use super::discovery::DiscoveryState;
+use super::Adapter;
+use crate::client::{EventSubscribe, Result};
+use crate::command;
+use crate::event::Event;
+use crate::packet::{Address, AddressTypes, ErrorCode, IoCapability, Settings, SuspendState};
+
+/// Transitions kept until read by [`AdapterStateMachine::transitions`]. Older ones are dropped.
+pub const TRANSITION_BACKLOG: usize = 64;
+
+type Resync = Pin<Box<dyn Future<Output = Result<command::ReadControllerInformationReply>> + Send>>;
+
+/// Modal state of a controller.
+///
+/// Discovery and pairing may run at once. The state shows the one with the higher
+/// precedence: `Suspended`, `Pairing`, `Discovering`, then `Idle`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdapterState {
+    Idle,
+    Discovering { transports: AddressTypes },
+    Pairing { addr: Address },
+    Suspended,
+}
+
+/// Source of a [`Transition`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionCause {
+    /// Event from the kernel.
+    Event,
+    /// Reply for a command sent by [`AdapterStateMachine`].
+    Reply,
+    /// Read of [`AdapterStateMachine::resync`].
+    Resync,
+}
+
+/// State change record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    from: AdapterState,
+    to: AdapterState,
+    at: Instant,
+    cause: TransitionCause,
+}
+
+impl Transition {
+    pub fn from(&self) -> &AdapterState {
+        &self.from
+    }
+
+    pub fn to(&self) -> &AdapterState {
+        &self.to
+    }
+
+    /// When observed.
+    pub fn at(&self) -> Instant {
+        self.at
+    }
+
+    pub fn cause(&self) -> TransitionCause {
+        self.cause
+    }
+}
+
+/// What is known of the controller. The state is derived from these.
+#[derive(Debug, Clone, Default)]
+struct Facts {
+    suspended: bool,
+    pairing: Option<Address>,
+    discovery: DiscoveryState,
+}
+
+impl Facts {
+    fn state(&self) -> AdapterState {
+        if self.suspended {
+            AdapterState::Suspended
+        } else if let Some(addr) = &self.pairing {
+            AdapterState::Pairing { addr: addr.clone() }
+        } else if self.discovery.discovering() {
+            AdapterState::Discovering {
+                transports: self.discovery.address_types().clone(),
+            }
+        } else {
+            AdapterState::Idle
+        }
+    }
+
+    /// Powered off or removed: nothing runs.
+    fn stopped(&mut self) {
+        self.pairing = None;
+        self.discovery = Default::default();
+    }
+
+    fn pairing_started(&mut self, addr: Address) {
+        if self.pairing.is_none() {
+            self.pairing = Some(addr);
+        }
+    }
+
+    fn pairing_ended(&mut self, addr: &Address) {
+        if self.pairing.as_ref() == Some(addr) {
+            self.pairing = None;
+        }
+    }
+
+    fn apply(&mut self, event: &Event) {
+        match event {
+            Event::Discovering(discovering) => self.discovery.apply(discovering),
+            Event::ControllerSuspend(state) => {
+                self.suspended = !matches!(**state, SuspendState::Running)
+            }
+            Event::ControllerResume(..) => self.suspended = false,
+            Event::NewSettings(settings) if !settings.contains(Settings::Powered) => self.stopped(),
+            Event::IndexRemoved(..) => *self = Default::default(),
+
+            Event::PinCodeRequest(ev) => self.pairing_started(ev.address()),
+            Event::UserConfirmationRequest(ev) => self.pairing_started(ev.address()),
+            Event::UserPasskeyRequest(ev) => self.pairing_started(ev.address()),
+            Event::PasskeyNotify(ev) => self.pairing_started(ev.address()),
+
+            Event::AuthenticationFailed(ev) => self.pairing_ended(&ev.address()),
+            Event::NewLinkKey(ev) => self.pairing_ended(&ev.key().address()),
+            Event::NewLongTermKey(ev) => self.pairing_ended(&ev.key().address()),
+            Event::DeviceDisconnect(ev) => self.pairing_ended(&ev.address()),
+            Event::ConnectFailed(ev) => self.pairing_ended(&ev.address()),
+            _ => {}
+        }
+    }
+}
+
+/// Discovery, pairing and suspend state of a controller, tracked from events and replies.
+///
+/// Events missed or not sent to this client are recovered from replies of the commands
+/// sent through this, and from reads of [`AdapterStateMachine::resync`].
+/// Received events are consumed by [`AdapterStateMachine::state`] and the predicates, or
+/// by polling [`AdapterStateMachine::transitions`].
+pub struct AdapterStateMachine {
+    adapter: Adapter,
+    events: EventSubscribe,
+    facts: Facts,
+    backlog: VecDeque<Transition>,
+    interval: Option<Interval>,
+    resync: Option<Resync>,
+}
+
+impl fmt::Debug for AdapterStateMachine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdapterStateMachine")
+            .field("index", self.adapter.index())
+            .field("facts", &self.facts)
+            .finish()
+    }
+}
+
+impl AdapterStateMachine {
+    /// Resync periodically while [`AdapterStateMachine::transitions`] is polled.
+    pub fn with_resync_interval(mut self, period: Duration) -> Self {
+        let mut interval = time::interval_at(Instant::now() + period, period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Current state, after consuming received events.
+    pub fn state(&mut self) -> AdapterState {
+        self.update();
+        self.facts.state()
+    }
+
+    /// Discovery state, after consuming received events.
+    pub fn discovery_state(&mut self) -> &DiscoveryState {
+        self.update();
+        &self.facts.discovery
+    }
+
+    /// false while suspended or discovering. (Start Discovery would be `Busy`)
+    pub fn can_start_discovery(&mut self) -> bool {
+        self.update();
+        !self.facts.suspended && !self.facts.discovery.discovering()
+    }
+
+    /// false while suspended or pairing.
+    pub fn can_pair(&mut self) -> bool {
+        self.update();
+        !self.facts.suspended && self.facts.pairing.is_none()
+    }
+
+    /// Start discovery, updating the state from the reply.
+    ///
+    /// `Busy` marks `address_types` running unless discovery is already tracked.
+    pub async fn start_discovery(&mut self, address_types: AddressTypes) -> Result<()> {
+        let reply = self
+            .adapter
+            .call_mutating(command::StartDiscovery::new(address_types.clone()))
+            .await;
+        self.update();
+        self.record(TransitionCause::Reply, |facts| match &reply {
+            Ok(..) => facts.discovery.start(&address_types),
+            Err(err) if err.status() == Some(&ErrorCode::Busy) => {
+                if !facts.discovery.discovering() && !facts.suspended {
+                    facts.discovery.start(&address_types);
+                }
+            }
+            Err(err) if err.status() == Some(&ErrorCode::NotPowered) => facts.stopped(),
+            Err(..) => {}
+        });
+        reply.map(drop)
+    }
+
+    /// Stop discovery, updating the state from the reply.
+    ///
+    /// `Rejected` (not discovering) clears the discovery state.
+    pub async fn stop_discovery(&mut self, address_types: AddressTypes) -> Result<()> {
+        let reply = self
+            .adapter
+            .call_mutating(command::StopDiscovery::new(address_types.clone()))
+            .await;
+        self.update();
+        self.record(TransitionCause::Reply, |facts| match &reply {
+            Ok(..) => facts.discovery.stop(&address_types),
+            Err(err) if err.status() == Some(&ErrorCode::Rejected) => {
+                facts.discovery = Default::default()
+            }
+            Err(..) => {}
+        });
+        reply.map(drop)
+    }
+
+    /// Pair with `addr`, in `Pairing` state until the reply.
+    ///
+    /// `Busy` (pairing with `addr` in progress elsewhere) keeps the `Pairing` state.
+    pub async fn pair_device(
+        &mut self,
+        addr: Address,
+        io_capability: IoCapability,
+    ) -> Result<command::PairDeviceReply> {
+        let request = self
+            .adapter
+            .call_mutating(command::PairDevice::new(addr.clone(), io_capability));
+        self.update();
+        self.record(TransitionCause::Reply, |facts| {
+            facts.pairing_started(addr.clone())
+        });
+
+        let reply = request.await;
+        self.update();
+        self.record(TransitionCause::Reply, |facts| match &reply {
+            Err(err) if err.status() == Some(&ErrorCode::Busy) => {
+                facts.pairing = Some(addr.clone())
+            }
+            _ => facts.pairing_ended(&addr),
+        });
+        reply
+    }
+
+    /// Read the controller, clearing discovery and pairing if not powered.
+    pub async fn resync(&mut self) -> Result<()> {
+        let reply = self
+            .adapter
+            .call(command::ReadControllerInformation)
+            .await?;
+        self.update();
+        self.resynced(&reply);
+        Ok(())
+    }
+
+    /// State changes with timestamps.
+    ///
+    /// Starts with transitions not read yet (up to [`TRANSITION_BACKLOG`]).
+    /// Failed periodic resyncs are logged, and retried at the next period.
+    pub fn transitions(&mut self) -> impl Stream<Item = Transition> + '_ {
+        stream::poll_fn(move |cx| self.poll_transition(cx))
+    }
+
+    fn resynced(&mut self, reply: &command::ReadControllerInformationReply) {
+        if !reply.current_settings().contains(Settings::Powered) {
+            self.record(TransitionCause::Resync, Facts::stopped);
+        }
+    }
+
+    /// Consume received events.
+    fn update(&mut self) {
+        while let Some(Some((index, event))) = self.events.next().now_or_never() {
+            if &index == self.adapter.index() {
+                self.record(TransitionCause::Event, |facts| facts.apply(&event));
+            }
+        }
+    }
+
+    /// Change facts, and record the transition if the state changed.
+    fn record<F>(&mut self, cause: TransitionCause, f: F)
+    where
+        F: FnOnce(&mut Facts),
+    {
+        let from = self.facts.state();
+        f(&mut self.facts);
+        let to = self.facts.state();
+        if from == to {
+            return;
+        }
+
+        log::debug!(
+            "{:?}: {:?} -> {:?} ({:?})",
+            self.adapter.index(),
+            from,
+            to,
+            cause
+        );
+        if self.backlog.len() == TRANSITION_BACKLOG {
+            self.backlog.pop_front();
+        }
+        self.backlog.push_back(Transition {
+            from,
+            to,
+            at: Instant::now(),
+            cause,
+        });
+    }
+
+    fn poll_transition(&mut self, cx: &mut Context<'_>) -> Poll<Option<Transition>> {
+        loop {
+            if let Some(transition) = self.backlog.pop_front() {
+                return Poll::Ready(Some(transition));
+            }
+
+            if let Some(resync) = &mut self.resync {
+                if let Poll::Ready(reply) = resync.poll_unpin(cx) {
+                    self.resync = None;
+                    match reply {
+                        Ok(reply) => self.resynced(&reply),
+                        Err(err) => log::warn!("failed to resync: {}", err),
+                    }
+                    continue;
+                }
+            } else if let Some(interval) = &mut self.interval {
+                if interval.poll_tick(cx).is_ready() {
+                    self.resync = Some(Box::pin(
+                        self.adapter.call(command::ReadControllerInformation),
+                    ));
+                    continue;
+                }
+            }
+
+            match self.events.poll_next_unpin(cx) {
+                Poll::Ready(Some((index, event))) => {
+                    if &index == self.adapter.index() {
+                        self.record(TransitionCause::Event, |facts| facts.apply(&event));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Adapter {
+    /// Track discovery, pairing and suspend state. See [`AdapterStateMachine`].
+    ///
+    /// Discovery starts from [`Adapter::discovery_state`], others from `Idle`.
+    pub async fn state_machine(&self) -> AdapterStateMachine {
+        // Subscribe before reading, not to miss transitions.
+        let events = self.client.events().await;
+        let discovery = self.discovery_state().await.unwrap_or_default();
+        AdapterStateMachine {
+            adapter: self.clone(),
+            events,
+            facts: Facts {
+                discovery,
+                ..Default::default()
+            },
+            backlog: Default::default(),
+            interval: None,
+            resync: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter::FromIterator;
+
+    use tokio::net::UnixDatagram;
+
+    use super::*;
+    use crate::frames::{complete_with, event, recv};
+    use crate::packet::{AddressType, BdAddr};
+    use crate::Client;
+
+    const DEVICE: [u8; 6] = [0x66, 0x55, 0x44, 0x33, 0x22, 0x11];
+    const OTHER: [u8; 6] = [0x01, 0x55, 0x44, 0x33, 0x22, 0x11];
+    /// Controller Resume: wake reason, address, address type.
+    const RESUME: &[u8] = &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+
+    /// `addr` (BR/EDR) followed by `rest`.
+    fn with_addr(addr: [u8; 6], rest: &[u8]) -> Vec<u8> {
+        let mut b = addr.to_vec();
+        b.push(0x00);
+        b.extend(rest);
+        b
+    }
+
+    fn unpack(b: &[u8]) -> Event {
+        crate::event::unpack_events(&mut &b[..]).unwrap().1
+    }
+
+    fn le() -> AddressTypes {
+        AddressTypes::from_iter([AddressType::LePublic, AddressType::LeRandom])
+    }
+
+    fn device() -> Address {
+        BdAddr::from(DEVICE).to_br_edr_addr()
+    }
+
+    /// Reply the next command, checking its opcode.
+    async fn expect(peer: &UnixDatagram, opcode: u16, reply: Vec<u8>) {
+        let sent = recv(peer).await;
+        assert!(sent.len() >= 6);
+        assert_eq!(opcode.to_le_bytes(), sent[..2]);
+        peer.send(&reply).await.unwrap();
+    }
+
+    /// State machine of controller 0, answering the discovery probe as not running.
+    async fn machine(client: &Client, peer: &UnixDatagram) -> AdapterStateMachine {
+        let adapter = client.adapter(0);
+        let (machine, _) = tokio::join!(
+            adapter.state_machine(),
+            expect(peer, 0x23, complete_with(0x23, 0x0D, &[0x00])),
+        );
+        machine
+    }
+
+    /// Read Controller Information reply, not powered.
+    fn powered_off_information() -> Vec<u8> {
+        let (mut info, _) = crate::packet::fixtures::controller_information_reply();
+        // header, address, version, manufacturer, supported settings
+        info[9 + 6 + 1 + 2 + 4..][..4].copy_from_slice(&[0; 4]);
+        info
+    }
+
+    #[test]
+    fn test_transition_table() {
+        let mut link_key = vec![0x01]; // store hint
+        link_key.extend(with_addr(DEVICE, &[0x04]));
+        link_key.extend([0; 17]);
+
+        let discovering = AdapterState::Discovering { transports: le() };
+        let pairing = AdapterState::Pairing { addr: device() };
+        let table = vec![
+            (event(0x13, &[0x06, 0x01]), discovering.clone()),
+            (event(0x0F, &with_addr(DEVICE, &[0; 5])), pairing.clone()),
+            // other devices do not change the pairing.
+            (event(0x17, &with_addr(OTHER, &[0; 5])), pairing.clone()),
+            (event(0x11, &with_addr(OTHER, &[0x05])), pairing.clone()),
+            (event(0x09, &link_key), discovering.clone()),
+            (event(0x2D, &[0x01]), AdapterState::Suspended),
+            (event(0x13, &[0x06, 0x00]), AdapterState::Suspended),
+            (event(0x2E, RESUME), AdapterState::Idle),
+            (event(0x10, &with_addr(DEVICE, &[])), pairing.clone()),
+            (event(0x11, &with_addr(DEVICE, &[0x05])), AdapterState::Idle),
+            (event(0x0E, &with_addr(DEVICE, &[0x00])), pairing.clone()),
+            (event(0x13, &[0x01, 0x01]), pairing.clone()),
+            // powered off: nothing runs.
+            (event(0x06, &[0x00, 0x00, 0x00, 0x00]), AdapterState::Idle),
+            (
+                event(0x13, &[0x07, 0x01]),
+                AdapterState::Discovering {
+                    transports: AddressTypes::from_iter([
+                        AddressType::BrEdr,
+                        AddressType::LePublic,
+                        AddressType::LeRandom,
+                    ]),
+                },
+            ),
+            (
+                event(0x2D, &[0x00]),
+                AdapterState::Discovering {
+                    transports: AddressTypes::from_iter([
+                        AddressType::BrEdr,
+                        AddressType::LePublic,
+                        AddressType::LeRandom,
+                    ]),
+                },
+            ),
+            (event(0x13, &[0x01, 0x00]), discovering),
+            (event(0x05, &[]), AdapterState::Idle),
+        ];
+
+        let mut facts = Facts::default();
+        assert_eq!(AdapterState::Idle, facts.state());
+        for (n, (b, expected)) in table.into_iter().enumerate() {
+            facts.apply(&unpack(&b));
+            assert_eq!(expected, facts.state(), "#{} {:?}", n, unpack(&b));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transitions() {
+        let (client, peer) = Client::pair();
+        let mut machine = machine(&client, &peer).await;
+        assert_eq!(AdapterState::Idle, machine.state());
+        assert!(machine.can_start_discovery());
+        assert!(machine.can_pair());
+
+        let start = Instant::now();
+        peer.send(&event(0x13, &[0x06, 0x01])).await.unwrap();
+        let mut other = event(0x2D, &[0x01]);
+        other[2] = 0x01; // other controller
+        peer.send(&other).await.unwrap();
+        peer.send(&event(0x2D, &[0x01])).await.unwrap();
+        peer.send(&event(0x2E, RESUME)).await.unwrap();
+
+        let transitions = machine.transitions().take(3).collect::<Vec<_>>().await;
+        let discovering = AdapterState::Discovering { transports: le() };
+        assert_eq!(
+            vec![
+                (AdapterState::Idle, discovering.clone()),
+                (discovering.clone(), AdapterState::Suspended),
+                (AdapterState::Suspended, discovering.clone()),
+            ],
+            transitions
+                .iter()
+                .map(|t| (t.from().clone(), t.to().clone()))
+                .collect::<Vec<_>>()
+        );
+        assert!(transitions
+            .iter()
+            .all(|t| t.cause() == TransitionCause::Event && t.at() >= start));
+        assert!(transitions.windows(2).all(|t| t[0].at() <= t[1].at()));
+        assert!(!machine.can_start_discovery());
+        assert!(machine.can_pair());
+    }
+
+    #[tokio::test]
+    async fn test_resync_from_replies() {
+        let (client, peer) = Client::pair();
+        let mut machine = machine(&client, &peer).await;
+
+        // Discovering event missed: Busy tells it is running.
+        let (result, _) = tokio::join!(
+            machine.start_discovery(le()),
+            expect(&peer, 0x23, complete_with(0x23, 0x0A, &[0x06])),
+        );
+        assert!(result.is_err());
+        assert_eq!(
+            AdapterState::Discovering { transports: le() },
+            machine.state()
+        );
+
+        // Stopped elsewhere: Rejected tells it is not running.
+        let (result, _) = tokio::join!(
+            machine.stop_discovery(le()),
+            expect(&peer, 0x24, complete_with(0x24, 0x0B, &[0x06])),
+        );
+        assert!(result.is_err());
+        assert_eq!(AdapterState::Idle, machine.state());
+
+        let (result, _) = tokio::join!(
+            machine.start_discovery(le()),
+            expect(&peer, 0x23, complete_with(0x23, 0x00, &[0x06])),
+        );
+        result.unwrap();
+        assert!(!machine.can_start_discovery());
+
+        let transitions = machine.transitions().take(3).collect::<Vec<_>>().await;
+        assert!(transitions
+            .iter()
+            .all(|t| t.cause() == TransitionCause::Reply));
+
+        // Powered off without New Settings received.
+        let info = powered_off_information();
+        let (result, _) = tokio::join!(machine.resync(), expect(&peer, 0x04, info));
+        result.unwrap();
+        assert_eq!(AdapterState::Idle, machine.state());
+        let transition = machine.transitions().next().await.unwrap();
+        assert_eq!(TransitionCause::Resync, transition.cause());
+    }
+
+    #[tokio::test]
+    async fn test_pair_device() {
+        let (client, peer) = Client::pair();
+        let mut machine = machine(&client, &peer).await;
+        let pairing = AdapterState::Pairing { addr: device() };
+
+        let kernel = async {
+            recv(&peer).await;
+            peer.send(&event(0x0F, &with_addr(DEVICE, &[0; 5])))
+                .await
+                .unwrap();
+            peer.send(&complete_with(0x19, 0x00, &with_addr(DEVICE, &[])))
+                .await
+                .unwrap();
+        };
+        let (reply, _) = tokio::join!(
+            machine.pair_device(device(), IoCapability::DisplayYesNo),
+            kernel
+        );
+        assert_eq!(device(), reply.unwrap().address());
+        assert_eq!(AdapterState::Idle, machine.state());
+        assert!(machine.can_pair());
+
+        let transitions = machine.transitions().take(2).collect::<Vec<_>>().await;
+        assert_eq!(&pairing, transitions[0].to());
+        assert_eq!(&AdapterState::Idle, transitions[1].to());
+
+        // Busy: pairing with the device in progress elsewhere.
+        let (reply, _) = tokio::join!(
+            machine.pair_device(device(), IoCapability::DisplayYesNo),
+            expect(
+                &peer,
+                0x19,
+                complete_with(0x19, 0x0A, &with_addr(DEVICE, &[]))
+            ),
+        );
+        assert!(reply.is_err());
+        assert_eq!(pairing, machine.state());
+        assert!(!machine.can_pair());
+    }
+
+    #[tokio::test]
+    async fn test_periodic_resync() {
+        let (client, peer) = Client::pair();
+        let mut machine = machine(&client, &peer)
+            .await
+            .with_resync_interval(Duration::from_millis(10));
+        peer.send(&event(0x13, &[0x06, 0x01])).await.unwrap();
+
+        let info = powered_off_information();
+        let kernel = async {
+            // failure is logged, and retried.
+            expect(&peer, 0x04, complete_with(0x04, 0x03, &[])).await;
+            expect(&peer, 0x04, info).await;
+        };
+        let (transitions, _) =
+            tokio::join!(machine.transitions().take(2).collect::<Vec<_>>(), kernel);
+        assert_eq!(TransitionCause::Event, transitions[0].cause());
+        assert_eq!(TransitionCause::Resync, transitions[1].cause());
+        assert_eq!(&AdapterState::Idle, transitions[1].to());
+    }
+}
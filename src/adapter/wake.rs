@@ -0,0 +1,325 @@
+//! Wake-on-BLE configuration.
+use super::Adapter;
+use crate::client::{Error, Result};
+use crate::command;
+use crate::packet::{Address, DeviceFlags, SystemConfigurationParameter};
+
+/// LE scan parameters for wake scenarios. (0.625 ms units)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WakeScanParams {
+    interval: u16,
+    window: u16,
+}
+
+impl WakeScanParams {
+    /// Returns [`Error::InvalidScanParameters`] if `window` is longer than `interval`.
+    pub fn new(interval: u16, window: u16) -> Result<Self> {
+        if window > interval {
+            return Err(Error::InvalidScanParameters { interval, window });
+        }
+        Ok(Self { interval, window })
+    }
+
+    pub fn interval(&self) -> u16 {
+        self.interval
+    }
+
+    pub fn window(&self) -> u16 {
+        self.window
+    }
+
+    /// Values in the default system configuration, if both present.
+    fn from_parameters<'a, I>(parameters: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = &'a SystemConfigurationParameter>,
+    {
+        let (mut interval, mut window) = (None, None);
+        for parameter in parameters {
+            match parameter {
+                SystemConfigurationParameter::LEScanningIntervalforwakescenarios(v) => {
+                    interval = Some(*v)
+                }
+                SystemConfigurationParameter::LEScanningWindowforwakescenarios(v) => {
+                    window = Some(*v)
+                }
+                _ => {}
+            }
+        }
+        Some(Self {
+            interval: interval?,
+            window: window?,
+        })
+    }
+}
+
+/// Outcome of [`Adapter::configure_wake`].
+#[derive(Debug, Clone)]
+pub struct WakeOutcome {
+    flags: DeviceFlags,
+    flags_changed: bool,
+    previous_scan: Option<WakeScanParams>,
+    scan_changed: bool,
+}
+
+impl WakeOutcome {
+    /// Current device flags.
+    pub fn flags(&self) -> &DeviceFlags {
+        &self.flags
+    }
+
+    /// true if Set Device Flags sent.
+    pub fn flags_changed(&self) -> bool {
+        self.flags_changed
+    }
+
+    /// Scan parameters before the change. `None` if not requested or not reported.
+    pub fn previous_scan(&self) -> Option<&WakeScanParams> {
+        self.previous_scan.as_ref()
+    }
+
+    /// true if Set Default System Configuration sent.
+    pub fn scan_changed(&self) -> bool {
+        self.scan_changed
+    }
+}
+
+impl Adapter {
+    /// Enable or disable wake up by the device, and optionally set the scan parameters
+    /// for wake scenarios.
+    ///
+    /// Device flags are read, modified and written, so other flags are kept.
+    /// Each change is read back, returning [`Error::Unexpected`] if not applied.
+    /// Returns [`Error::DeviceFlagNotSupported`] if the device can not wake up the host.
+    pub async fn configure_wake(
+        &self,
+        address: Address,
+        enable: bool,
+        scan: Option<WakeScanParams>,
+    ) -> Result<WakeOutcome> {
+        self.check_primary()?;
+
+        let reply = self
+            .call(command::GetDeviceFlag::new(address.clone()))
+            .await?;
+        let wakeup = DeviceFlags::RemoteWakeupEnabled;
+        if enable && !reply.supported_flags().contains(wakeup) {
+            return Err(Error::DeviceFlagNotSupported {
+                address,
+                flags: wakeup,
+            });
+        }
+
+        let mut flags = *reply.current_flags();
+        flags.set(wakeup, enable);
+        let flags_changed = flags != *reply.current_flags();
+        if flags_changed {
+            self.call(command::SetDeviceFlag::new(address.clone(), flags))
+                .await?;
+            let reply = self.call(command::GetDeviceFlag::new(address)).await?;
+            if *reply.current_flags() != flags {
+                return Err(Error::Unexpected(format!(
+                    "device flags {:?} not applied: {:?}",
+                    flags,
+                    reply.current_flags()
+                )));
+            }
+        }
+
+        let (previous_scan, scan_changed) = match scan {
+            Some(scan) => {
+                let reply = self.call(command::ReadDefaultSystemConfiguration).await?;
+                let previous = WakeScanParams::from_parameters(reply.iter());
+                let changed = previous != Some(scan);
+                if changed {
+                    self.set_wake_scan(scan).await?;
+                }
+                (previous, changed)
+            }
+            None => (None, false),
+        };
+
+        Ok(WakeOutcome {
+            flags,
+            flags_changed,
+            previous_scan,
+            scan_changed,
+        })
+    }
+
+    async fn set_wake_scan(&self, scan: WakeScanParams) -> Result<()> {
+        let parameters = vec![
+            SystemConfigurationParameter::LEScanningIntervalforwakescenarios(scan.interval),
+            SystemConfigurationParameter::LEScanningWindowforwakescenarios(scan.window),
+        ];
+        self.call(
+            parameters
+                .into_iter()
+                .collect::<command::SetDefaultSystemConfiguration>(),
+        )
+        .await?;
+
+        let reply = self.call(command::ReadDefaultSystemConfiguration).await?;
+        let current = WakeScanParams::from_parameters(reply.iter());
+        if current != Some(scan) {
+            return Err(Error::Unexpected(format!(
+                "scan parameters {:?} not applied: {:?}",
+                scan, current
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frames::{command, complete, kernel};
+    use crate::packet::BdAddr;
+    use crate::Client;
+
+    /// 00:11:22:33:44:55 (LE Public)
+    const ADDRESS: [u8; 7] = [0x55, 0x44, 0x33, 0x22, 0x11, 0x00, 0x01];
+
+    fn address() -> Address {
+        "00:11:22:33:44:55"
+            .parse::<BdAddr>()
+            .unwrap()
+            .to_le_public_addr()
+    }
+
+    fn device_flags(supported: u32, current: u32) -> Vec<u8> {
+        let mut b = ADDRESS.to_vec();
+        b.extend(supported.to_le_bytes());
+        b.extend(current.to_le_bytes());
+        b
+    }
+
+    fn wake_scan(interval: u16, window: u16) -> Vec<u8> {
+        let mut b = vec![0x0F, 0x00, 0x02];
+        b.extend(interval.to_le_bytes());
+        b.extend([0x10, 0x00, 0x02]);
+        b.extend(window.to_le_bytes());
+        b
+    }
+
+    #[tokio::test]
+    async fn test_configure_wake() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let mut set_flags = ADDRESS.to_vec();
+        set_flags.extend([0x07, 0x00, 0x00, 0x00]);
+        let mut default_params = vec![0x11, 0x00, 0x02, 0x60, 0x00]; // discovery interval
+        default_params.extend(wake_scan(0x0060, 0x0030));
+        let exchanges = vec![
+            (
+                command(0x4F, &ADDRESS),
+                complete(0x4F, &device_flags(0x07, 0x06)),
+            ),
+            (command(0x50, &set_flags), complete(0x50, &ADDRESS)),
+            (
+                command(0x4F, &ADDRESS),
+                complete(0x4F, &device_flags(0x07, 0x07)),
+            ),
+            (command(0x4B, &[]), complete(0x4B, &default_params)),
+            (
+                command(0x4C, &wake_scan(0x0100, 0x0010)),
+                complete(0x4C, &[]),
+            ),
+            (
+                command(0x4B, &[]),
+                complete(0x4B, &wake_scan(0x0100, 0x0010)),
+            ),
+        ];
+        let scan = WakeScanParams::new(0x0100, 0x0010).unwrap();
+        let (outcome, _) = tokio::join!(
+            adapter.configure_wake(address(), true, Some(scan)),
+            kernel(&peer, exchanges)
+        );
+        let outcome = outcome.unwrap();
+        // unknown flags kept.
+        assert_eq!(0x07, outcome.flags().bits());
+        assert!(outcome.flags_changed());
+        assert_eq!(
+            Some(&WakeScanParams::new(0x0060, 0x0030).unwrap()),
+            outcome.previous_scan()
+        );
+        assert!(outcome.scan_changed());
+
+        // already configured.
+        let exchanges = vec![
+            (
+                command(0x4F, &ADDRESS),
+                complete(0x4F, &device_flags(0x07, 0x07)),
+            ),
+            (
+                command(0x4B, &[]),
+                complete(0x4B, &wake_scan(0x0100, 0x0010)),
+            ),
+        ];
+        let (outcome, _) = tokio::join!(
+            adapter.configure_wake(address(), true, Some(scan)),
+            kernel(&peer, exchanges)
+        );
+        let outcome = outcome.unwrap();
+        assert!(!outcome.flags_changed());
+        assert!(!outcome.scan_changed());
+    }
+
+    #[tokio::test]
+    async fn test_configure_wake_disable() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let mut set_flags = ADDRESS.to_vec();
+        set_flags.extend([0x04, 0x00, 0x00, 0x00]);
+        let exchanges = vec![
+            (
+                command(0x4F, &ADDRESS),
+                complete(0x4F, &device_flags(0x05, 0x05)),
+            ),
+            (command(0x50, &set_flags), complete(0x50, &ADDRESS)),
+            (
+                command(0x4F, &ADDRESS),
+                complete(0x4F, &device_flags(0x05, 0x05)),
+            ),
+        ];
+        let (err, _) = tokio::join!(
+            adapter.configure_wake(address(), false, None),
+            kernel(&peer, exchanges)
+        );
+        // not applied.
+        assert!(matches!(err.unwrap_err(), Error::Unexpected(..)));
+    }
+
+    #[tokio::test]
+    async fn test_configure_wake_not_supported() {
+        let (client, peer) = Client::pair();
+        let adapter = client.adapter(0);
+
+        let exchanges = vec![(
+            command(0x4F, &ADDRESS),
+            complete(0x4F, &device_flags(0x00, 0x00)),
+        )];
+        let (err, _) = tokio::join!(
+            adapter.configure_wake(address(), true, None),
+            kernel(&peer, exchanges)
+        );
+        assert!(matches!(
+            err.unwrap_err(),
+            Error::DeviceFlagNotSupported { .. }
+        ));
+    }
+
+    #[test]
+    fn test_wake_scan_params() {
+        assert!(WakeScanParams::new(0x0010, 0x0010).is_ok());
+        assert!(matches!(
+            WakeScanParams::new(0x0010, 0x0011),
+            Err(Error::InvalidScanParameters {
+                interval: 0x0010,
+                window: 0x0011
+            })
+        ));
+    }
+}
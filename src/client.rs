@@ -1,59 +1,179 @@
 //! mgmt API client.
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 use std::fmt;
 use std::future::Future;
+use std::io::IoSlice;
 use std::mem::MaybeUninit;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::Path;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll, Waker};
+use std::time::Duration;
 
-use futures_channel::mpsc;
 use futures_core::stream::Stream;
 use futures_sink::Sink;
 use futures_util::future::FutureExt;
 use futures_util::lock::Mutex;
 use futures_util::sink::SinkExt;
 use futures_util::stream::{SplitSink, SplitStream, StreamExt};
+use futures_util::task::AtomicWaker;
 use tokio::io::{self, AsyncRead, AsyncWrite, ReadBuf};
 
+use crate::adapter::{Adapter, AdvertisingAllocators, DiscoveryTrackers};
 use crate::command::{self, Command};
 use crate::event::{self, Event};
-use crate::packet::pack::{self, Unpack};
-use crate::packet::{ControllerIndex, ErrorCode};
+use crate::packet::pack::Unpack;
+use crate::packet::{
+    AddressType, AddressTypes, BdAddr, CompanyId, ControllerBus, ControllerIndex, ControllerType,
+    ErrorCode, Name, SettingFlag, Settings,
+};
+use crate::role::{self, Role, RoleGuard, Roles};
+use crate::scope::{Owners, Resource, ScopedClient};
 use crate::sock::MgmtSocket;
+use crate::stats::Counters;
+use crate::throttle::ThrottledDeviceFound;
+
+mod discover;
+mod queue;
+mod split;
+mod storm;
+
+pub use discover::Discovery;
+pub use queue::EventOverflowPolicy;
+pub use split::{Commander, EventHub};
+pub use storm::DEFAULT_EVENT_RATE_LIMIT;
+
+use queue::Queue;
+use storm::EventLimiter;
+
+pub use crate::error::{Error, Result};
+
+const COMMAND_COMPLETE: u16 = 0x0001;
+const COMMAND_STATUS: u16 = 0x0002;
+
+/// Encoded command. (index, opcode, parameters)
+type RawCommand = (ControllerIndex, u16, Box<[u8]>);
+
+/// Reply for a command, parameters not decoded.
+///
+/// Command Complete (`opcode(2) status(1) params..`) and Command Status (`opcode(2) status(1)`)
+/// are both read into this first. [`Client::call`] fails with the exact status by
+/// [`Error::CommandStatus`] unless `Success`, and then decodes the parameters.
+#[derive(Debug, Clone)]
+pub struct RawReply {
+    opcode: u16,
+    status: ErrorCode,
+    params: Box<[u8]>,
+}
+
+impl RawReply {
+    /// Command opcode of this reply.
+    pub fn opcode(&self) -> u16 {
+        self.opcode
+    }
 
-/// mgmt API Client Errors.
-#[derive(Debug, thiserror::Error)]
-pub enum Error {
-    #[error(transparent)]
-    Io(#[from] io::Error),
+    /// Status of this reply.
+    pub fn status(&self) -> &ErrorCode {
+        &self.status
+    }
 
-    #[error(transparent)]
-    Pack(#[from] pack::Error),
+    /// Return parameters. (empty for Command Status)
+    pub fn params(&self) -> &[u8] {
+        &self.params
+    }
 
-    #[error("error occurred {0}")]
-    Reply(ErrorCode),
+    fn from_event(event: Event) -> Result<Self> {
+        match event {
+            Event::CommandComplete(comp) => Ok(Self {
+                opcode: comp.opcode().clone() as u16,
+                status: comp.status().clone(),
+                params: comp.data().clone(),
+            }),
+            Event::CommandStatus(status) => Ok(Self {
+                opcode: status.opcode as u16,
+                status: status.status,
+                params: Default::default(),
+            }),
+            Event::Unknown(code @ (COMMAND_COMPLETE | COMMAND_STATUS), data) => {
+                let mut data = &data[..];
+                let opcode = u16::unpack(&mut data)?;
+                let status = ErrorCode::unpack(&mut data)?;
+                let params = if code == COMMAND_COMPLETE {
+                    data.into()
+                } else {
+                    Default::default()
+                };
+                Ok(Self {
+                    opcode,
+                    status,
+                    params,
+                })
+            }
+            event => Err(Error::Unexpected(format!("unexpected event {:?}", event))),
+        }
+    }
+}
 
-    #[error("unexpected: {0}")]
-    Unexpected(String),
+/// Max frame length. (header and 16 bit parameter length)
+const MAX_FRAME_LEN: usize = 6 + u16::MAX as usize;
 
-    #[error("unreaded content exists {0}")]
-    HasRemaining(usize),
+/// Command frame being written.
+struct TxFrame {
+    header: [u8; command::COMMAND_HEADER_LEN],
+    params: Box<[u8]>,
+    written: usize,
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
+impl TxFrame {
+    fn new(index: &ControllerIndex, opcode: u16, params: Box<[u8]>) -> Result<Self> {
+        let mut header = [0; command::COMMAND_HEADER_LEN];
+        command::pack_command_header(index, opcode, params.len(), &mut &mut header[..])?;
+        Ok(Self {
+            header,
+            params,
+            written: 0,
+        })
+    }
+
+    /// Unwritten parts.
+    fn remaining(&self) -> [IoSlice<'_>; 2] {
+        let header = self.written.min(self.header.len());
+        let params = self.written - header;
+        [
+            IoSlice::new(&self.header[header..]),
+            IoSlice::new(&self.params[params..]),
+        ]
+    }
+
+    fn is_done(&self) -> bool {
+        self.written == self.header.len() + self.params.len()
+    }
+}
 
 struct EventStream<IO> {
     io: IO,
-    txbuf: Vec<u8>,
+    rxbuf: Box<[MaybeUninit<u8>]>,
+    tx: Option<TxFrame>,
     txpendings: Vec<Waker>,
+    counters: Arc<Counters>,
+    limiter: EventLimiter,
 }
 
+/// Max frames dropped in a poll, before yielding.
+const MAX_DROPS_PER_POLL: usize = 64;
+
 impl<IO> EventStream<IO> {
-    fn new(io: IO) -> Self {
+    fn new(io: IO, limiter: EventLimiter) -> Self {
         Self {
             io,
-            txbuf: vec![],
+            rxbuf: vec![MaybeUninit::uninit(); MAX_FRAME_LEN].into_boxed_slice(),
+            tx: None,
             txpendings: Default::default(),
+            counters: Default::default(),
+            limiter,
         }
     }
 }
@@ -65,23 +185,54 @@ where
     type Item = Result<(ControllerIndex, Event)>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let Self { io, .. } = self.get_mut();
+        let Self {
+            io,
+            rxbuf,
+            counters,
+            limiter,
+            ..
+        } = self.get_mut();
 
-        let mut rxbuf = [MaybeUninit::uninit(); 1024 * 8]; // TODO reasonable capacity
-        let mut b = ReadBuf::uninit(&mut rxbuf);
-        if Pin::new(io).poll_read(cx, &mut b)?.is_pending() {
-            return Poll::Pending;
-        };
-        let n = b.filled().len();
-        if n == 0 {
-            return Poll::Ready(None);
+        // Reused, not initialized for each read.
+        let mut b = ReadBuf::uninit(rxbuf);
+        let mut drops = 0;
+        loop {
+            b.clear();
+            if Pin::new(&mut *io).poll_read(cx, &mut b)?.is_pending() {
+                return Poll::Pending;
+            };
+            let n = b.filled().len();
+            if n == 0 {
+                return Poll::Ready(None);
+            }
+
+            if let [lo, hi, ..] = *b.filled() {
+                let code = u16::from_le_bytes([lo, hi]);
+                counters.event(code);
+                if !limiter.admit(code) {
+                    counters.event_dropped(code);
+                    drops += 1;
+                    if drops == MAX_DROPS_PER_POLL {
+                        cx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    }
+                    continue;
+                }
+            }
+            break;
         }
 
         let mut reader = b.filled();
-        let (index, event) = event::unpack_events(&mut reader)?;
+        let (index, event) = match event::unpack_events(&mut reader) {
+            Ok(result) => result,
+            Err(err) => {
+                counters.decode_failure();
+                return Poll::Ready(Some(Err(err.into())));
+            }
+        };
         log::trace!("RECV {:?} {:?}", index, event);
         if !reader.is_empty() {
-            Poll::Ready(Some(Err(Error::HasRemaining(rxbuf.len()))))
+            Poll::Ready(Some(Err(Error::HasRemaining(reader.len()))))
         } else {
             Poll::Ready(Some(Ok((index, event))))
         }
@@ -95,10 +246,8 @@ where
     type Error = Error;
 
     fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
-        let Self {
-            txbuf, txpendings, ..
-        } = self.get_mut();
-        if !txbuf.is_empty() {
+        let Self { tx, txpendings, .. } = self.get_mut();
+        if tx.is_some() {
             txpendings.push(cx.waker().clone());
             return Poll::Pending;
         }
@@ -109,36 +258,43 @@ where
         self: Pin<&mut Self>,
         (index, commands): (ControllerIndex, Command),
     ) -> Result<()> {
-        let Self { txbuf, .. } = self.get_mut();
+        let Self { tx, .. } = self.get_mut();
 
         log::trace!("SEND {:?} {:?}", index, commands);
-        command::pack_command(&index, &commands, txbuf)?;
+        let mut params = vec![];
+        commands.pack_inner(&mut params)?;
+        *tx = Some(TxFrame::new(&index, commands.code() as u16, params.into())?);
         Ok(())
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
         let this = self.get_mut();
 
-        loop {
-            if !this.txbuf.is_empty() {
-                let n = match Pin::new(&mut this.io).poll_write(cx, &this.txbuf)? {
-                    Poll::Ready(n) => n,
-                    Poll::Pending => return Poll::Pending,
-                };
-                this.txbuf = (&this.txbuf[n..]).into();
-
-                if this.txbuf.is_empty() {
-                    for waker in this.txpendings.drain(..) {
-                        waker.wake();
-                    }
-                }
-            } else {
-                if Pin::new(&mut this.io).poll_flush(cx).is_pending() {
-                    return Poll::Pending;
+        // Header and parameters in a single write. MgmtSocket fails a short send, as
+        // retrying would split the packet. Writing the rest is for stream transports of
+        // tests, which may take the frame in parts.
+        while let Some(frame) = &mut this.tx {
+            let result = match Pin::new(&mut this.io).poll_write_vectored(cx, &frame.remaining()) {
+                Poll::Ready(result) => result,
+                Poll::Pending => return Poll::Pending,
+            };
+            // A failed frame is dropped, not to block later sends.
+            if let Ok(n) = result {
+                frame.written += n;
+            }
+            if result.is_err() || frame.is_done() {
+                this.tx = None;
+                for waker in this.txpendings.drain(..) {
+                    waker.wake();
                 }
-                return Poll::Ready(Ok(()));
             }
+            result?;
+        }
+
+        if Pin::new(&mut this.io).poll_flush(cx).is_pending() {
+            return Poll::Pending;
         }
+        Poll::Ready(Ok(()))
     }
 
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
@@ -152,11 +308,200 @@ where
     }
 }
 
+impl<IO> Sink<RawCommand> for EventStream<IO>
+where
+    IO: AsyncWrite + Unpin,
+{
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        <Self as Sink<(ControllerIndex, Command)>>::poll_ready(self, cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, (index, opcode, params): RawCommand) -> Result<()> {
+        let Self { tx, .. } = self.get_mut();
+
+        log::trace!("SEND {:?} {:#06x} {:?}", index, opcode, params);
+        *tx = Some(TxFrame::new(&index, opcode, params)?);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        <Self as Sink<(ControllerIndex, Command)>>::poll_flush(self, cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        <Self as Sink<(ControllerIndex, Command)>>::poll_close(self, cx)
+    }
+}
+
+/// Request to stop reading, by [`split`] handles.
+#[derive(Debug, Default)]
+struct Shutdown {
+    requested: AtomicBool,
+    /// The last poller of the stream.
+    waker: AtomicWaker,
+}
+
+impl Shutdown {
+    fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+        self.waker.wake();
+    }
+
+    fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+}
+
+/// Calls dropped before the reply. (e.g. timed out) Their late replies are discarded.
+#[derive(Debug, Default)]
+struct Abandoned(std::sync::Mutex<Vec<(ControllerIndex, u16)>>);
+
+impl Abandoned {
+    fn push(&self, index: ControllerIndex, opcode: u16) {
+        self.0.lock().unwrap().push((index, opcode));
+    }
+
+    /// Remove the first call matching the reply. Same opcodes are replied in order.
+    fn take(&self, index: &ControllerIndex, opcode: u16) -> bool {
+        let mut calls = self.0.lock().unwrap();
+        match calls.iter().position(|(i, o)| i == index && *o == opcode) {
+            Some(n) => {
+                calls.remove(n);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Replies awaited by a call. Abandoned if dropped before received.
+struct PendingReplies<'a, S> {
+    receive: &'a Receive<S>,
+    calls: Vec<(ControllerIndex, u16)>,
+}
+
+impl<S> PendingReplies<'_, S> {
+    fn received(&mut self, index: &ControllerIndex, opcode: u16) {
+        if let Some(n) = self
+            .calls
+            .iter()
+            .position(|(i, o)| i == index && *o == opcode)
+        {
+            self.calls.remove(n);
+        }
+    }
+}
+
+impl<S> Drop for PendingReplies<'_, S> {
+    fn drop(&mut self) {
+        if self.calls.is_empty() {
+            return;
+        }
+        for (index, opcode) in self.calls.drain(..) {
+            self.receive.1.push(index, opcode);
+        }
+        // The reply may be the head already, blocking the subscribers.
+        if let Some(mut inner) = self.receive.0.try_lock() {
+            inner.discard_abandoned();
+        }
+    }
+}
+
+/// Opcode of Command Complete / Command Status.
+fn reply_opcode(event: &Event) -> Option<u16> {
+    match event {
+        Event::CommandComplete(comp) => Some(comp.opcode().clone() as u16),
+        Event::CommandStatus(status) => Some(status.opcode.clone() as u16),
+        Event::Unknown(COMMAND_COMPLETE | COMMAND_STATUS, data) => match **data {
+            [lo, hi, ..] => Some(u16::from_le_bytes([lo, hi])),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Predicate of events delivered to a subscriber.
+type EventFilter = Box<dyn Fn(&ControllerIndex, &Event) -> bool + Send>;
+
+struct Subscriber {
+    queue: Arc<Queue>,
+    filter: Option<EventFilter>,
+}
+
+impl Subscriber {
+    /// Send the event unless filtered out, without taking a reference otherwise.
+    fn send(&self, events: &SharedEvent) {
+        if let Some(filter) = &self.filter {
+            if !filter(&events.0, &events.1) {
+                return;
+            }
+        }
+        self.queue.push(events.clone());
+    }
+}
+
 struct RecvInner<S> {
     stream: S,
     wakers: Vec<Waker>,
     head: Option<Result<(ControllerIndex, Event)>>,
-    subscribers: Vec<mpsc::UnboundedSender<(ControllerIndex, Event)>>,
+    subscribers: Vec<Subscriber>,
+    owners: Arc<Owners>,
+    /// The stream ended. Not polled any more.
+    closed: bool,
+    shutdown: Arc<Shutdown>,
+    abandoned: Arc<Abandoned>,
+}
+
+impl<S> RecvInner<S> {
+    /// Discard the head if it is the reply of an abandoned call, waking the waiters.
+    fn discard_abandoned(&mut self) {
+        let abandoned = match &self.head {
+            Some(Ok((index, event))) => {
+                reply_opcode(event).is_some_and(|opcode| self.abandoned.take(index, opcode))
+            }
+            _ => false,
+        };
+        if abandoned {
+            log::trace!("DISCARD {:?}", self.head);
+            self.head = None;
+            for w in self.wakers.drain(..) {
+                w.wake();
+            }
+        }
+    }
+
+    /// true if closed, by the end of the stream or a shutdown request.
+    ///
+    /// Registers `cx` to be woken by a later request.
+    fn poll_closed(&mut self, cx: &mut Context<'_>) -> bool {
+        self.shutdown.waker.register(cx.waker());
+        if !self.closed && self.shutdown.is_requested() {
+            self.close_subscribers();
+            self.closed = true;
+            for w in self.wakers.drain(..) {
+                w.wake();
+            }
+        }
+        self.closed
+    }
+
+    fn close_subscribers(&mut self) {
+        for subscriber in self.subscribers.drain(..) {
+            subscriber.queue.close();
+        }
+    }
+
+    /// false if a subscriber blocks reading more events, registering `cx` to be woken
+    /// when it catches up. Forgets dropped subscribers.
+    fn poll_subscribers_ready(&mut self, cx: &mut Context<'_>) -> bool {
+        self.subscribers
+            .retain(|subscriber| !subscriber.queue.is_detached());
+        self.subscribers
+            .iter()
+            .all(|subscriber| subscriber.queue.poll_ready(cx))
+    }
 }
 
 struct Recv<S> {
@@ -176,9 +521,16 @@ where
                 Poll::Pending => return Poll::Pending,
             };
 
+            inner.discard_abandoned();
             if let Some(head) = inner.head.take() {
                 return Poll::Ready(head.map(Some));
             }
+            if inner.poll_closed(cx) {
+                return Poll::Ready(Ok(None));
+            }
+            if !inner.poll_subscribers_ready(cx) {
+                return Poll::Pending;
+            }
 
             let result = match inner.stream.poll_next_unpin(cx) {
                 Poll::Ready(result) => result,
@@ -190,18 +542,25 @@ where
             }
 
             match result {
-                result
-                @
-                Some(
-                    Ok((_, Event::CommandComplete(..) | Event::CommandStatus(..))) | Err(..),
+                result @ Some(
+                    Ok((
+                        _,
+                        Event::CommandComplete(..)
+                        | Event::CommandStatus(..)
+                        | Event::Unknown(COMMAND_COMPLETE | COMMAND_STATUS, ..),
+                    ))
+                    | Err(..),
                 ) => inner.head = result,
                 Some(Ok(events)) => {
-                    for tx in &inner.subscribers {
-                        tx.unbounded_send(events.clone()).ok();
+                    inner.owners.observed(&events.0, &events.1);
+                    let events = Arc::new(events);
+                    for subscriber in &inner.subscribers {
+                        subscriber.send(&events);
                     }
                 }
                 None => {
-                    inner.subscribers.clear();
+                    inner.close_subscribers();
+                    inner.closed = true;
                     return Poll::Ready(Ok(None));
                 }
             }
@@ -226,10 +585,17 @@ where
                 Poll::Pending => return Poll::Pending,
             };
 
+            if inner.poll_closed(cx) {
+                return Poll::Ready(None);
+            }
+            inner.discard_abandoned();
             if inner.head.is_some() {
                 inner.wakers.push(cx.waker().clone());
                 return Poll::Pending;
             }
+            if !inner.poll_subscribers_ready(cx) {
+                return Poll::Pending;
+            }
 
             let result = match inner.stream.poll_next_unpin(cx) {
                 Poll::Ready(result) => result,
@@ -241,18 +607,25 @@ where
             }
 
             match result {
-                result
-                @
-                Some(
-                    Ok((_, Event::CommandComplete(..) | Event::CommandStatus(..))) | Err(..),
+                result @ Some(
+                    Ok((
+                        _,
+                        Event::CommandComplete(..)
+                        | Event::CommandStatus(..)
+                        | Event::Unknown(COMMAND_COMPLETE | COMMAND_STATUS, ..),
+                    ))
+                    | Err(..),
                 ) => inner.head = result,
                 Some(Ok(events)) => {
-                    for tx in &inner.subscribers {
-                        tx.unbounded_send(events.clone()).ok();
+                    inner.owners.observed(&events.0, &events.1);
+                    let events = Arc::new(events);
+                    for subscriber in &inner.subscribers {
+                        subscriber.send(&events);
                     }
                 }
                 None => {
-                    inner.subscribers.clear();
+                    inner.close_subscribers();
+                    inner.closed = true;
                     return Poll::Ready(None);
                 }
             }
@@ -260,22 +633,38 @@ where
     }
 }
 
-struct Receive<S>(Arc<Mutex<RecvInner<S>>>);
+struct Receive<S>(Arc<Mutex<RecvInner<S>>>, Arc<Abandoned>);
 
 impl<S> Clone for Receive<S> {
     fn clone(&self) -> Self {
-        Self(self.0.clone())
+        Self(self.0.clone(), self.1.clone())
     }
 }
 
 impl<S> Receive<S> {
-    fn new(stream: S) -> Self {
-        Self(Arc::new(Mutex::new(RecvInner {
-            stream,
-            wakers: Default::default(),
-            head: Default::default(),
-            subscribers: vec![],
-        })))
+    fn new(stream: S, owners: Arc<Owners>, shutdown: Arc<Shutdown>) -> Self {
+        let abandoned = Arc::<Abandoned>::default();
+        Self(
+            Arc::new(Mutex::new(RecvInner {
+                stream,
+                wakers: Default::default(),
+                head: Default::default(),
+                subscribers: vec![],
+                owners,
+                closed: false,
+                shutdown,
+                abandoned: abandoned.clone(),
+            })),
+            abandoned,
+        )
+    }
+
+    /// Track replies of the calls, until received.
+    fn pending(&self, calls: Vec<(ControllerIndex, u16)>) -> PendingReplies<'_, S> {
+        PendingReplies {
+            receive: self,
+            calls,
+        }
     }
 }
 
@@ -295,31 +684,60 @@ where
         }
     }
 
-    async fn subscribe(&self) -> mpsc::UnboundedReceiver<(ControllerIndex, Event)> {
-        let (tx, rx) = mpsc::unbounded();
+    /// true if the stream ended, or its shutdown was requested.
+    async fn is_closed(&self) -> bool {
+        let inner = self.0.lock().await;
+        inner.closed || inner.shutdown.is_requested()
+    }
+
+    async fn subscribe(
+        &self,
+        filter: Option<EventFilter>,
+        bound: Option<(usize, EventOverflowPolicy)>,
+    ) -> Arc<Queue> {
+        let queue = Queue::new(bound);
 
         let mut inner = self.0.lock().await;
-        inner.subscribers.push(tx);
-        rx
+        if inner.closed {
+            queue.close();
+        } else {
+            inner.subscribers.push(Subscriber {
+                queue: queue.clone(),
+                filter,
+            });
+        }
+        queue
     }
 }
 
-struct EventSubscribeInner<S> {
+struct SharedSubscribeInner<S> {
     receive: Receive<SplitStream<EventStream<S>>>,
-    rx: mpsc::UnboundedReceiver<(ControllerIndex, Event)>,
+    queue: Arc<Queue>,
 }
 
-impl<S> Stream for EventSubscribeInner<S>
+impl<S> SharedSubscribeInner<S> {
+    fn dropped(&self) -> u64 {
+        self.queue.dropped()
+    }
+}
+
+impl<S> Drop for SharedSubscribeInner<S> {
+    fn drop(&mut self) {
+        self.queue.detach();
+    }
+}
+
+impl<S> Stream for SharedSubscribeInner<S>
 where
     S: AsyncRead + Unpin,
 {
-    type Item = (ControllerIndex, Event);
+    type Item = SharedEvent;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let Self { receive, rx } = self.get_mut();
+        let Self { receive, queue } = self.get_mut();
 
         loop {
-            match rx.poll_next_unpin(cx) {
+            match queue.poll_pop(cx) {
                 Poll::Ready(result) => return Poll::Ready(result),
                 Poll::Pending => {}
             }
@@ -332,32 +750,135 @@ where
     }
 }
 
-type ClientTx<S> = Arc<Mutex<SplitSink<EventStream<S>, (ControllerIndex, Command)>>>;
+struct EventSubscribeInner<S>(SharedSubscribeInner<S>);
+
+impl<S> Stream for EventSubscribeInner<S>
+where
+    S: AsyncRead + Unpin,
+{
+    type Item = (ControllerIndex, Event);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // the last subscriber takes the event without copying.
+        self.get_mut()
+            .0
+            .poll_next_unpin(cx)
+            .map(|item| item.map(|events| Arc::try_unwrap(events).unwrap_or_else(|e| (*e).clone())))
+    }
+}
+
+type ClientTx<S> = Arc<Mutex<SplitSink<EventStream<S>, RawCommand>>>;
+
+/// `call`, or [`Error::Timeout`] if not resolved in `timeout`.
+async fn within<T, F>(timeout: Option<Duration>, call: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, call)
+            .await
+            .map_err(|_| Error::Timeout)?,
+        None => call.await,
+    }
+}
 
 pub struct ClientInner<S> {
     rx: Receive<SplitStream<EventStream<S>>>,
     tx: ClientTx<S>,
+    counters: Arc<Counters>,
+    roles: Arc<Roles>,
+    owners: Arc<Owners>,
+    shutdown: Arc<Shutdown>,
+    default_timeout: Arc<std::sync::Mutex<Option<Duration>>>,
+    discovery: Arc<DiscoveryTrackers>,
+    advertising: Arc<AdvertisingAllocators>,
+}
+
+impl<S> Clone for ClientInner<S> {
+    fn clone(&self) -> Self {
+        Self {
+            rx: self.rx.clone(),
+            tx: self.tx.clone(),
+            counters: self.counters.clone(),
+            roles: self.roles.clone(),
+            owners: self.owners.clone(),
+            shutdown: self.shutdown.clone(),
+            default_timeout: self.default_timeout.clone(),
+            discovery: self.discovery.clone(),
+            advertising: self.advertising.clone(),
+        }
+    }
 }
 
 impl<S> ClientInner<S>
 where
     S: AsyncRead + AsyncWrite + Unpin + 'static,
 {
+    #[cfg(any(test, feature = "test-util"))]
     fn new(sock: S) -> Self {
-        let stream = EventStream::new(sock);
+        Self::with_limiter(sock, EventLimiter::default())
+    }
+
+    fn with_limiter(sock: S, limiter: EventLimiter) -> Self {
+        let stream = EventStream::new(sock, limiter);
+        let counters = stream.counters.clone();
         let (tx, rx) = stream.split();
+        let owners = Arc::<Owners>::default();
+        let shutdown = Arc::<Shutdown>::default();
         Self {
-            rx: Receive::new(rx),
+            rx: Receive::new(rx, owners.clone(), shutdown.clone()),
             tx: Arc::new(Mutex::new(tx)),
+            counters,
+            roles: Default::default(),
+            owners,
+            shutdown,
+            default_timeout: Default::default(),
+            discovery: Default::default(),
+            advertising: Default::default(),
         }
     }
 
+    fn default_timeout(&self) -> Option<Duration> {
+        *self.default_timeout.lock().unwrap()
+    }
+
+    fn set_default_timeout(&self, timeout: Option<Duration>) {
+        *self.default_timeout.lock().unwrap() = timeout;
+    }
+
     /// Subscribe mgmt API events.
     async fn events(&self) -> EventSubscribeInner<S> {
-        let rx = self.rx.subscribe().await;
-        EventSubscribeInner {
-            receive: Receive(self.rx.0.clone()),
-            rx,
+        EventSubscribeInner(self.events_shared().await)
+    }
+
+    /// Subscribe mgmt API events passing `filter`.
+    async fn events_filtered(&self, filter: EventFilter) -> EventSubscribeInner<S> {
+        EventSubscribeInner(self.subscribe(Some(filter), None).await)
+    }
+
+    /// Subscribe mgmt API events, queueing at most `capacity`.
+    async fn events_with(
+        &self,
+        capacity: usize,
+        policy: EventOverflowPolicy,
+    ) -> EventSubscribeInner<S> {
+        EventSubscribeInner(self.subscribe(None, Some((capacity, policy))).await)
+    }
+
+    /// Subscribe mgmt API events, shared with other subscribers.
+    async fn events_shared(&self) -> SharedSubscribeInner<S> {
+        self.subscribe(None, None).await
+    }
+
+    async fn subscribe(
+        &self,
+        filter: Option<EventFilter>,
+        bound: Option<(usize, EventOverflowPolicy)>,
+    ) -> SharedSubscribeInner<S> {
+        let queue = self.rx.subscribe(filter, bound).await;
+        SharedSubscribeInner {
+            receive: self.rx.clone(),
+            queue,
         }
     }
 
@@ -371,11 +892,83 @@ where
         C: command::CommandRequest + 'static,
         C::Reply: fmt::Debug,
         I: Into<ControllerIndex>,
+    {
+        self.call_as(None, index.into(), command)
+    }
+
+    /// Call mgmt API command, recording created resources for the owner.
+    fn call_as<C>(
+        &self,
+        owner: Option<&'static str>,
+        index: ControllerIndex,
+        command: C,
+    ) -> impl Future<Output = Result<C::Reply>> + 'static
+    where
+        C: command::CommandRequest + 'static,
+        C::Reply: fmt::Debug,
+    {
+        self.call_as_within(owner, index, command, self.default_timeout())
+    }
+
+    /// Call mgmt API command as [`Self::call_as`], resolving to [`Error::Timeout`] unless
+    /// replied in `timeout`.
+    fn call_as_within<C>(
+        &self,
+        owner: Option<&'static str>,
+        index: ControllerIndex,
+        command: C,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = Result<C::Reply>> + 'static
+    where
+        C: command::CommandRequest + 'static,
+        C::Reply: fmt::Debug,
+    {
+        let rx = self.rx.clone();
+        let tx = self.tx.clone();
+        let counters = self.counters.clone();
+        let owners = self.owners.clone();
+
+        within(
+            timeout,
+            Self::call_inner(index, command, rx, tx, counters, owners, owner),
+        )
+    }
+
+    /// Call mgmt API command with encoded parameters.
+    pub fn call_raw<I, P>(
+        &self,
+        index: I,
+        opcode: u16,
+        params: P,
+    ) -> impl Future<Output = Result<RawReply>> + 'static
+    where
+        I: Into<ControllerIndex>,
+        P: Into<Box<[u8]>>,
     {
         let rx = self.rx.clone();
         let tx = self.tx.clone();
+        let counters = self.counters.clone();
 
-        Self::call_inner(index.into(), command, rx, tx)
+        within(
+            self.default_timeout(),
+            Self::call_raw_inner(index.into(), opcode, params.into(), rx, tx, counters),
+        )
+    }
+
+    /// Call multiple commands.
+    fn call_batch(
+        &self,
+        index: ControllerIndex,
+        commands: Vec<Command>,
+    ) -> impl Future<Output = Result<Vec<Result<RawReply>>>> + 'static {
+        let rx = self.rx.clone();
+        let tx = self.tx.clone();
+        let counters = self.counters.clone();
+
+        within(
+            self.default_timeout(),
+            Self::call_batch_inner(index, commands, rx, tx, counters),
+        )
     }
 
     async fn call_inner<C>(
@@ -383,78 +976,458 @@ where
         command: C,
         rx: Receive<SplitStream<EventStream<S>>>,
         tx: ClientTx<S>,
+        counters: Arc<Counters>,
+        owners: Arc<Owners>,
+        owner: Option<&'static str>,
     ) -> Result<C::Reply>
     where
         C: command::CommandRequest,
         C::Reply: fmt::Debug,
     {
         let command = command.into();
-        let expected_code = command.code();
+        let code = command.code();
+        log::trace!("CALL {:?} {:?}", index, command);
+        let mut params = vec![];
+        command.pack_inner(&mut params)?;
+
+        let reply = Self::call_raw_inner(
+            index.clone(),
+            code.clone() as u16,
+            params.into(),
+            rx,
+            tx,
+            counters.clone(),
+        )
+        .await?;
+        if !reply.status.success() {
+            return Err(Error::CommandStatus {
+                command: code,
+                index,
+                status: reply.status,
+            });
+        }
+        owners.replied(owner, &index, &code, &reply.params);
+        let result =
+            C::Reply::unpack(&mut &reply.params[..]).inspect_err(|_| counters.decode_failure())?;
+        log::trace!("REPLY {} {:?}", C::NAME, result);
+        Ok(result)
+    }
 
+    async fn call_raw_inner(
+        index: ControllerIndex,
+        opcode: u16,
+        params: Box<[u8]>,
+        rx: Receive<SplitStream<EventStream<S>>>,
+        tx: ClientTx<S>,
+        counters: Arc<Counters>,
+    ) -> Result<RawReply> {
         let mut tx = tx.lock().await;
-        match tx.send((index.clone(), command)).await {
+        // Calls queued behind the one which saw the socket closed.
+        if rx.is_closed().await {
+            return Err(Error::Closed);
+        }
+        counters.command();
+        match tx.send((index.clone(), opcode, params)).await {
             Ok(..) => {}
             Err(Error::Io(err)) if err.kind() == io::ErrorKind::WriteZero => {} // Will probably receive an error reply
             Err(err) => return Err(err),
         }
 
-        let result = rx.recv().await?.unwrap(); // TODO EOF
+        let mut pending = rx.pending(vec![(index.clone(), opcode)]);
+        let result = rx.recv().await;
+        pending.calls.clear();
+        let result = result?.ok_or(Error::Closed)?;
         if index != result.0 {
             return Err(Error::Unexpected(format!(
                 "unexpected index {:?} != {:?}",
                 index, result.0
             )));
         }
-        match result.1 {
-            Event::CommandComplete(comp) => {
-                if comp.opcode() != &expected_code {
-                    return Err(Error::Unexpected(format!(
-                        "unexpected code received {:?} != {:?}",
-                        expected_code,
-                        comp.opcode()
-                    )));
-                }
-                if !comp.status().success() {
-                    return Err(Error::Unexpected("command complete but not success".into()));
-                }
-                let mut data = &comp.data()[..];
-                let result = C::Reply::unpack(&mut data)?;
-                log::trace!("REPLY {:?}", result);
-                Ok(result)
-            }
-            Event::CommandStatus(status) => {
-                if status.opcode != expected_code {
-                    return Err(Error::Unexpected(format!(
-                        "unexpected code received {:?} != {:?}",
-                        expected_code, status.opcode
-                    )));
-                }
-                Err(Error::Reply(status.status))
-            }
-            _ => unreachable!(),
+        let reply = RawReply::from_event(result.1)?;
+        if reply.opcode != opcode {
+            return Err(Error::Unexpected(format!(
+                "unexpected code received {:#06x} != {:#06x}",
+                opcode, reply.opcode
+            )));
         }
+        if !reply.status.success() {
+            counters.command_error(&reply.status);
+        }
+        Ok(reply)
     }
-}
 
-/// mgmt API Event subscription.
-pub struct EventSubscribe(EventSubscribeInner<MgmtSocket>);
+    async fn call_batch_inner(
+        index: ControllerIndex,
+        commands: Vec<Command>,
+        rx: Receive<SplitStream<EventStream<S>>>,
+        tx: ClientTx<S>,
+        counters: Arc<Counters>,
+    ) -> Result<Vec<Result<RawReply>>> {
+        let mut frames = Vec::with_capacity(commands.len());
+        for command in commands {
+            log::trace!("CALL {:?} {:?}", index, command);
+            let mut params = vec![];
+            command.pack_inner(&mut params)?;
+            frames.push((command.code(), params.into_boxed_slice()));
+        }
 
-impl Stream for EventSubscribe {
-    type Item = (ControllerIndex, Event);
+        let codes = frames
+            .iter()
+            .map(|(code, _)| code.clone())
+            .collect::<Vec<_>>();
+        let mut results = codes.iter().map(|_| None).collect::<Vec<_>>();
 
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.get_mut().0.poll_next_unpin(cx)
-    }
-}
+        let mut tx = tx.lock().await;
+        if rx.is_closed().await {
+            return Err(Error::Closed);
+        }
+        // One vectored write per frame, as the mgmt socket takes one command per write.
+        // On failure, the rest is not sent. Replies of the sent ones are still awaited.
+        let mut sent = 0;
+        for (code, params) in frames {
+            counters.command();
+            match tx.send((index.clone(), code as u16, params)).await {
+                Ok(..) => {}
+                Err(Error::Io(err)) if err.kind() == io::ErrorKind::WriteZero => {} // Will probably receive an error reply
+                Err(err) => {
+                    results[sent] = Some(Err(err));
+                    break;
+                }
+            }
+            sent += 1;
+        }
+        for result in results.iter_mut().skip(sent + 1) {
+            *result = Some(Err(Error::NotSent));
+        }
+
+        let mut pending = rx.pending(
+            codes[..sent]
+                .iter()
+                .map(|code| (index.clone(), code.clone() as u16))
+                .collect(),
+        );
+        while results.iter().any(Option::is_none) {
+            let result = rx.recv().await;
+            if !matches!(result, Ok(Some(..))) {
+                pending.calls.clear();
+            }
+            let (i, event) = result?.ok_or(Error::Closed)?;
+            if let Some(opcode) = reply_opcode(&event) {
+                pending.received(&i, opcode);
+            }
+            if index != i {
+                return Err(Error::Unexpected(format!(
+                    "unexpected index {:?} != {:?}",
+                    index, i
+                )));
+            }
+            let reply = RawReply::from_event(event)?;
+            // Same opcodes are replied in order.
+            let n = codes
+                .iter()
+                .zip(&results)
+                .position(|(code, r)| r.is_none() && code.clone() as u16 == reply.opcode)
+                .ok_or_else(|| {
+                    Error::Unexpected(format!("unexpected code received {:#06x}", reply.opcode))
+                })?;
+            results[n] = Some(if reply.status.success() {
+                Ok(reply)
+            } else {
+                counters.command_error(&reply.status);
+                Err(Error::CommandStatus {
+                    command: codes[n].clone(),
+                    index: index.clone(),
+                    status: reply.status,
+                })
+            });
+        }
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    /// Infer [`AddressType`] for an address given without type.
+    async fn infer_address_type(&self, index: ControllerIndex, bd: &BdAddr) -> Result<AddressType> {
+        let info = self.call(index, command::ReadControllerInformation).await?;
+        Ok(infer_address_type(info.current_settings(), bd))
+    }
+}
+
+/// Infer [`AddressType`] from current settings and the random-ness bits of address.
+fn infer_address_type(settings: &Settings, bd: &BdAddr) -> AddressType {
+    if !settings.contains(Settings::LowEnergy)
+        || settings.contains(Settings::BasicRateEnhancedDataRate)
+    {
+        return AddressType::BrEdr;
+    }
+
+    // Static Device Address has the two most significant bits set.
+    let b = <[u8; 6]>::from(bd.clone());
+    if b[5] >> 6 == 0b11 {
+        AddressType::LeRandom
+    } else {
+        AddressType::LePublic
+    }
+}
+
+/// Controller appeared or disappeared. See [`Client::watch_controllers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControllerChange {
+    Added(ControllerIndex),
+    Removed(ControllerIndex),
+}
+
+/// Controller indices of every kind. See [`Client::all_indices`].
+#[derive(Debug, Clone)]
+pub struct AllIndices {
+    configured: Vec<ControllerIndex>,
+    unconfigured: Option<Vec<ControllerIndex>>,
+    extended: Option<Vec<(ControllerIndex, ControllerType, ControllerBus)>>,
+}
+
+impl AllIndices {
+    /// Read Controller Index List.
+    pub fn configured(&self) -> &[ControllerIndex] {
+        &self.configured
+    }
+
+    /// Read Unconfigured Controller Index List. `None` if unknown to the kernel.
+    pub fn unconfigured(&self) -> Option<&[ControllerIndex]> {
+        self.unconfigured.as_deref()
+    }
+
+    /// Read Extended Controller Index List. `None` if unknown to the kernel.
+    pub fn extended(&self) -> Option<&[(ControllerIndex, ControllerType, ControllerBus)]> {
+        self.extended.as_deref()
+    }
+}
+
+/// What a controller is. See [`Client::controller_summaries`].
+#[derive(Debug, Clone)]
+pub struct ControllerSummary {
+    index: ControllerIndex,
+    address: BdAddr,
+    controller_type: Option<ControllerType>,
+    bus: Option<ControllerBus>,
+    manufacturer: CompanyId,
+    bluetooth_version: u8,
+    name: Name,
+    current_settings: Settings,
+}
+
+impl ControllerSummary {
+    pub fn index(&self) -> &ControllerIndex {
+        &self.index
+    }
+
+    pub fn address(&self) -> &BdAddr {
+        &self.address
+    }
+
+    /// `None` if Read Extended Controller Index List is unknown to the kernel.
+    pub fn controller_type(&self) -> Option<&ControllerType> {
+        self.controller_type.as_ref()
+    }
+
+    /// `None` if Read Extended Controller Index List is unknown to the kernel.
+    pub fn bus(&self) -> Option<&ControllerBus> {
+        self.bus.as_ref()
+    }
+
+    pub fn manufacturer(&self) -> CompanyId {
+        self.manufacturer
+    }
+
+    /// HCI version. (e.g. 9 for 5.0)
+    pub fn bluetooth_version(&self) -> u8 {
+        self.bluetooth_version
+    }
+
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    pub fn current_settings(&self) -> &Settings {
+        &self.current_settings
+    }
+}
+
+/// Pairing preset for [`Client::set_pairing_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PairingMode {
+    /// Not bondable. SSP and Secure Connections are left as is.
+    Off,
+    /// Bondable, PIN code pairing only. (SSP and Secure Connections disabled)
+    LegacyPin,
+    /// Bondable with SSP. (Secure Connections disabled)
+    Ssp,
+    /// Bondable with SSP, Secure Connections only.
+    SecureConnectionsOnly,
+}
+
+impl PairingMode {
+    fn requires(&self, flag: SettingFlag) -> bool {
+        match flag {
+            SettingFlag::Ssp => matches!(self, Self::Ssp | Self::SecureConnectionsOnly),
+            SettingFlag::SecureConnections => matches!(self, Self::SecureConnectionsOnly),
+            _ => false,
+        }
+    }
+}
+
+/// Event shared among subscribers.
+///
+/// Decoded events own their payloads, so this keeps one event alive,
+/// not the receive buffer it was read from.
+pub type SharedEvent = Arc<(ControllerIndex, Event)>;
+
+/// mgmt API Event subscription.
+pub struct EventSubscribe(EventSubscribeInner<MgmtSocket>);
+
+impl EventSubscribe {
+    /// Events dropped by the overflow policy. See [`Client::events_with`].
+    pub fn dropped(&self) -> u64 {
+        (self.0).0.dropped()
+    }
+}
+
+impl Stream for EventSubscribe {
+    type Item = (ControllerIndex, Event);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().0.poll_next_unpin(cx)
+    }
+}
+
+/// mgmt API Event subscription without copying events. See [`Client::events_shared`].
+pub struct SharedEventSubscribe(SharedSubscribeInner<MgmtSocket>);
+
+impl Stream for SharedEventSubscribe {
+    type Item = SharedEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().0.poll_next_unpin(cx)
+    }
+}
 
 /// mgmt API Client.
-pub struct Client(ClientInner<MgmtSocket>);
+///
+/// Cloned clients share the same connection, closed when the last clone is dropped.
+/// No background task is spawned: replies and events are read by whoever polls.
+/// [`Client::split`] hands out command and event halves with independent lifetimes.
+///
+/// # Ordering
+///
+/// Frames are dispatched strictly in the order received. An event received before
+/// the reply of a call is queued to every subscriber registered at that time before
+/// the call resolves, so it is ready on the next poll of the subscription. Events
+/// received after the reply are not read until the reply is taken.
+#[derive(Clone)]
+pub struct Client(ClientInner<MgmtSocket>, RawFd);
+
+/// Options to open [`Client`].
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    inheritable: bool,
+    event_rate_limit: Option<u32>,
+    drop_excess_events: bool,
+    default_timeout: Option<Duration>,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self {
+            inheritable: false,
+            event_rate_limit: Some(DEFAULT_EVENT_RATE_LIMIT),
+            drop_excess_events: false,
+            default_timeout: None,
+        }
+    }
+}
+
+impl ClientBuilder {
+    /// Keep the socket open in child processes across `exec`. Default false.
+    ///
+    /// A child holding the socket keeps receiving events of the controllers.
+    pub fn inheritable(&mut self, inheritable: bool) -> &mut Self {
+        self.inheritable = inheritable;
+        self
+    }
+
+    /// Events per second of each event code, before warning about an event storm.
+    /// Default [`DEFAULT_EVENT_RATE_LIMIT`]. `None` disables the check.
+    ///
+    /// The warning is logged at most once per 10 seconds and code. Command Complete
+    /// and Command Status are not limited.
+    pub fn event_rate_limit(&mut self, per_second: Option<u32>) -> &mut Self {
+        self.event_rate_limit = per_second;
+        self
+    }
+
+    /// Drop events over [`ClientBuilder::event_rate_limit`] before decoding. Default false.
+    ///
+    /// Dropped events are counted in `ClientStats::events_dropped`. (feature `stats`)
+    pub fn drop_excess_events(&mut self, drop: bool) -> &mut Self {
+        self.drop_excess_events = drop;
+        self
+    }
+
+    /// Timeout of calls. Default `None`, waiting for replies without limit.
+    /// See [`Client::set_default_timeout`].
+    pub fn default_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.default_timeout = timeout;
+        self
+    }
 
-impl Client {
     /// Open client.
+    ///
+    /// Returns [`Error::NotSupportedByKernel`] if the kernel lacks bluetooth support.
+    pub fn open(&self) -> Result<Client> {
+        let sock = MgmtSocket::new(self.inheritable).map_err(|err| match err.raw_os_error() {
+            Some(libc::EAFNOSUPPORT | libc::EPROTONOSUPPORT) => Error::NotSupportedByKernel(err),
+            _ => Error::Io(err),
+        })?;
+        let fd = sock.as_raw_fd();
+        let limiter = EventLimiter::new(self.event_rate_limit, self.drop_excess_events);
+        let inner = ClientInner::with_limiter(sock, limiter);
+        inner.set_default_timeout(self.default_timeout);
+        Ok(Client(inner, fd))
+    }
+}
+
+/// The mgmt socket, for read-only introspection. (e.g. `fcntl(F_GETFD)`)
+///
+/// Reading, writing or closing it breaks the client.
+impl AsRawFd for Client {
+    fn as_raw_fd(&self) -> RawFd {
+        self.1
+    }
+}
+
+impl Client {
+    /// Open client. The socket is close-on-exec. (see [`ClientBuilder::inheritable`])
+    ///
+    /// Returns [`Error::NotSupportedByKernel`] if the kernel lacks bluetooth support.
     pub fn open() -> Result<Self> {
-        let sock = MgmtSocket::new()?;
-        Ok(Self(ClientInner::new(sock)))
+        Self::builder().open()
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// Client connected to the peer which acts as the kernel.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn pair() -> (Self, tokio::net::UnixDatagram) {
+        let (sock, peer) = MgmtSocket::pair().unwrap();
+        let fd = sock.as_raw_fd();
+        (Self(ClientInner::new(sock), fd), peer)
+    }
+
+    /// Handle for the controller.
+    pub fn adapter<I>(&self, index: I) -> Adapter
+    where
+        I: Into<ControllerIndex>,
+    {
+        Adapter::new(self.clone(), index.into())
     }
 
     /// Subscribe mgmt API events.
@@ -463,7 +1436,68 @@ impl Client {
         EventSubscribe(inner)
     }
 
+    /// Subscribe mgmt API events, queueing at most `capacity` not yet taken.
+    ///
+    /// An event arriving when full is handled by `policy`, and counted by
+    /// [`EventSubscribe::dropped`] unless blocked. [`Client::events`] is unbounded.
+    pub async fn events_with(
+        &self,
+        capacity: usize,
+        policy: EventOverflowPolicy,
+    ) -> EventSubscribe {
+        let inner = self.0.events_with(capacity, policy).await;
+        EventSubscribe(inner)
+    }
+
+    /// Subscribe mgmt API events of type `E`, such as [`event::DeviceFound`], of the
+    /// controller or of any controller if `None`.
+    ///
+    /// Filtered when received as [`Client::events_filtered`], so other events are neither
+    /// queued nor copied. [`Event`] takes events of all types.
+    pub async fn events_for<E>(
+        &self,
+        index: Option<ControllerIndex>,
+    ) -> impl Stream<Item = (ControllerIndex, E)> + Send + Unpin
+    where
+        E: TryFrom<Event> + Send + 'static,
+        for<'a> &'a E: TryFrom<&'a Event>,
+    {
+        self.events_filtered(move |i, event| {
+            index.as_ref().is_none_or(|index| i == index) && <&E>::try_from(event).is_ok()
+        })
+        .await
+        .filter_map(|(index, event)| {
+            futures_util::future::ready(E::try_from(event).ok().map(|event| (index, event)))
+        })
+    }
+
+    /// Subscribe mgmt API events for which `filter` returns true.
+    ///
+    /// Filtered when received, so dropped events are neither queued nor copied for this
+    /// subscriber.
+    pub async fn events_filtered<F>(&self, filter: F) -> EventSubscribe
+    where
+        F: Fn(&ControllerIndex, &Event) -> bool + Send + 'static,
+    {
+        let inner = self.0.events_filtered(Box::new(filter)).await;
+        EventSubscribe(inner)
+    }
+
+    /// Subscribe mgmt API events, shared with other subscribers.
+    ///
+    /// Each event is decoded once and handed out by reference count, where
+    /// [`Client::events`] copies it for every subscriber.
+    pub async fn events_shared(&self) -> SharedEventSubscribe {
+        let inner = self.0.events_shared().await;
+        SharedEventSubscribe(inner)
+    }
+
     /// Call mgmt API command.
+    ///
+    /// Calls of a client, including its clones, are sent one at a time, each after the
+    /// reply of the previous one. So joining calls does not overlap them.
+    /// If the socket is closed, pending and later calls resolve to [`Error::Closed`].
+    /// The client does not reconnect; open a new one and retry.
     pub fn call<C, I>(
         &self,
         index: I,
@@ -476,78 +1510,701 @@ impl Client {
     {
         self.0.call(index.into(), command)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::command::CommandCode;
-    use crate::packet::ErrorCode;
+    /// Call mgmt API command, resolving to [`Error::Timeout`] unless replied in `timeout`.
+    ///
+    /// The time includes waiting for the calls queued before. A reply arriving after
+    /// the timeout is discarded, not taken for a later call. Overrides
+    /// [`Client::set_default_timeout`].
+    pub fn call_with_timeout<C, I>(
+        &self,
+        index: I,
+        command: C,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<C::Reply>> + 'static
+    where
+        C: command::CommandRequest + 'static,
+        C::Reply: fmt::Debug,
+        I: Into<ControllerIndex>,
+    {
+        self.0
+            .call_as_within(None, index.into(), command, Some(timeout))
+    }
 
-    use super::*;
+    /// Timeout of calls without one, as [`Client::call_with_timeout`]. Default `None`.
+    ///
+    /// `None` clears it. Shared by the clones and the halves of [`Client::split`].
+    /// Applies to the calls made afterwards, including ones by [`Adapter`].
+    pub fn set_default_timeout(&self, timeout: Option<Duration>) {
+        self.0.set_default_timeout(timeout)
+    }
 
-    #[tokio::test]
-    async fn test_stream_recv() {
-        let stream = tokio_test::io::Builder::new()
-            .read(&[
-                0x01, 0x00, 0xFF, 0xFF, 0x06, 0x00, 0x01, 0x00, 0x00, 0x01, 0x13, 0x00,
-            ])
-            .read(&[
-                0x01, 0x00, 0xFF, 0xFF, 0x06, 0x00, 0x01, 0x00, 0x00, 0x01, 0x13, 0x00,
-            ])
-            .read(&[
-                0x01, 0x00, 0xFF, 0xFF, 0x06, 0x00, 0x01, 0x00, 0x00, 0x01, 0x13, 0x00,
-            ])
-            .build();
-        let mut stream = EventStream::new(stream);
+    /// Call mgmt API command with encoded parameters.
+    ///
+    /// Replies for any opcode are delivered, including ones unknown to this crate.
+    /// Non-success status is not treated as an error. See [`RawReply::status`].
+    pub fn call_raw<I, P>(
+        &self,
+        index: I,
+        opcode: u16,
+        params: P,
+    ) -> impl Future<Output = Result<RawReply>> + 'static
+    where
+        I: Into<ControllerIndex>,
+        P: Into<Box<[u8]>>,
+    {
+        self.0.call_raw(index, opcode, params)
+    }
 
-        let mut n = 0usize;
-        while let Some(r) = stream.next().await {
-            let (index, event) = r.unwrap();
-            assert_eq!(ControllerIndex::NonController, index);
-            if let Event::CommandComplete(comp) = event {
-                assert_eq!(
-                    &CommandCode::ReadManagementVersionInformation,
-                    comp.opcode()
-                );
-                assert_eq!(&ErrorCode::Success, comp.status());
-                assert_eq!(&[0x01, 0x13, 0x00][..], comp.data().as_ref());
+    /// Call multiple commands for the controller at once.
+    ///
+    /// All commands are written before awaiting replies, so round trips overlap.
+    /// (The mgmt socket takes one command per write, so frames are not coalesced.)
+    /// Results are in the order of `commands`. Non-success status fails only that command.
+    /// The reply parameters are not decoded. See [`RawReply::params`].
+    pub fn call_batch<I>(
+        &self,
+        index: I,
+        commands: Vec<Command>,
+    ) -> impl Future<Output = Result<Vec<Result<RawReply>>>> + 'static
+    where
+        I: Into<ControllerIndex>,
+    {
+        self.0.call_batch(index.into(), commands)
+    }
+
+    /// Set a single settings flag by its command. Returns current settings.
+    pub async fn set_setting<I>(
+        &self,
+        index: I,
+        flag: SettingFlag,
+        enable: bool,
+    ) -> Result<Settings>
+    where
+        I: Into<ControllerIndex>,
+    {
+        use crate::packet::{Advertising, DebugKeys, Discoverable, SecureConnections};
+
+        let index = index.into();
+        let settings = match flag {
+            SettingFlag::Powered => *self.call(index, command::SetPowered::new(enable)).await?,
+            SettingFlag::Connectable => {
+                *self
+                    .call(index, command::SetConnectable::new(enable))
+                    .await?
+            }
+            SettingFlag::FastConnectable => {
+                *self
+                    .call(index, command::SetFastConnectable::new(enable))
+                    .await?
+            }
+            SettingFlag::Discoverable => {
+                let discoverable = if enable {
+                    Discoverable::General
+                } else {
+                    Discoverable::Disable
+                };
+                *self
+                    .call(index, command::SetDiscoverable::new(discoverable, 0))
+                    .await?
+            }
+            SettingFlag::Bondable => *self.call(index, command::SetBondable::new(enable)).await?,
+            SettingFlag::LinkSecurity => {
+                *self
+                    .call(index, command::SetLinkSecurity::new(enable))
+                    .await?
+            }
+            SettingFlag::Ssp => {
+                *self
+                    .call(index, command::SetSecureSimplePairing::new(enable))
+                    .await?
+            }
+            SettingFlag::HighSpeed => *self.call(index, command::SetHighSpeed::new(enable)).await?,
+            SettingFlag::Le => *self.call(index, command::SetLowEnergy::new(enable)).await?,
+            SettingFlag::BrEdr => *self.call(index, command::SetBrEdr::new(enable)).await?,
+            SettingFlag::Advertising => {
+                let advertising = if enable {
+                    Advertising::Enable
+                } else {
+                    Advertising::Disable
+                };
+                *self
+                    .call(index, command::SetAdvertising::new(advertising))
+                    .await?
+            }
+            SettingFlag::SecureConnections => {
+                let secure_connections = if enable {
+                    SecureConnections::Enable
+                } else {
+                    SecureConnections::Disable
+                };
+                *self
+                    .call(
+                        index,
+                        command::SetSecureConnections::new(secure_connections),
+                    )
+                    .await?
+            }
+            SettingFlag::DebugKeys => {
+                let debug_keys = if enable {
+                    DebugKeys::Enable
+                } else {
+                    DebugKeys::Disable
+                };
+                *self
+                    .call(index, command::SetDebugKeys::new(debug_keys))
+                    .await?
+            }
+            SettingFlag::WidebandSpeech => {
+                *self
+                    .call(index, command::SetWidbandSpeech::new(enable))
+                    .await?
+            }
+        };
+        Ok(settings)
+    }
+
+    /// Set bondable, Secure Simple Pairing and Secure Connections at once. Returns current settings.
+    ///
+    /// Secure Connections is changed before Secure Simple Pairing when disabling,
+    /// and after when enabling, since it requires SSP.
+    /// Settings not supported by the controller are left as is when the mode disables them,
+    /// and [`Error::SettingNotSupported`] is returned when the mode needs them.
+    pub async fn set_pairing_mode<I>(&self, index: I, mode: PairingMode) -> Result<Settings>
+    where
+        I: Into<ControllerIndex>,
+    {
+        use crate::packet::SecureConnections;
+
+        let index = index.into();
+        let info = self
+            .call(index.clone(), command::ReadControllerInformation)
+            .await?;
+        let supported = |flag: SettingFlag| {
+            if info.supported_settings().contains(Settings::from(flag)) {
+                Ok(true)
+            } else if mode.requires(flag) {
+                Err(Error::SettingNotSupported {
+                    flag,
+                    index: index.clone(),
+                })
             } else {
-                panic!()
-            };
-            n += 1;
+                Ok(false)
+            }
+        };
+        let ssp = supported(SettingFlag::Ssp)?;
+        let sc = supported(SettingFlag::SecureConnections)?;
+
+        if mode == PairingMode::Off {
+            return Ok(*self.call(index, command::SetBondable::new(false)).await?);
         }
-        assert_eq!(3, n);
+
+        if sc && !mode.requires(SettingFlag::SecureConnections) {
+            self.call(
+                index.clone(),
+                command::SetSecureConnections::new(SecureConnections::Disable),
+            )
+            .await?;
+        }
+        if ssp {
+            let enable = mode.requires(SettingFlag::Ssp);
+            self.call(index.clone(), command::SetSecureSimplePairing::new(enable))
+                .await?;
+        }
+        if mode == PairingMode::SecureConnectionsOnly {
+            self.call(
+                index.clone(),
+                command::SetSecureConnections::new(SecureConnections::Only),
+            )
+            .await?;
+        }
+        Ok(*self.call(index, command::SetBondable::new(true)).await?)
     }
 
-    #[tokio::test]
-    async fn test_stream_send() {
-        let io = <Vec<u8>>::new();
+    /// Start discovery and receive Device Found events rate limited per device.
+    ///
+    /// Name confirmation is not handled. It is never asked for LE only discovery
+    /// (see [`AddressTypes::is_le_only`]). With BR/EDR included, answer
+    /// [`crate::packet::DeviceConnectFlags::ConfirmName`] by [`command::ConfirmName`].
+    /// See [`ThrottledDeviceFound`].
+    pub async fn discover_throttled<I>(
+        &self,
+        index: I,
+        address_types: AddressTypes,
+        min_interval_per_device: Duration,
+    ) -> Result<ThrottledDeviceFound>
+    where
+        I: Into<ControllerIndex>,
+    {
+        let index = index.into();
+        let events = self.events().await;
+        self.call(index.clone(), command::StartDiscovery::new(address_types))
+            .await?;
+        Ok(ThrottledDeviceFound::new(
+            events,
+            index,
+            min_interval_per_device,
+        ))
+    }
 
-        let mut stream = EventStream::new(io);
+    /// Wait until a controller is available, returning the first one.
+    ///
+    /// Returns immediately if the index list is not empty. Otherwise waits for
+    /// Index Added, or returns [`Error::NoAdapters`] when `timeout` elapsed.
+    /// `None` waits forever.
+    pub async fn wait_for_controller(&self, timeout: Option<Duration>) -> Result<ControllerIndex> {
+        let mut events = self.events().await;
+        let list = self.call(None, command::ReadControllerIndexList).await?;
+        if let Some(index) = list.iter().next() {
+            return Ok(index.clone());
+        }
 
-        let i = ControllerIndex::ControllerId(0);
-        let c = command::SetPowered::from(true).into();
-        stream.send((i, c)).await.unwrap();
+        let added = async {
+            while let Some((index, event)) = events.next().await {
+                if let Event::IndexAdded(..) = event {
+                    return Ok(index);
+                }
+            }
+            Err(Error::Closed)
+        };
+        match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, added)
+                .await
+                .map_err(|_| Error::NoAdapters)?,
+            None => added.await,
+        }
     }
 
-    #[tokio::test]
-    async fn test_client_request() {
-        use btmgmt_packet as packet;
+    /// Stream of configured controllers coming and going.
+    ///
+    /// Starts with [`ControllerChange::Added`] for each controller of Read Controller
+    /// Index List, then follows Index Added and Index Removed. Events repeating the
+    /// known state (e.g. added while the list was read) are skipped, so each controller
+    /// alternates between added and removed.
+    pub async fn watch_controllers(
+        &self,
+    ) -> Result<impl Stream<Item = ControllerChange> + Send + Unpin> {
+        let events = self
+            .events_filtered(|_, event| {
+                matches!(event, Event::IndexAdded(..) | Event::IndexRemoved(..))
+            })
+            .await;
+        let list = self.call(None, command::ReadControllerIndexList).await?;
+        let initial = list.into_iter().collect::<Vec<_>>();
+
+        let mut known = initial.iter().cloned().collect::<HashSet<_>>();
+        let changes = events.filter_map(move |(index, event)| {
+            let change = match event {
+                Event::IndexAdded(..) if known.insert(index.clone()) => {
+                    Some(ControllerChange::Added(index))
+                }
+                Event::IndexRemoved(..) if known.remove(&index) => {
+                    Some(ControllerChange::Removed(index))
+                }
+                _ => None,
+            };
+            futures_util::future::ready(change)
+        });
+        Ok(
+            futures_util::stream::iter(initial.into_iter().map(ControllerChange::Added))
+                .chain(changes),
+        )
+    }
 
-        let stream = tokio_test::io::Builder::new()
-            .write(&[0x01, 0x00, 0xFF, 0xFF, 0x00, 0x00]) // read management version information
-            .read(&[
-                0x01, 0x00, 0xFF, 0xFF, 0x06, 0x00, 0x01, 0x00, 0x00, 0x01, 0x13, 0x00,
-            ]) // reply
-            .read(&[0x04, 0x00, 0x00, 0x00, 0x00, 0x00]) // index added
-            .build();
-        let client = ClientInner::new(stream);
-        let reply = client
-            .call(None, packet::command::ReadManagementVersionInformation)
+    /// Read the configured, unconfigured and extended controller index lists.
+    ///
+    /// Read in turn, as calls of a client are. Lists of commands unknown to the kernel are
+    /// `None`.
+    pub async fn all_indices(&self) -> Result<AllIndices> {
+        fn known<T>(result: Result<T>) -> Result<Option<T>> {
+            match result {
+                Err(Error::CommandStatus {
+                    status: ErrorCode::UnknownCommand,
+                    ..
+                }) => Ok(None),
+                result => result.map(Some),
+            }
+        }
+
+        let configured = self.call(None, command::ReadControllerIndexList).await;
+        let unconfigured = self
+            .call(None, command::ReadUnconfiguredControllerIndexList)
+            .await;
+        let extended = self
+            .call(None, command::ReadExtendedControllerIndexList)
+            .await;
+        Ok(AllIndices {
+            configured: configured?.into_iter().collect(),
+            unconfigured: known(unconfigured)?.map(|list| list.into_iter().collect()),
+            extended: known(extended)?.map(|list| list.into_iter().collect()),
+        })
+    }
+
+    /// Summaries of configured controllers, in index order.
+    ///
+    /// Reads Read Extended Controller Index List, falling back to Read Controller Index List
+    /// if unknown to the kernel, then Read Controller Information of each in turn.
+    /// Unconfigured and AMP controllers are left out, as are controllers removed before
+    /// their read (`Invalid Index`).
+    pub async fn controller_summaries(&self) -> Result<Vec<ControllerSummary>> {
+        let indices = match self
+            .call(None, command::ReadExtendedControllerIndexList)
             .await
-            .unwrap();
-        assert_eq!(1, *reply.version());
-        assert_eq!(0x0013, *reply.revision());
+        {
+            Ok(list) => list
+                .into_iter()
+                .filter(|(_, ty, _)| matches!(ty, ControllerType::PrimaryController))
+                .map(|(index, ty, bus)| (index, Some(ty), Some(bus)))
+                .collect::<Vec<_>>(),
+            Err(Error::CommandStatus {
+                status: ErrorCode::UnknownCommand,
+                ..
+            }) => self
+                .call(None, command::ReadControllerIndexList)
+                .await?
+                .into_iter()
+                .map(|index| (index, None, None))
+                .collect(),
+            Err(err) => return Err(err),
+        };
+
+        let mut summaries = vec![];
+        for (index, controller_type, bus) in indices {
+            let info = match self
+                .call(index.clone(), command::ReadControllerInformation)
+                .await
+            {
+                Ok(info) => info,
+                Err(err) if err.status() == Some(&ErrorCode::InvalidIndex) => {
+                    log::debug!("{:?} removed: {}", index, err);
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            summaries.push(ControllerSummary {
+                index,
+                address: info.address().clone(),
+                controller_type,
+                bus,
+                manufacturer: (*info.manufacturer()).into(),
+                bluetooth_version: *info.bluetooth_version(),
+                name: info.name().clone(),
+                current_settings: *info.current_settings(),
+            });
+        }
+        summaries.sort_by_key(|summary| u16::from(summary.index.clone()));
+        Ok(summaries)
+    }
+
+    /// Add advertising, and wait until it is live.
+    ///
+    /// The mgmt API does not tell when a controller starts sending advertising: Advertising
+    /// Added is not sent to the adding socket, and the Advertising setting is of Set
+    /// Advertising only. So live means the controller is powered and the instance is listed
+    /// by Read Advertising Features. While the Advertising setting is set, the kernel sends
+    /// that advertising instead of the instances.
+    ///
+    /// Returns [`Error::AdvertisingNotStarted`] if not powered in `timeout`, or if the
+    /// instance is gone meanwhile. The instance is kept if not powered.
+    pub async fn add_advertising_and_wait<I>(
+        &self,
+        index: I,
+        params: command::AddAdvertising,
+        timeout: Duration,
+    ) -> Result<command::AddAdvertisingReply>
+    where
+        I: Into<ControllerIndex>,
+    {
+        let index = index.into();
+        // Subscribe before adding, not to miss powering on.
+        let mut settings = {
+            let index = index.clone();
+            self.events_filtered(move |i, event| {
+                *i == index && matches!(event, Event::NewSettings(..))
+            })
+            .await
+        };
+        let reply = self.call(index.clone(), params).await?;
+        let instance = **reply;
+
+        let powered = async {
+            let info = self
+                .call(index.clone(), command::ReadControllerInformation)
+                .await?;
+            let mut powered = info.current_settings().contains(Settings::Powered);
+            while !powered {
+                match settings.next().await {
+                    Some((_, Event::NewSettings(settings))) => {
+                        powered = settings.contains(Settings::Powered)
+                    }
+                    Some(..) => {}
+                    None => return Err(Error::Closed),
+                }
+            }
+            Ok(())
+        };
+        tokio::time::timeout(timeout, powered)
+            .await
+            .map_err(|_| Error::AdvertisingNotStarted { instance })??;
+
+        let features = self.call(index, command::ReadAdvertisingFeature).await?;
+        if !features.instances().iter().any(|i| **i == instance) {
+            return Err(Error::AdvertisingNotStarted { instance });
+        }
+        Ok(reply)
+    }
+
+    /// Acquire advisory role for the controller. (lock file under [`role::DEFAULT_LOCK_DIR`])
+    ///
+    /// Returns [`Error::RoleHeld`] if another live process holds it.
+    /// A lock left by a dead process is taken over.
+    pub fn acquire_role<I>(&self, role: Role, scope: I) -> Result<RoleGuard>
+    where
+        I: Into<ControllerIndex>,
+    {
+        self.acquire_role_in(role::DEFAULT_LOCK_DIR, role, scope)
+    }
+
+    /// [`Client::acquire_role`] with lock file directory.
+    pub fn acquire_role_in<P, I>(&self, dir: P, role: Role, scope: I) -> Result<RoleGuard>
+    where
+        P: AsRef<Path>,
+        I: Into<ControllerIndex>,
+    {
+        role::acquire(&self.0.roles, dir.as_ref(), role, scope.into())
+    }
+
+    /// true if this client holds the role for the controller.
+    pub fn holds_role(&self, role: Role, scope: &ControllerIndex) -> bool {
+        self.0.roles.holds(role, scope)
+    }
+
+    /// Handle recording resources created through it under `owner`. See [`ScopedClient`].
+    pub fn scoped(&self, owner: &'static str) -> ScopedClient {
+        ScopedClient::new(self.clone(), owner)
+    }
+
+    /// Resources created through [`ScopedClient`]s and not released yet, by owner.
+    ///
+    /// For diagnostics. Removals are seen from replies of [`Client::call`] and from
+    /// events, as events are read.
+    pub fn resources_by_owner(&self) -> HashMap<&'static str, Vec<(ControllerIndex, Resource)>> {
+        self.0.owners.snapshot()
+    }
+
+    pub(crate) fn call_as<C, I>(
+        &self,
+        owner: &'static str,
+        index: I,
+        command: C,
+    ) -> impl Future<Output = Result<C::Reply>> + 'static
+    where
+        C: command::CommandRequest + 'static,
+        C::Reply: fmt::Debug,
+        I: Into<ControllerIndex>,
+    {
+        self.0.call_as(Some(owner), index.into(), command)
+    }
+
+    pub(crate) fn owners(&self) -> &Owners {
+        &self.0.owners
+    }
+
+    pub(crate) fn discovery_trackers(&self) -> &DiscoveryTrackers {
+        &self.0.discovery
+    }
+
+    pub(crate) fn advertising_allocators(&self) -> &AdvertisingAllocators {
+        &self.0.advertising
+    }
+
+    /// Snapshot of counters. (feature `stats`)
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::stats::ClientStats {
+        self.0.counters.snapshot()
+    }
+
+    /// Infer [`AddressType`] for an address given without type.
+    ///
+    /// For LE-only controllers (BR/EDR disabled in current settings), returns
+    /// [`AddressType::LeRandom`] for static random addresses and [`AddressType::LePublic`]
+    /// otherwise. Returns [`AddressType::BrEdr`] for other controllers.
+    pub async fn infer_address_type<I>(&self, index: I, bd: &BdAddr) -> Result<AddressType>
+    where
+        I: Into<ControllerIndex>,
+    {
+        self.0.infer_address_type(index.into(), bd).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adapter::ConnectableConflict;
+    use crate::command::CommandCode;
+    use crate::packet::advertising::AdvertisingParams;
+    use crate::packet::ErrorCode;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stream_recv() {
+        let stream = tokio_test::io::Builder::new()
+            .read(&[
+                0x01, 0x00, 0xFF, 0xFF, 0x06, 0x00, 0x01, 0x00, 0x00, 0x01, 0x13, 0x00,
+            ])
+            .read(&[
+                0x01, 0x00, 0xFF, 0xFF, 0x06, 0x00, 0x01, 0x00, 0x00, 0x01, 0x13, 0x00,
+            ])
+            .read(&[
+                0x01, 0x00, 0xFF, 0xFF, 0x06, 0x00, 0x01, 0x00, 0x00, 0x01, 0x13, 0x00,
+            ])
+            .build();
+        let mut stream = EventStream::new(stream, Default::default());
+
+        let mut n = 0usize;
+        while let Some(r) = stream.next().await {
+            let (index, event) = r.unwrap();
+            assert_eq!(ControllerIndex::NonController, index);
+            if let Event::CommandComplete(comp) = event {
+                assert_eq!(
+                    &CommandCode::ReadManagementVersionInformation,
+                    comp.opcode()
+                );
+                assert_eq!(&ErrorCode::Success, comp.status());
+                assert_eq!(&[0x01, 0x13, 0x00][..], comp.data().as_ref());
+            } else {
+                panic!()
+            };
+            n += 1;
+        }
+        assert_eq!(3, n);
+    }
+
+    #[tokio::test]
+    async fn test_stream_send() {
+        let io = <Vec<u8>>::new();
+
+        let mut stream = EventStream::new(io, Default::default());
+
+        let i = ControllerIndex::ControllerId(0);
+        let c = command::SetPowered::from(true).into();
+        stream.send((i, c)).await.unwrap();
+    }
+
+    /// Accepts at most 4 bytes per write.
+    struct ShortWrite(Vec<u8>, usize);
+
+    impl AsyncWrite for ShortWrite {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let this = self.get_mut();
+            let n = buf.len().min(4);
+            this.0.extend(&buf[..n]);
+            this.1 += 1;
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_send_partial() {
+        let mut stream = EventStream::new(ShortWrite(vec![], 0), Default::default());
+
+        let params = (0..10).collect::<Vec<u8>>();
+        let i = ControllerIndex::ControllerId(1);
+        stream
+            .send((i.clone(), 0x00FF, params.clone().into()))
+            .await
+            .unwrap();
+        stream
+            .send((i, command::SetPowered::from(true).into()))
+            .await
+            .unwrap();
+
+        let mut expected = vec![0xFF, 0x00, 0x01, 0x00, 0x0A, 0x00];
+        expected.extend(&params);
+        expected.extend([0x05, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01]);
+        assert_eq!(expected, stream.io.0);
+        // not vectored: header and parameters written separately.
+        assert_eq!(8, stream.io.1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_send_failed() {
+        let io = tokio_test::io::Builder::new()
+            .write_error(io::Error::from_raw_os_error(libc::EMSGSIZE))
+            .write(&[0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01])
+            .build();
+        let mut stream = EventStream::new(io, Default::default());
+
+        let i = ControllerIndex::ControllerId(0);
+        let err = stream
+            .send((i.clone(), command::SetPowered::from(true).into()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Io(..)), "{:?}", err);
+        // not blocked by the failed frame.
+        stream
+            .send((i, command::SetPowered::from(true).into()))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_large_frames() {
+        let (client, peer) = Client::pair();
+
+        let params = (0..1000).map(|i| i as u8).collect::<Vec<_>>();
+        let reply = (0..10000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+        let kernel = async {
+            let mut buf = vec![0; 2048];
+            let n = peer.recv(&mut buf).await.unwrap();
+            // single packet
+            assert_eq!(1006, n);
+            assert_eq!(&[0xFF, 0x00, 0x00, 0x00, 0xE8, 0x03], &buf[..6]);
+            assert_eq!(&params[..], &buf[6..n]);
+
+            // larger than a page
+            let mut b = vec![0x01, 0x00, 0x00, 0x00];
+            b.extend(&(reply.len() as u16 + 3).to_le_bytes());
+            b.extend([0xFF, 0x00, 0x00]);
+            b.extend(&reply);
+            peer.send(&b).await.unwrap();
+        };
+        let (result, _) = tokio::join!(client.call_raw(0, 0x00FF, params.clone()), kernel);
+        let result = result.unwrap();
+        assert_eq!(&ErrorCode::Success, result.status());
+        assert_eq!(&reply[..], result.params());
+    }
+
+    #[tokio::test]
+    async fn test_client_request() {
+        use btmgmt_packet as packet;
+
+        let stream = tokio_test::io::Builder::new()
+            .write(&[0x01, 0x00, 0xFF, 0xFF, 0x00, 0x00]) // read management version information
+            .read(&[
+                0x01, 0x00, 0xFF, 0xFF, 0x06, 0x00, 0x01, 0x00, 0x00, 0x01, 0x13, 0x00,
+            ]) // reply
+            .read(&[0x04, 0x00, 0x00, 0x00, 0x00, 0x00]) // index added
+            .build();
+        let client = ClientInner::new(stream);
+        let reply = client
+            .call(None, packet::command::ReadManagementVersionInformation)
+            .await
+            .unwrap();
+        assert_eq!(1, *reply.version());
+        assert_eq!(0x0013, *reply.revision());
 
         let mut events = client.events().await;
         let (idx, evt) = events.next().await.unwrap();
@@ -557,4 +2214,1252 @@ mod tests {
             packet::event::Event::IndexAdded(packet::event::IndexAdded)
         ));
     }
+
+    #[tokio::test]
+    async fn test_client_request_failed() {
+        use btmgmt_packet as packet;
+
+        let stream = tokio_test::io::Builder::new()
+            .write(&[0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01]) // set powered
+            .read(&[0x01, 0x00, 0x00, 0x00, 0x03, 0x00, 0x05, 0x00, 0x0F]) // complete (not powered)
+            .write(&[0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01]) // set powered
+            .read(&[0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x05, 0x00, 0x14]) // status (permission denied)
+            .build();
+        let client = ClientInner::new(stream);
+
+        let err = client
+            .call(0, packet::command::SetPowered::from(true))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::CommandStatus {
+                command: CommandCode::SetPowered,
+                index: ControllerIndex::ControllerId(0),
+                status: ErrorCode::NotPowered,
+            }
+        ));
+
+        let err = client
+            .call(0, packet::command::SetPowered::from(true))
+            .await
+            .unwrap_err();
+        assert_eq!(Some(&ErrorCode::PermissionDenied), err.status());
+        assert!(err.is_permission_denied());
+        assert_eq!(
+            "command SetPowered for ControllerId(0) failed: Permission Denied (0x14)",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_raw_reply_busy() {
+        // complete (busy), no parameters
+        let bytes = [0x01, 0x00, 0x00, 0x00, 0x03, 0x00, 0x05, 0x00, 0x0A];
+        let (_, event) = event::unpack_events(&mut &bytes[..]).unwrap();
+        let reply = RawReply::from_event(event).unwrap();
+        assert_eq!(0x0005, reply.opcode());
+        assert_eq!(&ErrorCode::Busy, reply.status());
+        assert!(reply.params().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_client_request_busy() {
+        use btmgmt_packet as packet;
+
+        let stream = tokio_test::io::Builder::new()
+            .write(&[0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01]) // set powered
+            .read(&[0x01, 0x00, 0x00, 0x00, 0x03, 0x00, 0x05, 0x00, 0x0A]) // complete (busy)
+            .build();
+        let client = ClientInner::new(stream);
+
+        let err = client
+            .call(0, packet::command::SetPowered::from(true))
+            .await
+            .unwrap_err();
+        assert_eq!(Some(&ErrorCode::Busy), err.status());
+        assert!(err.is_busy());
+        assert!(err.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_client_clone() {
+        use btmgmt_packet as packet;
+
+        let (client, peer) = Client::pair();
+        let kernel = |times: usize| {
+            let peer = &peer;
+            async move {
+                let mut buf = [0; 64];
+                for _ in 0..times {
+                    let n = peer.recv(&mut buf).await.unwrap();
+                    assert_eq!(&[0x01, 0x00, 0xFF, 0xFF, 0x00, 0x00], &buf[..n]);
+                    peer.send(&[
+                        0x01, 0x00, 0xFF, 0xFF, 0x06, 0x00, 0x01, 0x00, 0x00, 0x01, 0x13, 0x00,
+                    ])
+                    .await
+                    .unwrap();
+                }
+            }
+        };
+
+        let cloned = client.clone();
+        assert_eq!(client.as_raw_fd(), cloned.as_raw_fd());
+        let task = tokio::spawn({
+            let cloned = cloned.clone();
+            async move {
+                cloned
+                    .call(None, packet::command::ReadManagementVersionInformation)
+                    .await
+            }
+        });
+        let (reply, result, _) = tokio::join!(
+            client.call(None, packet::command::ReadManagementVersionInformation),
+            task,
+            kernel(2)
+        );
+        assert_eq!(1, *reply.unwrap().version());
+        assert_eq!(1, *result.unwrap().unwrap().version());
+
+        drop(client);
+        let (reply, _) = tokio::join!(
+            cloned.call(None, packet::command::ReadManagementVersionInformation),
+            kernel(1)
+        );
+        assert_eq!(0x0013, *reply.unwrap().revision());
+
+        // the socket is closed with the last clone.
+        drop(cloned);
+        assert!(peer.send(&[0x00]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_event_before_reply() {
+        let (client, peer) = Client::pair();
+        let mut events = client.events().await;
+
+        let settings = 0x01u32.to_le_bytes();
+        let kernel = async {
+            let mut buf = [0; 64];
+            peer.recv(&mut buf).await.unwrap();
+            // New Settings, then the Command Complete of Set Powered.
+            let mut b = vec![0x06, 0x00, 0x00, 0x00, 0x04, 0x00];
+            b.extend(settings);
+            peer.send(&b).await.unwrap();
+            let mut b = vec![0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0x05, 0x00, 0x00];
+            b.extend(settings);
+            peer.send(&b).await.unwrap();
+        };
+        let (reply, _) = tokio::join!(client.call(0, command::SetPowered::new(true)), kernel);
+        reply.unwrap();
+
+        // already queued: no need to read the socket.
+        let (_, event) = events.next().now_or_never().unwrap().unwrap();
+        assert!(matches!(event, Event::NewSettings(..)), "{:?}", event);
+        assert!(events.next().now_or_never().is_none());
+
+        // Command Complete, then New Settings: the event follows.
+        let kernel = async {
+            let mut buf = [0; 64];
+            peer.recv(&mut buf).await.unwrap();
+            let mut b = vec![0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0x05, 0x00, 0x00];
+            b.extend(settings);
+            peer.send(&b).await.unwrap();
+            let mut b = vec![0x06, 0x00, 0x00, 0x00, 0x04, 0x00];
+            b.extend(settings);
+            peer.send(&b).await.unwrap();
+        };
+        let (reply, _) = tokio::join!(client.call(0, command::SetPowered::new(true)), kernel);
+        reply.unwrap();
+        let (_, event) = events.next().await.unwrap();
+        assert!(matches!(event, Event::NewSettings(..)), "{:?}", event);
+    }
+
+    #[tokio::test]
+    async fn test_client_request_closed() {
+        use btmgmt_packet as packet;
+
+        let stream = tokio_test::io::Builder::new()
+            .write(&[0x01, 0x00, 0xFF, 0xFF, 0x00, 0x00])
+            .build();
+        let client = ClientInner::new(stream);
+        let err = client
+            .call(None, packet::command::ReadManagementVersionInformation)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Closed));
+    }
+
+    #[tokio::test]
+    async fn test_client_request_closed_pending() {
+        use btmgmt_packet as packet;
+
+        // one frame written, then the socket is gone.
+        let stream = tokio_test::io::Builder::new()
+            .write(&[0x01, 0x00, 0xFF, 0xFF, 0x00, 0x00])
+            .build();
+        let client = ClientInner::new(stream);
+        let mut events = client.events().await;
+        let (first, second) = tokio::join!(
+            client.call(None, packet::command::ReadManagementVersionInformation),
+            client.call(None, packet::command::ReadManagementSupportedCommands),
+        );
+        assert!(matches!(first.unwrap_err(), Error::Closed));
+        // not written.
+        assert!(matches!(second.unwrap_err(), Error::Closed));
+        assert!(events.next().await.is_none());
+
+        let err = client.call_raw(None, 0x0001, vec![]).await.unwrap_err();
+        assert!(matches!(err, Error::Closed));
+    }
+
+    fn controller_information_reply(settings: Settings) -> Vec<u8> {
+        let mut params = vec![0x00; 280];
+        params[13..17].copy_from_slice(&settings.bits().to_le_bytes());
+        let mut b = vec![0x01, 0x00, 0x00, 0x00, 0x1B, 0x01, 0x04, 0x00, 0x00];
+        b.extend(params);
+        b
+    }
+
+    #[tokio::test]
+    async fn test_infer_address_type() {
+        let le_only = Settings::Powered | Settings::LowEnergy;
+        let dual = le_only | Settings::BasicRateEnhancedDataRate;
+        let stream = tokio_test::io::Builder::new()
+            .write(&[0x04, 0x00, 0x00, 0x00, 0x00, 0x00])
+            .read(&controller_information_reply(le_only))
+            .write(&[0x04, 0x00, 0x00, 0x00, 0x00, 0x00])
+            .read(&controller_information_reply(le_only))
+            .write(&[0x04, 0x00, 0x00, 0x00, 0x00, 0x00])
+            .read(&controller_information_reply(dual))
+            .build();
+        let client = ClientInner::new(stream);
+
+        let random_static = "C1:22:33:44:55:66".parse::<BdAddr>().unwrap();
+        let public = "00:11:22:33:44:55".parse::<BdAddr>().unwrap();
+        let index = ControllerIndex::from(0);
+        assert_eq!(
+            AddressType::LeRandom,
+            client
+                .infer_address_type(index.clone(), &random_static)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            AddressType::LePublic,
+            client
+                .infer_address_type(index.clone(), &public)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            AddressType::BrEdr,
+            client.infer_address_type(index, &public).await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_call_raw_unknown_opcode() {
+        let stream = tokio_test::io::Builder::new()
+            .write(&[0xFF, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x02])
+            .read(&[
+                0x01, 0x00, 0x00, 0x00, 0x05, 0x00, 0xFF, 0x00, 0x00, 0xAB, 0xCD,
+            ])
+            .write(&[0xFF, 0x00, 0x00, 0x00, 0x00, 0x00])
+            .read(&[0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0xFF, 0x00, 0x01])
+            .build();
+        let client = ClientInner::new(stream);
+
+        let reply = client.call_raw(0, 0x00FF, vec![0x01, 0x02]).await.unwrap();
+        assert_eq!(0x00FF, reply.opcode());
+        assert_eq!(&ErrorCode::Success, reply.status());
+        assert_eq!(&[0xAB, 0xCD], reply.params());
+
+        let reply = client.call_raw(0, 0x00FF, vec![]).await.unwrap();
+        assert_eq!(0x00FF, reply.opcode());
+        assert_eq!(&ErrorCode::UnknownCommand, reply.status());
+        assert!(reply.params().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_set_setting() {
+        let (client, peer) = Client::pair();
+
+        let kernel = async {
+            let mut buf = [0; 1024];
+            let n = peer.recv(&mut buf).await.unwrap();
+            // Set Low Energy
+            assert_eq!(&[0x0D, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01], &buf[..n]);
+            peer.send(&[
+                0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0x0D, 0x00, 0x00, 0x81, 0x02, 0x00, 0x00,
+            ])
+            .await
+            .unwrap();
+
+            let n = peer.recv(&mut buf).await.unwrap();
+            // Set Discoverable, no timeout
+            assert_eq!(
+                &[0x06, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00],
+                &buf[..n]
+            );
+            peer.send(&[
+                0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0x06, 0x00, 0x00, 0x81, 0x02, 0x00, 0x00,
+            ])
+            .await
+            .unwrap();
+        };
+        let (settings, _) = tokio::join!(
+            async {
+                let le = client.set_setting(0, SettingFlag::Le, true).await?;
+                client
+                    .set_setting(0, SettingFlag::Discoverable, false)
+                    .await?;
+                Ok::<_, Error>(le)
+            },
+            kernel
+        );
+        let settings = settings.unwrap();
+        assert!(settings.contains(Settings::from(SettingFlag::Le)));
+        assert!(!settings.contains(Settings::Discoverable));
+    }
+
+    #[tokio::test]
+    async fn test_set_pairing_mode() {
+        let (client, peer) = Client::pair();
+
+        let kernel = async {
+            let mut buf = [0; 1024];
+            let n = peer.recv(&mut buf).await.unwrap();
+            // Read Controller Information
+            assert_eq!(&[0x04, 0x00, 0x00, 0x00, 0x00, 0x00], &buf[..n]);
+            let (info, _) = crate::packet::fixtures::controller_information_reply();
+            peer.send(&info).await.unwrap();
+
+            for &(opcode, param, settings) in &[
+                (0x0B, 0x01, 0x40), // Set Secure Simple Pairing
+                (0x2D, 0x02, 0x40), // Set Secure Connections (Only)
+                (0x09, 0x01, 0x50), // Set Bondable
+            ] {
+                let n = peer.recv(&mut buf).await.unwrap();
+                assert_eq!(&[opcode, 0x00, 0x00, 0x00, 0x01, 0x00, param], &buf[..n]);
+                peer.send(&[
+                    0x01, 0x00, 0x00, 0x00, 0x07, 0x00, opcode, 0x00, 0x00, settings, 0x08, 0x00,
+                    0x00,
+                ])
+                .await
+                .unwrap();
+            }
+        };
+        let (settings, _) = tokio::join!(
+            client.set_pairing_mode(0, PairingMode::SecureConnectionsOnly),
+            kernel
+        );
+        let settings = settings.unwrap();
+        assert!(settings.contains(Settings::from(SettingFlag::Bondable)));
+        assert!(settings.contains(Settings::from(SettingFlag::SecureConnections)));
+    }
+
+    #[tokio::test]
+    async fn test_set_pairing_mode_not_supported() {
+        let (client, peer) = Client::pair();
+
+        let kernel = async {
+            let mut buf = [0; 1024];
+            peer.recv(&mut buf).await.unwrap();
+            let (mut info, _) = crate::packet::fixtures::controller_information_reply();
+            info[19] &= !0x08; // no Secure Connections
+            peer.send(&info).await.unwrap();
+        };
+        let (err, _) = tokio::join!(
+            client.set_pairing_mode(0, PairingMode::SecureConnectionsOnly),
+            kernel
+        );
+        assert!(matches!(
+            err.unwrap_err(),
+            Error::SettingNotSupported {
+                flag: SettingFlag::SecureConnections,
+                ..
+            }
+        ));
+
+        // nothing sent
+        let mut buf = [0; 16];
+        assert_eq!(
+            std::io::ErrorKind::WouldBlock,
+            peer.try_recv(&mut buf).unwrap_err().kind()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_controller() {
+        let (client, peer) = Client::pair();
+
+        let kernel = async {
+            let mut buf = [0; 1024];
+            let n = peer.recv(&mut buf).await.unwrap();
+            // Read Controller Index List
+            assert_eq!(&[0x03, 0x00, 0xFF, 0xFF, 0x00, 0x00], &buf[..n]);
+            peer.send(&[
+                0x01, 0x00, 0xFF, 0xFF, 0x09, 0x00, 0x03, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00,
+                0x00,
+            ])
+            .await
+            .unwrap();
+        };
+        let (index, _) = tokio::join!(client.wait_for_controller(None), kernel);
+        assert_eq!(ControllerIndex::ControllerId(1), index.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_watch_controllers() {
+        let (client, peer) = Client::pair();
+
+        let kernel = async {
+            let mut buf = [0; 1024];
+            peer.recv(&mut buf).await.unwrap();
+            // Read Controller Index List [0, 1]
+            peer.send(&[
+                0x01, 0x00, 0xFF, 0xFF, 0x09, 0x00, 0x03, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01,
+                0x00,
+            ])
+            .await
+            .unwrap();
+        };
+        let (changes, _) = tokio::join!(client.watch_controllers(), kernel);
+        let mut changes = changes.unwrap();
+
+        for event in [
+            [0x04, 0x00, 0x01, 0x00, 0x00, 0x00], // added 1, already listed
+            [0x05, 0x00, 0x00, 0x00, 0x00, 0x00], // removed 0
+            [0x05, 0x00, 0x00, 0x00, 0x00, 0x00], // removed 0 again
+            [0x04, 0x00, 0x02, 0x00, 0x00, 0x00], // added 2
+            [0x04, 0x00, 0x00, 0x00, 0x00, 0x00], // added 0
+        ] {
+            peer.send(&event).await.unwrap();
+        }
+
+        let mut received = vec![];
+        for _ in 0..5 {
+            received.push(changes.next().await.unwrap());
+        }
+        assert_eq!(
+            vec![
+                ControllerChange::Added(0.into()),
+                ControllerChange::Added(1.into()),
+                ControllerChange::Removed(0.into()),
+                ControllerChange::Added(2.into()),
+                ControllerChange::Added(0.into()),
+            ],
+            received
+        );
+        assert!(changes.next().now_or_never().is_none());
+    }
+
+    /// Fake kernel for index lists and Read Controller Information.
+    ///
+    /// Controller 1 is removed after listed, if `removed`.
+    async fn summaries_kernel(peer: &tokio::net::UnixDatagram, extended: bool, removed: bool) {
+        let (info, _) = crate::packet::fixtures::controller_information_reply();
+        let mut buf = [0; 1024];
+        // list (after the unknown extended one), and two controllers.
+        let requests = if extended { 3 } else { 4 };
+        for _ in 0..requests {
+            let n = peer.recv(&mut buf).await.unwrap();
+            let reply = match (buf[0], buf[2], extended, removed) {
+                // [(0, primary, usb), (2, unconfigured, uart), (1, primary, virtual)]
+                (0x3C, _, true, _) => vec![
+                    0x01, 0x00, 0xFF, 0xFF, 0x11, 0x00, 0x3C, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00,
+                    0x00, 0x01, 0x02, 0x00, 0x01, 0x03, 0x01, 0x00, 0x00, 0x00,
+                ],
+                (0x3C, _, false, _) => vec![0x02, 0x00, 0xFF, 0xFF, 0x03, 0x00, 0x3C, 0x00, 0x01],
+                // [1, 0]
+                (0x03, _, false, _) => vec![
+                    0x01, 0x00, 0xFF, 0xFF, 0x09, 0x00, 0x03, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00,
+                    0x00, 0x00,
+                ],
+                (0x04, 0x00, ..) => info.clone(),
+                (0x04, 0x01, _, true) => vec![0x02, 0x00, 0x01, 0x00, 0x03, 0x00, 0x04, 0x00, 0x11],
+                (0x04, 0x01, _, false) => {
+                    let mut info = info.clone();
+                    info[2] = 0x01;
+                    info[16..18].copy_from_slice(&[0x5D, 0x00]); // manufacturer
+                    info
+                }
+                (opcode, ..) => panic!("unexpected opcode {:#04x} ({:?})", opcode, &buf[..n]),
+            };
+            peer.send(&reply).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_controller_summaries() {
+        let (client, peer) = Client::pair();
+
+        let (summaries, _) = tokio::join!(
+            client.controller_summaries(),
+            summaries_kernel(&peer, true, false)
+        );
+        let summaries = summaries.unwrap();
+        assert_eq!(2, summaries.len());
+        assert_eq!(&ControllerIndex::ControllerId(0), summaries[0].index());
+        assert!(matches!(
+            summaries[0].controller_type(),
+            Some(ControllerType::PrimaryController)
+        ));
+        assert!(matches!(summaries[0].bus(), Some(ControllerBus::Usb)));
+        assert_eq!(0x0002, summaries[0].manufacturer().id());
+        assert_eq!(9, summaries[0].bluetooth_version());
+        assert_eq!("btmgmt", summaries[0].name().to_string_lossy());
+        assert!(summaries[0].current_settings().contains(Settings::Powered));
+        assert_eq!(&ControllerIndex::ControllerId(1), summaries[1].index());
+        assert!(matches!(summaries[1].bus(), Some(ControllerBus::Virtual)));
+        assert_eq!(
+            Some("Realtek Semiconductor Corporation"),
+            summaries[1].manufacturer().name()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_controller_summaries_fallback() {
+        let (client, peer) = Client::pair();
+
+        // old kernel, and controller 1 removed in between.
+        let (summaries, _) = tokio::join!(
+            client.controller_summaries(),
+            summaries_kernel(&peer, false, true)
+        );
+        let summaries = summaries.unwrap();
+        assert_eq!(1, summaries.len());
+        assert_eq!(&ControllerIndex::ControllerId(0), summaries[0].index());
+        assert!(summaries[0].controller_type().is_none());
+        assert!(summaries[0].bus().is_none());
+        assert_eq!(
+            "00:11:22:33:44:55",
+            summaries[0].address().to_string().to_lowercase()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_all_indices() {
+        let (client, peer) = Client::pair();
+
+        let kernel = |unknown: bool| {
+            let peer = &peer;
+            async move {
+                let mut buf = [0; 1024];
+                for _ in 0..3 {
+                    let n = peer.recv(&mut buf).await.unwrap();
+                    assert_eq!(&[0x00, 0xFF, 0xFF, 0x00, 0x00], &buf[1..n]);
+                    let reply = match (buf[0], unknown) {
+                        // [0, 1]
+                        (0x03, _) => vec![
+                            0x01, 0x00, 0xFF, 0xFF, 0x09, 0x00, 0x03, 0x00, 0x00, 0x02, 0x00, 0x00,
+                            0x00, 0x01, 0x00,
+                        ],
+                        (0x36, true) => vec![0x02, 0x00, 0xFF, 0xFF, 0x03, 0x00, 0x36, 0x00, 0x01],
+                        // [2]
+                        (0x36, false) => vec![
+                            0x01, 0x00, 0xFF, 0xFF, 0x07, 0x00, 0x36, 0x00, 0x00, 0x01, 0x00, 0x02,
+                            0x00,
+                        ],
+                        (0x3C, true) => vec![0x02, 0x00, 0xFF, 0xFF, 0x03, 0x00, 0x3C, 0x00, 0x01],
+                        // [(0, primary, usb), (2, unconfigured, uart)]
+                        (0x3C, false) => vec![
+                            0x01, 0x00, 0xFF, 0xFF, 0x0D, 0x00, 0x3C, 0x00, 0x00, 0x02, 0x00, 0x00,
+                            0x00, 0x00, 0x01, 0x02, 0x00, 0x01, 0x03,
+                        ],
+                        (opcode, _) => panic!("unexpected opcode {:#04x}", opcode),
+                    };
+                    peer.send(&reply).await.unwrap();
+                }
+            }
+        };
+
+        let (indices, _) = tokio::join!(client.all_indices(), kernel(false));
+        let indices = indices.unwrap();
+        assert_eq!(
+            &[
+                ControllerIndex::ControllerId(0),
+                ControllerIndex::ControllerId(1)
+            ],
+            indices.configured()
+        );
+        assert_eq!(
+            Some(&[ControllerIndex::ControllerId(2)][..]),
+            indices.unconfigured()
+        );
+        let extended = indices.extended().unwrap();
+        assert_eq!(2, extended.len());
+        assert!(matches!(
+            extended[1],
+            (
+                ControllerIndex::ControllerId(2),
+                ControllerType::UnconfiguredController,
+                ControllerBus::Uart
+            )
+        ));
+
+        // old kernel
+        let (indices, _) = tokio::join!(client.all_indices(), kernel(true));
+        let indices = indices.unwrap();
+        assert_eq!(2, indices.configured().len());
+        assert!(indices.unconfigured().is_none());
+        assert!(indices.extended().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_controller_added() {
+        let (client, peer) = Client::pair();
+
+        let kernel = async {
+            let mut buf = [0; 1024];
+            peer.recv(&mut buf).await.unwrap();
+            // empty list
+            peer.send(&[
+                0x01, 0x00, 0xFF, 0xFF, 0x05, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00,
+            ])
+            .await
+            .unwrap();
+            // Index Added
+            peer.send(&[0x04, 0x00, 0x02, 0x00, 0x00, 0x00])
+                .await
+                .unwrap();
+        };
+        let (index, _) = tokio::join!(
+            client.wait_for_controller(Some(Duration::from_secs(10))),
+            kernel
+        );
+        assert_eq!(ControllerIndex::ControllerId(2), index.unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wait_for_controller_timeout() {
+        let (client, peer) = Client::pair();
+
+        let kernel = async {
+            let mut buf = [0; 1024];
+            peer.recv(&mut buf).await.unwrap();
+            peer.send(&[
+                0x01, 0x00, 0xFF, 0xFF, 0x05, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00,
+            ])
+            .await
+            .unwrap();
+        };
+        let (err, _) = tokio::join!(
+            client.wait_for_controller(Some(Duration::from_secs(10))),
+            kernel
+        );
+        let err = err.unwrap_err();
+        assert!(matches!(err, Error::NoAdapters));
+        assert!(err.is_retryable());
+    }
+
+    fn add_advertising() -> command::AddAdvertising {
+        command::AddAdvertising::new(
+            1.into(),
+            crate::packet::AdvertisingFlag::SwitchIntoConnectableMode,
+            0,
+            0,
+            crate::packet::AdvDataScanResp::new(vec![], vec![]),
+        )
+    }
+
+    /// Reply Add Advertising and Read Controller Information with `settings`, then send
+    /// `events`.
+    async fn advertising_kernel(
+        peer: &tokio::net::UnixDatagram,
+        settings: Settings,
+        events: &[&[u8]],
+    ) {
+        let mut buf = [0; 1024];
+        let n = peer.recv(&mut buf).await.unwrap();
+        assert_eq!(&[0x3E, 0x00, 0x00, 0x00, 0x0B, 0x00, 0x01], &buf[..7]);
+        assert_eq!(17, n);
+        peer.send(&[0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x3E, 0x00, 0x00, 0x01])
+            .await
+            .unwrap();
+        let n = peer.recv(&mut buf).await.unwrap();
+        assert_eq!(&[0x04, 0x00, 0x00, 0x00, 0x00, 0x00], &buf[..n]);
+        peer.send(&controller_information_reply(settings))
+            .await
+            .unwrap();
+        for event in events {
+            peer.send(event).await.unwrap();
+        }
+    }
+
+    /// Reply Read Advertising Features listing `instances`.
+    async fn features_kernel(peer: &tokio::net::UnixDatagram, instances: &[u8]) {
+        let mut buf = [0; 1024];
+        let n = peer.recv(&mut buf).await.unwrap();
+        assert_eq!(&[0x3D, 0x00, 0x00, 0x00, 0x00, 0x00], &buf[..n]);
+        let mut b = vec![0x01, 0x00, 0x00, 0x00];
+        b.extend((11 + instances.len() as u16).to_le_bytes());
+        b.extend([0x3D, 0x00, 0x00, 0xFF, 0x03, 0x00, 0x00, 0x1F, 0x1F, 0x05]);
+        b.push(instances.len() as u8);
+        b.extend(instances);
+        peer.send(&b).await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_add_advertising_and_wait() {
+        let (client, peer) = Client::pair();
+        let timeout = Duration::from_secs(5);
+
+        // already powered.
+        let kernel = async {
+            advertising_kernel(&peer, Settings::Powered, &[]).await;
+            features_kernel(&peer, &[1]).await;
+        };
+        let (reply, _) = tokio::join!(
+            client.add_advertising_and_wait(0, add_advertising(), timeout),
+            kernel
+        );
+        assert_eq!(1, **reply.unwrap());
+
+        let events: &[&[u8]] = &[
+            // other controller
+            &[0x06, 0x00, 0x01, 0x00, 0x04, 0x00, 0x01, 0x00, 0x00, 0x00],
+            // advertising setting only
+            &[0x06, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x04, 0x00, 0x00],
+            // powered
+            &[0x06, 0x00, 0x00, 0x00, 0x04, 0x00, 0x01, 0x00, 0x00, 0x00],
+        ];
+        let kernel = async {
+            advertising_kernel(&peer, Settings::empty(), events).await;
+            features_kernel(&peer, &[2, 1]).await;
+        };
+        let (reply, _) = tokio::join!(
+            client.add_advertising_and_wait(0, add_advertising(), timeout),
+            kernel
+        );
+        assert_eq!(1, **reply.unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_add_advertising_and_wait_timeout() {
+        let (client, peer) = Client::pair();
+
+        // Advertising setting is not powering on.
+        let events: &[&[u8]] = &[&[0x06, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x04, 0x00, 0x00]];
+        let (err, _) = tokio::join!(
+            client.add_advertising_and_wait(0, add_advertising(), Duration::from_secs(5)),
+            advertising_kernel(&peer, Settings::empty(), events)
+        );
+        assert!(matches!(
+            err.unwrap_err(),
+            Error::AdvertisingNotStarted { instance: 1 }
+        ));
+
+        // gone, such as by its duration.
+        let kernel = async {
+            advertising_kernel(&peer, Settings::Powered, &[]).await;
+            features_kernel(&peer, &[2]).await;
+        };
+        let (err, _) = tokio::join!(
+            client.add_advertising_and_wait(0, add_advertising(), Duration::from_secs(5)),
+            kernel
+        );
+        assert!(matches!(
+            err.unwrap_err(),
+            Error::AdvertisingNotStarted { instance: 1 }
+        ));
+    }
+
+    fn complete(opcode: u8, status: u8) -> [u8; 9] {
+        [0x01, 0x00, 0x00, 0x00, 0x03, 0x00, opcode, 0x00, status]
+    }
+
+    #[tokio::test]
+    async fn test_call_batch() {
+        let (client, peer) = Client::pair();
+
+        let commands = vec![
+            command::SetPowered::new(true).into(),
+            command::SetConnectable::new(true).into(),
+            command::SetBondable::new(true).into(),
+        ];
+        let kernel = async {
+            let mut buf = [0; 64];
+            for opcode in [0x05, 0x07, 0x09] {
+                let n = peer.recv(&mut buf).await.unwrap();
+                assert_eq!(&[opcode, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01], &buf[..n]);
+            }
+            // replied out of order.
+            peer.send(&complete(0x09, 0x00)).await.unwrap();
+            peer.send(&complete(0x05, 0x00)).await.unwrap();
+            peer.send(&complete(0x07, 0x0D)).await.unwrap();
+        };
+        let (results, _) = tokio::join!(client.call_batch(0, commands), kernel);
+
+        let results = results.unwrap();
+        assert_eq!(3, results.len());
+        assert_eq!(0x0005, results[0].as_ref().unwrap().opcode());
+        let err = results[1].as_ref().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::CommandStatus {
+                command: CommandCode::SetConnectable,
+                status: ErrorCode::InvalidParameters,
+                ..
+            }
+        ));
+        assert_eq!(0x0009, results[2].as_ref().unwrap().opcode());
+    }
+
+    #[tokio::test]
+    async fn test_call_batch_send_failed() {
+        let stream = tokio_test::io::Builder::new()
+            .write(&[0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01])
+            .write_error(io::Error::from_raw_os_error(libc::ENOBUFS))
+            .read(&complete(0x05, 0x00))
+            .build();
+        let client = ClientInner::new(stream);
+
+        let commands = vec![
+            command::SetPowered::new(true).into(),
+            command::SetConnectable::new(true).into(),
+            command::SetBondable::new(true).into(),
+        ];
+        let results = client.call_batch(0.into(), commands).await.unwrap();
+        assert_eq!(3, results.len());
+        assert_eq!(0x0005, results[0].as_ref().unwrap().opcode());
+        assert!(matches!(results[1], Err(Error::Io(..))), "{:?}", results[1]);
+        assert!(
+            matches!(results[2], Err(Error::NotSent)),
+            "{:?}",
+            results[2]
+        );
+    }
+
+    /// Sequential calls pay a round trip per command, batched calls about one.
+    #[tokio::test(start_paused = true)]
+    async fn test_call_batch_latency() {
+        use std::rc::Rc;
+        use tokio::time::{sleep, Instant};
+
+        const ROUND_TRIP: std::time::Duration = std::time::Duration::from_millis(10);
+
+        let (client, peer) = Client::pair();
+        let peer = Rc::new(peer);
+        let local = tokio::task::LocalSet::new();
+        let kernel = {
+            let peer = peer.clone();
+            async move {
+                let mut buf = [0; 64];
+                loop {
+                    peer.recv(&mut buf).await.unwrap();
+                    let opcode = buf[0];
+                    let peer = peer.clone();
+                    tokio::task::spawn_local(async move {
+                        sleep(ROUND_TRIP).await;
+                        peer.send(&complete(opcode, 0x00)).await.unwrap();
+                    });
+                }
+            }
+        };
+        local.spawn_local(kernel);
+
+        let commands = || -> Vec<Command> {
+            (0..8)
+                .map(|_| command::SetPowered::new(true).into())
+                .collect()
+        };
+        local
+            .run_until(async {
+                let start = Instant::now();
+                for command in commands() {
+                    client.call_batch(0, vec![command]).await.unwrap();
+                }
+                let sequential = start.elapsed();
+
+                let start = Instant::now();
+                let results = client.call_batch(0, commands()).await.unwrap();
+                let batched = start.elapsed();
+
+                assert!(results.iter().all(Result::is_ok));
+                assert!(sequential >= ROUND_TRIP * 8, "{:?}", sequential);
+                assert!(batched < ROUND_TRIP * 2, "{:?}", batched);
+            })
+            .await;
+    }
+
+    #[cfg(feature = "stats")]
+    #[tokio::test]
+    async fn test_stats() {
+        use crate::event::EventCode;
+
+        let stream = tokio_test::io::Builder::new()
+            .write(&[0x01, 0x00, 0xFF, 0xFF, 0x00, 0x00])
+            .read(&[
+                0x01, 0x00, 0xFF, 0xFF, 0x06, 0x00, 0x01, 0x00, 0x00, 0x01, 0x13, 0x00,
+            ])
+            .write(&[0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01])
+            .read(&[0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x05, 0x00, 0x14])
+            .write(&[0x01, 0x00, 0xFF, 0xFF, 0x00, 0x00])
+            .read(&[0x01, 0x00, 0xFF, 0xFF, 0x04, 0x00, 0x01, 0x00, 0x00, 0x01]) // short
+            .read(&[0x04, 0x00, 0x00, 0x00, 0x00, 0x00]) // index added
+            .build();
+        let client = ClientInner::new(stream);
+        client
+            .call(None, crate::command::ReadManagementVersionInformation)
+            .await
+            .unwrap();
+        client
+            .call(0, crate::command::SetPowered::from(true))
+            .await
+            .unwrap_err();
+        client
+            .call(None, crate::command::ReadManagementVersionInformation)
+            .await
+            .unwrap_err();
+        let mut events = client.events().await;
+        events.next().await.unwrap();
+        assert!(events.next().await.is_none());
+
+        let stats = client.counters.snapshot();
+        assert_eq!(3, stats.commands_total);
+        assert_eq!(2, stats.events_total[EventCode::CommandComplete]);
+        assert_eq!(1, stats.events_total[EventCode::CommandStatus]);
+        assert_eq!(1, stats.events_total[EventCode::IndexAdded]);
+        assert_eq!(4, stats.events_total.total());
+        assert_eq!(1, stats.command_errors[ErrorCode::PermissionDenied]);
+        assert_eq!(1, stats.command_errors.total());
+        assert_eq!(1, stats.decode_failures);
+    }
+
+    async fn storm(limiter: EventLimiter) -> (usize, ClientInner<tokio_test::io::Mock>) {
+        let (bytes, _) = crate::packet::fixtures::device_found();
+        let mut stream = tokio_test::io::Builder::new();
+        for _ in 0..30 {
+            stream.read(&bytes);
+        }
+        let client = ClientInner::with_limiter(stream.build(), limiter);
+
+        let events = client.events().await;
+        (events.count().await, client)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_event_storm() {
+        let (delivered, _client) = storm(EventLimiter::new(Some(10), false)).await;
+        assert_eq!(30, delivered);
+        #[cfg(feature = "stats")]
+        assert_eq!(0, _client.counters.snapshot().events_dropped.total());
+
+        let (delivered, _client) = storm(EventLimiter::new(Some(10), true)).await;
+        assert_eq!(10, delivered);
+        #[cfg(feature = "stats")]
+        {
+            let stats = _client.counters.snapshot();
+            assert_eq!(30, stats.events_total[crate::event::EventCode::DeviceFound]);
+            assert_eq!(
+                20,
+                stats.events_dropped[crate::event::EventCode::DeviceFound]
+            );
+            assert_eq!(20, stats.events_dropped.total());
+        }
+
+        let (delivered, _) = storm(EventLimiter::new(None, true)).await;
+        assert_eq!(30, delivered);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_call_with_timeout() {
+        let (client, peer) = Client::pair();
+        let timeout = Duration::from_secs(5);
+        let mut buf = [0; 16];
+
+        // never replied.
+        let (result, _) = tokio::join!(
+            client.call_with_timeout(0, command::SetPowered::from(true), timeout),
+            peer.recv(&mut buf)
+        );
+        assert!(matches!(result, Err(Error::Timeout)), "{:?}", result);
+
+        // late reply, discarded.
+        peer.send(&[
+            0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0x05, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        ])
+        .await
+        .unwrap();
+        // other command.
+        let (version, _) = tokio::join!(
+            client.call_with_timeout(None, command::ReadManagementVersionInformation, timeout),
+            async {
+                peer.recv(&mut buf).await.unwrap();
+                let reply = [
+                    0x01, 0x00, 0xFF, 0xFF, 0x06, 0x00, 0x01, 0x00, 0x00, 0x01, 0x13, 0x00,
+                ];
+                peer.send(&reply).await.unwrap();
+            }
+        );
+        assert_eq!(19, *version.unwrap().revision());
+
+        // same command, after another late reply.
+        let (result, _) = tokio::join!(
+            client.call_with_timeout(0, command::SetPowered::from(true), timeout),
+            peer.recv(&mut buf)
+        );
+        assert!(matches!(result, Err(Error::Timeout)), "{:?}", result);
+        let (settings, _) = tokio::join!(client.call(0, command::SetPowered::from(true)), async {
+            peer.recv(&mut buf).await.unwrap();
+            // late: not powered.
+            peer.send(&[
+                0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ])
+            .await
+            .unwrap();
+            // powered.
+            peer.send(&[
+                0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0x05, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+            ])
+            .await
+            .unwrap();
+        });
+        assert!(settings.unwrap().contains(Settings::Powered));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_default_timeout() {
+        let (client, peer) = Client::pair();
+        let mut buf = [0; 16];
+        client.set_default_timeout(Some(Duration::from_secs(5)));
+
+        let start = tokio::time::Instant::now();
+        let (result, _) = tokio::join!(
+            client.call(0, command::SetPowered::from(true)),
+            peer.recv(&mut buf)
+        );
+        assert!(matches!(result, Err(Error::Timeout)), "{:?}", result);
+        assert_eq!(Duration::from_secs(5), start.elapsed());
+
+        let (result, _) = tokio::join!(client.call_raw(0, 0x0005, vec![0x01]), peer.recv(&mut buf));
+        assert!(matches!(result, Err(Error::Timeout)), "{:?}", result);
+
+        // overridden.
+        let start = tokio::time::Instant::now();
+        let (result, _) = tokio::join!(
+            client.call_with_timeout(0, command::SetPowered::from(true), Duration::from_secs(10)),
+            peer.recv(&mut buf)
+        );
+        assert!(matches!(result, Err(Error::Timeout)), "{:?}", result);
+        assert_eq!(Duration::from_secs(10), start.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_default_timeout_cleared() {
+        let (client, peer) = Client::pair();
+        let mut buf = [0; 16];
+        client.set_default_timeout(Some(Duration::from_secs(5)));
+        client.set_default_timeout(None);
+
+        let (result, _) = tokio::join!(client.call(0, command::SetPowered::from(true)), async {
+            peer.recv(&mut buf).await.unwrap();
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            let reply = crate::frames::complete(0x05, &[0x01, 0x00, 0x00, 0x00]);
+            peer.send(&reply).await.unwrap();
+        });
+        assert!(result.unwrap().contains(Settings::Powered));
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_reply_not_blocking_events() {
+        let (client, peer) = Client::pair();
+        let mut events = client.events().await;
+        let mut buf = [0; 16];
+
+        let call = tokio::spawn(client.call(0, command::SetPowered::from(true)));
+        peer.recv(&mut buf).await.unwrap();
+        call.abort();
+        assert!(call.await.unwrap_err().is_cancelled());
+
+        // reply of the dropped call, then an event.
+        peer.send(&[
+            0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0x05, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        ])
+        .await
+        .unwrap();
+        peer.send(&[0x04, 0x00, 0x00, 0x00, 0x00, 0x00])
+            .await
+            .unwrap();
+        let (index, event) = events.next().await.unwrap();
+        assert_eq!(ControllerIndex::ControllerId(0), index);
+        assert!(matches!(event, Event::IndexAdded(..)));
+    }
+
+    #[tokio::test]
+    async fn test_events_for_type() {
+        let (client, peer) = Client::pair();
+        let mut added0 = client.events_for::<event::IndexAdded>(Some(0.into())).await;
+        let mut removed = client.events_for::<event::IndexRemoved>(None).await;
+
+        peer.send(&[0x04, 0x00, 0x00, 0x00, 0x00, 0x00])
+            .await
+            .unwrap(); // index added
+        peer.send(&[0x04, 0x00, 0x01, 0x00, 0x00, 0x00])
+            .await
+            .unwrap();
+        peer.send(&[0x05, 0x00, 0x01, 0x00, 0x00, 0x00])
+            .await
+            .unwrap(); // index removed
+        peer.send(&[0x04, 0x00, 0x00, 0x00, 0x00, 0x00])
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            added0.next().await,
+            Some((ControllerIndex::ControllerId(0), event::IndexAdded))
+        ));
+        assert!(matches!(
+            removed.next().await,
+            Some((ControllerIndex::ControllerId(1), event::IndexRemoved))
+        ));
+        assert!(matches!(
+            added0.next().await,
+            Some((ControllerIndex::ControllerId(0), event::IndexAdded))
+        ));
+        assert!(removed.next().now_or_never().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_events_for() {
+        let (client, peer) = Client::pair();
+        let mut hci0 = client.events_for::<Event>(Some(0.into())).await;
+        let mut hci1 = client.events_for::<Event>(Some(1.into())).await;
+        let mut removed = client
+            .events_filtered(|_, event| matches!(event, Event::IndexRemoved(..)))
+            .await;
+
+        peer.send(&[0x04, 0x00, 0x00, 0x00, 0x00, 0x00])
+            .await
+            .unwrap(); // index added
+        peer.send(&[0x04, 0x00, 0x01, 0x00, 0x00, 0x00])
+            .await
+            .unwrap();
+        peer.send(&[0x05, 0x00, 0x00, 0x00, 0x00, 0x00])
+            .await
+            .unwrap(); // index removed
+
+        assert!(matches!(
+            hci1.next().await,
+            Some((_, Event::IndexAdded(..)))
+        ));
+        assert!(matches!(
+            hci0.next().await,
+            Some((_, Event::IndexAdded(..)))
+        ));
+        assert!(matches!(
+            hci0.next().await,
+            Some((_, Event::IndexRemoved(..)))
+        ));
+        assert!(matches!(
+            removed.next().await,
+            Some((ControllerIndex::ControllerId(0), Event::IndexRemoved(..)))
+        ));
+
+        // nothing else queued.
+        assert!(hci1.next().now_or_never().is_none());
+        assert!(removed.next().now_or_never().is_none());
+    }
+
+    async fn index_added(peer: &tokio::net::UnixDatagram, count: u16) {
+        for i in 0..count {
+            let [lo, hi] = i.to_le_bytes();
+            peer.send(&[0x04, 0x00, lo, hi, 0x00, 0x00]).await.unwrap();
+        }
+    }
+
+    async fn indices(events: &mut EventSubscribe, count: usize) -> Vec<u16> {
+        let mut indices = vec![];
+        for _ in 0..count {
+            let (index, _) = events.next().await.unwrap();
+            indices.push(index.into());
+        }
+        indices
+    }
+
+    #[tokio::test]
+    async fn test_events_with_drop() {
+        for (policy, kept) in [
+            (EventOverflowPolicy::DropOldest, vec![3, 4]),
+            (EventOverflowPolicy::DropNewest, vec![0, 1]),
+        ] {
+            let (client, peer) = Client::pair();
+            let mut bounded = client.events_with(2, policy).await;
+            let mut all = client.events().await;
+
+            index_added(&peer, 5).await;
+            assert_eq!(vec![0, 1, 2, 3, 4], indices(&mut all, 5).await);
+
+            assert_eq!(kept, indices(&mut bounded, 2).await, "{:?}", policy);
+            assert!(bounded.next().now_or_never().is_none());
+            assert_eq!(3, bounded.dropped());
+            assert_eq!(0, all.dropped());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_with_block() {
+        let (client, peer) = Client::pair();
+        let mut bounded = client.events_with(1, EventOverflowPolicy::Block).await;
+        let mut all = client.events().await;
+
+        index_added(&peer, 2).await;
+        assert_eq!(vec![0], indices(&mut all, 1).await);
+        // not read while full.
+        assert!(all.next().now_or_never().is_none());
+
+        assert_eq!(vec![0], indices(&mut bounded, 1).await);
+        assert_eq!(vec![1], indices(&mut all, 1).await);
+        assert_eq!(vec![1], indices(&mut bounded, 1).await);
+        assert_eq!(0, bounded.dropped());
+
+        // dropped subscriptions no longer block.
+        drop(bounded);
+        index_added(&peer, 2).await;
+        assert_eq!(vec![0, 1], indices(&mut all, 2).await);
+    }
+
+    /// Usable on multi-threaded runtimes. Fails to compile on regression.
+    ///
+    /// Futures are constructed but not polled.
+    #[tokio::test]
+    async fn test_send_sync() {
+        fn assert_clone<T: Clone>() {}
+        fn assert_send_sync<T: Send + Sync>() {}
+        fn assert_send<T: Send>(_: &T) {}
+
+        let (client, _peer) = Client::pair();
+
+        assert_clone::<Client>();
+
+        assert_send_sync::<Client>();
+        assert_send_sync::<EventStream<MgmtSocket>>();
+        assert_send_sync::<EventSubscribe>();
+        assert_send_sync::<SharedEventSubscribe>();
+        assert_send_sync::<ThrottledDeviceFound>();
+        assert_send_sync::<RoleGuard>();
+        assert_send_sync::<Error>();
+        assert_send_sync::<Adapter>();
+        assert_send_sync::<crate::adapter::Debounced>();
+        assert_send_sync::<crate::adapter::AdvertisingHandle>();
+
+        assert_send(&client.call(0, command::SetPowered::from(true)));
+        assert_send(&client.call_raw(0, 0x0005, vec![0x01]));
+        assert_send(&client.call_batch(0, vec![]));
+        assert_send(&client.events());
+        assert_send(&client.events_shared());
+        assert_send(&client.set_setting(0, SettingFlag::Le, true));
+        assert_send(&client.discover_throttled(0, AddressTypes::default(), Duration::ZERO));
+        assert_send(&client.discover(0, AddressTypes::default()));
+        assert_send(&client.wait_for_controller(None));
+
+        let adapter = client.adapter(0);
+        assert_send(&adapter.call(command::SetPowered::from(true)));
+        assert_send(&adapter.ensure_not_discovering());
+        assert_send(&adapter.add_advertising_auto(AdvertisingParams::new(1)));
+        assert_send(&adapter.set_connectable_checked(false, ConnectableConflict::Error));
+        assert_send(&adapter.debounced().flush());
+    }
 }
@@ -0,0 +1,163 @@
+//! Device Found events of a discovery.
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::stream::Stream;
+use futures_util::stream::StreamExt;
+
+use super::{Client, EventSubscribe, Result};
+use crate::command;
+use crate::event::{DeviceFound, Event};
+use crate::packet::{AddressTypes, ControllerIndex};
+
+/// Device Found events of [`Client::discover`].
+///
+/// Ends when the discovery stops (Discovering event with `discovering` false) or the
+/// client is closed. Dropped before that, sends [`command::StopDiscovery`] in background.
+pub struct Discovery {
+    client: Client,
+    events: EventSubscribe,
+    index: ControllerIndex,
+    address_types: AddressTypes,
+    stopped: bool,
+}
+
+impl Stream for Discovery {
+    type Item = DeviceFound;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.stopped {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            match this.events.poll_next_unpin(cx) {
+                Poll::Ready(Some((index, event))) if index == this.index => match event {
+                    Event::DeviceFound(found) => return Poll::Ready(Some(found)),
+                    Event::Discovering(discovering) if !*discovering.discovering() => {
+                        this.stopped = true;
+                        return Poll::Ready(None);
+                    }
+                    _ => {}
+                },
+                Poll::Ready(Some(..)) => {}
+                Poll::Ready(None) => {
+                    this.stopped = true;
+                    return Poll::Ready(None);
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Drop for Discovery {
+    fn drop(&mut self) {
+        if self.stopped {
+            return;
+        }
+        let stop = self.client.call(
+            self.index.clone(),
+            command::StopDiscovery::new(self.address_types.clone()),
+        );
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    if let Err(err) = stop.await {
+                        log::warn!("failed to stop discovery: {}", err);
+                    }
+                });
+            }
+            Err(..) => log::warn!("discovery left running: no runtime"),
+        }
+    }
+}
+
+impl Client {
+    /// Start discovery and receive Device Found events until it stops.
+    ///
+    /// Name confirmation is not handled, as [`Client::discover_throttled`].
+    pub async fn discover<I>(&self, index: I, address_types: AddressTypes) -> Result<Discovery>
+    where
+        I: Into<ControllerIndex>,
+    {
+        let index = index.into();
+        let events = self.events().await;
+        self.call(
+            index.clone(),
+            command::StartDiscovery::new(address_types.clone()),
+        )
+        .await?;
+        Ok(Discovery {
+            client: self.clone(),
+            events,
+            index,
+            address_types,
+            stopped: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::AddressType;
+
+    const DEVICE_FOUND: [u8; 20] = [
+        0x12, 0x00, 0x00, 0x00, 0x0E, 0x00, 0x66, 0x55, 0x44, 0x33, 0x22, 0xC1, 0x02, 0xC4, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    async fn start(client: &Client, peer: &tokio::net::UnixDatagram) -> Discovery {
+        let mut types = AddressTypes::default();
+        types.extend(vec![AddressType::LePublic, AddressType::LeRandom]);
+        let kernel = async {
+            let mut buf = [0; 16];
+            let n = peer.recv(&mut buf).await.unwrap();
+            assert_eq!(&[0x23, 0x00, 0x00, 0x00, 0x01, 0x00, 0x06], &buf[..n]);
+            peer.send(&[0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x23, 0x00, 0x00, 0x06])
+                .await
+                .unwrap();
+        };
+        let (discovery, _) = tokio::join!(client.discover(0, types), kernel);
+        discovery.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_discover_until_stopped() {
+        let (client, peer) = Client::pair();
+        let mut discovery = start(&client, &peer).await;
+
+        peer.send(&DEVICE_FOUND).await.unwrap();
+        // other controller.
+        let mut other = DEVICE_FOUND;
+        other[2] = 0x01;
+        peer.send(&other).await.unwrap();
+        // discovering stopped.
+        peer.send(&[0x13, 0x00, 0x00, 0x00, 0x02, 0x00, 0x06, 0x00])
+            .await
+            .unwrap();
+
+        let found = discovery.next().await.unwrap();
+        assert_eq!(0xC4, *found.rssi());
+        assert!(discovery.next().await.is_none());
+
+        // nothing to stop.
+        drop(discovery);
+        tokio::task::yield_now().await;
+        let mut buf = [0; 16];
+        assert!(peer.try_recv(&mut buf).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_discover_drop_stops() {
+        let (client, peer) = Client::pair();
+        let discovery = start(&client, &peer).await;
+
+        drop(discovery);
+        let mut buf = [0; 16];
+        let n = peer.recv(&mut buf).await.unwrap();
+        assert_eq!(&[0x24, 0x00, 0x00, 0x00, 0x01, 0x00, 0x06], &buf[..n]);
+    }
+}
@@ -0,0 +1,209 @@
+//! Per subscriber event queues, optionally bounded.
+//!
+//! Events are pushed by whoever reads the socket, under the receive lock, and popped
+//! by the subscriber. A full queue of [`EventOverflowPolicy::Block`] stops the reading
+//! until the subscriber pops.
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use super::SharedEvent;
+
+/// What a bounded subscription does with an event arriving when full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventOverflowPolicy {
+    /// Drop the oldest queued event, keeping the latest ones.
+    DropOldest,
+    /// Drop the arriving event.
+    DropNewest,
+    /// Stop reading the socket until the subscriber catches up.
+    ///
+    /// Replies are read from the same socket, so calls stall meanwhile. Do not await
+    /// calls from the task consuming the subscription.
+    Block,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    events: VecDeque<SharedEvent>,
+    /// No more events pushed.
+    closed: bool,
+    /// The subscription is dropped.
+    detached: bool,
+    rx: Option<Waker>,
+    /// Readers stopped by a full [`EventOverflowPolicy::Block`] queue.
+    blocked: Vec<Waker>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Queue {
+    bound: Option<(usize, EventOverflowPolicy)>,
+    state: Mutex<State>,
+    dropped: AtomicU64,
+}
+
+impl Queue {
+    pub(crate) fn new(bound: Option<(usize, EventOverflowPolicy)>) -> Arc<Self> {
+        Arc::new(Self {
+            bound,
+            state: Default::default(),
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Queue the event, applying the overflow policy.
+    pub(crate) fn push(&self, event: SharedEvent) {
+        let mut state = self.state.lock().unwrap();
+        if state.closed || state.detached {
+            return;
+        }
+        match self.bound {
+            Some((capacity, policy)) if state.events.len() >= capacity => match policy {
+                EventOverflowPolicy::DropOldest => {
+                    state.events.pop_front();
+                    state.events.push_back(event);
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                // Block is checked before reading. Dropped only if not (e.g. capacity 0).
+                EventOverflowPolicy::DropNewest | EventOverflowPolicy::Block => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+            },
+            _ => state.events.push_back(event),
+        }
+        if let Some(w) = state.rx.take() {
+            w.wake();
+        }
+    }
+
+    /// false if full with [`EventOverflowPolicy::Block`], registering `cx` to be woken
+    /// by the next pop.
+    pub(crate) fn poll_ready(&self, cx: &mut Context<'_>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match self.bound {
+            Some((capacity, EventOverflowPolicy::Block))
+                if capacity > 0 && !state.detached && state.events.len() >= capacity =>
+            {
+                state.blocked.push(cx.waker().clone());
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// true if the subscription is dropped.
+    pub(crate) fn is_detached(&self) -> bool {
+        self.state.lock().unwrap().detached
+    }
+
+    /// End the subscription after the queued events.
+    pub(crate) fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        if let Some(w) = state.rx.take() {
+            w.wake();
+        }
+    }
+
+    pub(crate) fn poll_pop(&self, cx: &mut Context<'_>) -> Poll<Option<SharedEvent>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(event) = state.events.pop_front() {
+            for w in state.blocked.drain(..) {
+                w.wake();
+            }
+            return Poll::Ready(Some(event));
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        state.rx = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    /// Mark the subscription dropped, releasing the queued events and blocked readers.
+    pub(crate) fn detach(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.detached = true;
+        state.events.clear();
+        for w in state.blocked.drain(..) {
+            w.wake();
+        }
+    }
+
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::task::noop_waker;
+
+    use super::*;
+    use crate::event::{Event, IndexAdded};
+    use crate::packet::ControllerIndex;
+
+    fn event(index: u16) -> SharedEvent {
+        Arc::new((ControllerIndex::from(index), Event::IndexAdded(IndexAdded)))
+    }
+
+    fn drain(queue: &Queue) -> Vec<ControllerIndex> {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut indices = vec![];
+        while let Poll::Ready(Some(event)) = queue.poll_pop(&mut cx) {
+            indices.push(event.0.clone());
+        }
+        indices
+    }
+
+    #[test]
+    fn test_unbounded() {
+        let queue = Queue::new(None);
+        for i in 0..100 {
+            queue.push(event(i));
+        }
+        assert_eq!(100, drain(&queue).len());
+        assert_eq!(0, queue.dropped());
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let queue = Queue::new(Some((2, EventOverflowPolicy::DropOldest)));
+        for i in 0..5 {
+            queue.push(event(i));
+        }
+        assert_eq!(vec![ControllerIndex::from(3), 4.into()], drain(&queue));
+        assert_eq!(3, queue.dropped());
+    }
+
+    #[test]
+    fn test_drop_newest() {
+        let queue = Queue::new(Some((2, EventOverflowPolicy::DropNewest)));
+        for i in 0..5 {
+            queue.push(event(i));
+        }
+        assert_eq!(vec![ControllerIndex::from(0), 1.into()], drain(&queue));
+        assert_eq!(3, queue.dropped());
+    }
+
+    #[test]
+    fn test_block() {
+        let queue = Queue::new(Some((1, EventOverflowPolicy::Block)));
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert!(queue.poll_ready(&mut cx));
+        queue.push(event(0));
+        assert!(!queue.poll_ready(&mut cx));
+        assert_eq!(vec![ControllerIndex::from(0)], drain(&queue));
+        assert!(queue.poll_ready(&mut cx));
+
+        // dropped subscriptions never block.
+        queue.push(event(1));
+        queue.detach();
+        assert!(queue.poll_ready(&mut cx));
+        assert_eq!(0, queue.dropped());
+    }
+}
@@ -0,0 +1,205 @@
+//! Client split into a command half and an event half.
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use super::{
+    Client, ClientInner, EventSubscribe, RawReply, Result, SharedEventSubscribe, Shutdown,
+};
+use crate::command::{self, Command};
+use crate::packet::ControllerIndex;
+use crate::sock::MgmtSocket;
+
+/// Requests the shutdown when the last handle is dropped.
+#[derive(Debug)]
+struct Keepalive(Arc<Shutdown>);
+
+impl Drop for Keepalive {
+    fn drop(&mut self) {
+        self.0.request();
+    }
+}
+
+/// Command half of [`Client::split`]. Clones are handles as well.
+#[derive(Clone)]
+pub struct Commander {
+    inner: ClientInner<MgmtSocket>,
+    _keepalive: Arc<Keepalive>,
+}
+
+impl Commander {
+    /// Call mgmt API command. See [`Client::call`].
+    pub fn call<C, I>(
+        &self,
+        index: I,
+        command: C,
+    ) -> impl Future<Output = Result<C::Reply>> + 'static
+    where
+        C: command::CommandRequest + 'static,
+        C::Reply: fmt::Debug,
+        I: Into<ControllerIndex>,
+    {
+        self.inner.call(index.into(), command)
+    }
+
+    /// Call mgmt API command with encoded parameters. See [`Client::call_raw`].
+    pub fn call_raw<I, P>(
+        &self,
+        index: I,
+        opcode: u16,
+        params: P,
+    ) -> impl Future<Output = Result<RawReply>> + 'static
+    where
+        I: Into<ControllerIndex>,
+        P: Into<Box<[u8]>>,
+    {
+        self.inner.call_raw(index, opcode, params)
+    }
+
+    /// Call multiple commands for the controller at once. See [`Client::call_batch`].
+    pub fn call_batch<I>(
+        &self,
+        index: I,
+        commands: Vec<Command>,
+    ) -> impl Future<Output = Result<Vec<Result<RawReply>>>> + 'static
+    where
+        I: Into<ControllerIndex>,
+    {
+        self.inner.call_batch(index.into(), commands)
+    }
+}
+
+/// Event half of [`Client::split`]. Clones are handles as well.
+#[derive(Clone)]
+pub struct EventHub {
+    inner: ClientInner<MgmtSocket>,
+    keepalive: Arc<Keepalive>,
+}
+
+impl EventHub {
+    /// Subscribe mgmt API events. See [`Client::events`].
+    pub async fn events(&self) -> EventSubscribe {
+        EventSubscribe(self.inner.events().await)
+    }
+
+    /// Subscribe mgmt API events, shared with other subscribers. See [`Client::events_shared`].
+    pub async fn events_shared(&self) -> SharedEventSubscribe {
+        SharedEventSubscribe(self.inner.events_shared().await)
+    }
+
+    /// Stop reading now, as if the last handle was dropped.
+    pub fn close(&self) {
+        self.keepalive.0.request();
+    }
+
+    /// Resolves when reading stopped. Not a handle itself.
+    ///
+    /// Polling it reads the socket as a subscription does, so events are
+    /// delivered to subscribers meanwhile.
+    pub fn closed(&self) -> impl Future<Output = ()> + 'static {
+        let rx = self.inner.rx.clone();
+        async move { while rx.next().await.is_some() {} }
+    }
+}
+
+impl Client {
+    /// Split into a [`Commander`] and an [`EventHub`], with independent lifetimes.
+    ///
+    /// The socket is read while any handle, a `Commander` or an `EventHub` or
+    /// their clones, exists. Dropping all of one kind doesn't affect the other:
+    /// subscriptions of the hub keep receiving events, and calls keep resolving.
+    ///
+    /// Reading stops when the last handle of both kinds is dropped, or by
+    /// [`EventHub::close`]. Then subscriptions end, pending and later calls resolve
+    /// to [`Error::Closed`](crate::Error::Closed), and [`EventHub::closed`] resolves.
+    ///
+    /// Subscriptions, call futures and clones of this client made before the split
+    /// (including [`Adapter`](crate::Adapter)s) are not handles. They keep working
+    /// while reading, and see the client closed after it stops. The socket is closed
+    /// when the last of all of them is dropped.
+    pub fn split(self) -> (Commander, EventHub) {
+        let keepalive = Arc::new(Keepalive(self.0.shutdown.clone()));
+        let commander = Commander {
+            inner: self.0.clone(),
+            _keepalive: keepalive.clone(),
+        };
+        let hub = EventHub {
+            inner: self.0,
+            keepalive,
+        };
+        (commander, hub)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::stream::StreamExt;
+
+    use super::*;
+    use crate::event::Event;
+    use crate::Error;
+
+    const NEW_SETTINGS: [u8; 10] = [0x06, 0x00, 0x00, 0x00, 0x04, 0x00, 0x01, 0x00, 0x00, 0x00];
+
+    #[tokio::test]
+    async fn test_split_events_without_commander() {
+        let (client, peer) = Client::pair();
+        let (commander, hub) = client.split();
+        let mut events = hub.events().await;
+
+        drop(commander);
+        peer.send(&NEW_SETTINGS).await.unwrap();
+        let (index, event) = events.next().await.unwrap();
+        assert_eq!(ControllerIndex::ControllerId(0), index);
+        assert!(matches!(event, Event::NewSettings(..)));
+    }
+
+    #[tokio::test]
+    async fn test_split_calls_without_hub() {
+        let (client, peer) = Client::pair();
+        let (commander, hub) = client.split();
+
+        drop(hub);
+        let (reply, _) = tokio::join!(
+            commander.call(None, command::ReadManagementVersionInformation),
+            async {
+                let mut buf = [0; 16];
+                peer.recv(&mut buf).await.unwrap();
+                let reply = [
+                    0x01, 0x00, 0xFF, 0xFF, 0x06, 0x00, 0x01, 0x00, 0x00, 0x01, 0x13, 0x00,
+                ];
+                peer.send(&reply).await.unwrap();
+            }
+        );
+        assert_eq!(19, *reply.unwrap().revision());
+    }
+
+    #[tokio::test]
+    async fn test_split_drop_all() {
+        let (client, _peer) = Client::pair();
+        let (commander, hub) = client.split();
+        let commander2 = commander.clone();
+        let mut events = hub.events().await;
+        let closed = hub.closed();
+        let pending = tokio::spawn(commander.call(None, command::ReadManagementVersionInformation));
+        tokio::task::yield_now().await;
+
+        drop((commander, commander2, hub));
+        closed.await;
+        assert!(events.next().await.is_none());
+        assert!(matches!(pending.await.unwrap(), Err(Error::Closed)));
+    }
+
+    #[tokio::test]
+    async fn test_split_close() {
+        let (client, _peer) = Client::pair();
+        let (commander, hub) = client.split();
+
+        hub.close();
+        hub.closed().await;
+        let result = commander
+            .call(None, command::ReadManagementVersionInformation)
+            .await;
+        assert!(matches!(result, Err(Error::Closed)));
+    }
+}
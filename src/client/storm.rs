@@ -0,0 +1,186 @@
+//! Per event code rate limiting, against event storms of a misbehaving controller.
+//!
+//! A token bucket per code, checked on the frame header before decoding. Exceeding
+//! the rate logs a warning, at most once per [`WARN_INTERVAL`] and code, and drops
+//! the frame if configured. See [`ClientBuilder::event_rate_limit`](super::ClientBuilder::event_rate_limit).
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use super::{COMMAND_COMPLETE, COMMAND_STATUS};
+
+/// Default events per second and code.
+pub const DEFAULT_EVENT_RATE_LIMIT: u32 = 2000;
+
+/// Min interval of warnings for a code.
+const WARN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Codes limited. Higher codes are not.
+const SLOTS: usize = 0x40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Verdict {
+    Pass,
+    /// Over the rate.
+    Excess,
+    /// Over the rate, and the warning is due.
+    ExcessWarn,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    refilled: Option<Instant>,
+    warned: Option<Instant>,
+    /// Excess since the last warning.
+    excess: u64,
+}
+
+#[derive(Debug)]
+pub(crate) struct EventLimiter {
+    per_second: Option<u32>,
+    drop_excess: bool,
+    buckets: Box<[Bucket]>,
+}
+
+impl Default for EventLimiter {
+    fn default() -> Self {
+        Self::new(Some(DEFAULT_EVENT_RATE_LIMIT), false)
+    }
+}
+
+impl EventLimiter {
+    pub(crate) fn new(per_second: Option<u32>, drop_excess: bool) -> Self {
+        let bucket = Bucket {
+            tokens: per_second.unwrap_or_default() as f64,
+            refilled: None,
+            warned: None,
+            excess: 0,
+        };
+        Self {
+            per_second,
+            drop_excess,
+            buckets: vec![bucket; SLOTS].into_boxed_slice(),
+        }
+    }
+
+    /// Check a frame of `code` received at `now`.
+    pub(crate) fn check(&mut self, code: u16, now: Instant) -> Verdict {
+        let per_second = match self.per_second {
+            Some(per_second) if !matches!(code, COMMAND_COMPLETE | COMMAND_STATUS) => {
+                per_second as f64
+            }
+            _ => return Verdict::Pass,
+        };
+        let bucket = match self.buckets.get_mut(code as usize) {
+            Some(bucket) => bucket,
+            None => return Verdict::Pass,
+        };
+
+        if let Some(refilled) = bucket.refilled {
+            let elapsed = now.duration_since(refilled).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * per_second).min(per_second);
+        }
+        bucket.refilled = Some(now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Verdict::Pass;
+        }
+
+        bucket.excess += 1;
+        match bucket.warned {
+            Some(warned) if now.duration_since(warned) < WARN_INTERVAL => Verdict::Excess,
+            _ => {
+                bucket.warned = Some(now);
+                Verdict::ExcessWarn
+            }
+        }
+    }
+
+    /// true to deliver the frame of `code`, false to drop it.
+    pub(crate) fn admit(&mut self, code: u16) -> bool {
+        match self.check(code, Instant::now()) {
+            Verdict::Pass => true,
+            Verdict::Excess => !self.drop_excess,
+            Verdict::ExcessWarn => {
+                let bucket = &mut self.buckets[code as usize];
+                log::warn!(
+                    "event {:#06x} exceeds {} per second: {} over the rate{}",
+                    code,
+                    self.per_second.unwrap_or_default(),
+                    bucket.excess,
+                    if self.drop_excess { ", dropped" } else { "" },
+                );
+                bucket.excess = 0;
+                !self.drop_excess
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEVICE_FOUND: u16 = 0x0012;
+
+    #[test]
+    fn test_warn_once_per_window() {
+        let mut limiter = EventLimiter::new(Some(10), false);
+        let start = Instant::now();
+
+        let verdicts = (0..30)
+            .map(|_| limiter.check(DEVICE_FOUND, start))
+            .collect::<Vec<_>>();
+        assert!(verdicts[..10].iter().all(|v| *v == Verdict::Pass));
+        assert_eq!(Verdict::ExcessWarn, verdicts[10]);
+        assert!(verdicts[11..].iter().all(|v| *v == Verdict::Excess));
+
+        // refilled, but still in the window.
+        let later = start + Duration::from_secs(5);
+        assert_eq!(Verdict::Pass, limiter.check(DEVICE_FOUND, later));
+        let verdicts = (0..20)
+            .map(|_| limiter.check(DEVICE_FOUND, later))
+            .collect::<Vec<_>>();
+        assert!(!verdicts.contains(&Verdict::ExcessWarn));
+
+        // next window.
+        let next = start + WARN_INTERVAL;
+        let verdicts = (0..20)
+            .map(|_| limiter.check(DEVICE_FOUND, next))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            1,
+            verdicts
+                .iter()
+                .filter(|v| **v == Verdict::ExcessWarn)
+                .count()
+        );
+    }
+
+    #[test]
+    fn test_per_code() {
+        let mut limiter = EventLimiter::new(Some(1), false);
+        let now = Instant::now();
+        assert_eq!(Verdict::Pass, limiter.check(DEVICE_FOUND, now));
+        assert_eq!(Verdict::ExcessWarn, limiter.check(DEVICE_FOUND, now));
+        // other code.
+        assert_eq!(Verdict::Pass, limiter.check(0x0006, now));
+        // replies and codes out of the table are never limited.
+        for _ in 0..3 {
+            assert_eq!(Verdict::Pass, limiter.check(COMMAND_COMPLETE, now));
+            assert_eq!(Verdict::Pass, limiter.check(COMMAND_STATUS, now));
+            assert_eq!(Verdict::Pass, limiter.check(0x1000, now));
+        }
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut limiter = EventLimiter::new(None, true);
+        let now = Instant::now();
+        for _ in 0..DEFAULT_EVENT_RATE_LIMIT * 2 {
+            assert!(limiter.admit(DEVICE_FOUND));
+        }
+        assert_eq!(Verdict::Pass, limiter.check(DEVICE_FOUND, now));
+    }
+}
@@ -0,0 +1,543 @@
+//! Error types.
+use std::io;
+use std::time::Duration;
+
+use crate::command::CommandCode;
+use crate::packet::advertising::AdvertisingParamsError;
+use crate::packet::pack;
+use crate::packet::{
+    Address, ControllerIndex, DeviceFlags, Discoverable, ErrorCode, NameError, SettingFlag,
+};
+use crate::role::Role;
+
+/// mgmt API Client Errors.
+///
+/// Each variant documents whether retrying the same operation may succeed.
+/// [`Error::is_retryable`] summarizes that for programmatic use.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// I/O error on the mgmt socket.
+    ///
+    /// Retryable only for transient kinds (`Interrupted`, `WouldBlock`, `TimedOut`).
+    #[error("I/O error")]
+    Io(#[from] io::Error),
+
+    /// Failed to encode a command or decode a reply / event.
+    ///
+    /// Not retryable.
+    #[error("failed to pack or unpack packet")]
+    Pack(#[from] pack::Error),
+
+    /// The kernel replied with a non-success status.
+    ///
+    /// Retryable if the status is `Busy` or `Timeout`.
+    #[error("command {command:?} for {index:?} failed: {status}")]
+    CommandStatus {
+        command: CommandCode,
+        index: ControllerIndex,
+        status: ErrorCode,
+    },
+
+    /// Non-success status without the command, such as one converted by [`From`].
+    /// Replies to calls fail with [`Error::CommandStatus`] instead.
+    ///
+    /// Retryable if the status is `Busy` or `Timeout`.
+    #[error("{0}")]
+    Protocol(ErrorCode),
+
+    /// No reply received in time.
+    ///
+    /// Retryable.
+    #[error("timed out")]
+    Timeout,
+
+    /// The mgmt socket was closed. Pending and later calls fail with this.
+    ///
+    /// Not retryable. Open a new client.
+    #[error("connection closed")]
+    Closed,
+
+    /// The running kernel does not provide the bluetooth mgmt API.
+    ///
+    /// Not retryable.
+    #[error("bluetooth mgmt API is not supported by kernel")]
+    NotSupportedByKernel(#[source] io::Error),
+
+    /// Protocol violation. (e.g. reply for other command)
+    ///
+    /// Not retryable.
+    #[error("unexpected: {0}")]
+    Unexpected(String),
+
+    /// Packet has trailing bytes.
+    ///
+    /// Not retryable.
+    #[error("unreaded content exists {0}")]
+    HasRemaining(usize),
+
+    /// The role is held by another live process.
+    ///
+    /// `pid` is for diagnostics, 0 if the holder has not written it yet. Retryable. The holder may release it.
+    #[error("{role:?} role for {scope:?} is held by pid {pid}")]
+    RoleHeld {
+        role: Role,
+        scope: ControllerIndex,
+        pid: u32,
+    },
+
+    /// All advertising instances of the controller are in use.
+    ///
+    /// Not retryable until an instance is removed.
+    #[error("all {max_instances} advertising instances are in use")]
+    AdvertisingExhausted { max_instances: u8 },
+
+    /// Mutating helper called without the required role.
+    ///
+    /// Not retryable until the role is acquired.
+    #[error("{role:?} role for {scope:?} is required")]
+    RoleRequired { role: Role, scope: ControllerIndex },
+
+    /// Disabling connectable refused, because connectable advertising instances owned by
+    /// this process are active. See [`crate::Adapter::set_connectable_checked`].
+    ///
+    /// Not retryable until the instances are removed.
+    #[error("connectable advertising instances {instances:?} are active")]
+    ConnectableAdvertising { instances: Vec<u8> },
+
+    /// No controller appeared in time. See [`crate::client::Client::wait_for_controller`].
+    ///
+    /// Retryable. An adapter may be enumerated later.
+    #[error("no Bluetooth adapters found")]
+    NoAdapters,
+
+    /// The controller does not support the setting.
+    ///
+    /// Not retryable.
+    #[error("{flag:?} is not supported by {index:?}")]
+    SettingNotSupported {
+        flag: SettingFlag,
+        index: ControllerIndex,
+    },
+
+    /// Scan window longer than the interval.
+    ///
+    /// Not retryable.
+    #[error("scan window {window} is longer than interval {interval}")]
+    InvalidScanParameters { interval: u16, window: u16 },
+
+    /// The device does not support the device flags.
+    ///
+    /// Not retryable.
+    #[error("{flags:?} is not supported by {address:?}")]
+    DeviceFlagNotSupported {
+        address: Address,
+        flags: DeviceFlags,
+    },
+
+    /// Advertising instance not added by this process. See
+    /// [`crate::Adapter::effective_advertising`].
+    ///
+    /// Not retryable.
+    #[error("advertising instance {instance} is not added by this process")]
+    UnknownAdvertising { instance: u8 },
+
+    /// Advertising added, but the controller not powered in time, or the instance gone.
+    ///
+    /// Not retryable. (e.g. the controller is powered off)
+    #[error("advertising instance {instance} did not start")]
+    AdvertisingNotStarted { instance: u8 },
+
+    /// Discoverable window not openable by the kernel.
+    ///
+    /// Not retryable.
+    #[error("discoverable window {mode:?} for {duration:?} is out of range")]
+    InvalidDiscoverableWindow {
+        mode: Discoverable,
+        duration: Duration,
+    },
+
+    /// Local name not representable by the mgmt API. (e.g. too long)
+    ///
+    /// Not retryable.
+    #[error("invalid local name")]
+    InvalidName(#[source] NameError),
+
+    /// Advertising data not addable, or EIR data not parsable as AD structures.
+    ///
+    /// Not retryable.
+    #[error("invalid advertising data")]
+    InvalidAdvertising(#[from] AdvertisingParamsError),
+
+    /// Command of [`crate::Client::call_batch`] not sent, as sending an earlier one failed.
+    ///
+    /// Retryable.
+    #[error("not sent")]
+    NotSent,
+
+    /// List not fitting in a single command frame. Load commands replace the whole
+    /// list in the kernel, so it is not split. See [`crate::Adapter::load_long_term_keys`].
+    ///
+    /// Not retryable.
+    #[error("{count} items for {command:?} exceed the limit of {max} per command")]
+    TooManyItems {
+        command: CommandCode,
+        count: usize,
+        max: usize,
+    },
+}
+
+impl Error {
+    /// true if retrying the same operation may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Io(err) => matches!(
+                err.kind(),
+                io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            ),
+            Self::CommandStatus { status, .. } | Self::Protocol(status) => {
+                matches!(status, ErrorCode::Busy | ErrorCode::Timeout)
+            }
+            Self::Timeout | Self::RoleHeld { .. } | Self::NoAdapters | Self::NotSent => true,
+            Self::Pack(..)
+            | Self::Closed
+            | Self::NotSupportedByKernel(..)
+            | Self::Unexpected(..)
+            | Self::HasRemaining(..)
+            | Self::AdvertisingExhausted { .. }
+            | Self::RoleRequired { .. }
+            | Self::ConnectableAdvertising { .. }
+            | Self::SettingNotSupported { .. }
+            | Self::InvalidScanParameters { .. }
+            | Self::DeviceFlagNotSupported { .. }
+            | Self::AdvertisingNotStarted { .. }
+            | Self::UnknownAdvertising { .. }
+            | Self::InvalidDiscoverableWindow { .. }
+            | Self::InvalidName(..)
+            | Self::InvalidAdvertising(..)
+            | Self::TooManyItems { .. } => false,
+        }
+    }
+
+    /// Returns [`ErrorCode`] if the kernel replied with a non-success status.
+    pub fn status(&self) -> Option<&ErrorCode> {
+        match self {
+            Self::CommandStatus { status, .. } | Self::Protocol(status) => Some(status),
+            _ => None,
+        }
+    }
+
+    /// true if the kernel replied with `PermissionDenied`. (e.g. no `CAP_NET_ADMIN`)
+    pub fn is_permission_denied(&self) -> bool {
+        self.status() == Some(&ErrorCode::PermissionDenied)
+    }
+
+    /// true if the kernel replied with `Busy`.
+    pub fn is_busy(&self) -> bool {
+        self.status() == Some(&ErrorCode::Busy)
+    }
+
+    fn io_error_kind(&self) -> io::ErrorKind {
+        match self {
+            Self::Io(err) => err.kind(),
+            Self::CommandStatus { status, .. } | Self::Protocol(status) => status.io_error_kind(),
+            Self::Timeout => io::ErrorKind::TimedOut,
+            Self::Closed => io::ErrorKind::NotConnected,
+            Self::RoleHeld { .. } => io::ErrorKind::WouldBlock,
+            Self::RoleRequired { .. } => io::ErrorKind::PermissionDenied,
+            Self::NoAdapters | Self::UnknownAdvertising { .. } => io::ErrorKind::NotFound,
+            Self::NotSupportedByKernel(..)
+            | Self::SettingNotSupported { .. }
+            | Self::DeviceFlagNotSupported { .. } => io::ErrorKind::Unsupported,
+            Self::Pack(..) | Self::Unexpected(..) | Self::HasRemaining(..) => {
+                io::ErrorKind::InvalidData
+            }
+            Self::InvalidScanParameters { .. }
+            | Self::InvalidDiscoverableWindow { .. }
+            | Self::InvalidName(..)
+            | Self::InvalidAdvertising(..)
+            | Self::TooManyItems { .. } => io::ErrorKind::InvalidInput,
+            Self::NotSent => io::ErrorKind::Interrupted,
+            Self::AdvertisingExhausted { .. }
+            | Self::ConnectableAdvertising { .. }
+            | Self::AdvertisingNotStarted { .. } => io::ErrorKind::Other,
+        }
+    }
+
+    /// Best-effort [`io::Error`] for io-centric code, with a copy of this error inside.
+    ///
+    /// The copy is reachable by [`io::Error::get_ref`], and `source()` continues its chain.
+    /// Wrapped I/O errors are copied by errno or message, [`Error::InvalidName`] and
+    /// [`Error::InvalidAdvertising`] only by message. Convert by [`From`] to keep the error itself.
+    pub fn to_io_error(&self) -> io::Error {
+        let kind = self.io_error_kind();
+        match self.try_clone() {
+            Some(err) => io::Error::new(kind, err),
+            None => io::Error::new(kind, self.to_string()),
+        }
+    }
+
+    fn try_clone(&self) -> Option<Self> {
+        fn copy_io(err: &io::Error) -> io::Error {
+            match err.raw_os_error() {
+                Some(errno) => io::Error::from_raw_os_error(errno),
+                None => io::Error::new(err.kind(), err.to_string()),
+            }
+        }
+
+        Some(match self {
+            Self::Io(err) => Self::Io(copy_io(err)),
+            Self::Pack(err) => Self::Pack(match err {
+                pack::Error::NoDataAvailable => pack::Error::NoDataAvailable,
+                pack::Error::Io(err) => pack::Error::Io(copy_io(err)),
+                pack::Error::UnexpectedValue(v) => pack::Error::UnexpectedValue(v.clone()),
+                pack::Error::Truncated { expected, actual } => pack::Error::Truncated {
+                    expected: *expected,
+                    actual: *actual,
+                },
+                pack::Error::InvalidEnumValue { name, field, value } => {
+                    pack::Error::InvalidEnumValue {
+                        name,
+                        field: *field,
+                        value: *value,
+                    }
+                }
+            }),
+            Self::CommandStatus {
+                command,
+                index,
+                status,
+            } => Self::CommandStatus {
+                command: command.clone(),
+                index: index.clone(),
+                status: status.clone(),
+            },
+            Self::Protocol(status) => Self::Protocol(status.clone()),
+            Self::Timeout => Self::Timeout,
+            Self::Closed => Self::Closed,
+            Self::NotSent => Self::NotSent,
+            Self::NotSupportedByKernel(err) => Self::NotSupportedByKernel(copy_io(err)),
+            Self::Unexpected(msg) => Self::Unexpected(msg.clone()),
+            Self::HasRemaining(n) => Self::HasRemaining(*n),
+            Self::RoleHeld { role, scope, pid } => Self::RoleHeld {
+                role: *role,
+                scope: scope.clone(),
+                pid: *pid,
+            },
+            Self::AdvertisingExhausted { max_instances } => Self::AdvertisingExhausted {
+                max_instances: *max_instances,
+            },
+            Self::RoleRequired { role, scope } => Self::RoleRequired {
+                role: *role,
+                scope: scope.clone(),
+            },
+            Self::ConnectableAdvertising { instances } => Self::ConnectableAdvertising {
+                instances: instances.clone(),
+            },
+            Self::NoAdapters => Self::NoAdapters,
+            Self::SettingNotSupported { flag, index } => Self::SettingNotSupported {
+                flag: *flag,
+                index: index.clone(),
+            },
+            Self::InvalidScanParameters { interval, window } => Self::InvalidScanParameters {
+                interval: *interval,
+                window: *window,
+            },
+            Self::DeviceFlagNotSupported { address, flags } => Self::DeviceFlagNotSupported {
+                address: address.clone(),
+                flags: *flags,
+            },
+            Self::AdvertisingNotStarted { instance } => Self::AdvertisingNotStarted {
+                instance: *instance,
+            },
+            Self::UnknownAdvertising { instance } => Self::UnknownAdvertising {
+                instance: *instance,
+            },
+            Self::InvalidDiscoverableWindow { mode, duration } => Self::InvalidDiscoverableWindow {
+                mode: *mode,
+                duration: *duration,
+            },
+            Self::InvalidName(..) | Self::InvalidAdvertising(..) => return None,
+            Self::TooManyItems {
+                command,
+                count,
+                max,
+            } => Self::TooManyItems {
+                command: command.clone(),
+                count: *count,
+                max: *max,
+            },
+        })
+    }
+}
+
+impl From<ErrorCode> for Error {
+    fn from(status: ErrorCode) -> Self {
+        Self::Protocol(status)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        io::Error::new(err.io_error_kind(), err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+
+    #[test]
+    fn test_io_source() {
+        let err = Error::from(io::Error::other("boom"));
+        let source = err.source().unwrap();
+        let source = source.downcast_ref::<io::Error>().unwrap();
+        assert_eq!("boom", source.to_string());
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_pack_source_chain() {
+        let err = pack::Error::from(io::Error::from(io::ErrorKind::UnexpectedEof));
+        let err = Error::from(err);
+        let source = err.source().unwrap();
+        assert!(source.downcast_ref::<pack::Error>().is_some());
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_not_supported_source() {
+        let err = Error::NotSupportedByKernel(io::Error::from_raw_os_error(libc::EAFNOSUPPORT));
+        let source = err.source().unwrap();
+        let source = source.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(Some(libc::EAFNOSUPPORT), source.raw_os_error());
+    }
+
+    #[test]
+    fn test_command_status() {
+        let err = Error::CommandStatus {
+            command: CommandCode::SetPowered,
+            index: ControllerIndex::ControllerId(0),
+            status: ErrorCode::Busy,
+        };
+        assert!(err.source().is_none());
+        assert!(err.is_retryable());
+        assert_eq!(Some(&ErrorCode::Busy), err.status());
+        assert!(err.to_string().contains("Busy (0x0A)"));
+        assert!(err.is_busy());
+        assert!(!err.is_permission_denied());
+        assert!(!Error::Timeout.is_busy());
+    }
+
+    #[test]
+    fn test_protocol() {
+        let err = Error::from(ErrorCode::Busy);
+        assert!(matches!(err, Error::Protocol(ErrorCode::Busy)));
+        assert!(err.is_retryable());
+        assert!(err.is_busy());
+        assert_eq!("Busy (0x0A)", err.to_string());
+        assert_eq!(io::ErrorKind::WouldBlock, err.to_io_error().kind());
+
+        let err = Error::from(ErrorCode::PermissionDenied);
+        assert!(err.is_permission_denied());
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_to_io_error_status() {
+        use io::ErrorKind::*;
+
+        let table = [
+            (ErrorCode::Success, Other),
+            (ErrorCode::UnknownCommand, Unsupported),
+            (ErrorCode::NotConnected, NotConnected),
+            (ErrorCode::Failed, Other),
+            (ErrorCode::ConnectFailed, ConnectionRefused),
+            (ErrorCode::AuthenticationFailed, PermissionDenied),
+            (ErrorCode::NotPaired, NotFound),
+            (ErrorCode::NoResources, OutOfMemory),
+            (ErrorCode::Timeout, TimedOut),
+            (ErrorCode::AlreadyConnected, AlreadyExists),
+            (ErrorCode::Busy, WouldBlock),
+            (ErrorCode::Rejected, Other),
+            (ErrorCode::NotSupported, Unsupported),
+            (ErrorCode::InvalidParameters, InvalidInput),
+            (ErrorCode::Disconnected, ConnectionAborted),
+            (ErrorCode::NotPowered, NetworkDown),
+            (ErrorCode::Cancelled, Other),
+            (ErrorCode::InvalidIndex, NotFound),
+            (ErrorCode::RfKilled, NetworkDown),
+            (ErrorCode::AlreadyPaired, AlreadyExists),
+            (ErrorCode::PermissionDenied, PermissionDenied),
+        ];
+        // every code, in order.
+        for (n, (status, _)) in table.iter().enumerate() {
+            assert_eq!(n, status.clone() as usize);
+        }
+        assert!(pack::Unpack::unpack(&mut &[table.len() as u8][..])
+            .map(|_: ErrorCode| ())
+            .is_err());
+
+        for (status, kind) in table.iter() {
+            assert_eq!(*kind, status.io_error_kind(), "{}", status);
+
+            let err = Error::CommandStatus {
+                command: CommandCode::SetPowered,
+                index: ControllerIndex::ControllerId(0),
+                status: status.clone(),
+            };
+            let io_err = err.to_io_error();
+            assert_eq!(*kind, io_err.kind(), "{}", status);
+            assert_eq!(err.to_string(), io_err.to_string());
+            let inner = io_err.get_ref().unwrap().downcast_ref::<Error>().unwrap();
+            assert_eq!(Some(status), inner.status());
+
+            let io_err = io::Error::from(err);
+            let inner = io_err.into_inner().unwrap().downcast::<Error>().unwrap();
+            assert_eq!(Some(status), inner.status());
+        }
+    }
+
+    #[test]
+    fn test_to_io_error_source() {
+        let err = Error::NotSupportedByKernel(io::Error::from_raw_os_error(libc::EAFNOSUPPORT));
+        let io_err = err.to_io_error();
+        assert_eq!(io::ErrorKind::Unsupported, io_err.kind());
+        assert!(matches!(
+            io_err.get_ref().unwrap().downcast_ref::<Error>(),
+            Some(Error::NotSupportedByKernel(..))
+        ));
+        let source = io_err
+            .source()
+            .unwrap()
+            .downcast_ref::<io::Error>()
+            .unwrap();
+        assert_eq!(Some(libc::EAFNOSUPPORT), source.raw_os_error());
+
+        let err = Error::Pack(pack::Error::UnexpectedValue("x".into()));
+        let io_err = err.to_io_error();
+        assert_eq!(io::ErrorKind::InvalidData, io_err.kind());
+        let source = io_err.source().unwrap().downcast_ref::<pack::Error>();
+        assert!(matches!(source, Some(pack::Error::UnexpectedValue(v)) if v == "x"));
+
+        let err = Error::InvalidName(crate::packet::Name::new("a\0b").unwrap_err());
+        let io_err = err.to_io_error();
+        assert_eq!(io::ErrorKind::InvalidInput, io_err.kind());
+        assert_eq!("invalid local name", io_err.to_string());
+        assert!(io_err.get_ref().unwrap().downcast_ref::<Error>().is_none());
+
+        let err = Error::from(AdvertisingParamsError::Malformed(3));
+        assert!(!err.is_retryable());
+        assert_eq!(io::ErrorKind::InvalidInput, err.to_io_error().kind());
+
+        assert_eq!(io::ErrorKind::TimedOut, Error::Timeout.to_io_error().kind());
+        assert_eq!(
+            io::ErrorKind::NotConnected,
+            Error::Closed.to_io_error().kind()
+        );
+    }
+}
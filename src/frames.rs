@@ -0,0 +1,71 @@
+//! Frames of the mgmt API and a scripted kernel, for tests on [`crate::Client::pair`].
+//!
+//! Frames are for controller 0.
+use tokio::net::UnixDatagram;
+
+/// Code, index and parameter length (all little endian) followed by `params`.
+fn frame(code: u16, params: &[u8]) -> Vec<u8> {
+    let mut b = code.to_le_bytes().to_vec();
+    b.extend(0u16.to_le_bytes());
+    b.extend((params.len() as u16).to_le_bytes());
+    b.extend(params);
+    b
+}
+
+pub(crate) fn command(opcode: u16, params: &[u8]) -> Vec<u8> {
+    frame(opcode, params)
+}
+
+pub(crate) fn event(code: u16, params: &[u8]) -> Vec<u8> {
+    frame(code, params)
+}
+
+/// Command Complete with success.
+pub(crate) fn complete(opcode: u16, params: &[u8]) -> Vec<u8> {
+    complete_with(opcode, 0x00, params)
+}
+
+/// Command Complete with `status`.
+pub(crate) fn complete_with(opcode: u16, status: u8, params: &[u8]) -> Vec<u8> {
+    let mut b = opcode.to_le_bytes().to_vec();
+    b.push(status);
+    b.extend(params);
+    frame(0x0001, &b)
+}
+
+/// Command Status.
+pub(crate) fn status(opcode: u16, status: u8) -> Vec<u8> {
+    let mut b = opcode.to_le_bytes().to_vec();
+    b.push(status);
+    frame(0x0002, &b)
+}
+
+/// Next frame sent by the client.
+pub(crate) async fn recv(peer: &UnixDatagram) -> Vec<u8> {
+    let mut buf = vec![0; 0x10006];
+    let n = peer.recv(&mut buf).await.unwrap();
+    buf.truncate(n);
+    buf
+}
+
+/// Expect requests in order, replying each.
+pub(crate) async fn kernel(peer: &UnixDatagram, exchanges: Vec<(Vec<u8>, Vec<u8>)>) {
+    for (request, reply) in exchanges {
+        assert_eq!(request, recv(peer).await);
+        peer.send(&reply).await.unwrap();
+    }
+}
+
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_params() {
+        let b = complete(0x0042, &[0; 300]);
+        assert_eq!(
+            &[0x01, 0x00, 0x00, 0x00, 0x2F, 0x01, 0x42, 0x00, 0x00],
+            &b[..9]
+        );
+        assert_eq!(6 + 303, b.len());
+    }
+}
@@ -61,6 +61,7 @@
 //!   ([LICENSE-APACHE](LICENSE-APACHE) or <http://www.apache.org/licenses/LICENSE-2.0>)
 //! * MIT license
 //!   ([LICENSE-MIT](LICENSE-MIT) or <http://opensource.org/licenses/MIT>)
+//!
 //! at your option.
 //!
 //! ## Contribution
@@ -68,8 +69,24 @@
 //! Unless you explicitly state otherwise, any contribution intentionally submitted
 //! for inclusion in the work by you, as defined in the Apache-2.0 license, shall be
 //! dual licensed as above, without any additional terms or conditions.!
+pub use adapter::Adapter;
 pub use btmgmt_packet as packet;
-pub use client::Client;
+pub use client::{
+    AllIndices, Client, ClientBuilder, Commander, ControllerChange, ControllerSummary, Discovery,
+    EventHub, EventOverflowPolicy, PairingMode, RawReply,
+};
+pub use error::{Error, Result};
 pub use packet::{command, event};
+pub use scope::ScopedClient;
+pub mod adapter;
 pub mod client;
+mod error;
+#[cfg(test)]
+mod frames;
+pub mod role;
+pub mod scope;
 mod sock;
+pub mod stats;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+pub mod throttle;
@@ -0,0 +1,282 @@
+//! Advisory roles among processes sharing controllers.
+//!
+//! The kernel accepts commands from every mgmt socket, so two daemons running side by side
+//! (e.g. during an upgrade) may fight over the same setting. A role is an `flock(2)` on a
+//! lock file, released by the kernel when its owner exits. The file holds the owner's pid
+//! for diagnostics only. Nothing is enforced by the kernel, and nothing changes unless
+//! roles are used. See [`crate::Client::acquire_role`] and [`crate::Adapter::require_primary`].
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::client::{Error, Result};
+use crate::packet::ControllerIndex;
+
+/// Default directory of lock files.
+pub const DEFAULT_LOCK_DIR: &str = "/run/btmgmt";
+
+/// Role of this process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// The process allowed to mutate controller state.
+    Primary,
+}
+
+/// Roles held by a client and its handles.
+#[derive(Debug, Default)]
+pub(crate) struct Roles(Mutex<HashSet<(Role, ControllerIndex)>>);
+
+impl Roles {
+    pub(crate) fn holds(&self, role: Role, scope: &ControllerIndex) -> bool {
+        self.0.lock().unwrap().contains(&(role, scope.clone()))
+    }
+}
+
+/// Held role. Released on drop.
+#[derive(Debug)]
+pub struct RoleGuard {
+    role: Role,
+    scope: ControllerIndex,
+    path: PathBuf,
+    /// Holds the `flock(2)`. Released when closed.
+    _lock: fs::File,
+    roles: Weak<Roles>,
+}
+
+impl RoleGuard {
+    /// Held role.
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// Controller the role is held for.
+    pub fn scope(&self) -> &ControllerIndex {
+        &self.scope
+    }
+
+    /// Lock file path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for RoleGuard {
+    fn drop(&mut self) {
+        if let Some(roles) = self.roles.upgrade() {
+            roles
+                .0
+                .lock()
+                .unwrap()
+                .remove(&(self.role, self.scope.clone()));
+        }
+        // unlink while still locked. who opened it before sees it unlinked and retries.
+        if let Err(err) = fs::remove_file(&self.path) {
+            log::warn!("failed to remove {}: {}", self.path.display(), err);
+        }
+    }
+}
+
+fn lock_path(dir: &Path, role: Role, scope: &ControllerIndex) -> PathBuf {
+    let role = match role {
+        Role::Primary => "primary",
+    };
+    let name = match scope {
+        ControllerIndex::ControllerId(id) => format!("{}-hci{}.lock", role, id),
+        ControllerIndex::NonController => format!("{}-global.lock", role),
+    };
+    dir.join(name)
+}
+
+fn read_pid(path: &Path) -> io::Result<Option<u32>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content.trim().parse().ok()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Take `flock(2)` without blocking. `Ok(false)` if another open file holds it.
+fn try_lock(file: &fs::File) -> io::Result<bool> {
+    let r = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+    if r == 0 {
+        return Ok(true);
+    }
+    match io::Error::last_os_error() {
+        err if err.raw_os_error() == Some(libc::EWOULDBLOCK) => Ok(false),
+        err => Err(err),
+    }
+}
+
+/// Whether `file` is still linked at `path`, i.e. not unlinked by a releasing holder
+/// between our open and lock.
+fn is_linked(file: &fs::File, path: &Path) -> io::Result<bool> {
+    let opened = file.metadata()?;
+    match fs::metadata(path) {
+        Ok(linked) => Ok(opened.dev() == linked.dev() && opened.ino() == linked.ino()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+pub(crate) fn acquire(
+    roles: &Arc<Roles>,
+    dir: &Path,
+    role: Role,
+    scope: ControllerIndex,
+) -> Result<RoleGuard> {
+    fs::create_dir_all(dir)?;
+    let path = lock_path(dir, role, &scope);
+
+    for _ in 0..3 {
+        let mut file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        if !try_lock(&file)? {
+            // pid is written right after locking. 0 if we read in between.
+            let pid = read_pid(&path)?.unwrap_or(0);
+            return Err(Error::RoleHeld { role, scope, pid });
+        }
+        if !is_linked(&file, &path)? {
+            continue;
+        }
+
+        if let Some(holder) = read_pid(&path)? {
+            log::warn!("take over stale lock {} ({})", path.display(), holder);
+        }
+        file.set_len(0)?;
+        writeln!(file, "{}", std::process::id())?;
+
+        roles.0.lock().unwrap().insert((role, scope.clone()));
+        return Ok(RoleGuard {
+            role,
+            scope,
+            path,
+            _lock: file,
+            roles: Arc::downgrade(roles),
+        });
+    }
+    Err(Error::Unexpected(format!(
+        "lock {} keeps changing",
+        path.display()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    fn lock_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("btmgmt-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        dir
+    }
+
+    #[test]
+    fn test_acquire() {
+        let dir = lock_dir("acquire");
+        let roles = Arc::new(Roles::default());
+        let scope = ControllerIndex::ControllerId(0);
+
+        let guard = acquire(&roles, &dir, Role::Primary, scope.clone()).unwrap();
+        assert!(roles.holds(Role::Primary, &scope));
+        assert!(!roles.holds(Role::Primary, &ControllerIndex::ControllerId(1)));
+        assert_eq!(Some(std::process::id()), read_pid(guard.path()).unwrap());
+
+        // held by live process. (this one)
+        let err = acquire(&roles, &dir, Role::Primary, scope.clone()).unwrap_err();
+        assert!(matches!(err, Error::RoleHeld { pid, .. } if pid == std::process::id()));
+
+        let path = guard.path().to_owned();
+        drop(guard);
+        assert!(!roles.holds(Role::Primary, &scope));
+        assert!(!path.exists());
+
+        acquire(&roles, &dir, Role::Primary, scope).unwrap();
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_take_over_stale() {
+        let dir = lock_dir("stale");
+        let roles = Arc::new(Roles::default());
+        let scope = ControllerIndex::ControllerId(0);
+
+        let mut child = Command::new("true").spawn().unwrap();
+        let dead = child.id();
+        child.wait().unwrap();
+
+        fs::create_dir_all(&dir).unwrap();
+        let path = lock_path(&dir, Role::Primary, &scope);
+        fs::write(&path, format!("{}\n", dead)).unwrap();
+
+        let guard = acquire(&roles, &dir, Role::Primary, scope).unwrap();
+        assert_eq!(Some(std::process::id()), read_pid(guard.path()).unwrap());
+        drop(guard);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_take_over_unlocked_live_pid() {
+        // a reused pid does not keep the lock.
+        let dir = lock_dir("reused");
+        let roles = Arc::new(Roles::default());
+        let scope = ControllerIndex::ControllerId(0);
+
+        let init = 1;
+        fs::create_dir_all(&dir).unwrap();
+        let path = lock_path(&dir, Role::Primary, &scope);
+        fs::write(&path, format!("{}\n", init)).unwrap();
+
+        let guard = acquire(&roles, &dir, Role::Primary, scope).unwrap();
+        assert_eq!(Some(std::process::id()), read_pid(guard.path()).unwrap());
+        drop(guard);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_contention() {
+        let dir = lock_dir("contention");
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+        let scope = ControllerIndex::ControllerId(0);
+
+        let handles = (0..2)
+            .map(|_| {
+                let dir = dir.clone();
+                let barrier = barrier.clone();
+                let scope = scope.clone();
+                std::thread::spawn(move || {
+                    let roles = Arc::new(Roles::default());
+                    let mut won = 0;
+                    for _ in 0..200 {
+                        barrier.wait();
+                        let result = acquire(&roles, &dir, Role::Primary, scope.clone());
+                        // both attempts are done before either releases.
+                        barrier.wait();
+                        match result {
+                            Ok(..) => won += 1,
+                            Err(Error::RoleHeld { .. }) => {}
+                            Err(err) => panic!("{}", err),
+                        }
+                    }
+                    won
+                })
+            })
+            .collect::<Vec<_>>();
+        let won = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .sum::<usize>();
+        assert_eq!(200, won);
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,402 @@
+//! Resources attributed to owners sharing a client.
+//!
+//! Several components of one process may share a [`Client`]. A [`ScopedClient`] records
+//! advertising instances, advertisement monitors, devices and discovery it creates under
+//! an owner tag, so that leaks can be traced (see [`Client::resources_by_owner`]) and
+//! released at once (see [`ScopedClient::cleanup`]).
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::Mutex;
+
+use crate::client::{Client, Result};
+use crate::command::{self, CommandCode};
+use crate::event::Event;
+use crate::packet::pack::Unpack;
+use crate::packet::{
+    Address, AddressTypes, AdvertiseInstance, AdvertisementMonitorHandle, BdAddr, ControllerIndex,
+};
+
+/// Resource created by a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resource {
+    /// Added by Add Advertising.
+    Advertising(AdvertiseInstance),
+
+    /// Added by Add Advertisement Patterns Monitor.
+    AdvertisementMonitor(AdvertisementMonitorHandle),
+
+    /// Added by Add Device.
+    Device(Address),
+
+    /// Started by Start (Limited / Service) Discovery.
+    Discovery(AddressTypes),
+}
+
+impl Resource {
+    /// true if `self` is removed by removing `other`.
+    fn removed_by(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Advertising(a), Self::Advertising(b)) => a == b || **b == 0,
+            (Self::AdvertisementMonitor(a), Self::AdvertisementMonitor(b)) => a == b || **b == 0,
+            (Self::Device(a), Self::Device(b)) => {
+                a == b || b == &BdAddr::from([0; 6]).to_br_edr_addr()
+            }
+            // one discovery per controller.
+            (Self::Discovery(..), Self::Discovery(..)) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Owned {
+    owner: &'static str,
+    index: ControllerIndex,
+    resource: Resource,
+}
+
+/// Resources of a client, by owner.
+#[derive(Debug, Default)]
+pub(crate) struct Owners(Mutex<Vec<Owned>>);
+
+impl Owners {
+    fn created(&self, owner: &'static str, index: &ControllerIndex, resource: Resource) {
+        let mut owned = self.0.lock().unwrap();
+        // added again: the last owner takes it.
+        owned.retain(|o| !(&o.index == index && o.resource == resource));
+        owned.push(Owned {
+            owner,
+            index: index.clone(),
+            resource,
+        });
+    }
+
+    fn removed(&self, index: &ControllerIndex, resource: &Resource) {
+        let mut owned = self.0.lock().unwrap();
+        owned.retain(|o| !(&o.index == index && o.resource.removed_by(resource)));
+    }
+
+    /// Record the successful reply of a command.
+    pub(crate) fn replied(
+        &self,
+        owner: Option<&'static str>,
+        index: &ControllerIndex,
+        code: &CommandCode,
+        params: &[u8],
+    ) {
+        fn unpack<R: Unpack>(params: &[u8]) -> Option<R> {
+            R::unpack(&mut &params[..]).ok()
+        }
+
+        let created = match code {
+            CommandCode::AddAdvertising => unpack::<command::AddAdvertisingReply>(params)
+                .map(|reply| Resource::Advertising((*reply).clone())),
+            CommandCode::AddAdvertisementPatternsMonitor => {
+                unpack::<command::AddAdvertisementPatternsMonitorReply>(params)
+                    .map(|reply| Resource::AdvertisementMonitor((*reply).clone()))
+            }
+            CommandCode::AddDevice => unpack::<command::AddDeviceReply>(params)
+                .map(|reply| Resource::Device(reply.address())),
+            CommandCode::StartDiscovery => unpack::<command::StartDiscoveryReply>(params)
+                .map(|reply| Resource::Discovery((*reply).clone())),
+            CommandCode::StartLimitedDiscovery => {
+                unpack::<command::StartLimitedDiscoveryReply>(params)
+                    .map(|reply| Resource::Discovery((*reply).clone()))
+            }
+            CommandCode::StartServiceDiscovery => {
+                unpack::<command::StartServiceDiscoveryReply>(params)
+                    .map(|reply| Resource::Discovery((*reply).clone()))
+            }
+            _ => None,
+        };
+        if let Some(resource) = created {
+            if let Some(owner) = owner {
+                self.created(owner, index, resource);
+            }
+            return;
+        }
+
+        let removed = match code {
+            CommandCode::RemoveAdvertising => unpack::<command::RemoveAdvertisingReply>(params)
+                .map(|reply| Resource::Advertising((*reply).clone())),
+            CommandCode::RemoveAdvertisementPatternsMonitor => {
+                unpack::<command::RemoveAdvertisementPatternsMonitorReply>(params)
+                    .map(|reply| Resource::AdvertisementMonitor((*reply).clone()))
+            }
+            CommandCode::RemoveDevice => unpack::<command::RemoveDeviceReply>(params)
+                .map(|reply| Resource::Device(reply.address())),
+            CommandCode::StopDiscovery => unpack::<command::StopDiscoveryReply>(params)
+                .map(|reply| Resource::Discovery((*reply).clone())),
+            _ => None,
+        };
+        if let Some(resource) = removed {
+            self.removed(index, &resource);
+        }
+    }
+
+    /// Forget resources removed by the kernel or other clients.
+    pub(crate) fn observed(&self, index: &ControllerIndex, event: &Event) {
+        let removed = match event {
+            Event::AdvertisingRemoved(removed) => Resource::Advertising((**removed).clone()),
+            Event::AdvertisementMonitorRemoved(removed) => {
+                Resource::AdvertisementMonitor((**removed).clone())
+            }
+            Event::DeviceRemoved(removed) => Resource::Device(removed.address()),
+            Event::Discovering(discovering) if !discovering.discovering() => {
+                Resource::Discovery(discovering.address_type().clone())
+            }
+            Event::IndexRemoved(..) => {
+                let mut owned = self.0.lock().unwrap();
+                owned.retain(|o| &o.index != index);
+                return;
+            }
+            _ => return,
+        };
+        self.removed(index, &removed);
+    }
+
+    pub(crate) fn snapshot(&self) -> HashMap<&'static str, Vec<(ControllerIndex, Resource)>> {
+        let mut snapshot = HashMap::<_, Vec<_>>::new();
+        for o in self.0.lock().unwrap().iter() {
+            snapshot
+                .entry(o.owner)
+                .or_default()
+                .push((o.index.clone(), o.resource.clone()));
+        }
+        snapshot
+    }
+
+    fn owned_by(&self, owner: &'static str) -> Vec<(ControllerIndex, Resource)> {
+        self.snapshot().remove(owner).unwrap_or_default()
+    }
+
+    fn forget(&self, owner: &'static str) {
+        self.0.lock().unwrap().retain(|o| o.owner != owner);
+    }
+}
+
+/// Client recording resources created through it under an owner tag.
+///
+/// Created by [`Client::scoped`]. Only commands sent by [`ScopedClient::call`] are recorded.
+/// Resources removed by the kernel or by any client are forgotten.
+#[derive(Clone)]
+pub struct ScopedClient {
+    client: Client,
+    owner: &'static str,
+}
+
+impl fmt::Debug for ScopedClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScopedClient")
+            .field("owner", &self.owner)
+            .finish()
+    }
+}
+
+impl ScopedClient {
+    pub(crate) fn new(client: Client, owner: &'static str) -> Self {
+        Self { client, owner }
+    }
+
+    /// Owner tag.
+    pub fn owner(&self) -> &'static str {
+        self.owner
+    }
+
+    /// Client.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Call mgmt API command. See [`Client::call`].
+    pub fn call<C, I>(
+        &self,
+        index: I,
+        command: C,
+    ) -> impl Future<Output = Result<C::Reply>> + 'static
+    where
+        C: command::CommandRequest + 'static,
+        C::Reply: fmt::Debug,
+        I: Into<ControllerIndex>,
+    {
+        self.client.call_as(self.owner, index, command)
+    }
+
+    /// Resources of this owner, not released yet.
+    pub fn resources(&self) -> Vec<(ControllerIndex, Resource)> {
+        self.client.owners().owned_by(self.owner)
+    }
+
+    /// Release every resource of this owner, the latest first.
+    ///
+    /// Failed removals do not stop the others; the first error is returned.
+    /// The resources are forgotten either way.
+    pub async fn cleanup(&self) -> Result<()> {
+        let mut result = Ok(());
+        for (index, resource) in self.resources().into_iter().rev() {
+            let removed = match &resource {
+                Resource::Advertising(instance) => self
+                    .call(index, command::RemoveAdvertising::new(instance.clone()))
+                    .await
+                    .map(drop),
+                Resource::AdvertisementMonitor(handle) => self
+                    .call(
+                        index,
+                        command::RemoveAdvertisementPatternsMonitor::new(handle.clone()),
+                    )
+                    .await
+                    .map(drop),
+                Resource::Device(address) => self
+                    .call(index, command::RemoveDevice::new(address.clone()))
+                    .await
+                    .map(drop),
+                Resource::Discovery(types) => self
+                    .call(index, command::StopDiscovery::new(types.clone()))
+                    .await
+                    .map(drop),
+            };
+            if let Err(err) = removed {
+                log::warn!("{}: failed to release {:?}: {}", self.owner, resource, err);
+                result = result.and(Err(err));
+            }
+        }
+        self.client.owners().forget(self.owner);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::stream::StreamExt;
+    use tokio::net::UnixDatagram;
+
+    use super::*;
+    use crate::frames::{command, complete, kernel};
+    use crate::packet::{Action, AddressType, AdvDataScanResp, AdvertisingFlag};
+
+    /// Reply the next command, checking its opcode and parameters.
+    async fn expect(peer: &UnixDatagram, opcode: u16, params: &[u8], reply: &[u8]) {
+        kernel(
+            peer,
+            vec![(command(opcode, params), complete(opcode, reply))],
+        )
+        .await;
+    }
+
+    fn add_advertising(instance: u8) -> command::AddAdvertising {
+        command::AddAdvertising::new(
+            instance.into(),
+            AdvertisingFlag::empty(),
+            0,
+            0,
+            AdvDataScanResp::new(vec![], vec![]),
+        )
+    }
+
+    fn le_only() -> AddressTypes {
+        let mut types = AddressTypes::default();
+        types.extend([AddressType::LePublic, AddressType::LeRandom]);
+        types
+    }
+
+    const DEVICE: [u8; 6] = [0x66, 0x55, 0x44, 0x33, 0x22, 0x11];
+
+    #[tokio::test]
+    async fn test_scoped_cleanup() {
+        let (client, peer) = Client::pair();
+        let a = client.scoped("a");
+        let b = client.scoped("b");
+        let device = BdAddr::from(DEVICE).to_br_edr_addr();
+
+        let kernel = async {
+            let mut add = vec![0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+            add.extend([0x00, 0x00, 0x00]);
+            expect(&peer, 0x3E, &add, &[0x01]).await;
+            expect(&peer, 0x23, &[0x06], &[0x06]).await;
+            add[0] = 0x02;
+            expect(&peer, 0x3E, &add, &[0x02]).await;
+            let mut params = DEVICE.to_vec();
+            params.extend([0x00, 0x02]);
+            expect(&peer, 0x33, &params, &params[..7]).await;
+        };
+        let calls = async {
+            a.call(0, add_advertising(1)).await.unwrap();
+            a.call(0, command::StartDiscovery::new(le_only()))
+                .await
+                .unwrap();
+            b.call(0, add_advertising(2)).await.unwrap();
+            b.call(
+                0,
+                command::AddDevice::new(device.clone(), Action::AutoConnect),
+            )
+            .await
+            .unwrap();
+        };
+        tokio::join!(kernel, calls);
+
+        let index = ControllerIndex::ControllerId(0);
+        let resources = client.resources_by_owner();
+        assert_eq!(
+            vec![
+                (index.clone(), Resource::Advertising(1.into())),
+                (index.clone(), Resource::Discovery(le_only())),
+            ],
+            resources["a"]
+        );
+        assert_eq!(2, resources["b"].len());
+
+        // removed by other process.
+        let mut events = client.events().await;
+        peer.send(&[0x24, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02])
+            .await
+            .unwrap();
+        events.next().await.unwrap();
+        assert_eq!(
+            vec![(index.clone(), Resource::Device(device.clone()))],
+            b.resources()
+        );
+
+        let kernel = async {
+            expect(&peer, 0x24, &[0x06], &[0x06]).await;
+            expect(&peer, 0x3F, &[0x01], &[0x01]).await;
+        };
+        let (result, _) = tokio::join!(a.cleanup(), kernel);
+        result.unwrap();
+
+        let resources = client.resources_by_owner();
+        assert!(!resources.contains_key("a"));
+        assert_eq!(vec![(index, Resource::Device(device))], resources["b"]);
+        // nothing sent for b.
+        let mut buf = [0; 64];
+        assert_eq!(
+            std::io::ErrorKind::WouldBlock,
+            peer.try_recv(&mut buf).unwrap_err().kind()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_removed_by_any_client() {
+        let (client, peer) = Client::pair();
+        let scoped = client.scoped("a");
+
+        let kernel = async {
+            expect(&peer, 0x52, &[0x00, 0x00], &[0x01, 0x00]).await;
+            expect(&peer, 0x52, &[0x00, 0x00], &[0x02, 0x00]).await;
+            // all monitors
+            expect(&peer, 0x53, &[0x00, 0x00], &[0x00, 0x00]).await;
+        };
+        let calls = async {
+            let monitor = command::AddAdvertisementPatternsMonitor::from_iter(vec![]);
+            scoped.call(0, monitor).await.unwrap();
+            let monitor = command::AddAdvertisementPatternsMonitor::from_iter(vec![]);
+            scoped.call(0, monitor).await.unwrap();
+            assert_eq!(2, scoped.resources().len());
+
+            let remove = command::RemoveAdvertisementPatternsMonitor::new(0.into());
+            client.call(0, remove).await.unwrap();
+        };
+        tokio::join!(kernel, calls);
+        assert!(scoped.resources().is_empty());
+    }
+}
@@ -1,9 +1,11 @@
 use std::io;
+use std::mem;
 use std::net::Shutdown;
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use libc::{c_int, c_ushort, sa_family_t};
+use libc::{c_int, c_ushort, sa_family_t, socklen_t};
 use socket2::{Domain, Protocol, SockAddr, Socket, Type};
 use tokio::io::unix::AsyncFd;
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
@@ -21,20 +23,34 @@ struct sockaddr_hci {
     hci_channel: c_ushort,
 }
 
-fn mgmt_open_bind() -> io::Result<Socket> {
+/// Non-blocking, and close-on-exec unless `inheritable`.
+///
+/// Flags are set by `socket(2)` itself, not to race with `fork` in other threads.
+/// Use with [`Socket::new_raw`], which adds no flags.
+fn socket_type(r#type: Type, inheritable: bool) -> Type {
+    let r#type = r#type.nonblocking();
+    if inheritable {
+        r#type
+    } else {
+        r#type.cloexec()
+    }
+}
+
+fn mgmt_open_bind(inheritable: bool) -> io::Result<Socket> {
     let domain = Domain::from(libc::AF_BLUETOOTH);
-    let r#type = Type::RAW.nonblocking().cloexec();
+    let r#type = socket_type(Type::RAW, inheritable);
     let proto = Protocol::from(BTPROTO_HCI);
-    let sock = Socket::new(domain, r#type, Some(proto))?;
+    let sock = Socket::new_raw(domain, r#type, Some(proto))?;
 
     let (_, addr) = unsafe {
-        SockAddr::init(move |addr, _| {
+        SockAddr::try_init(move |addr, len| {
             let addr = &mut *(addr as *mut sockaddr_hci);
             *addr = sockaddr_hci {
                 hci_family: libc::AF_BLUETOOTH as sa_family_t,
                 hci_dev: HCI_DEV_NONE,
                 hci_channel: HCI_CHANNEL_CONTROL,
             };
+            *len = mem::size_of::<sockaddr_hci>() as socklen_t;
             Ok(())
         })
     }?;
@@ -48,11 +64,29 @@ pub(crate) struct MgmtSocket {
 }
 
 impl MgmtSocket {
-    pub(crate) fn new() -> io::Result<Self> {
-        let sock = mgmt_open_bind()?;
+    pub(crate) fn new(inheritable: bool) -> io::Result<Self> {
+        let sock = mgmt_open_bind(inheritable)?;
         let sock = AsyncFd::new(sock)?;
         Ok(Self { inner: sock })
     }
+
+    /// Connected pair for tests. The peer acts as the kernel.
+    #[cfg(any(test, feature = "test-util"))]
+    pub(crate) fn pair() -> io::Result<(Self, tokio::net::UnixDatagram)> {
+        let (local, peer) = std::os::unix::net::UnixDatagram::pair()?;
+        local.set_nonblocking(true)?;
+        peer.set_nonblocking(true)?;
+        let sock = Self {
+            inner: AsyncFd::new(Socket::from(local))?,
+        };
+        Ok((sock, tokio::net::UnixDatagram::from_std(peer)?))
+    }
+}
+
+impl AsRawFd for MgmtSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
 }
 
 impl AsyncRead for MgmtSocket {
@@ -102,13 +136,46 @@ impl AsyncWrite for MgmtSocket {
                         "write zero.",
                     )))
                 }
-                Ok(Ok(n)) => return Poll::Ready(Ok(n)),
+                Ok(Ok(n)) => return Poll::Ready(whole_packet(n, buf.len())),
                 Ok(Err(err)) => return Poll::Ready(Err(err)),
                 Err(..) => {}
             }
         }
     }
 
+    /// Sends buffers as one packet.
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            let mut guard = match self.inner.poll_write_ready(cx)? {
+                Poll::Ready(guard) => guard,
+                Poll::Pending => return Poll::Pending,
+            };
+            let result = guard.try_io(|fd| fd.get_ref().send_vectored(bufs));
+            match result {
+                Ok(Ok(0)) => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "write zero.",
+                    )))
+                }
+                Ok(Ok(n)) => {
+                    let len = bufs.iter().map(|b| b.len()).sum();
+                    return Poll::Ready(whole_packet(n, len));
+                }
+                Ok(Err(err)) => return Poll::Ready(Err(err)),
+                Err(..) => {}
+            }
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+
     fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
         Poll::Ready(Ok(()))
     }
@@ -123,13 +190,52 @@ impl AsyncWrite for MgmtSocket {
     }
 }
 
+/// Sent length of a packet. A short send would be a truncated packet, which must not be
+/// completed by another send.
+fn whole_packet(sent: usize, len: usize) -> io::Result<usize> {
+    if sent != len {
+        return Err(io::Error::other(format!(
+            "short send {} of {} bytes.",
+            sent, len
+        )));
+    }
+    Ok(sent)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::os::unix::net::UnixDatagram;
+    use std::process::Command;
     use std::thread;
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
+    /// Targets of fds open in a child process. (e.g. `socket:[1234]`)
+    fn child_fds() -> Vec<String> {
+        let output = Command::new("sh")
+            .args(["-c", "readlink /proc/$$/fd/*"])
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn test_socket_type_cloexec() {
+        for &inheritable in &[false, true] {
+            let r#type = socket_type(Type::DGRAM, inheritable);
+            let sock = Socket::new_raw(Domain::UNIX, r#type, None).unwrap();
+            assert!(sock.nonblocking().unwrap());
+            let target = std::fs::read_link(format!("/proc/self/fd/{}", sock.as_raw_fd())).unwrap();
+            let target = target.to_str().unwrap().to_string();
+            assert!(target.starts_with("socket:"), "{}", target);
+            assert_eq!(inheritable, child_fds().contains(&target));
+        }
+    }
+
     #[tokio::test]
     async fn test_sock() {
         const N: usize = 1024;
@@ -174,4 +280,25 @@ mod tests {
         tokio::try_join!(t2, t3).unwrap();
         t1.join().unwrap();
     }
+
+    #[tokio::test]
+    async fn test_write_vectored() {
+        let (sock, peer) = MgmtSocket::pair().unwrap();
+        let (_, mut tx) = tokio::io::split(sock);
+
+        let bufs = [io::IoSlice::new(b"Hello, "), io::IoSlice::new(b"World!")];
+        let n = tx.write_vectored(&bufs).await.unwrap();
+        assert_eq!(b"Hello, World!".len(), n);
+
+        let mut buf = [0; 64];
+        let n = peer.recv(&mut buf).await.unwrap();
+        assert_eq!(b"Hello, World!", &buf[..n]);
+    }
+
+    #[test]
+    fn test_whole_packet() {
+        assert_eq!(13, whole_packet(13, 13).unwrap());
+        let err = whole_packet(6, 13).unwrap_err();
+        assert_eq!(io::ErrorKind::Other, err.kind());
+    }
 }
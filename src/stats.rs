@@ -0,0 +1,191 @@
+//! Client statistics. (feature `stats`)
+//!
+//! Counters are kept in atomics and read one by one, so a snapshot taken while
+//! the client is busy may be slightly stale between fields.
+pub(crate) use imp::Counters;
+#[cfg(feature = "stats")]
+pub use imp::{ClientStats, Code, Counts};
+
+#[cfg(feature = "stats")]
+mod imp {
+    use std::fmt;
+    use std::marker::PhantomData;
+    use std::ops::Index;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use crate::event::EventCode;
+    use crate::packet::ErrorCode;
+
+    const EVENT_SLOTS: usize = 0x40;
+    const ERROR_SLOTS: usize = 0x20;
+
+    /// Code usable as an index of [`Counts`].
+    pub trait Code {
+        fn index(self) -> usize;
+    }
+
+    impl Code for EventCode {
+        fn index(self) -> usize {
+            self as usize
+        }
+    }
+
+    impl Code for ErrorCode {
+        fn index(self) -> usize {
+            self as usize
+        }
+    }
+
+    /// Counts per code, indexable by the code enum.
+    #[derive(Clone)]
+    pub struct Counts<K> {
+        counts: Box<[u64]>,
+        _marker: PhantomData<K>,
+    }
+
+    impl<K> Counts<K> {
+        fn load(counters: &[AtomicU64]) -> Self {
+            Self {
+                counts: counters.iter().map(|c| c.load(Ordering::Relaxed)).collect(),
+                _marker: PhantomData,
+            }
+        }
+
+        /// Iterate non-zero counts with the raw code.
+        pub fn iter(&self) -> impl Iterator<Item = (u16, u64)> + '_ {
+            self.counts
+                .iter()
+                .enumerate()
+                .filter(|(_, n)| **n != 0)
+                .map(|(code, n)| (code as u16, *n))
+        }
+
+        /// Sum of all counts.
+        pub fn total(&self) -> u64 {
+            self.counts.iter().sum()
+        }
+    }
+
+    impl<K> Index<K> for Counts<K>
+    where
+        K: Code,
+    {
+        type Output = u64;
+
+        fn index(&self, code: K) -> &u64 {
+            self.counts.get(code.index()).unwrap_or(&0)
+        }
+    }
+
+    impl<K> fmt::Debug for Counts<K> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_map().entries(self.iter()).finish()
+        }
+    }
+
+    /// Snapshot of client counters.
+    #[derive(Debug, Clone)]
+    #[non_exhaustive]
+    pub struct ClientStats {
+        /// Received events per [`EventCode`]. (including Command Complete / Status)
+        pub events_total: Counts<EventCode>,
+        /// Sent commands.
+        pub commands_total: u64,
+        /// Non-success replies per [`ErrorCode`].
+        pub command_errors: Counts<ErrorCode>,
+        /// Events dropped over the rate limit per [`EventCode`].
+        /// See [`ClientBuilder::drop_excess_events`](crate::ClientBuilder::drop_excess_events).
+        pub events_dropped: Counts<EventCode>,
+        /// Packets failed to decode.
+        pub decode_failures: u64,
+    }
+
+    pub(crate) struct Counters {
+        events: [AtomicU64; EVENT_SLOTS],
+        dropped: [AtomicU64; EVENT_SLOTS],
+        commands: AtomicU64,
+        errors: [AtomicU64; ERROR_SLOTS],
+        decode_failures: AtomicU64,
+    }
+
+    impl Default for Counters {
+        fn default() -> Self {
+            Self {
+                events: std::array::from_fn(|_| AtomicU64::new(0)),
+                dropped: std::array::from_fn(|_| AtomicU64::new(0)),
+                commands: AtomicU64::new(0),
+                errors: std::array::from_fn(|_| AtomicU64::new(0)),
+                decode_failures: AtomicU64::new(0),
+            }
+        }
+    }
+
+    impl fmt::Debug for Counters {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.snapshot().fmt(f)
+        }
+    }
+
+    impl Counters {
+        pub(crate) fn event(&self, code: u16) {
+            if let Some(c) = self.events.get(code as usize) {
+                c.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        pub(crate) fn event_dropped(&self, code: u16) {
+            if let Some(c) = self.dropped.get(code as usize) {
+                c.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        pub(crate) fn command(&self) {
+            self.commands.fetch_add(1, Ordering::Relaxed);
+        }
+
+        pub(crate) fn command_error(&self, status: &ErrorCode) {
+            if let Some(c) = self.errors.get(status.clone() as usize) {
+                c.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        pub(crate) fn decode_failure(&self) {
+            self.decode_failures.fetch_add(1, Ordering::Relaxed);
+        }
+
+        pub(crate) fn snapshot(&self) -> ClientStats {
+            ClientStats {
+                events_total: Counts::load(&self.events),
+                events_dropped: Counts::load(&self.dropped),
+                commands_total: self.commands.load(Ordering::Relaxed),
+                command_errors: Counts::load(&self.errors),
+                decode_failures: self.decode_failures.load(Ordering::Relaxed),
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "stats"))]
+mod imp {
+    use crate::packet::ErrorCode;
+
+    #[derive(Debug, Default)]
+    pub(crate) struct Counters;
+
+    impl Counters {
+        #[inline]
+        pub(crate) fn event(&self, _: u16) {}
+
+        #[inline]
+        pub(crate) fn event_dropped(&self, _: u16) {}
+
+        #[inline]
+        pub(crate) fn command(&self) {}
+
+        #[inline]
+        pub(crate) fn command_error(&self, _: &ErrorCode) {}
+
+        #[inline]
+        pub(crate) fn decode_failure(&self) {}
+    }
+}
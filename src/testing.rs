@@ -0,0 +1,281 @@
+//! Mock client for tests of downstream crates, without bluetooth controllers.
+//!
+//! ```
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! use btmgmt::command::{CommandCode, ReadManagementVersionInformation};
+//! use btmgmt::packet::ControllerIndex;
+//! use btmgmt::testing::MockClient;
+//!
+//! let mock = MockClient::new(vec![(
+//!     (
+//!         CommandCode::ReadManagementVersionInformation,
+//!         ControllerIndex::NonController,
+//!         vec![],
+//!     ),
+//!     vec![0x01, 0x12, 0x00],
+//! )]);
+//!
+//! let reply = mock
+//!     .client()
+//!     .call(None, ReadManagementVersionInformation)
+//!     .await
+//!     .unwrap();
+//! assert_eq!(18, *reply.revision());
+//! mock.assert_called_with(
+//!     CommandCode::ReadManagementVersionInformation,
+//!     ControllerIndex::NonController,
+//!     &[],
+//! );
+//! # }
+//! ```
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use futures_channel::mpsc;
+use futures_util::stream::StreamExt;
+use tokio::net::UnixDatagram;
+use tokio::task::JoinHandle;
+
+use crate::command::CommandCode;
+use crate::event::EventCode;
+use crate::packet::pack::{Pack, Unpack};
+use crate::packet::{ControllerIndex, ErrorCode};
+use crate::Client;
+
+/// Command code, controller index and encoded parameters of a call.
+pub type MockCall = (CommandCode, ControllerIndex, Vec<u8>);
+
+/// Event code, controller index and encoded parameters of an injected event.
+pub type MockEvent = (EventCode, ControllerIndex, Vec<u8>);
+
+/// [`Client`] connected to a fake kernel replying the registered bytes.
+///
+/// Calls matching a registered [`MockCall`] get Command Complete with the registered
+/// return parameters, others Command Status Unknown Command. All calls are recorded.
+/// The fake kernel runs on the tokio runtime until the mock is dropped, after which the
+/// client is closed.
+pub struct MockClient {
+    client: Client,
+    calls: Arc<Mutex<Vec<MockCall>>>,
+    events: mpsc::UnboundedSender<MockEvent>,
+    tasks: [JoinHandle<()>; 2],
+}
+
+impl MockClient {
+    /// Mock replying the return parameters for each call. Must be called in a tokio
+    /// runtime.
+    pub fn new<R>(replies: R) -> Self
+    where
+        R: IntoIterator<Item = (MockCall, Vec<u8>)>,
+    {
+        let replies = replies.into_iter().collect::<HashMap<_, _>>();
+        let (client, peer) = Client::pair();
+        let peer = Arc::new(peer);
+        let calls = Arc::<Mutex<Vec<MockCall>>>::default();
+        let (events, rx) = mpsc::unbounded();
+
+        let kernel = tokio::spawn(serve(peer.clone(), replies, calls.clone()));
+        let inject = tokio::spawn(rx.for_each(move |(code, index, params): MockEvent| {
+            let peer = peer.clone();
+            async move {
+                let frame = frame(code.clone() as u16, index, &params);
+                if let Err(err) = peer.send(&frame).await {
+                    log::warn!("failed to inject event {:?}: {}", code, err);
+                }
+            }
+        }));
+
+        Self {
+            client,
+            calls,
+            events,
+            tasks: [kernel, inject],
+        }
+    }
+
+    /// Client connected to the fake kernel.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Sender of events to the client.
+    pub fn events(&self) -> mpsc::UnboundedSender<MockEvent> {
+        self.events.clone()
+    }
+
+    /// Calls received so far, in order.
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Panic unless the command has been called with the parameters.
+    #[track_caller]
+    pub fn assert_called_with<I>(&self, code: CommandCode, index: I, params: &[u8])
+    where
+        I: Into<ControllerIndex>,
+    {
+        let index = index.into();
+        let calls = self.calls.lock().unwrap();
+        if !calls
+            .iter()
+            .any(|(c, i, p)| *c == code && *i == index && p[..] == *params)
+        {
+            panic!(
+                "{:?} for {:?} with {:02x?} not called. calls: {:02x?}",
+                code, index, params, *calls
+            );
+        }
+    }
+}
+
+impl Drop for MockClient {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+fn frame(code: u16, index: ControllerIndex, params: &[u8]) -> Vec<u8> {
+    let mut b = Vec::with_capacity(6 + params.len());
+    b.extend(code.to_le_bytes());
+    b.extend(u16::from(index).to_le_bytes());
+    b.extend((params.len() as u16).to_le_bytes());
+    b.extend(params);
+    b
+}
+
+async fn serve(
+    peer: Arc<UnixDatagram>,
+    replies: HashMap<MockCall, Vec<u8>>,
+    calls: Arc<Mutex<Vec<MockCall>>>,
+) {
+    let mut buf = vec![0; 6 + u16::MAX as usize];
+    loop {
+        let n = match peer.recv(&mut buf).await {
+            Ok(n) if n >= 6 => n,
+            Ok(..) => continue,
+            Err(err) => {
+                log::warn!("mock kernel stopped: {}", err);
+                return;
+            }
+        };
+        let opcode = u16::from_le_bytes([buf[0], buf[1]]);
+        let index = ControllerIndex::from(u16::from_le_bytes([buf[2], buf[3]]));
+        let params = buf[6..n].to_vec();
+
+        let reply = match CommandCode::unpack(&mut &buf[..2]) {
+            Ok(code) => {
+                let call = (code, index.clone(), params);
+                let reply = replies.get(&call).cloned();
+                calls.lock().unwrap().push(call);
+                reply
+            }
+            Err(..) => None,
+        };
+        let frame = match reply {
+            Some(reply) => {
+                let mut params = opcode.to_le_bytes().to_vec();
+                params.push(ErrorCode::Success as u8);
+                params.extend(reply);
+                frame(EventCode::CommandComplete as u16, index, &params)
+            }
+            None => {
+                log::warn!("mock kernel: no reply registered for {:#06x}", opcode);
+                let mut params = opcode.to_le_bytes().to_vec();
+                ErrorCode::UnknownCommand.pack(&mut params).unwrap();
+                frame(EventCode::CommandStatus as u16, index, &params)
+            }
+        };
+        if let Err(err) = peer.send(&frame).await {
+            log::warn!("mock kernel stopped: {}", err);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{ReadControllerInformation, SetPowered};
+    use crate::event::Event;
+    use crate::Error;
+
+    #[tokio::test]
+    async fn test_mock_call() {
+        let mock = MockClient::new(vec![(
+            (
+                CommandCode::SetPowered,
+                ControllerIndex::from(0),
+                vec![0x01],
+            ),
+            vec![0x01, 0x00, 0x00, 0x00],
+        )]);
+
+        let settings = mock.client().call(0, SetPowered::new(true)).await.unwrap();
+        assert_eq!(1, settings.bits());
+        mock.assert_called_with(CommandCode::SetPowered, 0, &[0x01]);
+
+        // not registered.
+        let err = mock
+            .client()
+            .call(0, SetPowered::new(false))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::CommandStatus {
+                status: ErrorCode::UnknownCommand,
+                ..
+            }
+        ));
+        let err = mock
+            .client()
+            .call(1, ReadControllerInformation)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::CommandStatus { .. }));
+
+        assert_eq!(
+            vec![
+                (
+                    CommandCode::SetPowered,
+                    ControllerIndex::from(0),
+                    vec![0x01]
+                ),
+                (
+                    CommandCode::SetPowered,
+                    ControllerIndex::from(0),
+                    vec![0x00]
+                ),
+                (
+                    CommandCode::ReadControllerInformation,
+                    ControllerIndex::from(1),
+                    vec![]
+                ),
+            ],
+            mock.calls()
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "not called")]
+    async fn test_mock_assert_not_called() {
+        let mock = MockClient::new(vec![]);
+        mock.assert_called_with(CommandCode::SetPowered, 0, &[0x01]);
+    }
+
+    #[tokio::test]
+    async fn test_mock_events() {
+        let mock = MockClient::new(vec![]);
+        let mut events = mock.client().events().await;
+
+        mock.events()
+            .unbounded_send((EventCode::IndexAdded, ControllerIndex::from(1), vec![]))
+            .unwrap();
+        let (index, event) = events.next().await.unwrap();
+        assert_eq!(ControllerIndex::from(1), index);
+        assert!(matches!(event, Event::IndexAdded(..)));
+    }
+}
@@ -0,0 +1,182 @@
+//! Device Found rate limiting.
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_core::stream::Stream;
+use futures_util::stream::StreamExt;
+use tokio::time::Instant;
+
+use crate::client::EventSubscribe;
+use crate::event::{DeviceFound, Event};
+use crate::packet::{Address, ControllerIndex};
+
+/// Table size at which devices not seen within the interval are forgotten.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Device Found events, at most one per device per interval.
+///
+/// The first advertisement of a device is emitted immediately. Advertisements within the
+/// interval are dropped, and the next one after it is emitted, so each update carries the
+/// latest RSSI at that time.
+pub struct ThrottledDeviceFound {
+    events: EventSubscribe,
+    index: ControllerIndex,
+    min_interval: Duration,
+    last: HashMap<Address, Instant>,
+}
+
+impl ThrottledDeviceFound {
+    pub(crate) fn new(
+        events: EventSubscribe,
+        index: ControllerIndex,
+        min_interval: Duration,
+    ) -> Self {
+        Self {
+            events,
+            index,
+            min_interval,
+            last: Default::default(),
+        }
+    }
+
+    fn accept(&mut self, address: Address, now: Instant) -> bool {
+        let min_interval = self.min_interval;
+        if let Some(last) = self.last.get(&address) {
+            if now.duration_since(*last) < min_interval {
+                return false;
+            }
+        }
+
+        if self.last.len() >= PRUNE_THRESHOLD {
+            self.last
+                .retain(|_, last| now.duration_since(*last) < min_interval);
+        }
+        self.last.insert(address, now);
+        true
+    }
+}
+
+impl Stream for ThrottledDeviceFound {
+    type Item = DeviceFound;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            match this.events.poll_next_unpin(cx) {
+                Poll::Ready(Some((index, Event::DeviceFound(found)))) if index == this.index => {
+                    if this.accept(found.address(), Instant::now()) {
+                        return Poll::Ready(Some(found));
+                    }
+                }
+                Poll::Ready(Some(..)) => {}
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packet::{AddressType, AddressTypes};
+    use crate::Client;
+
+    use super::*;
+
+    fn device_found(last: u8, rssi: u8) -> Vec<u8> {
+        let mut bytes = vec![0x12, 0x00, 0x00, 0x00, 0x0E, 0x00];
+        bytes.extend([last, 0x55, 0x44, 0x33, 0x22, 0xC1]); // address
+        bytes.extend([0x02]); // address type
+        bytes.extend([rssi]);
+        bytes.extend([0x00, 0x00, 0x00, 0x00]); // flags
+        bytes.extend([0x00, 0x00]); // eir length
+        bytes
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_discover_throttled() {
+        let (client, peer) = Client::pair();
+
+        let mut types = AddressTypes::default();
+        types.extend([AddressType::LePublic, AddressType::LeRandom]);
+        let interval = Duration::from_secs(1);
+
+        let kernel = async {
+            let mut buf = [0; 1024];
+            let n = peer.recv(&mut buf).await.unwrap();
+            assert_eq!(&[0x23, 0x00, 0x00, 0x00, 0x01, 0x00, 0x06], &buf[..n]);
+            peer.send(&[0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x23, 0x00, 0x00, 0x06])
+                .await
+                .unwrap();
+        };
+        let (found, _) = tokio::join!(client.discover_throttled(0, types, interval), kernel);
+        let mut found = found.unwrap();
+
+        for packet in [
+            device_found(0x66, 0xC4),
+            device_found(0x66, 0xC5),
+            device_found(0x66, 0xC6),
+            device_found(0x77, 0xB0),
+        ] {
+            peer.send(&packet).await.unwrap();
+        }
+        let first = found.next().await.unwrap();
+        assert_eq!(0x66, <[u8; 6]>::from(first.address().into_bd_addr())[0]);
+        assert_eq!(0xC4, *first.rssi());
+        let second = found.next().await.unwrap();
+        assert_eq!(0x77, <[u8; 6]>::from(second.address().into_bd_addr())[0]);
+
+        // within the interval: dropped.
+        peer.send(&device_found(0x66, 0xC7)).await.unwrap();
+        peer.send(&device_found(0x88, 0xA0)).await.unwrap();
+        let other = found.next().await.unwrap();
+        assert_eq!(0x88, <[u8; 6]>::from(other.address().into_bd_addr())[0]);
+
+        tokio::time::advance(interval).await;
+        peer.send(&device_found(0x66, 0xC8)).await.unwrap();
+
+        let third = found.next().await.unwrap();
+        assert_eq!(0x66, <[u8; 6]>::from(third.address().into_bd_addr())[0]);
+        assert_eq!(0xC8, *third.rssi());
+    }
+
+    #[tokio::test]
+    async fn test_le_only_no_confirm_name() {
+        let (client, peer) = Client::pair();
+
+        let mut types = AddressTypes::default();
+        types.extend([AddressType::LePublic, AddressType::LeRandom]);
+        assert!(types.is_le_only());
+
+        let kernel = async {
+            let mut buf = [0; 1024];
+            peer.recv(&mut buf).await.unwrap();
+            peer.send(&[0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x23, 0x00, 0x00, 0x06])
+                .await
+                .unwrap();
+        };
+        let (found, _) = tokio::join!(
+            client.discover_throttled(0, types, Duration::from_secs(1)),
+            kernel
+        );
+        let mut found = found.unwrap();
+
+        let mut packet = device_found(0x66, 0xC4);
+        packet[14] = 0x01; // confirm name
+        peer.send(&packet).await.unwrap();
+        let device = found.next().await.unwrap();
+        assert!(device
+            .flags()
+            .contains(crate::packet::DeviceConnectFlags::ConfirmName));
+
+        // no Confirm Name sent.
+        let mut buf = [0; 16];
+        assert_eq!(
+            std::io::ErrorKind::WouldBlock,
+            peer.try_recv(&mut buf).unwrap_err().kind()
+        );
+    }
+}
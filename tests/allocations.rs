@@ -0,0 +1,156 @@
+//! Allocation counts of event delivery.
+//!
+//! Own test target, as the counting `#[global_allocator]` applies to the whole binary.
+use std::sync::Arc;
+
+use btmgmt::event::Event;
+use btmgmt::Client;
+use btmgmt_packet::fixtures;
+use futures::{Stream, StreamExt};
+
+/// Counts allocations and live bytes of the current thread.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCS: std::cell::Cell<(usize, isize)> = const { std::cell::Cell::new((0, 0)) };
+}
+
+fn track(count: usize, bytes: isize) {
+    ALLOCS
+        .try_with(|a| {
+            let (c, b) = a.get();
+            a.set((c + count, b + bytes));
+        })
+        .ok();
+}
+
+unsafe impl std::alloc::GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        track(1, layout.size() as isize);
+        std::alloc::System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        track(0, -(layout.size() as isize));
+        std::alloc::System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+fn allocs() -> (usize, isize) {
+    ALLOCS.with(|a| a.get())
+}
+
+const SUBSCRIBERS: usize = 16;
+const FLOOD: usize = 100;
+
+/// Allocations to deliver a Device Found flood to every subscriber.
+async fn fanout<S>(peer: &tokio::net::UnixDatagram, mut subscribers: Vec<S>) -> usize
+where
+    S: Stream + Unpin,
+{
+    let (bytes, _) = fixtures::device_found();
+    for _ in 0..FLOOD {
+        peer.send(&bytes).await.unwrap();
+    }
+
+    let (before, _) = allocs();
+    for subscriber in &mut subscribers {
+        for _ in 0..FLOOD {
+            subscriber.next().await.unwrap();
+        }
+    }
+    allocs().0 - before
+}
+
+#[tokio::test]
+async fn test_events_shared_allocations() {
+    let (client, peer) = Client::pair();
+    let mut subscribers = vec![];
+    for _ in 0..SUBSCRIBERS {
+        subscribers.push(client.events().await);
+    }
+    let copied = fanout(&peer, subscribers).await;
+
+    let (client, peer) = Client::pair();
+    let mut subscribers = vec![];
+    for _ in 0..SUBSCRIBERS {
+        subscribers.push(client.events_shared().await);
+    }
+    let shared = fanout(&peer, subscribers).await;
+
+    // copied allocates a payload per subscriber but the last one.
+    assert!(
+        copied >= shared + (SUBSCRIBERS - 2) * FLOOD,
+        "copied {}, shared {}",
+        copied,
+        shared
+    );
+}
+
+#[tokio::test]
+async fn test_events_filtered_not_copied() {
+    let (client, peer) = Client::pair();
+    let alone = fanout(&peer, vec![client.events().await]).await;
+
+    let (client, peer) = Client::pair();
+    let subscriber = client.events().await;
+    let _filtered = client.events_filtered(|_, _| false).await;
+    let filtered = fanout(&peer, vec![subscriber]).await;
+
+    // a subscriber holding the event would make the other copy it.
+    assert!(
+        filtered < alone + FLOOD,
+        "filtered {}, alone {}",
+        filtered,
+        alone
+    );
+}
+
+#[tokio::test]
+async fn test_events_shared_retention() {
+    let (client, peer) = Client::pair();
+    let mut subscribers = vec![];
+    for _ in 0..SUBSCRIBERS {
+        subscribers.push(client.events_shared().await);
+    }
+    let (bytes, expected) = fixtures::device_found();
+    for _ in 0..FLOOD {
+        peer.send(&bytes).await.unwrap();
+    }
+
+    let mut held = vec![];
+    for _ in 0..FLOOD {
+        held.push(subscribers[0].next().await.unwrap());
+    }
+    let first = held[0].clone();
+    match &*first {
+        (_, Event::DeviceFound(found)) => {
+            assert_eq!(&expected.eir_data()[..], &found.eir_data()[..])
+        }
+        (_, event) => panic!("{:?}", event),
+    }
+    assert_eq!(SUBSCRIBERS + 1, Arc::strong_count(&first));
+
+    // others drain and drop. only the held events stay.
+    let (_, before) = allocs();
+    for subscriber in &mut subscribers[1..] {
+        for _ in 0..FLOOD {
+            subscriber.next().await.unwrap();
+        }
+    }
+    drop(subscribers);
+    assert_eq!(2, Arc::strong_count(&first));
+    let (_, after) = allocs();
+    assert!(after <= before, "{} -> {}", before, after);
+
+    // each held event keeps its own payload, not the receive buffer.
+    let (_, before) = allocs();
+    drop(held);
+    drop(first);
+    let (_, after) = allocs();
+    let per_event = (before - after) as usize / FLOOD;
+    assert!(per_event < 256, "{} bytes per event", per_event);
+}